@@ -0,0 +1,437 @@
+# English UI strings. Placeables such as { $ms } are filled in by the app.
+
+app-title = Typst to LaTeX Math Converter
+
+## Menus
+
+menu-file = File
+menu-open = Open…
+menu-open-recent = Open recent
+menu-save-output = Save output…
+menu-from-image = From image
+menu-open-image = Open image…
+menu-paste-image = Paste image
+menu-ocr-command = OCR command
+menu-export-standalone = Export standalone .tex…
+menu-export-png = Export PNG…
+menu-export-svg = Export SVG…
+menu-export-colors = Export colors
+menu-export-settings = Export settings…
+menu-import-settings = Import settings…
+settings-imported = Settings, rules, profiles and history imported
+export-foreground = Formula
+export-background = Background
+export-use-preview-color = Preview color
+export-transparent = Transparent
+menu-edit = Edit
+menu-vim = Vim keys in the input editor
+menu-auto-close = Type brackets in pairs
+menu-rules = Rewrite rules…
+menu-backend = Backend
+menu-backend-hint = Plugins are programs named typst2latex-backend-<name> on the PATH
+menu-typst-style = Typst style
+menu-typst-style-hint = How LaTeX → Typst output is written, to match the Typst it goes into
+typst-style-pandoc = As pandoc writes it
+typst-style-shorthands = Arrows and relations
+typst-style-shorthand = Shorthands, like -> and <=
+typst-style-explicit = Names, like arrow.r and lt.eq
+typst-style-symbols = Symbols
+typst-style-names = Names, like alpha
+typst-style-unicode = Characters, like α
+typst-style-spacing = Operators
+typst-style-spaced = Spaced, like a + b
+typst-style-compact = Compact, like a+b
+rules = Rewrite rules
+rules-input = Input rules
+rules-input-hint = Applied to the input before every conversion, from top to bottom, e.g. to expand shorthands
+rules-output = Output rules
+rules-output-hint = Applied to the output after every conversion, from top to bottom
+hooks = Hooks
+hooks-hint = Shell commands for what rules can't do. Each reads the text on stdin and writes the new text on stdout; the direction is in TYPST2LATEX_DIRECTION
+hooks-pre = Before converting
+hooks-post = After converting
+macros = Macros
+macros-hint = Paste the \newcommand, \DeclareMathOperator and \def lines of your preamble; they are expanded in LaTeX input before converting
+theorems = Theorems
+theorems-hint = Typst theorem, lemma and proof blocks in documents become amsthm environments of the same name; rename them with lines like theorem = thm
+rules-find = Find
+rules-replace = Replace with
+rules-enabled = Use this rule
+rules-add = Add rule
+menu-strip-wrappers = Strip math wrappers from pasted input
+strip-wrappers-hint = Converts what is inside $ … $, $$ … $$, code fences and #math.equation(…), and puts the output back in the same wrapper
+menu-primary-selection = Also put conversions in the primary selection
+menu-insert = Insert
+menu-view = View
+menu-tectonic-preview = Compile preview with tectonic
+menu-renderer = Preview renderer
+renderer-builtin = MathJax or tectonic (built in)
+renderer-hint = Plugins are programs named typst2latex-render-<name> on the PATH that read LaTeX and write SVG
+menu-validate = Check that output compiles with TeX
+menu-checkerboard = Show transparency in preview
+menu-diagnostics = Diagnostics log
+menu-mapping = Mapping mode
+menu-mapping-hint = Hover a token to highlight its counterpart in the other pane, matched by name
+menu-diff = Diff against previous conversion
+menu-layout = Layout
+layout-stacked = Stacked
+layout-side-by-side = Side by side
+menu-wrap = Word wrap
+wrap-input = Wrap input lines
+wrap-output = Wrap output lines
+menu-learning = Learning mode
+learning-off = Off
+learning-comments = Explain in output comments
+learning-table = Explain in a table
+menu-math-font = Math font
+math-font-default = MathJax (default)
+math-font-hint = Other fonts are typeset with tectonic.
+menu-preview-cache = Preview cache
+preview-cache-usage = { $count } previews, { $size } MB
+preview-cache-hint = Previews shown before appear again without rendering. The least recently shown are dropped beyond this size.
+menu-editor-font = Editor font
+font-proportional = Proportional
+font-monospace = Monospace
+font-size = Size
+line-spacing = Line spacing
+menu-language = Language
+menu-symbols = Symbol lookup…
+menu-history = History…
+menu-batch = Batch table…
+menu-command-palette = Command palette…  Ctrl+P
+menu-help = Help
+menu-allow-network = Allow network access
+menu-allow-network-hint = When off, typst2latex never connects to the internet
+menu-check-updates = Check for updates at startup
+menu-check-updates-now = Check for updates now
+menu-release-notes = Release notes…
+menu-reference = Typst math reference…
+menu-usage = Usage statistics…
+menu-onboarding = Welcome walkthrough…
+menu-load-example = Load example
+
+## Theme
+
+menu-theme = Theme
+theme-system = System
+theme-light = Light
+theme-dark = Dark
+menu-customize-colors = Customize colors…
+theme-editor = Colors
+color-accent = Accent
+color-background = Background
+color-text = Text
+color-preview = Formula preview
+color-reset = Reset
+
+## Main window
+
+choose-math-block = Choose a math block
+detected-direction = (detected { $direction })
+copy-target = Copy { $target }
+copy-as = Copy as…
+copy-markdown = Inline or display
+copy-markdown-table-cell = { $engine } table cell
+markdown-sanitize = Escape for this engine
+markdown-sanitize-hint = Adjust the characters that { $engine } would take for Markdown, such as *, _ and |, so that the formula reaches the math renderer intact
+copy-html-svg = HTML (inline SVG)
+copy-html-katex = HTML (KaTeX)
+copy-html-attribute = HTML attribute (escaped)
+copy-html-script = HTML (MathJax script tag)
+numbering = Numbering
+numbering-hint = How the copy button wraps LaTeX for numbered documents
+units = Units
+units-hint = How quantities such as 3 "m"/"s" are written in LaTeX
+units-as-is = As pandoc writes them
+units-siunitx = siunitx, like \SI{3}{\meter\per\second}
+units-upright = Upright text, like 3\,\mathrm{m}/\mathrm{s}
+numbers = Numbers
+numbers-hint = How numbers are written in LaTeX, e.g. for a journal's style
+numbers-decimal-comma = Decimal comma, like 1{,}5
+numbers-grouping = Thin spaces between thousands, like 12\,345
+numbers-scientific = Scientific notation
+numbers-scientific-as-is = As written
+numbers-scientific-times = Powers of ten, like 1.5 \times 10^{3}
+numbers-scientific-e = E-notation, like 1.5\mathrm{e}{3}
+characters = Characters
+characters-hint = How non-ASCII characters are written in LaTeX output
+characters-as-is = As converted
+characters-ascii = ASCII only, with commands like \alpha
+characters-unicode = Unicode symbols like α, for unicode-math
+diagrams = Diagram
+diagrams-hint = This matrix of arrows looks like a commutative diagram
+diagrams-matrix = Matrix of arrows
+diagrams-cd = amscd CD
+diagrams-tikz-cd = tikz-cd
+diagrams-warning = Check the diagram, its arrows may need adjusting by hand
+numbering-as-is = As converted
+numbering-numbered = Numbered (equation)
+numbering-unnumbered = Unnumbered (equation*, tags removed)
+numbering-tag = Tagged with a Typst numbering pattern
+numbering-pattern = Pattern
+delimiters = Delimiters
+profile-none = No profile
+profile-hint = Profiles bundle the output language, delimiters, numbering, rewrite rules and export colors for one destination
+profile-name = New profile name
+profile-save = Save
+profile-save-hint = Save the current settings as a profile with this name
+profile-update = Update “{ $name }” to the current settings
+profile-delete = Delete “{ $name }”
+numbering-next = Next number
+grouping-separate = Separate blocks
+grouping-hint = How to put together the { $count } equations of the input
+prose-spans = { $count } math spans converted
+prose-hint = The input is prose with math in it: only the math is converted and the text is left as it is
+prose-failed = { $count } math spans could not be converted and were left as they are: { $error }
+clear = Clear
+tex-compiles = ✔ Compiles with TeX
+tex-fails = This LaTeX will not compile: { $error }
+preview-rendering = Rendering…
+tex-checking = Checking with TeX…
+preview-downscaled = This formula is very large, so the preview is shown at reduced resolution.
+diff-none = No earlier conversion to compare with yet.
+learning-breakdown = How it was converted
+learning-nothing = Nothing to explain in this formula
+stats-characters = { $count } characters
+stats-commands = { $count } LaTeX commands
+stats-depth = nesting depth { $depth }
+stats-width = about { $width } em wide
+
+## Find and replace
+
+find-hint = Find
+find-regex = Regex
+find-matches = { $count } matches
+find-invalid = Invalid pattern
+find-show-replace = Replace…
+replace-hint = Replace with
+replace = Replace
+replace-all = Replace all
+
+## Anki
+
+anki = Anki
+anki-deck = Deck
+anki-note-type = Note type
+anki-connect = AnkiConnect
+anki-image-front = Rendered image on front
+anki-send = Send to Anki
+anki-sent = Note added to Anki
+anki-failed = Failed to send to Anki
+
+## Accessibility
+
+announce-converted = Conversion complete
+announce-failed = Conversion failed: { $error }
+preview-alt = Formula preview: { $description }
+speech-copy = Copy
+
+## Status bar and diagnostics
+
+status-pandoc-missing = pandoc not found
+pandoc-too-old = pandoc { $version } can't read Typst, so only LaTeX to Typst works. Typst input needs pandoc { $required } or newer.
+pandoc-install = Install a newer pandoc
+status-convert-time = convert { $ms } ms
+status-render-time = render { $ms } ms
+converting = Converting…
+conversion-cancel = Cancel
+conversion-cancel-hint = Stop pandoc (Esc)
+conversion-cancelled = Conversion cancelled
+retry = Retry
+diagnostics = Diagnostics
+diagnostics-copy = Copy diagnostics
+diagnostics-frames = { $count } frames drawn
+diagnostics-startup = window up { $ms } ms after launch
+
+## History
+
+history = History
+history-search = Search Typst or LaTeX
+history-select-all = Select shown
+history-select-none = Select none
+history-export = Export selected…
+history-empty = No conversions yet. Copied and saved conversions are kept here.
+history-restore = Click to load into the editor
+
+## Batch table
+
+batch = Batch table
+batch-paste-hint = Paste expressions, one per line or separated by blank lines
+batch-add-rows = Add as rows
+batch-add-row = Add empty row
+batch-summary = { $rows } rows, { $failed } failed
+batch-copy = Copy LaTeX
+
+## Command palette
+
+palette-hint = Type a command
+command-convert = Convert
+command-cancel-conversion = Cancel conversion
+command-undo = Undo
+command-redo = Redo
+command-find = Find…
+command-replace = Replace…
+command-swap-direction = Swap direction
+command-direction = Direction: { $direction }
+command-target = Target: { $target }
+command-copy = Copy output
+command-copy-as = Copy as { $format }
+command-copy-speech = Copy spoken description
+command-send-to-anki = Send to Anki
+command-open = Open file…
+command-save-output = Save output…
+command-open-image = Open image…
+command-paste-image = Paste image
+command-export-standalone = Export standalone .tex…
+command-export-png = Export PNG…
+command-export-svg = Export SVG…
+command-clear = Clear
+command-tectonic-preview = Settings: Compile preview with tectonic
+command-validate = Settings: Check that output compiles with TeX
+command-diagnostics = View: Diagnostics log
+command-mapping = View: Mapping mode
+command-diff = View: Diff against previous conversion
+command-history = View: History
+command-batch = View: Batch table
+command-usage = Help: Usage statistics
+command-symbols = View: Symbol lookup
+command-reference = Help: Typst math reference
+command-insert-template = Insert: { $template }
+command-load-example = Example: { $example }
+
+## Updates
+
+update-available = Version { $version } is available
+update-none = typst2latex is up to date
+release-notes = Release notes
+release-version = Version { $version } (you have { $current })
+release-download = Download from GitHub
+
+## Symbol lookup
+
+symbols = Symbol lookup
+symbols-search = Search by LaTeX command, Typst name or symbol
+symbols-none = No matching symbols
+symbols-unsupported = Not available in StarMath
+symbols-insert = Insert
+
+## Quick reference
+
+reference = Typst math reference
+reference-search = Search by Typst, LaTeX or description
+reference-none = Nothing matches
+reference-syntax = Syntax
+reference-functions = Functions
+reference-symbols = Symbols
+reference-superscript = Superscript
+reference-subscript = Subscript
+reference-group = Parentheses group a sub- or superscript and are dropped
+reference-fraction = Fraction
+reference-fraction-group = Parentheses around a numerator or denominator are dropped
+reference-product = Separate letters with spaces; "ab" is a name
+reference-text = Upright text in quotes
+reference-prime = Prime
+reference-limits = Limits under an operator
+reference-align = Alignment point
+reference-linebreak = Line break
+reference-display = Spaces inside the dollars make a display equation
+
+## Templates
+
+template-limit = Limit
+template-derivative = Derivative
+template-integral = Definite integral
+template-sum = Sum with conditions
+template-fraction = Fraction
+template-root = Roots
+template-piecewise = Piecewise function
+template-matrix = Matrix
+template-determinant = Determinant
+template-vector = Vectors
+template-aligned = Aligned equations
+template-arrows = Arrows with labels
+
+## Examples
+
+example-quadratic = Quadratic formula
+example-euler = Euler's identity
+example-gaussian = Gaussian integral
+example-taylor = Taylor series
+example-derivation = Step-by-step derivation
+example-system = System of equations
+example-eigenvector = Eigenvector
+example-bayes = Bayes' theorem
+example-fourier = Fourier transform
+example-maxwell = Maxwell's equations
+example-schrodinger = Schrödinger equation
+example-energy = Energy–momentum relation
+
+## Usage statistics
+
+usage = Usage statistics
+usage-local = Kept on this computer only, never uploaded.
+usage-conversions = Conversions
+usage-failures = Failed conversions
+usage-convert-time = Average conversion time
+usage-render-time = Average preview time
+usage-ms = { $ms } ms
+usage-symbols = Most used commands
+usage-no-symbols = No conversions yet
+usage-reset = Reset
+
+## First run
+
+onboarding-title = Welcome
+onboarding-step = Step { $step } of { $count }
+onboarding-welcome = typst2latex converts math between Typst and LaTeX.
+onboarding-pandoc = The conversion itself is done by pandoc, which has to be installed separately.
+onboarding-pandoc-probing = Looking for pandoc…
+onboarding-pandoc-found = Found pandoc { $version }. You're all set.
+onboarding-pandoc-missing = pandoc was not found. Install it and make sure it's on your PATH, then restart typst2latex.
+onboarding-pandoc-get = Get pandoc
+onboarding-profile = Where does the output go?
+onboarding-profile-hint = A profile sets the output language, delimiters and numbering for one destination. You can change it next to the copy button.
+onboarding-delimiters = Copy LaTeX with delimiters
+onboarding-delimiters-hint = What copied LaTeX is wrapped in, for pasting straight into a document.
+onboarding-theme = Theme
+onboarding-back = Back
+onboarding-next = Next
+onboarding-finish = Finish
+onboarding-skip = Skip
+
+## Crash report
+
+crash-title = typst2latex crashed
+crash-saved = typst2latex ran into a bug and has to close. A crash report was saved to { $path } — please attach it when reporting the problem.
+crash-not-saved = typst2latex ran into a bug and has to close. The crash report could not be saved: { $error }
+crash-background = A background task ran into a bug
+crash-background-saved = A crash report was saved to { $path } — please attach it when reporting the problem.
+crash-background-not-saved = The crash report could not be saved: { $error }
+
+## Errors
+
+explain-apply = Apply fix
+explain-unclosed-string = The string opened on line { $line } is never closed.
+explain-unknown = Typst has no function or symbol called { $name }. Did you mean { $suggestion }?
+explain-unknown-text = Typst has no function or symbol called { $name }. Words in formulas go in quotes.
+explain-mat-comma = Row { $row } of the matrix has fewer entries than the others. Is a comma missing?
+brackets-unclosed = Unclosed { $bracket } on line { $line }, column { $column }
+brackets-unexpected = Unexpected { $bracket } on line { $line }, column { $column }
+brackets-mismatched = { $bracket } on line { $line }, column { $column } doesn't close { $open }
+error-nothing-converted = Nothing has been converted yet
+error-copy = Failed to copy to clipboard
+error-clipboard = Failed to initialize clipboard support
+error-save-settings = Failed to save settings
+error-update-check = Failed to check for updates: { $error }
+error-save-history = Failed to save history
+error-save-session = Failed to save session
+error-read-file = Failed to read { $path }
+error-write-file = Failed to write file
+error-export-png = Failed to render PNG: { $error }
+error-recognize-image = Failed to recognize image
+error-no-clipboard-image = No image on the clipboard
+error-encode-clipboard-image = Failed to encode clipboard image
+error-write-temporary-file = Failed to write temporary file
+error-render-anki-image = Failed to render image for Anki
+error-render-svg = Failed to convert LaTeX to SVG
+error-texture = Failed to convert SVG to texture
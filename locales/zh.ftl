@@ -0,0 +1,437 @@
+# 简体中文界面文本。
+
+app-title = Typst 与 LaTeX 数学公式转换器
+
+## Menus
+
+menu-file = 文件
+menu-open = 打开…
+menu-open-recent = 最近打开
+menu-save-output = 保存输出…
+menu-from-image = 从图片识别
+menu-open-image = 打开图片…
+menu-paste-image = 粘贴图片
+menu-ocr-command = OCR 命令
+menu-export-standalone = 导出独立 .tex 文件…
+menu-export-png = 导出 PNG…
+menu-export-svg = 导出 SVG…
+menu-export-colors = 导出颜色
+menu-export-settings = 导出设置…
+menu-import-settings = 导入设置…
+settings-imported = 已导入设置、替换规则、配置和历史记录
+export-foreground = 公式
+export-background = 背景
+export-use-preview-color = 预览颜色
+export-transparent = 透明
+menu-edit = 编辑
+menu-vim = 输入编辑器使用 Vim 按键
+menu-auto-close = 成对输入括号
+menu-rules = 替换规则…
+menu-backend = 转换后端
+menu-backend-hint = 插件是 PATH 中名为 typst2latex-backend-<name> 的程序
+menu-typst-style = Typst 风格
+menu-typst-style-hint = LaTeX → Typst 输出的写法，以便与所用的 Typst 代码一致
+typst-style-pandoc = 保持 pandoc 的写法
+typst-style-shorthands = 箭头与关系符
+typst-style-shorthand = 简写，如 -> 和 <=
+typst-style-explicit = 名称，如 arrow.r 和 lt.eq
+typst-style-symbols = 符号
+typst-style-names = 名称，如 alpha
+typst-style-unicode = 字符，如 α
+typst-style-spacing = 运算符
+typst-style-spaced = 两侧加空格，如 a + b
+typst-style-compact = 紧凑，如 a+b
+rules = 替换规则
+rules-input = 输入规则
+rules-input-hint = 每次转换前按从上到下的顺序应用于输入，例如展开简写
+rules-output = 输出规则
+rules-output-hint = 每次转换后按从上到下的顺序应用于输出
+hooks = 钩子
+hooks-hint = 用于规则做不到的处理的 Shell 命令。每个命令从标准输入读取文本，把新文本写到标准输出；转换方向在 TYPST2LATEX_DIRECTION 中
+hooks-pre = 转换前
+hooks-post = 转换后
+macros = 宏
+macros-hint = 粘贴文档导言区中的 \newcommand、\DeclareMathOperator 和 \def 定义；转换前会在 LaTeX 输入中展开
+theorems = 定理
+theorems-hint = 文档中的 Typst 定理、引理和证明块会转换为同名的 amsthm 环境；用 theorem = thm 这样的行重命名
+rules-find = 查找
+rules-replace = 替换为
+rules-enabled = 使用此规则
+rules-add = 添加规则
+menu-strip-wrappers = 去掉粘贴内容外层的公式标记
+strip-wrappers-hint = 转换 $ … $、$$ … $$、代码块和 #math.equation(…) 内部的内容，并将结果放回同样的标记中
+menu-primary-selection = 同时将转换结果放入主选区
+menu-insert = 插入
+menu-view = 视图
+menu-tectonic-preview = 使用 tectonic 编译预览
+menu-renderer = 预览渲染器
+renderer-builtin = MathJax 或 tectonic（内置）
+renderer-hint = 插件是 PATH 中名为 typst2latex-render-<name> 的程序，读入 LaTeX 并输出 SVG
+menu-validate = 检查输出能否用 TeX 编译
+menu-checkerboard = 在预览中显示透明区域
+menu-diagnostics = 诊断日志
+menu-mapping = 映射模式
+menu-mapping-hint = 悬停在记号上以高亮另一栏中按名称匹配的对应部分
+menu-diff = 与上一次转换对比
+menu-layout = 布局
+layout-stacked = 上下排列
+layout-side-by-side = 左右并排
+menu-wrap = 自动换行
+wrap-input = 输入自动换行
+wrap-output = 输出自动换行
+menu-learning = 学习模式
+learning-off = 关闭
+learning-comments = 在输出注释中说明
+learning-table = 在表格中说明
+menu-math-font = 数学字体
+math-font-default = MathJax（默认）
+math-font-hint = 其他字体使用 tectonic 排版。
+menu-preview-cache = 预览缓存
+preview-cache-usage = { $count } 个预览，{ $size } MB
+preview-cache-hint = 显示过的预览再次出现时无需重新渲染。超过此大小时丢弃最久未显示的预览。
+menu-editor-font = 编辑器字体
+font-proportional = 比例字体
+font-monospace = 等宽字体
+font-size = 字号
+line-spacing = 行距
+menu-language = 语言
+menu-symbols = 符号查询…
+menu-history = 历史记录…
+menu-batch = 批量表格…
+menu-command-palette = 命令面板…  Ctrl+P
+menu-help = 帮助
+menu-allow-network = 允许访问网络
+menu-allow-network-hint = 关闭后，typst2latex 不会连接互联网
+menu-check-updates = 启动时检查更新
+menu-check-updates-now = 立即检查更新
+menu-release-notes = 更新说明…
+menu-reference = Typst 数学速查…
+menu-usage = 使用统计…
+menu-onboarding = 入门向导…
+menu-load-example = 载入示例
+
+## Theme
+
+menu-theme = 主题
+theme-system = 跟随系统
+theme-light = 浅色
+theme-dark = 深色
+menu-customize-colors = 自定义颜色…
+theme-editor = 颜色
+color-accent = 强调色
+color-background = 背景
+color-text = 文字
+color-preview = 公式预览
+color-reset = 重置
+
+## Main window
+
+choose-math-block = 选择一个公式块
+detected-direction = （检测为 { $direction }）
+copy-target = 复制 { $target }
+copy-as = 复制为…
+copy-markdown = 行内或独立公式
+copy-markdown-table-cell = { $engine } 表格单元格
+markdown-sanitize = 针对此引擎转义
+markdown-sanitize-hint = 调整 { $engine } 会当作 Markdown 处理的字符（如 *、_ 和 |），使公式完整地交给数学渲染器
+copy-html-svg = HTML（内嵌 SVG）
+copy-html-katex = HTML（KaTeX）
+copy-html-attribute = HTML 属性（已转义）
+copy-html-script = HTML（MathJax script 标签）
+numbering = 编号
+numbering-hint = 复制按钮如何为有编号的文档包装 LaTeX
+units = 单位
+units-hint = 3 "m"/"s" 这类物理量在 LaTeX 中的写法
+units-as-is = 保持 pandoc 的写法
+units-siunitx = siunitx，如 \SI{3}{\meter\per\second}
+units-upright = 正体文本，如 3\,\mathrm{m}/\mathrm{s}
+numbers = 数字
+numbers-hint = LaTeX 中数字的写法，例如按期刊的要求
+numbers-decimal-comma = 小数逗号，如 1{,}5
+numbers-grouping = 千位之间加细空格，如 12\,345
+numbers-scientific = 科学记数法
+numbers-scientific-as-is = 保持原样
+numbers-scientific-times = 10 的幂，如 1.5 \times 10^{3}
+numbers-scientific-e = E 记数法，如 1.5\mathrm{e}{3}
+characters = 字符
+characters-hint = LaTeX 输出中非 ASCII 字符的写法
+characters-as-is = 保持转换结果
+characters-ascii = 仅 ASCII，使用 \alpha 等命令
+characters-unicode = Unicode 符号如 α，用于 unicode-math
+diagrams = 交换图
+diagrams-hint = 这个箭头矩阵看起来像交换图
+diagrams-matrix = 箭头矩阵
+diagrams-cd = amscd CD
+diagrams-tikz-cd = tikz-cd
+diagrams-warning = 请检查交换图，箭头可能需要手动调整
+numbering-as-is = 保持转换结果
+numbering-numbered = 编号（equation）
+numbering-unnumbered = 不编号（equation*，去掉 tag）
+numbering-tag = 按 Typst 编号格式添加 tag
+numbering-pattern = 格式
+delimiters = 定界符
+profile-none = 无配置
+profile-hint = 配置将输出语言、定界符、编号、替换规则和导出颜色按用途打包
+profile-name = 新配置名称
+profile-save = 保存
+profile-save-hint = 将当前设置保存为此名称的配置
+profile-update = 用当前设置更新“{ $name }”
+profile-delete = 删除“{ $name }”
+numbering-next = 下一个编号
+grouping-separate = 分开的公式块
+grouping-hint = 如何组合输入中的 { $count } 个公式
+prose-spans = 已转换 { $count } 处公式
+prose-hint = 输入是夹有公式的文字：只转换公式，文字保持原样
+prose-failed = { $count } 处公式无法转换，已保持原样：{ $error }
+clear = 清空
+tex-compiles = ✔ 可以用 TeX 编译
+tex-fails = 此 LaTeX 无法编译：{ $error }
+preview-rendering = 正在渲染…
+tex-checking = 正在用 TeX 检查…
+preview-downscaled = 公式过大，预览以较低分辨率显示。
+diff-none = 还没有可对比的上一次转换。
+learning-breakdown = 转换说明
+learning-nothing = 此公式没有需要说明的内容
+stats-characters = { $count } 个字符
+stats-commands = { $count } 个 LaTeX 命令
+stats-depth = 嵌套深度 { $depth }
+stats-width = 约 { $width } em 宽
+
+## Find and replace
+
+find-hint = 查找
+find-regex = 正则表达式
+find-matches = { $count } 处匹配
+find-invalid = 无效的模式
+find-show-replace = 替换…
+replace-hint = 替换为
+replace = 替换
+replace-all = 全部替换
+
+## Anki
+
+anki = Anki
+anki-deck = 牌组
+anki-note-type = 笔记类型
+anki-connect = AnkiConnect
+anki-image-front = 正面显示渲染图片
+anki-send = 发送到 Anki
+anki-sent = 已添加笔记到 Anki
+anki-failed = 发送到 Anki 失败
+
+## Accessibility
+
+announce-converted = 转换完成
+announce-failed = 转换失败：{ $error }
+preview-alt = 公式预览：{ $description }
+speech-copy = 复制
+
+## Status bar and diagnostics
+
+status-pandoc-missing = 未找到 pandoc
+pandoc-too-old = pandoc { $version } 无法读取 Typst，只能将 LaTeX 转换为 Typst。读取 Typst 需要 pandoc { $required } 或更高版本。
+pandoc-install = 安装新版 pandoc
+status-convert-time = 转换 { $ms } 毫秒
+status-render-time = 渲染 { $ms } 毫秒
+converting = 正在转换…
+conversion-cancel = 取消
+conversion-cancel-hint = 停止 pandoc（Esc）
+conversion-cancelled = 已取消转换
+retry = 重试
+diagnostics = 诊断
+diagnostics-copy = 复制诊断信息
+diagnostics-frames = 已绘制 { $count } 帧
+diagnostics-startup = 启动后 { $ms } 毫秒显示窗口
+
+## History
+
+history = 历史记录
+history-search = 搜索 Typst 或 LaTeX
+history-select-all = 选择显示的条目
+history-select-none = 取消选择
+history-export = 导出所选…
+history-empty = 暂无转换记录。复制或保存过的转换会保存在这里。
+history-restore = 点击载入编辑器
+
+## Batch table
+
+batch = 批量表格
+batch-paste-hint = 粘贴表达式，每行一个或以空行分隔
+batch-add-rows = 添加为行
+batch-add-row = 添加空行
+batch-summary = 共 { $rows } 行，{ $failed } 行失败
+batch-copy = 复制 LaTeX
+
+## Command palette
+
+palette-hint = 输入命令
+command-convert = 转换
+command-cancel-conversion = 取消转换
+command-undo = 撤销
+command-redo = 重做
+command-find = 查找…
+command-replace = 替换…
+command-swap-direction = 交换方向
+command-direction = 方向：{ $direction }
+command-target = 目标格式：{ $target }
+command-copy = 复制输出
+command-copy-as = 复制为 { $format }
+command-copy-speech = 复制朗读描述
+command-send-to-anki = 发送到 Anki
+command-open = 打开文件…
+command-save-output = 保存输出…
+command-open-image = 打开图片…
+command-paste-image = 粘贴图片
+command-export-standalone = 导出独立 .tex 文件…
+command-export-png = 导出 PNG…
+command-export-svg = 导出 SVG…
+command-clear = 清空
+command-tectonic-preview = 设置：使用 tectonic 编译预览
+command-validate = 设置：检查输出能否用 TeX 编译
+command-diagnostics = 视图：诊断日志
+command-mapping = 视图：映射模式
+command-diff = 视图：与上一次转换对比
+command-history = 视图：历史记录
+command-batch = 视图：批量表格
+command-usage = 帮助：使用统计
+command-symbols = 视图：符号查询
+command-reference = 帮助：Typst 数学速查
+command-insert-template = 插入：{ $template }
+command-load-example = 示例：{ $example }
+
+## Updates
+
+update-available = 新版本 { $version } 可用
+update-none = typst2latex 已是最新版本
+release-notes = 更新说明
+release-version = 版本 { $version }（当前为 { $current }）
+release-download = 从 GitHub 下载
+
+## Symbol lookup
+
+symbols = 符号查询
+symbols-search = 按 LaTeX 命令、Typst 名称或符号搜索
+symbols-none = 没有匹配的符号
+symbols-unsupported = StarMath 中不可用
+symbols-insert = 插入
+
+## Quick reference
+
+reference = Typst 数学速查
+reference-search = 按 Typst、LaTeX 或说明搜索
+reference-none = 没有匹配项
+reference-syntax = 语法
+reference-functions = 函数
+reference-symbols = 符号
+reference-superscript = 上标
+reference-subscript = 下标
+reference-group = 圆括号把上下标括成一组，转换后去掉
+reference-fraction = 分式
+reference-fraction-group = 分子或分母外的圆括号转换后去掉
+reference-product = 字母之间用空格隔开；"ab" 是一个名称
+reference-text = 引号中的正体文字
+reference-prime = 撇号
+reference-limits = 运算符下方的极限
+reference-align = 对齐点
+reference-linebreak = 换行
+reference-display = 美元符号内侧有空格时为行间公式
+
+## Templates
+
+template-limit = 极限
+template-derivative = 导数
+template-integral = 定积分
+template-sum = 带条件的求和
+template-fraction = 分式
+template-root = 根式
+template-piecewise = 分段函数
+template-matrix = 矩阵
+template-determinant = 行列式
+template-vector = 向量
+template-aligned = 对齐的等式
+template-arrows = 带标注的箭头
+
+## Examples
+
+example-quadratic = 求根公式
+example-euler = 欧拉恒等式
+example-gaussian = 高斯积分
+example-taylor = 泰勒级数
+example-derivation = 分步推导
+example-system = 方程组
+example-eigenvector = 特征向量
+example-bayes = 贝叶斯定理
+example-fourier = 傅里叶变换
+example-maxwell = 麦克斯韦方程组
+example-schrodinger = 薛定谔方程
+example-energy = 能量–动量关系
+
+## Usage statistics
+
+usage = 使用统计
+usage-local = 仅保存在本机，从不上传。
+usage-conversions = 转换次数
+usage-failures = 转换失败次数
+usage-convert-time = 平均转换时间
+usage-render-time = 平均预览时间
+usage-ms = { $ms } 毫秒
+usage-symbols = 最常用的命令
+usage-no-symbols = 尚无转换
+usage-reset = 重置
+
+## First run
+
+onboarding-title = 欢迎
+onboarding-step = 第 { $step } 步，共 { $count } 步
+onboarding-welcome = typst2latex 在 Typst 和 LaTeX 之间转换数学公式。
+onboarding-pandoc = 转换由 pandoc 完成，需要另行安装。
+onboarding-pandoc-probing = 正在查找 pandoc…
+onboarding-pandoc-found = 已找到 pandoc { $version }，一切就绪。
+onboarding-pandoc-missing = 未找到 pandoc。请安装并确保它在 PATH 中，然后重新启动 typst2latex。
+onboarding-pandoc-get = 获取 pandoc
+onboarding-profile = 输出用在哪里？
+onboarding-profile-hint = 配置为一种用途设定输出语言、定界符和编号。之后可在复制按钮旁更改。
+onboarding-delimiters = 复制 LaTeX 时添加定界符
+onboarding-delimiters-hint = 复制的 LaTeX 外包裹的符号，便于直接粘贴到文档中。
+onboarding-theme = 主题
+onboarding-back = 上一步
+onboarding-next = 下一步
+onboarding-finish = 完成
+onboarding-skip = 跳过
+
+## Crash report
+
+crash-title = typst2latex 崩溃了
+crash-saved = typst2latex 遇到错误，需要关闭。崩溃报告已保存到 { $path }，报告问题时请附上该文件。
+crash-not-saved = typst2latex 遇到错误，需要关闭。无法保存崩溃报告：{ $error }
+crash-background = 后台任务遇到错误
+crash-background-saved = 崩溃报告已保存到 { $path }，报告问题时请附上该文件。
+crash-background-not-saved = 无法保存崩溃报告：{ $error }
+
+## Errors
+
+explain-apply = 应用修正
+explain-unclosed-string = 第 { $line } 行开始的字符串没有闭合。
+explain-unknown = Typst 中没有名为 { $name } 的函数或符号。你是想写 { $suggestion } 吗？
+explain-unknown-text = Typst 中没有名为 { $name } 的函数或符号。公式中的文字需要加引号。
+explain-mat-comma = 矩阵第 { $row } 行的元素比其他行少。是否漏了逗号？
+brackets-unclosed = 第 { $line } 行第 { $column } 列的 { $bracket } 未闭合
+brackets-unexpected = 第 { $line } 行第 { $column } 列有多余的 { $bracket }
+brackets-mismatched = 第 { $line } 行第 { $column } 列的 { $bracket } 与 { $open } 不匹配
+error-nothing-converted = 尚未转换任何内容
+error-copy = 复制到剪贴板失败
+error-clipboard = 无法初始化剪贴板
+error-save-settings = 保存设置失败
+error-update-check = 检查更新失败：{ $error }
+error-save-history = 保存历史记录失败
+error-save-session = 保存会话失败
+error-read-file = 无法读取 { $path }
+error-write-file = 写入文件失败
+error-export-png = 渲染 PNG 失败：{ $error }
+error-recognize-image = 图片识别失败
+error-no-clipboard-image = 剪贴板中没有图片
+error-encode-clipboard-image = 无法编码剪贴板图片
+error-write-temporary-file = 写入临时文件失败
+error-render-anki-image = 无法为 Anki 渲染图片
+error-render-svg = 无法将 LaTeX 转换为 SVG
+error-texture = 无法将 SVG 转换为纹理
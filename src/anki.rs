@@ -0,0 +1,148 @@
+use crate::json::Value;
+use crate::output;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+pub struct Note {
+    pub deck: String,
+    pub model: String,
+    pub front: String,
+    pub back: String,
+    // PNG image attached to the front field.
+    pub picture: Option<Vec<u8>>,
+}
+
+// Add a note through the AnkiConnect HTTP API and return the new note id.
+pub fn add_note(url: &str, note: Note) -> Result<f64, String> {
+    let mut params = Value::object([
+        ("deckName", note.deck.as_str().into()),
+        ("modelName", note.model.as_str().into()),
+        (
+            "fields",
+            Value::object([
+                ("Front", note.front.as_str().into()),
+                ("Back", note.back.as_str().into()),
+            ]),
+        ),
+        ("options", Value::object([("allowDuplicate", false.into())])),
+        ("tags", Value::Array(vec!["typst2latex".into()])),
+    ]);
+    if let Some(png) = &note.picture {
+        let filename = format!(
+            "typst2latex-{}.png",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis())
+                .unwrap_or_default()
+        );
+        params.insert(
+            "picture",
+            Value::Array(vec![Value::object([
+                ("data", base64(png).into()),
+                ("filename", filename.into()),
+                ("fields", Value::Array(vec!["Front".into()])),
+            ])]),
+        );
+    }
+    let request = Value::object([
+        ("action", "addNote".into()),
+        ("version", 6.0.into()),
+        ("params", Value::object([("note", params)])),
+    ]);
+
+    let response = Value::parse(&post(url, &request.to_string())?)
+        .map_err(|_| "Invalid response from AnkiConnect")?;
+    match response.get("error") {
+        Some(Value::String(error)) => Err(error.clone()),
+        _ => response
+            .get("result")
+            .and_then(Value::as_f64)
+            .ok_or_else(|| "Invalid response from AnkiConnect".to_string()),
+    }
+}
+
+// Minimal HTTP/1.1 POST for talking to the local AnkiConnect server.
+fn post(url: &str, body: &str) -> Result<String, String> {
+    let address = url
+        .strip_prefix("http://")
+        .ok_or("Only http:// AnkiConnect URLs are supported")?;
+    let (host, path) = address.split_once('/').unwrap_or((address, ""));
+    let mut stream = TcpStream::connect(host)
+        .map_err(|_| "Failed to connect to AnkiConnect. Is Anki running?")?;
+    stream.set_read_timeout(Some(Duration::from_secs(10))).ok();
+    write!(
+        stream,
+        "POST /{} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        host,
+        body.len(),
+        body
+    )
+    .map_err(|_| "Failed to send request to AnkiConnect")?;
+
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|_| "Failed to read response from AnkiConnect")?;
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or("Invalid response from AnkiConnect")?;
+    if !head.starts_with("HTTP/1.1 200") && !head.starts_with("HTTP/1.0 200") {
+        return Err(format!(
+            "AnkiConnect returned {}",
+            head.lines().next().unwrap_or_default()
+        ));
+    }
+    Ok(body.to_string())
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// Anki fields are HTML, so LaTeX and Typst source must be escaped.
+pub fn escape_html(text: &str) -> String {
+    output::escape_html(text).replace('\n', "<br>")
+}
+
+// `escape_html` for math in `\( ... \)`, which MathJax can't find once a `<br>`
+// splits it across elements.
+pub fn escape_math(text: &str) -> String {
+    output::escape_html(text).replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn math_stays_on_one_line() {
+        assert_eq!(escape_math("a &< b \\\\\nc"), "a &amp;&lt; b \\\\ c");
+        assert_eq!(escape_html("a\nb"), "a<br>b");
+    }
+
+    #[test]
+    fn base64_pads() {
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"Man"), "TWFu");
+        assert_eq!(base64(b"M"), "TQ==");
+    }
+}
@@ -0,0 +1,3912 @@
+use crate::batch::Batch;
+use crate::convert::{convert, Direction};
+use crate::cursors::Cursors;
+use crate::find::Search;
+use crate::history::History;
+use crate::i18n::Language;
+use crate::onboarding::{Onboarding, Pandoc};
+use crate::output::{Delimiters, Environment, Grouping, Markdown, Numbering, Target};
+use crate::palette::{Command, Palette};
+use crate::profiles::Profile;
+use crate::reference::Reference;
+use crate::render_cache::{RenderCache, Rendered};
+use crate::settings::{Session, Settings};
+use crate::symbols::Lookup;
+use crate::theme::ThemeMode;
+use crate::toast::Toasts;
+use crate::undo::{Snapshot, UndoStack};
+use crate::usage::Usage;
+use crate::vim::Vim;
+use crate::{
+    anki, backend, backup, brackets, clipboard, convert, crash, diagnostics, diagrams, diff,
+    document, examples, explain, find, history, hooks, i18n, instance, learning, macros, mapping,
+    numbers, ocr, output, render_plugin, rules, settings, speech, stats, templates, tex, theme,
+    theorems, typst_style, unicode, units, update, vim,
+};
+use clipboard_rs::common::RustImage;
+use clipboard_rs::{Clipboard, ClipboardContext};
+use eframe::egui;
+use mathjax_svg;
+use resvg;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tiny_skia;
+use tiny_skia_path;
+use usvg;
+
+// Formulas render black, so their coverage is all in the alpha channel. Paint them
+// in `color` instead, leaving the pixels unmultiplied as egui expects them.
+fn tint_pixmap(pixmap: &mut tiny_skia::Pixmap, color: egui::Color32) {
+    for pixel in pixmap.data_mut().chunks_exact_mut(4) {
+        pixel[0] = color.r();
+        pixel[1] = color.g();
+        pixel[2] = color.b();
+    }
+}
+
+const RENDER_SCALE: f32 = 5.0;
+// Limits that keep huge formulas from failing to allocate or upload: the usual
+// maximum texture side, and 64 MiB of pixels.
+const MAX_PIXMAP_SIDE: f32 = 8192.0;
+const MAX_PIXMAP_PIXELS: f32 = 16_777_216.0;
+
+// The scale to render at, lowered from `RENDER_SCALE` for formulas that would get
+// too big.
+fn render_scale(size: usvg::Size) -> f32 {
+    let (width, height) = (size.width(), size.height());
+    RENDER_SCALE
+        .min(MAX_PIXMAP_SIDE / width)
+        .min(MAX_PIXMAP_SIDE / height)
+        .min((MAX_PIXMAP_PIXELS / (width * height)).sqrt())
+}
+
+// Rasterize the SVG, returning the scale it was rendered at.
+fn svg_to_pixmap(svg: &str) -> Result<(tiny_skia::Pixmap, f32), Box<dyn std::error::Error>> {
+    let tree = usvg::Tree::from_str(&svg, &usvg::Options::default())?;
+    let scale = render_scale(tree.size());
+    let width = tree.size().width() * scale;
+    let height = tree.size().height() * scale;
+    let mut pixmap =
+        tiny_skia::Pixmap::new(width as u32, height as u32).ok_or("Failed to create pixmap")?;
+    resvg::render(
+        &tree,
+        tiny_skia_path::Transform::from_scale(scale * 0.9, scale * 0.9),
+        &mut pixmap.as_mut(),
+    );
+    Ok((pixmap, scale))
+}
+
+// Rasterize a rendered SVG for the preview. Fails with the error of the formula, or
+// with nothing when only rasterizing failed.
+fn rasterize(svg: Result<String, String>) -> Result<Rendered, Option<String>> {
+    let svg = svg.map_err(Some)?;
+    let (pixmap, scale) = svg_to_pixmap(&svg).map_err(|err| {
+        diagnostics::log(format!("Failed to rasterize SVG: {}", err));
+        None
+    })?;
+    Ok(Rendered { svg, pixmap, scale })
+}
+
+// A PNG of the formula in `color` on `background`, or on a transparent background.
+// Unlike the texture, the pixels are premultiplied so that the edges blend when
+// composited.
+fn svg_to_png(
+    svg: &str,
+    color: egui::Color32,
+    background: Option<egui::Color32>,
+) -> Result<Vec<u8>, String> {
+    let (mut pixmap, _) = svg_to_pixmap(svg).map_err(|err| err.to_string())?;
+    for pixel in pixmap.data_mut().chunks_exact_mut(4) {
+        let alpha = pixel[3] as u16;
+        let blend = |fg: u8, bg: u8| ((fg as u16 * alpha + bg as u16 * (255 - alpha)) / 255) as u8;
+        match background {
+            Some(background) => {
+                pixel[0] = blend(color.r(), background.r());
+                pixel[1] = blend(color.g(), background.g());
+                pixel[2] = blend(color.b(), background.b());
+                pixel[3] = 255;
+            }
+            None => {
+                pixel[0] = blend(color.r(), 0);
+                pixel[1] = blend(color.g(), 0);
+                pixel[2] = blend(color.b(), 0);
+            }
+        }
+    }
+    pixmap.encode_png().map_err(|err| err.to_string())
+}
+
+// Light and dark gray squares filling `rect`, the usual backdrop for transparency.
+fn checkerboard(rect: egui::Rect) -> egui::Shape {
+    const SQUARE: f32 = 8.0;
+    let mut squares = vec![egui::Shape::rect_filled(
+        rect,
+        0.0,
+        egui::Color32::from_gray(200),
+    )];
+    let (columns, rows) = (
+        (rect.width() / SQUARE).ceil() as usize,
+        (rect.height() / SQUARE).ceil() as usize,
+    );
+    for row in 0..rows {
+        for column in (row % 2..columns).step_by(2) {
+            let min = rect.min + egui::vec2(column as f32, row as f32) * SQUARE;
+            let square = egui::Rect::from_min_size(min, egui::Vec2::splat(SQUARE));
+            squares.push(egui::Shape::rect_filled(
+                square.intersect(rect),
+                0.0,
+                egui::Color32::from_gray(150),
+            ));
+        }
+    }
+    egui::Shape::Vec(squares)
+}
+
+// The preview texture, and whether it had to be rendered at a reduced scale.
+fn svg_to_texture(
+    ctx: &egui::Context,
+    slot: &mut Option<egui::TextureHandle>,
+    svg: &str,
+    color: egui::Color32,
+) -> Result<(egui::TextureHandle, bool), Box<dyn std::error::Error>> {
+    let (pixmap, scale) = svg_to_pixmap(svg)?;
+    Ok((
+        pixmap_to_texture(ctx, slot, pixmap, color),
+        scale < RENDER_SCALE,
+    ))
+}
+
+// The texture in `slot` is updated in place, so that editing doesn't allocate a
+// new one on the GPU for every render.
+fn pixmap_to_texture(
+    ctx: &egui::Context,
+    slot: &mut Option<egui::TextureHandle>,
+    mut pixmap: tiny_skia::Pixmap,
+    color: egui::Color32,
+) -> egui::TextureHandle {
+    let (width, height) = (pixmap.width(), pixmap.height());
+    tint_pixmap(&mut pixmap, color);
+
+    let image =
+        egui::ColorImage::from_rgba_unmultiplied([width as usize, height as usize], pixmap.data());
+    let texture = match slot {
+        Some(texture) => {
+            texture.set(image, Default::default());
+            texture.clone()
+        }
+        None => slot
+            .insert(ctx.load_texture("latex_svg", image, Default::default()))
+            .clone(),
+    };
+    texture
+}
+
+// egui's bundled fonts have no CJK glyphs, so borrow a system font for Chinese.
+fn read_cjk_font() -> Option<Vec<u8>> {
+    const PATHS: [&str; 8] = [
+        "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+        "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+        "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+        "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+        "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+        "C:\\Windows\\Fonts\\msyh.ttc",
+        "/System/Library/Fonts/PingFang.ttc",
+        "/System/Library/Fonts/STHeiti Light.ttc",
+    ];
+    let data = PATHS.iter().find_map(|path| std::fs::read(path).ok());
+    if data.is_none() {
+        diagnostics::log("No CJK font found, Chinese text may not display");
+    }
+    data
+}
+
+fn install_cjk_font(ctx: &egui::Context, data: Vec<u8>) {
+    let mut fonts = egui::FontDefinitions::default();
+    fonts
+        .font_data
+        .insert("cjk".to_string(), egui::FontData::from_owned(data));
+    for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+        fonts
+            .families
+            .entry(family)
+            .or_default()
+            .push("cjk".to_string());
+    }
+    ctx.set_fonts(fonts);
+}
+
+// Lay out editor text in the configured font, with extra space between lines and
+// the `highlights` (byte ranges) shaded.
+fn layout_editor_text(
+    ui: &egui::Ui,
+    text: &str,
+    wrap_width: f32,
+    settings: &Settings,
+    highlights: &[std::ops::Range<usize>],
+) -> std::sync::Arc<egui::Galley> {
+    let family = match settings.editor_font.as_str() {
+        "monospace" => egui::FontFamily::Monospace,
+        _ => egui::FontFamily::Proportional,
+    };
+    let format = egui::TextFormat {
+        font_id: egui::FontId::new(settings.editor_font_size, family),
+        line_height: Some(settings.editor_font_size * settings.editor_line_spacing),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let highlight = egui::TextFormat {
+        background: ui.visuals().selection.bg_fill.gamma_multiply(0.6),
+        ..format.clone()
+    };
+    let mut job = egui::text::LayoutJob::default();
+    job.wrap.max_width = wrap_width;
+    let mut last = 0;
+    for range in highlights {
+        // Skip ranges that went stale while the text was being edited.
+        if range.start < last || text.get(range.clone()).is_none() {
+            continue;
+        }
+        job.append(&text[last..range.start], 0.0, format.clone());
+        job.append(&text[range.clone()], 0.0, highlight.clone());
+        last = range.end;
+    }
+    job.append(&text[last..], 0.0, format);
+    ui.fonts(|fonts| fonts.layout_job(job))
+}
+
+// Number the lines of an editor in the space left of it, marking the line a
+// conversion error points at.
+fn paint_gutter(
+    ui: &egui::Ui,
+    gutter: egui::Rect,
+    output: &egui::text_edit::TextEditOutput,
+    error_line: Option<usize>,
+) {
+    let painter = ui.painter();
+    let mut line = 1;
+    for (i, row) in output.galley.rows.iter().enumerate() {
+        // Wrapped rows continue the line above and get no number.
+        if i > 0 && !output.galley.rows[i - 1].ends_with_newline {
+            continue;
+        }
+        let y = output.galley_pos.y + row.rect.center().y;
+        let (text, color) = if error_line == Some(line) {
+            (format!("▸{}", line), ui.visuals().error_fg_color)
+        } else {
+            (line.to_string(), ui.visuals().weak_text_color())
+        };
+        painter.text(
+            egui::pos2(gutter.right() - 4.0, y),
+            egui::Align2::RIGHT_CENTER,
+            text,
+            egui::FontId::monospace(12.0),
+            color,
+        );
+        line += 1;
+    }
+}
+
+// A handle below a pane that resizes it when dragged. Returns true when a drag
+// ends, so that the new height can be saved.
+fn splitter(ui: &mut egui::Ui, height: &mut f32) -> bool {
+    let (rect, response) =
+        ui.allocate_exact_size(egui::vec2(ui.available_width(), 8.0), egui::Sense::drag());
+    let response = response.on_hover_cursor(egui::CursorIcon::ResizeVertical);
+    if response.dragged() {
+        *height = (*height + response.drag_delta().y).clamp(40.0, 1000.0);
+    }
+    let stroke = if response.hovered() || response.dragged() {
+        ui.visuals().widgets.hovered.fg_stroke
+    } else {
+        ui.visuals().widgets.noninteractive.bg_stroke
+    };
+    ui.painter().hline(
+        rect.x_range().shrink(rect.width() * 0.4),
+        rect.center().y,
+        stroke,
+    );
+    response.drag_stopped()
+}
+
+// The token under the mouse in an editor, for the mapping mode.
+fn hovered_token(
+    output: &egui::text_edit::TextEditOutput,
+    text: &str,
+    from_input: bool,
+) -> Option<mapping::Hover> {
+    let pos = output.response.hover_pos()?;
+    let cursor = output.galley.cursor_from_pos(pos - output.galley_pos);
+    let index = text
+        .char_indices()
+        .nth(cursor.ccursor.index)
+        .map_or(text.len(), |(i, _)| i);
+    let (range, name) = mapping::token_at(text, index)?;
+    Some(mapping::Hover {
+        from_input,
+        range,
+        name,
+    })
+}
+
+// Initial window size, wide enough for the panes of the chosen layout.
+fn window_size(settings: &Settings) -> [f32; 2] {
+    match settings.layout.as_str() {
+        "side-by-side" => [900.0, 485.0],
+        _ => [450.0, 485.0],
+    }
+}
+
+// What a conversion needs on its worker thread.
+struct Conversion {
+    source: String,
+    direction: Direction,
+    // Display equations of Typst input, converted one by one.
+    equations: Option<Vec<String>>,
+    // Prose with math spans in it, of which only the spans are converted.
+    prose: bool,
+    grouping: Grouping,
+    // Shell commands the input goes through before converting and the output after,
+    // or "" for none.
+    pre_hook: String,
+    post_hook: String,
+    // The user's LaTeX preamble, whose macros are expanded in LaTeX input.
+    macros: String,
+    // amsthm names for the theorem blocks of Typst documents.
+    theorems: String,
+}
+
+// The outcome of a conversion, with the gathered equations for the preview when
+// the output keeps them separate or LaTeX input with the user's macros expanded,
+// and the math spans of prose that failed.
+struct Converted {
+    result: Result<String, String>,
+    preview: Option<String>,
+    failed_spans: Vec<String>,
+    // The prose's math spans that did convert, counted in what the worker
+    // converted, after the input hook and the theorem environments.
+    converted_spans: usize,
+    // Whether pandoc was given something else than the source, line for line, so
+    // that the line of its error isn't one of the source's.
+    rewritten: bool,
+}
+
+impl Conversion {
+    fn run(mut self) -> Converted {
+        let original = self.source.clone();
+        if !self.pre_hook.is_empty() {
+            if let Err(err) = self.run_pre_hook() {
+                return Converted {
+                    result: Err(err),
+                    preview: None,
+                    failed_spans: Vec::new(),
+                    converted_spans: 0,
+                    rewritten: true,
+                };
+            }
+        }
+        let mut preview = None;
+        if self.direction == Direction::LatexToTypst {
+            let expanded = macros::expand(&self.source, &macros::parse(&self.macros));
+            if expanded != self.source {
+                self.source = expanded;
+                preview = Some(self.source.clone());
+            }
+        }
+        let mut failed_spans = Vec::new();
+        let mut converted_spans = 0;
+        // Equations and prose spans go to pandoc one by one.
+        let rewritten = self.equations.is_some()
+            || self.prose
+            || self.source != original
+            || convert::moves_lines(&self.source, self.direction);
+        let result = match self.equations {
+            Some(equations) => equations
+                .iter()
+                .map(|equation| convert(equation, Direction::TypstToLatex))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|latex| {
+                    // Separate blocks can't be previewed as one formula.
+                    if self.grouping == Grouping::Separate {
+                        preview = Some(Grouping::Gather.join(&latex));
+                    }
+                    self.grouping.join(&latex)
+                }),
+            None if self.prose => {
+                if self.direction != Direction::LatexToTypst {
+                    self.source = theorems::to_amsthm(&self.source, &self.theorems);
+                }
+                let (text, warnings) = document::convert_math_regions_with(
+                    &self.source,
+                    self.direction,
+                    |math, direction| {
+                        let converted = convert(math, direction);
+                        converted_spans += usize::from(converted.is_ok());
+                        converted
+                    },
+                );
+                failed_spans = warnings;
+                Ok(text)
+            }
+            None => convert(&self.source, self.direction),
+        };
+        let result = match result {
+            Ok(output) if !self.post_hook.is_empty() => {
+                hooks::run(&self.post_hook, &output, self.direction)
+            }
+            result => result,
+        };
+        // Spans of prose fail one by one when cancelled, rather than the whole.
+        let result = if convert::cancelled() {
+            Err(convert::CANCELLED.to_string())
+        } else {
+            result
+        };
+        Converted {
+            result,
+            preview,
+            failed_spans,
+            converted_spans,
+            rewritten,
+        }
+    }
+
+    // Put what's about to be converted through the input hook: each display
+    // equation, or the source.
+    fn run_pre_hook(&mut self) -> Result<(), String> {
+        if let Some(equations) = &mut self.equations {
+            for equation in equations.iter_mut() {
+                *equation = hooks::run(&self.pre_hook, equation, self.direction)?;
+            }
+        } else {
+            self.source = hooks::run(&self.pre_hook, &self.source, self.direction)?;
+        }
+        Ok(())
+    }
+}
+
+// A conversion running in the background, and what's needed to finish it.
+struct Pending {
+    rx: mpsc::Receiver<Converted>,
+    cancel: Arc<AtomicBool>,
+    started: std::time::Instant,
+    input: String,
+    source: String,
+    wrappers: Vec<document::Wrapper>,
+    prose: bool,
+    // Hand edits of the output to bring back afterwards, when undoing.
+    restore_output: Option<String>,
+}
+
+// A preview rendering in the background, and what's needed to finish it.
+struct PendingRender {
+    rx: mpsc::Receiver<Result<Rendered, Option<String>>>,
+    cancel: Arc<AtomicBool>,
+    // Where it goes in the render cache.
+    key: String,
+    started: std::time::Instant,
+}
+
+// How long a conversion runs before it can be cancelled, so that Escape keeps its
+// other uses while typing.
+const SLOW_CONVERSION: Duration = Duration::from_millis(300);
+
+// The least time between two renders of the preview.
+const RENDER_INTERVAL: Duration = Duration::from_millis(100);
+
+struct MyApp {
+    input: String,
+    output: String,
+    latex: String,
+    svg: Option<String>,
+    preview_error: Option<String>,
+    validation: Option<Result<(), String>>,
+    validation_rx: Option<mpsc::Receiver<Result<(), String>>>,
+    // Stops the running check's tectonic once the LaTeX it checks is outdated.
+    validation_cancel: Arc<AtomicBool>,
+    // Spoken description of the formula, generated in the background.
+    speech: Option<String>,
+    speech_rx: Option<mpsc::Receiver<String>>,
+    texture: Option<egui::TextureHandle>,
+    // Where `texture` is drawn, kept while the preview is hidden to be reused.
+    texture_slot: Option<egui::TextureHandle>,
+    previews: RenderCache,
+    // The preview is out of date and gets rendered by `render_if_due`.
+    render_pending: bool,
+    rendering: Option<PendingRender>,
+    last_render: Option<std::time::Instant>,
+    // Opened on first use, and again after a failure, since a clipboard that isn't
+    // ready at startup (common under Wayland) often is later.
+    clipboard: Option<ClipboardContext>,
+    clipboard_error: Option<String>,
+    // The last text put on the clipboard, handed off when quitting.
+    copied: Option<String>,
+    copy_enabled: bool,
+    direction: Direction,
+    resolved_direction: Direction,
+    target: Target,
+    settings: Settings,
+    // Math blocks of a loaded file, offered in a chooser.
+    math_choices: Vec<String>,
+    toasts: Toasts,
+    show_diagnostics: bool,
+    // Timings of the last conversion and preview render, for the status bar.
+    conversion_ms: Option<u128>,
+    render_ms: Option<u128>,
+    // Width of the rendered formula, for the statistics under the output.
+    width_em: Option<f32>,
+    // The formula was too large to render at full resolution.
+    preview_downscaled: bool,
+    palette: Palette,
+    show_theme_editor: bool,
+    show_rules: bool,
+    // Name typed in for saving the current settings as a profile.
+    profile_name: String,
+    search: Search,
+    undo: UndoStack,
+    // Input line of the last conversion error, marked in the gutter.
+    error_line: Option<usize>,
+    // The first bracket of the input that doesn't pair up, flagged under the editor.
+    unbalanced: Option<brackets::Unbalanced>,
+    // What probably went wrong with the last failed conversion.
+    hint: Option<explain::Hint>,
+    // The number the next copied equation gets with `\tag` numbering.
+    equation_number: usize,
+    // How the output puts together input of several display equations, and how
+    // many the last conversion had.
+    grouping: Grouping,
+    equation_count: usize,
+    // How many math spans were converted when the input was prose with math in it.
+    prose_spans: usize,
+    // Whether the last LaTeX output has a matrix of arrows, to offer writing it as
+    // a commutative diagram.
+    diagram: bool,
+    pending: Option<Pending>,
+    // Outcome of the last conversion, read out by screen readers.
+    announcement: String,
+    // Mapping mode: the token under the mouse and its counterparts are highlighted.
+    show_mapping: bool,
+    hover: Option<mapping::Hover>,
+    // The last successful output and the one before it, compared in the diff view.
+    show_diff: bool,
+    converted: String,
+    diff_base: Option<String>,
+    history: History,
+    batch: Batch,
+    usage: Usage,
+    onboarding: Onboarding,
+    symbols: Lookup,
+    reference: Reference,
+    // The backends to choose from, found when the Edit menu first shows them.
+    backends: Vec<String>,
+    // What each construct became, for learning mode.
+    notes: Vec<learning::Note>,
+    // Where the caret was last seen in the input, as a character index.
+    input_cursor: Option<usize>,
+    // Carets besides the editor's own, added with Alt+click or Ctrl+Alt+Up/Down.
+    cursors: Cursors,
+    vim: Vim,
+    // Whether the last frame was dark, to notice the system theme flipping.
+    dark_mode: bool,
+    // False when the installed pandoc predates the Typst reader, which leaves only
+    // LaTeX to Typst working.
+    pandoc_reads_typst: bool,
+    // Set while pandoc is looked for in the background.
+    pandoc_rx: Option<mpsc::Receiver<bool>>,
+    // From launch to the first frame.
+    startup_ms: Option<u128>,
+    // Requests from later launches, which hand over to this window.
+    instance_rx: Option<mpsc::Receiver<instance::Request>>,
+    // Set while the OCR command reads an image.
+    ocr_rx: Option<mpsc::Receiver<Result<String, String>>>,
+    // Set while a note is on its way to Anki.
+    anki_rx: Option<mpsc::Receiver<Result<(), String>>>,
+    update_rx: Option<mpsc::Receiver<Result<Option<update::Release>, String>>>,
+    // Whether the running check was asked for, and should report when there's
+    // nothing new.
+    update_manual: bool,
+    release: Option<update::Release>,
+    show_release_notes: bool,
+}
+
+impl MyApp {
+    // `cjk_font` is read in the background while the window comes up.
+    fn new(
+        ctx: &egui::Context,
+        request: instance::Request,
+        settings: Settings,
+        cjk_font: Option<JoinHandle<Option<Vec<u8>>>>,
+    ) -> Self {
+        let mut app = Self {
+            input: String::new(),
+            output: String::new(),
+            latex: String::new(),
+            svg: None,
+            preview_error: None,
+            validation: None,
+            validation_rx: None,
+            validation_cancel: Arc::new(AtomicBool::new(false)),
+            speech: None,
+            speech_rx: None,
+            texture: None,
+            texture_slot: None,
+            previews: RenderCache::default(),
+            render_pending: false,
+            rendering: None,
+            last_render: None,
+            clipboard: None,
+            clipboard_error: None,
+            copied: None,
+            copy_enabled: false,
+            direction: Direction::TypstToLatex,
+            resolved_direction: Direction::TypstToLatex,
+            target: Target::Latex,
+            settings,
+            math_choices: Vec::new(),
+            toasts: Toasts::default(),
+            show_diagnostics: false,
+            conversion_ms: None,
+            render_ms: None,
+            width_em: None,
+            preview_downscaled: false,
+            palette: Palette::default(),
+            show_theme_editor: false,
+            show_rules: false,
+            profile_name: String::new(),
+            search: Search::default(),
+            undo: UndoStack::default(),
+            error_line: None,
+            unbalanced: None,
+            hint: None,
+            equation_number: 1,
+            grouping: Grouping::default(),
+            equation_count: 0,
+            prose_spans: 0,
+            diagram: false,
+            pending: None,
+            announcement: String::new(),
+            show_mapping: false,
+            hover: None,
+            show_diff: false,
+            converted: String::new(),
+            diff_base: None,
+            history: History::load(),
+            batch: Batch::default(),
+            usage: Usage::load(),
+            onboarding: Onboarding::default(),
+            symbols: Lookup::default(),
+            reference: Reference::default(),
+            backends: Vec::new(),
+            notes: Vec::new(),
+            input_cursor: None,
+            cursors: Cursors::default(),
+            vim: Vim::default(),
+            dark_mode: ctx.style().visuals.dark_mode,
+            // Until the probe says otherwise.
+            pandoc_reads_typst: true,
+            pandoc_rx: None,
+            startup_ms: None,
+            instance_rx: None,
+            ocr_rx: None,
+            anki_rx: None,
+            update_rx: None,
+            update_manual: false,
+            release: None,
+            show_release_notes: false,
+        };
+        app.start_pandoc_probe(ctx);
+        if !app.settings.onboarded {
+            app.onboarding.start();
+        }
+        if let Err(err) = backend::select(&app.settings.backend) {
+            app.toasts.error(err);
+        }
+        if let Some(data) = cjk_font.and_then(|font| font.join().ok()).flatten() {
+            install_cjk_font(ctx, data);
+        }
+        theme::apply(ctx, &app.settings);
+        if let Some(session) = Session::load() {
+            app.input = session.input;
+            app.output = session.output;
+            app.latex = session.latex;
+            app.direction = session.direction;
+            app.target = session.target;
+            app.resolved_direction = app.direction.resolve(&app.input);
+            app.update_texture(ctx);
+        }
+        let repaint = ctx.clone();
+        match instance::listen(move || repaint.request_repaint()) {
+            Ok(rx) => app.instance_rx = Some(rx),
+            Err(err) => diagnostics::log(err),
+        }
+        app.handle_request(ctx, request);
+        if app.settings.check_updates {
+            app.start_update_check(ctx, false);
+        }
+        app
+    }
+
+    // Come to the front and convert what a launch passed along, if anything.
+    fn handle_request(&mut self, ctx: &egui::Context, request: instance::Request) {
+        ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+        ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+        let input = if request.paste {
+            self.clipboard()
+                .ok()
+                .and_then(|clipboard| clipboard.get_text().ok())
+        } else {
+            request.input
+        };
+        if let Some(input) = input.filter(|input| !input.trim().is_empty()) {
+            self.checkpoint();
+            self.input = input;
+            if let Some(direction) = request.direction {
+                self.direction = direction;
+            }
+            self.convert(ctx);
+        }
+    }
+
+    fn save_session(&mut self) {
+        let session = Session {
+            input: self.input.clone(),
+            output: self.output.clone(),
+            latex: self.latex.clone(),
+            direction: self.direction,
+            target: self.target,
+        };
+        if session.save().is_err() {
+            self.toasts.error(i18n::tr("error-save-session"));
+        }
+    }
+
+    // Start converting the input in the background, replacing and stopping any
+    // conversion still running. `finish_conversion` takes over when it's done.
+    fn convert(&mut self, ctx: &egui::Context) {
+        self.cancel_conversion();
+        self.texture = None;
+        self.copy_enabled = false;
+        // Shorthands expanded by the input rules, e.g. `RR` to `bb(R)`.
+        let input = if self.settings.input_rules_enabled {
+            rules::apply(&self.settings.input_rules, &self.input)
+        } else {
+            self.input.clone()
+        };
+        let equations = (self.direction.resolve(&input) != Direction::LatexToTypst)
+            .then(|| document::display_equations(&input))
+            .flatten();
+        // The input without what smart paste takes off, e.g. `$ ... $`.
+        let (source, wrappers) = match &equations {
+            None if self.settings.strip_wrappers => document::unwrap_math(&input),
+            _ => (input.as_str(), Vec::new()),
+        };
+        let source = source.to_string();
+        // Prose with math spans in it has only the spans converted.
+        let prose = match &equations {
+            None if wrappers.is_empty() => document::prose_math(&source),
+            _ => None,
+        };
+        self.resolved_direction = match (&equations, &prose) {
+            (Some(_), _) => Direction::TypstToLatex,
+            (None, Some(math)) => self.direction.resolve(math),
+            (None, None) => self.direction.resolve(&source),
+        };
+        let typst = self.resolved_direction != Direction::LatexToTypst;
+        self.unbalanced = match prose {
+            Some(_) => None,
+            None => brackets::unbalanced(&source, typst),
+        };
+        crash::record_input(&self.input);
+        self.equation_count = equations.as_ref().map_or(0, Vec::len);
+
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let job = Conversion {
+            source: source.clone(),
+            direction: self.resolved_direction,
+            equations,
+            prose: prose.is_some(),
+            grouping: self.grouping,
+            pre_hook: self.settings.pre_hook.trim().to_string(),
+            post_hook: self.settings.post_hook.trim().to_string(),
+            macros: self.settings.latex_macros.clone(),
+            theorems: self.settings.theorem_environments.clone(),
+        };
+        let flag = cancel.clone();
+        let repaint = ctx.clone();
+        std::thread::spawn(move || {
+            convert::cancel_on(flag);
+            if tx.send(job.run()).is_ok() {
+                repaint.request_repaint();
+            }
+        });
+        self.pending = Some(Pending {
+            rx,
+            cancel,
+            started: std::time::Instant::now(),
+            input,
+            source,
+            wrappers,
+            prose: prose.is_some(),
+            restore_output: None,
+        });
+        // Offer to cancel once the conversion turns out to be slow.
+        ctx.request_repaint_after(SLOW_CONVERSION);
+    }
+
+    // Stop the running conversion, if any, killing its pandoc. Returns whether there
+    // was one.
+    fn cancel_conversion(&mut self) -> bool {
+        match self.pending.take() {
+            Some(pending) => {
+                pending.cancel.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Cancel the running conversion on request, e.g. of a huge document pasted by
+    // mistake, leaving no output for the input.
+    fn stop_conversion(&mut self, ctx: &egui::Context) {
+        if !self.cancel_conversion() {
+            return;
+        }
+        self.output.clear();
+        self.latex.clear();
+        self.conversion_ms = None;
+        self.update_texture(ctx);
+        self.announcement = i18n::tr("conversion-cancelled").to_string();
+        self.toasts.info(i18n::tr("conversion-cancelled"));
+    }
+
+    // The running conversion, once it's been going long enough to offer cancelling.
+    fn slow_conversion(&self) -> Option<&Pending> {
+        self.pending
+            .as_ref()
+            .filter(|pending| pending.started.elapsed() >= SLOW_CONVERSION)
+    }
+
+    fn finish_conversion(&mut self, ctx: &egui::Context, pending: Pending, converted: Converted) {
+        let Pending {
+            started,
+            input,
+            source,
+            wrappers,
+            prose,
+            restore_output,
+            ..
+        } = pending;
+        let Converted {
+            result,
+            preview,
+            failed_spans,
+            converted_spans,
+            rewritten,
+        } = converted;
+        let typst = self.resolved_direction != Direction::LatexToTypst;
+        self.prose_spans = if prose { converted_spans } else { 0 };
+        if let Some(first) = failed_spans.first() {
+            for warning in &failed_spans {
+                diagnostics::log(format!("Math span not converted: {}", warning));
+            }
+            self.toasts.error(i18n::tr_args(
+                "prose-failed",
+                &[("count", &failed_spans.len().to_string()), ("error", first)],
+            ));
+        }
+        let ms = started.elapsed().as_millis();
+        self.conversion_ms = Some(ms);
+        let latex = match (&result, self.resolved_direction) {
+            (Ok(_), Direction::LatexToTypst) => Some(source.as_str()),
+            (Ok(latex), _) => Some(latex.as_str()),
+            (Err(_), _) => None,
+        };
+        self.usage.record_conversion(ms, latex);
+        // A stray bracket is the likelier culprit than where pandoc gave up. Lines
+        // count from where the source starts in the input, and aren't marked when the
+        // input rules rewrote it.
+        let offset = (input == self.input)
+            .then(|| input.find(source.as_str()))
+            .flatten()
+            .map(|start| input[..start].matches('\n').count());
+        self.error_line = result.as_ref().err().and_then(|err| {
+            let line = self
+                .unbalanced
+                .as_ref()
+                .map(|unbalanced| unbalanced.line)
+                .or_else(|| convert::error_line(err).filter(|_| !rewritten))?;
+            Some(line + offset?)
+        });
+        self.hint = result.as_ref().err().and_then(|err| {
+            let mut hint = explain::explain(&source, err, self.unbalanced.as_ref(), typst)?;
+            // Fix the input as pasted, wrapper and all. A fix to what the input rules
+            // made of it can't be carried back.
+            hint.fix = hint
+                .fix
+                .filter(|_| input == self.input)
+                .map(|fix| self.input.replacen(&source, &fix, 1));
+            Some(hint)
+        });
+        match result {
+            Ok(result) => {
+                // Typst in the style of the code it goes into.
+                let result = if self.resolved_direction == Direction::LatexToTypst && !prose {
+                    typst_style::restyle(
+                        &result,
+                        &self.settings.typst_shorthands,
+                        &self.settings.typst_symbols,
+                        &self.settings.typst_spacing,
+                    )
+                } else {
+                    result
+                };
+                // Keep the LaTeX side around for the preview and the copy variants.
+                let from_latex = self.resolved_direction == Direction::LatexToTypst;
+                self.diagram = false;
+                if prose {
+                    // The preview shows the spans' LaTeX gathered.
+                    let latex = if from_latex { &source } else { &result };
+                    let spans: Vec<String> = document::latex_math_regions(latex)
+                        .into_iter()
+                        .map(|region| latex[region.content].trim().to_string())
+                        .collect();
+                    self.latex = Grouping::Gather.join(&spans);
+                    self.output = result;
+                } else if from_latex {
+                    // MathJax doesn't know the user's macros either.
+                    self.latex = preview.unwrap_or_else(|| source.clone());
+                    self.output = result;
+                } else {
+                    let numbers = numbers::Style {
+                        decimal_comma: self.settings.decimal_comma,
+                        grouping: self.settings.digit_grouping,
+                        scientific: &self.settings.scientific,
+                    };
+                    let style = |latex: &str, units_style: &str| {
+                        numbers::restyle(&units::restyle(latex, units_style), &numbers)
+                    };
+                    // siunitx is for LaTeX documents. MathJax and the other targets
+                    // get upright units.
+                    let upright_units = if self.settings.units.is_empty() {
+                        ""
+                    } else {
+                        "upright"
+                    };
+                    let upright = style(&result, upright_units);
+                    self.diagram = self.target == Target::Latex && diagrams::is_diagram(&result);
+                    self.output = match self.target {
+                        Target::Latex => unicode::restyle(
+                            &diagrams::emit(
+                                &style(&result, &self.settings.units),
+                                &self.settings.diagrams,
+                            ),
+                            &self.settings.latex_characters,
+                        ),
+                        target => target.emit(&upright),
+                    };
+                    self.latex = match preview {
+                        Some(preview) => style(&preview, upright_units),
+                        None => upright,
+                    };
+                }
+                self.notes.clear();
+                if !self.settings.learning_mode.is_empty() && !prose {
+                    let typst = if from_latex { &self.output } else { &source };
+                    self.notes = learning::notes(typst, &self.latex);
+                }
+                if self.settings.learning_mode == "comments" && !prose {
+                    let comment = match self.target {
+                        _ if from_latex => "//",
+                        Target::Latex | Target::PlainTex | Target::Context => "%",
+                        Target::StarMath => "%%",
+                        Target::Eqn => ".\\\"",
+                    };
+                    self.output =
+                        learning::annotate(&self.output, &self.notes, comment, from_latex);
+                }
+                // The output rules see the output as it's copied, after the target
+                // and the restyling, to have the last word.
+                self.output = rules::apply(&self.settings.output_rules, &self.output);
+                if from_latex || self.target == Target::Latex {
+                    self.output =
+                        document::rewrap(&self.output, &wrappers, self.resolved_direction);
+                }
+                // Ready for middle-click paste, e.g. into a terminal editor.
+                if self.settings.primary_selection {
+                    if let Err(err) = clipboard::set_primary(&self.output) {
+                        self.toasts.error(err);
+                    }
+                }
+                if self.output != self.converted {
+                    let previous = std::mem::replace(&mut self.converted, self.output.clone());
+                    self.diff_base = Some(previous).filter(|previous| !previous.is_empty());
+                }
+                self.update_texture(ctx);
+                self.announcement = i18n::tr("announce-converted").to_string();
+            }
+            Err(err) => {
+                // A missing backend is no problem with the input, so call it out.
+                if err.starts_with("Failed to execute") {
+                    self.toasts.error(err.clone());
+                }
+                self.latex.clear();
+                self.announcement = i18n::tr_args("announce-failed", &[("error", &err)]);
+                self.output = format!("Error: {}", err);
+            }
+        }
+        if let Some(output) = restore_output {
+            self.restore_output(ctx, output);
+        }
+    }
+
+    fn clipboard(&mut self) -> Result<&ClipboardContext, String> {
+        if self.clipboard.is_none() {
+            match ClipboardContext::new() {
+                Ok(clipboard) => {
+                    self.clipboard = Some(clipboard);
+                    self.clipboard_error = None;
+                }
+                Err(err) => {
+                    diagnostics::log(format!("Failed to open clipboard: {}", err));
+                    self.clipboard_error = Some(err.to_string());
+                }
+            }
+        }
+        self.clipboard
+            .as_ref()
+            .ok_or_else(|| i18n::tr("error-clipboard").to_string())
+    }
+
+    // Keep what was copied available after quitting, unless something else has been
+    // copied since.
+    fn hand_off_clipboard(&mut self) {
+        let Some(copied) = self.copied.take() else {
+            return;
+        };
+        let current = self
+            .clipboard()
+            .ok()
+            .and_then(|clipboard| clipboard.get_text().ok());
+        if current.as_deref() == Some(copied.as_str()) {
+            if let Err(err) = clipboard::hand_off(&copied) {
+                diagnostics::log(err);
+            }
+        }
+    }
+
+    fn copy_to_clipboard(&mut self, text: String) {
+        match self
+            .clipboard()
+            .map(|clipboard| clipboard.set_text(text.clone()).is_ok())
+        {
+            Ok(true) => self.copied = Some(text),
+            Ok(false) => self.toasts.error(i18n::tr("error-copy")),
+            Err(err) => self.toasts.error(err),
+        }
+    }
+
+    // The Typst side of the conversion.
+    fn typst(&self) -> &str {
+        match self.resolved_direction {
+            Direction::LatexToTypst => &self.output,
+            _ => &self.input,
+        }
+    }
+
+    // Add the formula to Anki in the background, since AnkiConnect may take its time
+    // to answer. Fails at once when the image for the front can't be made.
+    fn send_to_anki(&mut self, ctx: &egui::Context) -> Result<(), String> {
+        let settings = &self.settings;
+        let latex = format!(r"\({}\)", anki::escape_math(&self.latex));
+        let picture = match &self.svg {
+            Some(svg) if settings.anki_image_front => Some(
+                svg_to_pixmap(svg)
+                    .ok()
+                    .and_then(|(pixmap, _)| pixmap.encode_png().ok())
+                    .ok_or(i18n::tr("error-render-anki-image"))?,
+            ),
+            _ => None,
+        };
+        let typst = anki::escape_html(self.typst());
+        let note = anki::Note {
+            deck: settings.anki_deck.clone(),
+            model: settings.anki_model.clone(),
+            front: if picture.is_some() {
+                String::new()
+            } else {
+                typst
+            },
+            back: latex,
+            picture,
+        };
+        let url = settings.anki_url.clone();
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            if tx.send(anki::add_note(&url, note).map(|_| ())).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+        self.anki_rx = Some(rx);
+        Ok(())
+    }
+
+    // Load a source file into the input. Files with several math blocks offer them
+    // in a chooser, and files without any are loaded whole.
+    fn load_file(&mut self, ctx: &egui::Context, path: &Path) -> Result<(), String> {
+        let text = std::fs::read_to_string(path).map_err(|_| {
+            i18n::tr_args("error-read-file", &[("path", &path.display().to_string())])
+        })?;
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let (mut blocks, direction) = document::math_blocks(&text, &extension);
+        self.checkpoint();
+        self.direction = direction;
+        match blocks.len() {
+            0 => self.input = text.trim().to_string(),
+            1 => self.input = blocks.remove(0),
+            _ => {
+                self.math_choices = blocks;
+                return Ok(());
+            }
+        }
+        self.convert(ctx);
+        Ok(())
+    }
+
+    fn open_file(&mut self, ctx: &egui::Context, path: &Path) {
+        match self.load_file(ctx, path) {
+            Ok(()) => {
+                let path = path.display().to_string();
+                let recent = &mut self.settings.recent_files;
+                recent.retain(|recent_path| *recent_path != path);
+                recent.insert(0, path);
+                recent.truncate(10);
+                if self.settings.save().is_err() {
+                    self.toasts.error(i18n::tr("error-save-settings"));
+                }
+            }
+            Err(err) => self.toasts.error(err),
+        }
+    }
+
+    fn save_output(&self) -> Result<(), String> {
+        let (name, extension) = match self.resolved_direction {
+            Direction::LatexToTypst => ("Typst", "typ"),
+            _ => ("LaTeX", "tex"),
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(name, &[extension])
+            .set_file_name(format!("formula.{}", extension))
+            .save_file()
+        else {
+            return Ok(());
+        };
+        std::fs::write(path, &self.output).map_err(|_| i18n::tr("error-write-file").to_string())
+    }
+
+    // Recognize a formula image with the OCR command in the background; the LaTeX
+    // is converted to Typst once it arrives. Temporary images are removed after.
+    fn load_image(&mut self, ctx: &egui::Context, path: &Path, temporary: bool) {
+        let command = self.settings.ocr_command.clone();
+        let path = path.to_path_buf();
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let result = ocr::recognize(&command, &path);
+            if temporary {
+                std::fs::remove_file(&path).ok();
+            }
+            if tx.send(result).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+        self.ocr_rx = Some(rx);
+    }
+
+    fn finish_ocr(&mut self, ctx: &egui::Context, result: Result<String, String>) {
+        match result {
+            Ok(latex) => {
+                self.checkpoint();
+                self.input = latex;
+                self.direction = Direction::LatexToTypst;
+                self.convert(ctx);
+            }
+            Err(err) => self
+                .toasts
+                .error_with_details(i18n::tr("error-recognize-image"), err),
+        }
+    }
+
+    fn paste_image(&mut self, ctx: &egui::Context) -> Result<(), String> {
+        let clipboard = self.clipboard()?;
+        let image = clipboard
+            .get_image()
+            .map_err(|_| i18n::tr("error-no-clipboard-image"))?;
+        let png = image
+            .to_png()
+            .map_err(|_| i18n::tr("error-encode-clipboard-image"))?;
+        let path = std::env::temp_dir().join(format!("typst2latex-ocr-{}.png", std::process::id()));
+        std::fs::write(&path, png.get_bytes())
+            .map_err(|_| i18n::tr("error-write-temporary-file"))?;
+        self.load_image(ctx, &path, true);
+        Ok(())
+    }
+
+    // Save history entries as a Markdown or CSV table, by the chosen extension.
+    fn export_history(&self, entries: &[history::Entry]) -> Result<(), String> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Markdown", &["md"])
+            .add_filter("CSV", &["csv"])
+            .set_file_name("formulas.md")
+            .save_file()
+        else {
+            return Ok(());
+        };
+        let table = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => history::csv(entries),
+            _ => history::markdown(entries),
+        };
+        std::fs::write(path, table).map_err(|_| i18n::tr("error-write-file").to_string())
+    }
+
+    // Bundle settings, rules, profiles and history into one file for another machine.
+    fn export_settings(&mut self) -> Result<(), String> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .set_file_name("typst2latex-settings.json")
+            .save_file()
+        else {
+            return Ok(());
+        };
+        // Usage is otherwise only saved on quitting.
+        self.settings.save()?;
+        self.usage.save()?;
+        backup::export(&path)
+    }
+
+    fn import_settings(&mut self, ctx: &egui::Context) -> Result<(), String> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON", &["json"])
+            .pick_file()
+        else {
+            return Ok(());
+        };
+        backup::import(&path)?;
+        self.settings = Settings::load();
+        self.history = History::load();
+        self.usage = Usage::load();
+        if let Some(language) = Language::from_name(&self.settings.language) {
+            self.set_language(ctx, language);
+        }
+        theme::apply(ctx, &self.settings);
+        self.toasts.info(i18n::tr("settings-imported"));
+        self.convert(ctx);
+        Ok(())
+    }
+
+    fn export_png(&self, ctx: &egui::Context) -> Result<(), String> {
+        let Some(svg) = &self.svg else {
+            return Ok(());
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("PNG", &["png"])
+            .set_file_name("formula.png")
+            .save_file()
+        else {
+            return Ok(());
+        };
+        let settings = &self.settings;
+        let color = theme::parse_color(&settings.export_foreground)
+            .unwrap_or_else(|| theme::preview_color(ctx, settings));
+        let background = theme::parse_color(&settings.export_background);
+        let png = svg_to_png(svg, color, background)
+            .map_err(|err| i18n::tr_args("error-export-png", &[("error", &err)]))?;
+        std::fs::write(path, png).map_err(|_| i18n::tr("error-write-file").to_string())
+    }
+
+    fn export_svg(&self) -> Result<(), String> {
+        let Some(svg) = &self.svg else {
+            return Ok(());
+        };
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("SVG", &["svg"])
+            .set_file_name("formula.svg")
+            .save_file()
+        else {
+            return Ok(());
+        };
+        std::fs::write(path, output::svg_file(svg))
+            .map_err(|_| i18n::tr("error-write-file").to_string())
+    }
+
+    fn export_standalone(&self) -> Result<(), String> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("LaTeX", &["tex"])
+            .set_file_name("formula.tex")
+            .save_file()
+        else {
+            return Ok(());
+        };
+        std::fs::write(
+            path,
+            tex::standalone_document(&self.latex, &self.settings.math_font),
+        )
+        .map_err(|_| i18n::tr("error-write-file").to_string())
+    }
+
+    fn set_language(&mut self, ctx: &egui::Context, language: Language) {
+        i18n::set_language(language);
+        if language == Language::Chinese {
+            if let Some(data) = read_cjk_font() {
+                install_cjk_font(ctx, data);
+            }
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::Title(
+            i18n::tr("app-title").to_string(),
+        ));
+        self.settings.language = language.name().to_string();
+        if self.settings.save().is_err() {
+            self.toasts.error(i18n::tr("error-save-settings"));
+        }
+    }
+
+    fn apply_theme(&mut self, ctx: &egui::Context) {
+        theme::apply(ctx, &self.settings);
+        if self.settings.save().is_err() {
+            self.toasts.error(i18n::tr("error-save-settings"));
+        }
+        // The preview is drawn in the theme's colors.
+        self.retint_preview(ctx);
+    }
+
+    // Redraw the cached SVG in the current preview color, without rendering the
+    // formula again.
+    fn retint_preview(&mut self, ctx: &egui::Context) {
+        let Some(svg) = &self.svg else {
+            return;
+        };
+        let color = theme::preview_color(ctx, &self.settings);
+        match svg_to_texture(ctx, &mut self.texture_slot, svg, color) {
+            Ok((texture, _)) => self.texture = Some(texture),
+            Err(err) => diagnostics::log(format!("Failed to rasterize SVG: {}", err)),
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            input: self.input.clone(),
+            output: self.output.clone(),
+            direction: self.direction,
+        }
+    }
+
+    // Remember the editor contents before changing them programmatically.
+    fn checkpoint(&mut self) {
+        let snapshot = self.snapshot();
+        self.undo.push(snapshot);
+    }
+
+    fn restore(&mut self, ctx: &egui::Context, snapshot: Snapshot) {
+        self.input = snapshot.input;
+        self.direction = snapshot.direction;
+        self.convert(ctx);
+        // Bring back hand edits of the output, too, once it's converted.
+        if let Some(pending) = &mut self.pending {
+            pending.restore_output = Some(snapshot.output);
+        }
+    }
+
+    fn restore_output(&mut self, ctx: &egui::Context, output: String) {
+        if self.output != output {
+            self.output = output;
+            if self.resolved_direction != Direction::LatexToTypst && self.target == Target::Latex {
+                self.latex = self.output.clone();
+            }
+            self.update_texture(ctx);
+        }
+    }
+
+    // Move the input editor's caret, e.g. after editing the text around it.
+    fn set_input_caret(&mut self, ctx: &egui::Context, editor_id: egui::Id, caret: usize) {
+        self.set_input_selection(ctx, editor_id, caret..caret);
+    }
+
+    fn set_input_selection(
+        &mut self,
+        ctx: &egui::Context,
+        editor_id: egui::Id,
+        selection: std::ops::Range<usize>,
+    ) {
+        let mut state = egui::TextEdit::load_state(ctx, editor_id).unwrap_or_default();
+        let range = egui::text::CCursorRange::two(
+            egui::text::CCursor::new(selection.start),
+            egui::text::CCursor::new(selection.end),
+        );
+        state.cursor.set_char_range(Some(range));
+        state.store(ctx, editor_id);
+        self.input_cursor = Some(selection.end);
+    }
+
+    fn insert_at_caret(&mut self, ctx: &egui::Context, text: &str) {
+        self.checkpoint();
+        let index = self
+            .input_cursor
+            .and_then(|cursor| self.input.char_indices().nth(cursor))
+            .map_or(self.input.len(), |(index, _)| index);
+        self.input.insert_str(index, text);
+        self.input_cursor = None;
+        self.convert(ctx);
+    }
+
+    // Put a template at the caret, or replace LaTeX input with it since templates are
+    // Typst.
+    fn insert_template(&mut self, ctx: &egui::Context, template: &templates::Template) {
+        if self.resolved_direction == Direction::LatexToTypst {
+            self.checkpoint();
+            self.input = template.typst.to_string();
+            self.direction = Direction::TypstToLatex;
+            self.convert(ctx);
+        } else {
+            self.insert_at_caret(ctx, template.typst);
+        }
+    }
+
+    fn set_backend(&mut self, ctx: &egui::Context, name: &str) {
+        if let Err(err) = backend::select(name) {
+            self.toasts.error(err);
+            return;
+        }
+        self.settings.backend = if name == backend::PANDOC {
+            String::new()
+        } else {
+            name.to_string()
+        };
+        if self.settings.save().is_err() {
+            self.toasts.error(i18n::tr("error-save-settings"));
+        }
+        self.convert(ctx);
+    }
+
+    // Replace the input with an example, which is Typst.
+    fn load_example(&mut self, ctx: &egui::Context, example: &examples::Example) {
+        self.checkpoint();
+        self.input = example.typst.to_string();
+        self.input_cursor = None;
+        if self.resolved_direction == Direction::LatexToTypst {
+            self.direction = Direction::TypstToLatex;
+        }
+        self.convert(ctx);
+    }
+
+    // Feed the result back in as input and convert the other way.
+    fn swap_direction(&mut self, ctx: &egui::Context) {
+        if !self.copy_enabled
+            || (self.resolved_direction == Direction::LatexToTypst && !self.pandoc_reads_typst)
+        {
+            return;
+        }
+        self.checkpoint();
+        if self.resolved_direction == Direction::LatexToTypst {
+            self.input = self.output.clone();
+            self.direction = Direction::TypstToLatex;
+        } else {
+            self.input = self.latex.clone();
+            self.direction = Direction::LatexToTypst;
+        }
+        self.convert(ctx);
+    }
+
+    fn run_command(&mut self, ctx: &egui::Context, command: Command) {
+        let needs_result = matches!(
+            command,
+            Command::Copy
+                | Command::CopyAs(_)
+                | Command::CopyMarkdown(..)
+                | Command::CopyHtmlSvg
+                | Command::CopyHtmlKatex
+                | Command::CopyHtmlAttribute
+                | Command::CopyHtmlScript
+                | Command::CopySpeech
+                | Command::SendToAnki
+                | Command::SaveOutput
+                | Command::ExportPng
+                | Command::ExportSvg
+        );
+        if needs_result && !self.copy_enabled {
+            self.toasts.error(i18n::tr("error-nothing-converted"));
+            return;
+        }
+        // Conversions worth keeping are the ones that get copied or saved.
+        if needs_result {
+            let entry = history::Entry {
+                typst: self.typst().to_string(),
+                latex: self.latex.clone(),
+            };
+            if self.history.record(entry).is_err() {
+                self.toasts.error(i18n::tr("error-save-history"));
+            }
+        }
+        match command {
+            Command::Convert => self.convert(ctx),
+            Command::CancelConversion => self.stop_conversion(ctx),
+            Command::Undo => {
+                if let Some(snapshot) = self.undo.undo(self.snapshot()) {
+                    self.restore(ctx, snapshot);
+                }
+            }
+            Command::Redo => {
+                if let Some(snapshot) = self.undo.redo(self.snapshot()) {
+                    self.restore(ctx, snapshot);
+                }
+            }
+            Command::Find => self.search.open(false),
+            Command::Replace => self.search.open(true),
+            Command::SwapDirection => self.swap_direction(ctx),
+            Command::SetDirection(direction) => {
+                self.direction = direction;
+                self.convert(ctx);
+            }
+            Command::SetTarget(target) => {
+                self.target = target;
+                self.convert(ctx);
+            }
+            Command::Copy => {
+                let numbering = Numbering::from_name(&self.settings.numbering);
+                let latex = self.resolved_direction != Direction::LatexToTypst
+                    && self.target == Target::Latex;
+                if latex && numbering != Numbering::AsIs {
+                    let pattern = &self.settings.numbering_pattern;
+                    let copied = numbering.apply(&self.output, pattern, self.equation_number);
+                    if numbering == Numbering::Tag {
+                        self.equation_number += 1;
+                    }
+                    self.copy_to_clipboard(copied);
+                } else if latex {
+                    let delimiters = Delimiters::from_name(&self.settings.delimiters);
+                    self.copy_to_clipboard(delimiters.wrap(&self.output));
+                } else {
+                    self.copy_to_clipboard(self.output.clone());
+                }
+            }
+            Command::CopyAs(environment) => self.copy_to_clipboard(environment.wrap(&self.latex)),
+            Command::CopyMarkdown(engine, in_table) => {
+                let sanitize = !self
+                    .settings
+                    .raw_markdown
+                    .iter()
+                    .any(|name| name == engine.name());
+                self.copy_to_clipboard(engine.wrap(&self.latex, in_table, sanitize))
+            }
+            Command::CopyHtmlSvg => {
+                let html = self
+                    .svg
+                    .as_ref()
+                    .map(|svg| output::html_svg(svg, &self.latex));
+                if let Some(html) = html {
+                    self.copy_to_clipboard(html);
+                }
+            }
+            Command::CopyHtmlKatex => self.copy_to_clipboard(output::html_katex(&self.latex)),
+            Command::CopyHtmlAttribute => {
+                self.copy_to_clipboard(output::html_attribute(&self.latex))
+            }
+            Command::CopyHtmlScript => self.copy_to_clipboard(output::html_script(&self.latex)),
+            Command::CopySpeech => {
+                if let Some(speech) = self.speech.clone() {
+                    self.copy_to_clipboard(speech);
+                }
+            }
+            Command::SendToAnki => {
+                if let Err(err) = self.send_to_anki(ctx) {
+                    self.toasts.error_with_details(i18n::tr("anki-failed"), err);
+                }
+            }
+            Command::Open => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Math sources", &document::SOURCE_EXTENSIONS)
+                    .add_filter("All files", &["*"])
+                    .pick_file()
+                {
+                    self.open_file(ctx, &path);
+                }
+            }
+            Command::SaveOutput => {
+                if let Err(err) = self.save_output() {
+                    self.toasts.error(err);
+                }
+            }
+            Command::OpenImage => {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Images", &ocr::IMAGE_EXTENSIONS)
+                    .pick_file()
+                {
+                    self.load_image(ctx, &path, false);
+                }
+            }
+            Command::PasteImage => {
+                if let Err(err) = self.paste_image(ctx) {
+                    self.toasts.error(err);
+                }
+            }
+            Command::ExportStandalone => {
+                if let Err(err) = self.export_standalone() {
+                    self.toasts.error(err);
+                }
+            }
+            Command::ExportPng => {
+                if let Err(err) = self.export_png(ctx) {
+                    self.toasts.error(err);
+                }
+            }
+            Command::ExportSvg => {
+                if let Err(err) = self.export_svg() {
+                    self.toasts.error(err);
+                }
+            }
+            Command::History => self.history.open = !self.history.open,
+            Command::Batch => self.batch.open = !self.batch.open,
+            Command::Usage => self.usage.open = !self.usage.open,
+            Command::Symbols => self.symbols.open = !self.symbols.open,
+            Command::Reference => self.reference.open = !self.reference.open,
+            Command::InsertTemplate(i) => self.insert_template(ctx, &templates::ALL[i]),
+            Command::LoadExample(i) => self.load_example(ctx, &examples::ALL[i]),
+            Command::Clear => {
+                self.checkpoint();
+                self.error_line = None;
+                self.input.clear();
+                self.output.clear();
+                self.latex.clear();
+                self.svg = None;
+                self.texture = None;
+                self.copy_enabled = false;
+            }
+            Command::ToggleTectonicPreview => {
+                self.settings.tectonic_preview = !self.settings.tectonic_preview;
+                if self.settings.save().is_err() {
+                    self.toasts.error(i18n::tr("error-save-settings"));
+                }
+                self.update_texture(ctx);
+            }
+            Command::ToggleValidation => {
+                self.settings.validate_with_tex = !self.settings.validate_with_tex;
+                if self.settings.save().is_err() {
+                    self.toasts.error(i18n::tr("error-save-settings"));
+                }
+                self.start_validation(ctx);
+            }
+            Command::ToggleDiagnostics => self.show_diagnostics = !self.show_diagnostics,
+            Command::ToggleMapping => self.show_mapping = !self.show_mapping,
+            Command::ToggleDiff => self.show_diff = !self.show_diff,
+        }
+    }
+
+    // The input editor with its find bar.
+    fn input_pane(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        ui.add_space(5.0);
+        let matches = self.search.matches(&self.input);
+        if self.search.open {
+            if let Some(action) = self.search.show(ui, &matches) {
+                let all = matches!(action, find::Action::ReplaceAll);
+                match self.search.replace(&self.input, all) {
+                    Ok(text) => {
+                        self.checkpoint();
+                        self.input = text;
+                        self.convert(ctx);
+                    }
+                    Err(err) => self.toasts.error(err),
+                }
+            }
+        }
+        let editor_id = egui::Id::new("input_editor");
+        let focused = ctx.memory(|memory| memory.has_focus(editor_id));
+        let mut typed = false;
+        if let Some(caret) = self
+            .input_cursor
+            .filter(|_| focused && self.settings.vim_mode)
+        {
+            let mut input = self.input.clone();
+            let mode = self.vim.mode;
+            match self.vim.handle(ctx, &mut input, caret) {
+                Some(vim::Action::Moved(caret)) => {
+                    // One undo step per stay in insert mode.
+                    if mode != self.vim.mode {
+                        self.checkpoint();
+                    }
+                    self.set_input_caret(ctx, editor_id, caret);
+                }
+                Some(vim::Action::Edited(caret)) => {
+                    self.checkpoint();
+                    self.input = input;
+                    self.set_input_caret(ctx, editor_id, caret);
+                    typed = true;
+                }
+                Some(vim::Action::Undo) => self.run_command(ctx, Command::Undo),
+                Some(vim::Action::Redo) => self.run_command(ctx, Command::Redo),
+                None => {}
+            }
+        }
+        let normal_mode = self.settings.vim_mode && self.vim.mode == vim::Mode::Normal;
+        if focused && self.settings.auto_close_brackets && self.cursors.is_empty() && !normal_mode {
+            let selection = egui::TextEdit::load_state(ctx, editor_id)
+                .and_then(|state| state.cursor.char_range())
+                .map(|range| {
+                    let (a, b) = (range.primary.index, range.secondary.index);
+                    a.min(b)..a.max(b)
+                });
+            if let Some(selection) = selection
+                .and_then(|selection| brackets::auto_close(ctx, &mut self.input, selection))
+            {
+                self.set_input_selection(ctx, editor_id, selection);
+                typed = true;
+            }
+        }
+        if let Some(primary) = self.input_cursor.filter(|_| focused) {
+            let alt_command = egui::Modifiers::ALT | egui::Modifiers::COMMAND;
+            for (key, down) in [(egui::Key::ArrowUp, false), (egui::Key::ArrowDown, true)] {
+                if ctx.input_mut(|i| i.consume_key(alt_command, key)) {
+                    self.cursors.add_vertical(&self.input, primary, down);
+                }
+            }
+            if !self.cursors.is_empty() {
+                if let Some(primary) = self.cursors.edit(ctx, &mut self.input, primary) {
+                    self.set_input_caret(ctx, editor_id, primary);
+                    typed = true;
+                }
+            }
+        }
+        let mut highlights = matches.unwrap_or_default();
+        if self.show_mapping {
+            highlights.extend(mapping::highlights(self.hover.as_ref(), &self.input, true));
+        }
+        if let Some(caret) = self.input_cursor.filter(|_| focused) {
+            let typst = self.resolved_direction != Direction::LatexToTypst;
+            highlights.extend(brackets::matching(&self.input, caret, typst));
+        }
+        highlights.sort_by_key(|range| range.start);
+        let settings = &self.settings;
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            layout_editor_text(ui, text, wrap_width, settings, &highlights)
+        };
+        // Without wrapping the content is unbounded horizontally and scrolls instead.
+        let input_response = egui::ScrollArea::new([!settings.input_wrap, true])
+            .id_salt("input_scroll_area")
+            .auto_shrink([false, false])
+            .max_height(settings.input_height)
+            .show(ui, |ui| {
+                ui.horizontal_top(|ui| {
+                    let digits = self.input.lines().count().max(1).to_string().len();
+                    let (gutter, _) = ui.allocate_exact_size(
+                        egui::vec2(digits as f32 * 9.0 + 14.0, 0.0),
+                        egui::Sense::hover(),
+                    );
+                    let output = egui::TextEdit::multiline(&mut self.input)
+                        .id(editor_id)
+                        .layouter(&mut layouter)
+                        .desired_rows(4)
+                        .desired_width(f32::INFINITY)
+                        .show(ui);
+                    let previous = self.input_cursor;
+                    if let Some(range) = output.cursor_range {
+                        self.input_cursor = Some(range.primary.ccursor.index);
+                    }
+                    // Alt+click leaves a caret behind, Alt+Shift+click a column of them,
+                    // a plain click drops them.
+                    if output.response.clicked() {
+                        let modifiers = ui.input(|i| i.modifiers);
+                        match (previous, self.input_cursor) {
+                            (Some(from), Some(to)) if modifiers.alt && modifiers.shift => {
+                                self.cursors.add_column(&self.input, from, to)
+                            }
+                            (Some(from), Some(to)) if modifiers.alt => self.cursors.add(from, to),
+                            _ => self.cursors.clear(),
+                        }
+                    }
+                    self.cursors.paint(ui, &output);
+                    if let Some(caret) = self.input_cursor.filter(|_| self.settings.vim_mode) {
+                        self.vim.paint(ui, &output, caret);
+                    }
+                    paint_gutter(ui, gutter, &output, self.error_line);
+                    let hover = hovered_token(&output, &self.input, true);
+                    (output.response, hover)
+                })
+                .inner
+            })
+            .inner;
+        let (input_response, hover) = input_response;
+        self.update_hover(&input_response, hover, true);
+        if let Some(unbalanced) = &self.unbalanced {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("⚠ {}", unbalanced.message()),
+            );
+        }
+        if splitter(ui, &mut self.settings.input_height) && self.settings.save().is_err() {
+            self.toasts.error(i18n::tr("error-save-settings"));
+        }
+
+        if typed || input_response.changed() {
+            self.convert(ctx);
+        }
+    }
+
+    // The copy actions and the output editor.
+    fn output_pane(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        if self.slow_conversion().is_some() {
+            ui.horizontal(|ui| {
+                ui.add_space(70.0);
+                ui.spinner();
+                ui.label(i18n::tr("converting"));
+                if ui
+                    .button(i18n::tr("conversion-cancel"))
+                    .on_hover_text(i18n::tr("conversion-cancel-hint"))
+                    .clicked()
+                {
+                    self.stop_conversion(ctx);
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.add_space(70.0);
+            let copy_label = match self.resolved_direction {
+                Direction::LatexToTypst => i18n::tr_args("copy-target", &[("target", "Typst")]),
+                _ => i18n::tr_args("copy-target", &[("target", self.target.label())]),
+            };
+            if ui
+                .add_enabled(self.copy_enabled, egui::Button::new(copy_label))
+                .clicked()
+            {
+                self.run_command(ctx, Command::Copy);
+            }
+            self.profile_menu(ctx, ui);
+            // Copy variants of the LaTeX side, e.g. wrapped in a display environment.
+            ui.add_enabled_ui(self.copy_enabled, |ui| {
+                ui.menu_button(i18n::tr("copy-as"), |ui| {
+                    for environment in Environment::ALL {
+                        if ui.button(environment.name()).clicked() {
+                            self.run_command(ctx, Command::CopyAs(environment));
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    for engine in Markdown::ALL {
+                        ui.menu_button(engine.label(), |ui| {
+                            if ui.button(i18n::tr("copy-markdown")).clicked() {
+                                self.run_command(ctx, Command::CopyMarkdown(engine, false));
+                                ui.close_menu();
+                            }
+                            let table_cell = i18n::tr_args(
+                                "copy-markdown-table-cell",
+                                &[("engine", engine.label())],
+                            );
+                            if ui.button(table_cell).clicked() {
+                                self.run_command(ctx, Command::CopyMarkdown(engine, true));
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            let raw = &mut self.settings.raw_markdown;
+                            let mut sanitize = !raw.iter().any(|name| name == engine.name());
+                            if ui
+                                .checkbox(&mut sanitize, i18n::tr("markdown-sanitize"))
+                                .on_hover_text(i18n::tr_args(
+                                    "markdown-sanitize-hint",
+                                    &[("engine", engine.label())],
+                                ))
+                                .changed()
+                            {
+                                raw.retain(|name| name != engine.name());
+                                if !sanitize {
+                                    raw.push(engine.name().to_string());
+                                }
+                                if self.settings.save().is_err() {
+                                    self.toasts.error(i18n::tr("error-save-settings"));
+                                }
+                            }
+                        });
+                    }
+                    ui.separator();
+                    for (label, command) in [
+                        (i18n::tr("copy-html-svg"), Command::CopyHtmlSvg),
+                        (i18n::tr("copy-html-katex"), Command::CopyHtmlKatex),
+                        (i18n::tr("copy-html-attribute"), Command::CopyHtmlAttribute),
+                        (i18n::tr("copy-html-script"), Command::CopyHtmlScript),
+                    ] {
+                        if ui.button(label).clicked() {
+                            self.run_command(ctx, command);
+                            ui.close_menu();
+                        }
+                    }
+                });
+            });
+            ui.menu_button(i18n::tr("numbering"), |ui| {
+                let mut changed = false;
+                for numbering in Numbering::ALL {
+                    let label = match numbering {
+                        Numbering::AsIs => i18n::tr("numbering-as-is"),
+                        Numbering::Numbered => i18n::tr("numbering-numbered"),
+                        Numbering::Unnumbered => i18n::tr("numbering-unnumbered"),
+                        Numbering::Tag => i18n::tr("numbering-tag"),
+                    };
+                    let selected = Numbering::from_name(&self.settings.numbering) == numbering;
+                    if ui.radio(selected, label).clicked() {
+                        self.settings.numbering = numbering.name().to_string();
+                        changed = true;
+                    }
+                }
+                if self.settings.numbering == Numbering::AsIs.name() {
+                    ui.separator();
+                    ui.label(i18n::tr("delimiters"));
+                    let current = Delimiters::from_name(&self.settings.delimiters);
+                    for delimiters in Delimiters::ALL {
+                        if ui
+                            .radio(current == delimiters, delimiters.label())
+                            .clicked()
+                        {
+                            self.settings.delimiters = delimiters.name().to_string();
+                            changed = true;
+                        }
+                    }
+                }
+                if self.settings.numbering == Numbering::Tag.name() {
+                    ui.separator();
+                    egui::Grid::new("numbering_settings").show(ui, |ui| {
+                        ui.label(i18n::tr("numbering-pattern"));
+                        changed |= ui
+                            .text_edit_singleline(&mut self.settings.numbering_pattern)
+                            .changed();
+                        ui.end_row();
+                        ui.label(i18n::tr("numbering-next"));
+                        ui.add(egui::DragValue::new(&mut self.equation_number).range(1..=9999));
+                        ui.end_row();
+                    });
+                    ui.weak(output::typst_number(
+                        &self.settings.numbering_pattern,
+                        self.equation_number,
+                    ));
+                }
+                if changed && self.settings.save().is_err() {
+                    self.toasts.error(i18n::tr("error-save-settings"));
+                }
+            })
+            .response
+            .on_hover_text(i18n::tr("numbering-hint"));
+            ui.menu_button(i18n::tr("units"), |ui| {
+                for (style, label) in [
+                    ("", "units-as-is"),
+                    ("siunitx", "units-siunitx"),
+                    ("upright", "units-upright"),
+                ] {
+                    if ui
+                        .radio(self.settings.units == style, i18n::tr(label))
+                        .clicked()
+                    {
+                        self.settings.units = style.to_string();
+                        if self.settings.save().is_err() {
+                            self.toasts.error(i18n::tr("error-save-settings"));
+                        }
+                        self.convert(ctx);
+                        ui.close_menu();
+                    }
+                }
+            })
+            .response
+            .on_hover_text(i18n::tr("units-hint"));
+            ui.menu_button(i18n::tr("numbers"), |ui| {
+                let mut changed = ui
+                    .checkbox(
+                        &mut self.settings.decimal_comma,
+                        i18n::tr("numbers-decimal-comma"),
+                    )
+                    .changed();
+                changed |= ui
+                    .checkbox(
+                        &mut self.settings.digit_grouping,
+                        i18n::tr("numbers-grouping"),
+                    )
+                    .changed();
+                ui.separator();
+                ui.label(i18n::tr("numbers-scientific"));
+                for (scientific, label) in [
+                    ("", "numbers-scientific-as-is"),
+                    ("times", "numbers-scientific-times"),
+                    ("e", "numbers-scientific-e"),
+                ] {
+                    if ui
+                        .radio(self.settings.scientific == scientific, i18n::tr(label))
+                        .clicked()
+                    {
+                        self.settings.scientific = scientific.to_string();
+                        changed = true;
+                    }
+                }
+                if changed {
+                    if self.settings.save().is_err() {
+                        self.toasts.error(i18n::tr("error-save-settings"));
+                    }
+                    self.convert(ctx);
+                }
+            })
+            .response
+            .on_hover_text(i18n::tr("numbers-hint"));
+            ui.menu_button(i18n::tr("characters"), |ui| {
+                for (style, label) in [
+                    ("", "characters-as-is"),
+                    ("ascii", "characters-ascii"),
+                    ("unicode", "characters-unicode"),
+                ] {
+                    if ui
+                        .radio(self.settings.latex_characters == style, i18n::tr(label))
+                        .clicked()
+                    {
+                        self.settings.latex_characters = style.to_string();
+                        if self.settings.save().is_err() {
+                            self.toasts.error(i18n::tr("error-save-settings"));
+                        }
+                        self.convert(ctx);
+                        ui.close_menu();
+                    }
+                }
+            })
+            .response
+            .on_hover_text(i18n::tr("characters-hint"));
+            if self.diagram {
+                ui.menu_button(i18n::tr("diagrams"), |ui| {
+                    for (style, label) in [
+                        ("", "diagrams-matrix"),
+                        ("cd", "diagrams-cd"),
+                        ("tikz-cd", "diagrams-tikz-cd"),
+                    ] {
+                        if ui
+                            .radio(self.settings.diagrams == style, i18n::tr(label))
+                            .clicked()
+                        {
+                            self.settings.diagrams = style.to_string();
+                            if self.settings.save().is_err() {
+                                self.toasts.error(i18n::tr("error-save-settings"));
+                            }
+                            self.convert(ctx);
+                            ui.close_menu();
+                        }
+                    }
+                })
+                .response
+                .on_hover_text(i18n::tr("diagrams-hint"));
+                if !self.settings.diagrams.is_empty() {
+                    ui.weak(i18n::tr("diagrams-warning"));
+                }
+            }
+            if self.prose_spans > 0 {
+                ui.weak(i18n::tr_args(
+                    "prose-spans",
+                    &[("count", &self.prose_spans.to_string())],
+                ))
+                .on_hover_text(i18n::tr("prose-hint"));
+            }
+            if self.equation_count > 1 {
+                let label = |grouping: Grouping| match grouping {
+                    Grouping::Separate => i18n::tr("grouping-separate"),
+                    Grouping::Gather => "gather*",
+                    Grouping::Align => "align*",
+                };
+                let mut grouping = self.grouping;
+                egui::ComboBox::from_id_salt("grouping")
+                    .selected_text(label(grouping))
+                    .show_ui(ui, |ui| {
+                        for option in Grouping::ALL {
+                            ui.selectable_value(&mut grouping, option, label(option));
+                        }
+                    })
+                    .response
+                    .on_hover_text(i18n::tr_args(
+                        "grouping-hint",
+                        &[("count", &self.equation_count.to_string())],
+                    ));
+                if grouping != self.grouping {
+                    self.grouping = grouping;
+                    self.convert(ctx);
+                }
+            }
+            ui.add_enabled_ui(self.copy_enabled, |ui| {
+                ui.menu_button(i18n::tr("anki"), |ui| {
+                    let mut changed = false;
+                    egui::Grid::new("anki_settings").show(ui, |ui| {
+                        ui.label(i18n::tr("anki-deck"));
+                        changed |= ui
+                            .text_edit_singleline(&mut self.settings.anki_deck)
+                            .changed();
+                        ui.end_row();
+                        ui.label(i18n::tr("anki-note-type"));
+                        changed |= ui
+                            .text_edit_singleline(&mut self.settings.anki_model)
+                            .changed();
+                        ui.end_row();
+                        ui.label(i18n::tr("anki-connect"));
+                        changed |= ui
+                            .text_edit_singleline(&mut self.settings.anki_url)
+                            .changed();
+                        ui.end_row();
+                    });
+                    changed |= ui
+                        .checkbox(
+                            &mut self.settings.anki_image_front,
+                            i18n::tr("anki-image-front"),
+                        )
+                        .changed();
+                    if changed && self.settings.save().is_err() {
+                        self.toasts.error(i18n::tr("error-save-settings"));
+                    }
+                    if ui.button(i18n::tr("anki-send")).clicked() {
+                        self.run_command(ctx, Command::SendToAnki);
+                        ui.close_menu();
+                    }
+                });
+            });
+            if ui.button(i18n::tr("clear")).clicked() {
+                self.run_command(ctx, Command::Clear);
+            }
+        });
+
+        ui.add_space(10.0);
+        if let Some(hint) = &self.hint {
+            let mut apply = None;
+            ui.horizontal_wrapped(|ui| {
+                ui.label("💡");
+                ui.label(&hint.message);
+                if let Some(fix) = &hint.fix {
+                    if ui.button(i18n::tr("explain-apply")).clicked() {
+                        apply = Some(fix.clone());
+                    }
+                }
+            });
+            if let Some(fix) = apply {
+                self.checkpoint();
+                self.input = fix;
+                self.convert(ctx);
+            }
+        }
+        let highlights = if self.show_mapping {
+            mapping::highlights(self.hover.as_ref(), &self.output, false)
+        } else {
+            Vec::new()
+        };
+        let settings = &self.settings;
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            layout_editor_text(ui, text, wrap_width, settings, &highlights)
+        };
+        let output_response = egui::ScrollArea::new([!settings.output_wrap, true])
+            .id_salt("output_scroll_area")
+            .auto_shrink([false, false])
+            .max_height(settings.output_height)
+            .show(ui, |ui| {
+                let output = egui::TextEdit::multiline(&mut self.output)
+                    .layouter(&mut layouter)
+                    .desired_rows(4)
+                    .desired_width(f32::INFINITY)
+                    .show(ui);
+                let hover = hovered_token(&output, &self.output, false);
+                (output.response, hover)
+            })
+            .inner;
+        let (output_response, hover) = output_response;
+        self.update_hover(&output_response, hover, false);
+        if !self.latex.is_empty() {
+            self.stats_line(ui);
+        }
+        if self.settings.learning_mode == "breakdown" && !self.latex.is_empty() {
+            self.breakdown(ui);
+        }
+        if self.show_diff {
+            self.diff_view(ui);
+        }
+        if splitter(ui, &mut self.settings.output_height) && self.settings.save().is_err() {
+            self.toasts.error(i18n::tr("error-save-settings"));
+        }
+        if output_response.changed() {
+            // Edits to LaTeX output feed straight back into the preview.
+            if self.resolved_direction != Direction::LatexToTypst && self.target == Target::Latex {
+                self.latex = self.output.clone();
+            }
+            self.update_texture(ctx);
+        }
+    }
+
+    // Choose, save and delete profiles, next to the copy button.
+    fn profile_menu(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        let mut chosen = None;
+        let mut changed = false;
+        let selected = match self.settings.profile.as_str() {
+            "" => i18n::tr("profile-none").to_string(),
+            name => name.to_string(),
+        };
+        egui::ComboBox::from_id_salt("profile")
+            .selected_text(selected)
+            .show_ui(ui, |ui| {
+                for profile in &self.settings.profiles {
+                    let current = profile.name == self.settings.profile;
+                    if ui.selectable_label(current, &profile.name).clicked() {
+                        chosen = Some(profile.clone());
+                    }
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.profile_name)
+                            .hint_text(i18n::tr("profile-name"))
+                            .desired_width(120.0),
+                    );
+                    let name = self.profile_name.trim().to_string();
+                    if ui
+                        .add_enabled(
+                            !name.is_empty(),
+                            egui::Button::new(i18n::tr("profile-save")),
+                        )
+                        .on_hover_text(i18n::tr("profile-save-hint"))
+                        .clicked()
+                    {
+                        let profile = Profile::capture(&name, &self.settings, self.target);
+                        match self.settings.profiles.iter_mut().find(|p| p.name == name) {
+                            Some(existing) => *existing = profile,
+                            None => self.settings.profiles.push(profile),
+                        }
+                        self.settings.profile = name;
+                        self.profile_name.clear();
+                        changed = true;
+                    }
+                });
+                let active = self.settings.profile.clone();
+                if !active.is_empty() {
+                    if ui
+                        .button(i18n::tr_args("profile-update", &[("name", &active)]))
+                        .clicked()
+                    {
+                        let profile = Profile::capture(&active, &self.settings, self.target);
+                        if let Some(existing) =
+                            self.settings.profiles.iter_mut().find(|p| p.name == active)
+                        {
+                            *existing = profile;
+                        }
+                        changed = true;
+                    }
+                    if ui
+                        .button(i18n::tr_args("profile-delete", &[("name", &active)]))
+                        .clicked()
+                    {
+                        self.settings.profiles.retain(|p| p.name != active);
+                        self.settings.profile.clear();
+                        changed = true;
+                    }
+                }
+            })
+            .response
+            .on_hover_text(i18n::tr("profile-hint"));
+        if let Some(profile) = chosen {
+            profile.apply(&mut self.settings, &mut self.target);
+            changed = true;
+            self.convert(ctx);
+        }
+        if changed && self.settings.save().is_err() {
+            self.toasts.error(i18n::tr("error-save-settings"));
+        }
+    }
+
+    fn stats_line(&self, ui: &mut egui::Ui) {
+        let stats = stats::latex(&self.latex);
+        let commands = stats
+            .command_names
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        ui.horizontal_wrapped(|ui| {
+            ui.weak(i18n::tr_args(
+                "stats-characters",
+                &[("count", &self.output.chars().count().to_string())],
+            ));
+            ui.weak(i18n::tr_args(
+                "stats-commands",
+                &[("count", &stats.commands.to_string())],
+            ))
+            .on_hover_text(commands);
+            ui.weak(i18n::tr_args(
+                "stats-depth",
+                &[("depth", &stats.depth.to_string())],
+            ));
+            if let Some(width) = self.width_em {
+                ui.weak(i18n::tr_args(
+                    "stats-width",
+                    &[("width", &format!("{:.1}", width))],
+                ));
+            }
+        });
+    }
+
+    // Learning mode's table of what each construct became.
+    fn breakdown(&self, ui: &mut egui::Ui) {
+        if self.notes.is_empty() {
+            ui.weak(i18n::tr("learning-nothing"));
+            return;
+        }
+        egui::CollapsingHeader::new(i18n::tr("learning-breakdown"))
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::Grid::new("learning_breakdown")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Typst");
+                        ui.strong("LaTeX");
+                        ui.end_row();
+                        for note in &self.notes {
+                            ui.monospace(&note.typst);
+                            ui.monospace(&note.latex);
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    // How the output changed with the last conversion: removed text struck through
+    // in red, added text in green.
+    fn diff_view(&self, ui: &mut egui::Ui) {
+        let Some(base) = &self.diff_base else {
+            ui.weak(i18n::tr("diff-none"));
+            return;
+        };
+        let font_id = egui::FontId::monospace(13.0);
+        let text_color = ui.visuals().text_color();
+        let mut job = egui::text::LayoutJob::default();
+        job.wrap.max_width = ui.available_width();
+        for (change, text) in diff::diff(base, &self.converted) {
+            let format = match change {
+                diff::Change::Same => egui::TextFormat::simple(font_id.clone(), text_color),
+                diff::Change::Removed => egui::TextFormat {
+                    background: egui::Color32::from_rgba_unmultiplied(220, 60, 60, 60),
+                    strikethrough: egui::Stroke::new(1.0, text_color),
+                    ..egui::TextFormat::simple(font_id.clone(), text_color)
+                },
+                diff::Change::Added => egui::TextFormat {
+                    background: egui::Color32::from_rgba_unmultiplied(60, 180, 60, 60),
+                    ..egui::TextFormat::simple(font_id.clone(), text_color)
+                },
+            };
+            job.append(&text, 0.0, format);
+        }
+        egui::ScrollArea::vertical()
+            .id_salt("diff_scroll_area")
+            .max_height(80.0)
+            .show(ui, |ui| ui.label(job));
+    }
+
+    // Track the hovered token of a pane, dropping it once the mouse leaves.
+    fn update_hover(
+        &mut self,
+        response: &egui::Response,
+        hover: Option<mapping::Hover>,
+        from_input: bool,
+    ) {
+        if !self.show_mapping {
+            self.hover = None;
+        } else if response.hovered() {
+            self.hover = hover;
+        } else if self
+            .hover
+            .as_ref()
+            .is_some_and(|hover| hover.from_input == from_input)
+        {
+            self.hover = None;
+        }
+    }
+
+    // Preview, validation and spoken description of the formula.
+    fn preview_pane(&mut self, ui: &mut egui::Ui) {
+        ui.add_space(10.0);
+        if let Some(err) = &self.preview_error {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        }
+        if self.preview_downscaled {
+            ui.colored_label(ui.visuals().warn_fg_color, i18n::tr("preview-downscaled"));
+        }
+        // Kept apart from conversion errors: the conversion worked, but a real
+        // TeX engine rejects the result.
+        match &self.validation {
+            Some(Ok(())) => {
+                ui.weak(i18n::tr("tex-compiles"));
+            }
+            Some(Err(err)) => {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    i18n::tr_args("tex-fails", &[("error", err)]),
+                );
+            }
+            None if self.validation_rx.is_some() => {
+                ui.weak(i18n::tr("tex-checking"));
+            }
+            None => {}
+        }
+        if self.rendering.is_some() {
+            ui.weak(i18n::tr("preview-rendering"));
+        }
+        if let Some(speech) = self.speech.clone() {
+            ui.horizontal_wrapped(|ui| {
+                if ui.small_button(i18n::tr("speech-copy")).clicked() {
+                    self.copy_to_clipboard(speech.clone());
+                }
+                ui.weak(speech);
+            });
+        }
+        if let Some(texture) = &self.texture {
+            let available_width = ui.available_width();
+            let size = texture.size_vec2();
+            let scale = f32::min(available_width / size.x * 0.9, 1.0);
+            let scaled_size = egui::vec2(size.x * scale, size.y * scale);
+            let checkerboard_shown = self.settings.preview_checkerboard;
+            ui.centered_and_justified(|ui| {
+                // Reserve a place below the image for the backdrop, which needs
+                // the image's final position.
+                let backdrop = ui.painter().add(egui::Shape::Noop);
+                // Focusable so that keyboard and screen reader users reach the
+                // preview, which is described in words or by its LaTeX source.
+                let response = ui.add(
+                    egui::Image::new((texture.id(), scaled_size))
+                        .sense(egui::Sense::focusable_noninteractive()),
+                );
+                if checkerboard_shown {
+                    ui.painter().set(backdrop, checkerboard(response.rect));
+                }
+                let description = self.speech.as_ref().unwrap_or(&self.latex);
+                response.widget_info(|| {
+                    egui::WidgetInfo::labeled(
+                        egui::WidgetType::Label,
+                        true,
+                        i18n::tr_args("preview-alt", &[("description", description)]),
+                    )
+                });
+            });
+        }
+    }
+
+    // Check the LaTeX side with a real TeX engine in the background, since compiling
+    // takes a while. A check of outdated LaTeX is stopped, tectonic and all.
+    fn start_validation(&mut self, ctx: &egui::Context) {
+        self.cancel_validation();
+        if !self.settings.validate_with_tex || self.latex.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let latex = self.latex.clone();
+        let flag = self.validation_cancel.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            convert::cancel_on(flag);
+            if tx.send(tex::validate(&latex)).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+        self.validation_rx = Some(rx);
+    }
+
+    fn cancel_validation(&mut self) {
+        self.validation = None;
+        self.validation_rx = None;
+        self.validation_cancel.store(true, Ordering::Relaxed);
+        self.validation_cancel = Arc::new(AtomicBool::new(false));
+    }
+
+    // Look for pandoc in the background: starting it takes long enough to hold up
+    // the window, which is meant to pop up at once.
+    fn start_pandoc_probe(&mut self, ctx: &egui::Context) {
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            match convert::pandoc_version() {
+                Some(version) => diagnostics::log(format!("Found pandoc {}", version)),
+                None => diagnostics::log("pandoc not found"),
+            }
+            if tx.send(convert::pandoc_reads_typst()).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+        self.pandoc_rx = Some(rx);
+    }
+
+    fn start_update_check(&mut self, ctx: &egui::Context, manual: bool) {
+        if self.settings.offline {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            if tx.send(update::check()).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+        self.update_rx = Some(rx);
+        self.update_manual = manual;
+    }
+
+    // Describe the formula in words in the background, since the speech engine
+    // takes a while to start.
+    fn start_speech(&mut self, ctx: &egui::Context) {
+        self.speech = None;
+        self.speech_rx = None;
+        if self.latex.is_empty() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let latex = self.latex.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            if tx.send(speech::describe(&latex)).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+        self.speech_rx = Some(rx);
+    }
+
+    // Render the preview of the output again. Rendering waits for the next frame and
+    // then for `RENDER_INTERVAL` since the last render, so that outputs replaced
+    // within that time, e.g. while typing, are never rendered.
+    fn update_texture(&mut self, ctx: &egui::Context) {
+        self.cancel_render();
+        self.cancel_validation();
+        self.svg = None;
+        self.preview_error = None;
+        self.texture = None;
+        self.copy_enabled = false;
+        self.render_ms = None;
+        self.width_em = None;
+        self.preview_downscaled = false;
+        self.speech = None;
+        self.speech_rx = None;
+        self.render_pending = !self.output.starts_with("Error") && !self.output.is_empty();
+        if self.render_pending {
+            ctx.request_repaint();
+        }
+    }
+
+    // Render the preview now if one is waiting and it's been long enough.
+    fn render_if_due(&mut self, ctx: &egui::Context) {
+        if !self.render_pending {
+            return;
+        }
+        let wait = self.last_render.map_or(Duration::ZERO, |last| {
+            RENDER_INTERVAL.saturating_sub(last.elapsed())
+        });
+        if !wait.is_zero() {
+            ctx.request_repaint_after(wait);
+            return;
+        }
+        self.render_pending = false;
+        self.last_render = Some(std::time::Instant::now());
+        self.render_preview(ctx);
+    }
+
+    // Render the LaTeX side to an SVG and rasterize it. A render plugin, when one is
+    // chosen, takes over from both tectonic and MathJax. tectonic and the plugins
+    // take seconds, so they run in the background and are killed once the preview
+    // is replaced; MathJax is quick, and keeps to the V8 instance of this thread.
+    fn render_preview(&mut self, ctx: &egui::Context) {
+        self.start_validation(ctx);
+        self.start_speech(ctx);
+        // Compiling with a real TeX engine catches input that MathJax tolerates, and
+        // is the only way to typeset in a chosen math font.
+        let tex = self.settings.tectonic_preview || !self.settings.math_font.is_empty();
+        let key = format!(
+            "{}\n{}\n{}\n{}",
+            self.settings.render_backend, tex, self.settings.math_font, self.latex
+        );
+        let started = std::time::Instant::now();
+        if let Some(rendered) = self.previews.get(&key).cloned() {
+            self.show_render(ctx, started, Ok(rendered));
+            return;
+        }
+        if self.settings.render_backend.is_empty() && !tex {
+            let svg = mathjax_svg::convert_to_svg(&self.latex)
+                .map_err(|_| i18n::tr("error-render-svg").to_string());
+            self.finish_render(ctx, key, started, rasterize(svg));
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        let backend = self.settings.render_backend.clone();
+        let math_font = self.settings.math_font.clone();
+        let latex = self.latex.clone();
+        let repaint = ctx.clone();
+        std::thread::spawn(move || {
+            convert::cancel_on(flag);
+            let svg = if backend.is_empty() {
+                tex::compile_to_svg(&latex, &math_font)
+            } else {
+                render_plugin::render(&backend, &latex)
+            };
+            if tx.send(rasterize(svg)).is_ok() {
+                repaint.request_repaint();
+            }
+        });
+        self.rendering = Some(PendingRender {
+            rx,
+            cancel,
+            key,
+            started,
+        });
+    }
+
+    // Stop the preview rendering in the background, if any, killing tectonic or the
+    // render plugin.
+    fn cancel_render(&mut self) {
+        if let Some(rendering) = self.rendering.take() {
+            rendering.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn finish_render(
+        &mut self,
+        ctx: &egui::Context,
+        key: String,
+        started: std::time::Instant,
+        rendered: Result<Rendered, Option<String>>,
+    ) {
+        if let Ok(rendered) = &rendered {
+            let budget = self.preview_cache_budget();
+            self.previews.insert(key, rendered.clone(), budget);
+        }
+        self.show_render(ctx, started, rendered);
+    }
+
+    fn show_render(
+        &mut self,
+        ctx: &egui::Context,
+        started: std::time::Instant,
+        rendered: Result<Rendered, Option<String>>,
+    ) {
+        match rendered {
+            Ok(rendered) => {
+                self.width_em = stats::width_em(&rendered.svg);
+                self.svg = Some(rendered.svg);
+                let color = theme::preview_color(ctx, &self.settings);
+                let downscaled = rendered.scale < RENDER_SCALE;
+                if downscaled {
+                    diagnostics::log("Preview too large, rendered at reduced scale");
+                }
+                self.preview_downscaled = downscaled;
+                let texture =
+                    pixmap_to_texture(ctx, &mut self.texture_slot, rendered.pixmap, color);
+                let elapsed = started.elapsed().as_millis();
+                diagnostics::log(format!("Rendered preview in {} ms", elapsed));
+                self.render_ms = Some(elapsed);
+                self.usage.record_render(elapsed);
+                self.texture = Some(texture);
+                self.copy_enabled = true;
+            }
+            Err(None) => self.toasts.error(i18n::tr("error-texture")),
+            Err(Some(err)) => {
+                diagnostics::log(format!("Render error: {}", err));
+                self.announcement = i18n::tr_args("announce-failed", &[("error", &err)]);
+                self.preview_error = Some(err);
+            }
+        }
+    }
+
+    fn preview_cache_budget(&self) -> usize {
+        (self.settings.preview_cache_mb.max(0.0) * 1024.0 * 1024.0) as usize
+    }
+}
+
+impl eframe::App for MyApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Following the system theme, the visuals change under us when the OS
+        // switches between light and dark.
+        let dark_mode = ctx.style().visuals.dark_mode;
+        if dark_mode != self.dark_mode {
+            self.dark_mode = dark_mode;
+            self.retint_preview(ctx);
+        }
+        // A blinking cursor repaints twice a second, so stop it while the window is
+        // in the background; otherwise frames only follow input and finished work.
+        let focused = ctx.input(|i| i.focused);
+        if ctx.style().visuals.text_cursor.blink != focused {
+            ctx.all_styles_mut(|style| style.visuals.text_cursor.blink = focused);
+        }
+        if let Some(result) = self
+            .validation_rx
+            .as_ref()
+            .and_then(|rx| rx.try_recv().ok())
+        {
+            self.validation = Some(result);
+            self.validation_rx = None;
+        }
+        if self.startup_ms.is_none() {
+            let ms = diagnostics::since_start().as_millis();
+            diagnostics::log(format!("First frame {} ms after launch", ms));
+            self.startup_ms = Some(ms);
+        }
+        if let Some(reads_typst) = self.pandoc_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.pandoc_rx = None;
+            self.pandoc_reads_typst = reads_typst;
+            if !reads_typst {
+                self.direction = Direction::LatexToTypst;
+                self.resolved_direction = Direction::LatexToTypst;
+            }
+        }
+        if let Some(speech) = self.speech_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.speech = Some(speech);
+            self.speech_rx = None;
+        }
+        if let Some(result) = self.update_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.update_rx = None;
+            match result {
+                Ok(Some(release)) => {
+                    self.toasts.info(i18n::tr_args(
+                        "update-available",
+                        &[("version", &release.version)],
+                    ));
+                    self.release = Some(release);
+                }
+                Ok(None) if self.update_manual => self.toasts.info(i18n::tr("update-none")),
+                Ok(None) => {}
+                Err(err) if self.update_manual => self
+                    .toasts
+                    .error(i18n::tr_args("error-update-check", &[("error", &err)])),
+                Err(err) => diagnostics::log(format!("Update check failed: {}", err)),
+            }
+        }
+        let rendered = self
+            .rendering
+            .as_ref()
+            .and_then(|rendering| rendering.rx.try_recv().ok());
+        if let Some(rendered) = rendered {
+            if let Some(rendering) = self.rendering.take() {
+                self.finish_render(ctx, rendering.key, rendering.started, rendered);
+            }
+        }
+        if let Some(description) = crash::take_pending() {
+            self.toasts
+                .error_with_details(i18n::tr("crash-background"), description);
+        }
+        if let Some(result) = self.ocr_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.ocr_rx = None;
+            self.finish_ocr(ctx, result);
+        }
+        if let Some(result) = self.anki_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.anki_rx = None;
+            match result {
+                Ok(()) => self.toasts.info(i18n::tr("anki-sent")),
+                Err(err) => self.toasts.error_with_details(i18n::tr("anki-failed"), err),
+            }
+        }
+        let converted = self
+            .pending
+            .as_ref()
+            .and_then(|pending| pending.rx.try_recv().ok());
+        if let Some(converted) = converted {
+            if let Some(pending) = self.pending.take() {
+                self.finish_conversion(ctx, pending, converted);
+            }
+        }
+        let requests: Vec<instance::Request> = self
+            .instance_rx
+            .iter()
+            .flat_map(|rx| rx.try_iter())
+            .collect();
+        for request in requests {
+            self.handle_request(ctx, request);
+        }
+        self.render_if_due(ctx);
+
+        if ctx.input(|i| i.viewport().close_requested()) {
+            self.cancel_conversion();
+            self.save_session();
+            if let Err(err) = self.usage.save() {
+                diagnostics::log(err);
+            }
+            self.hand_off_clipboard();
+        }
+
+        let dropped: Vec<PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect()
+        });
+        for path in dropped {
+            let extension = path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if ocr::IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+                self.load_image(ctx, &path, false);
+            } else if document::SOURCE_EXTENSIONS.contains(&extension.as_str()) {
+                self.open_file(ctx, &path);
+            }
+        }
+
+        if !self.math_choices.is_empty() {
+            let mut open = true;
+            let mut chosen = None;
+            egui::Window::new(i18n::tr("choose-math-block"))
+                .open(&mut open)
+                .collapsible(false)
+                .vscroll(true)
+                .show(ctx, |ui| {
+                    for (i, block) in self.math_choices.iter().enumerate() {
+                        let mut label: String = block.chars().take(60).collect();
+                        if label.len() < block.len() {
+                            label.push('…');
+                        }
+                        if ui.button(label.replace('\n', " ")).clicked() {
+                            chosen = Some(i);
+                        }
+                    }
+                });
+            if let Some(i) = chosen {
+                self.input = self.math_choices.swap_remove(i);
+                self.convert(ctx);
+            }
+            if !open || chosen.is_some() {
+                self.math_choices.clear();
+            }
+        }
+
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::P)) {
+            self.palette.toggle();
+        }
+        if let Some(command) = self.palette.show(ctx) {
+            self.run_command(ctx, command);
+        }
+        match self.history.show(ctx) {
+            Some(history::Action::Restore(entry)) => {
+                self.checkpoint();
+                self.input = entry.typst;
+                self.direction = Direction::TypstToLatex;
+                self.convert(ctx);
+            }
+            Some(history::Action::Export(entries)) => {
+                if let Err(err) = self.export_history(&entries) {
+                    self.toasts.error(err);
+                }
+            }
+            None => {}
+        }
+        self.usage.show(ctx);
+        if self.onboarding.open {
+            let pandoc = if self.pandoc_rx.is_some() {
+                Pandoc::Probing
+            } else {
+                match convert::pandoc_version() {
+                    Some(version) => Pandoc::Found(version, self.pandoc_reads_typst),
+                    None => Pandoc::Missing,
+                }
+            };
+            let target = self.target;
+            if self
+                .onboarding
+                .show(ctx, &mut self.settings, &mut self.target, pandoc)
+            {
+                if self.settings.save().is_err() {
+                    self.toasts.error(i18n::tr("error-save-settings"));
+                }
+                if self.target != target {
+                    self.convert(ctx);
+                }
+            }
+        }
+        if let Some(symbol) = self.symbols.show(ctx) {
+            // In the input's own syntax, with a space so it doesn't run into what
+            // follows.
+            let text = match self.resolved_direction {
+                Direction::LatexToTypst => format!("\\{} ", symbol.latex),
+                _ => format!("{} ", symbol.typst),
+            };
+            self.insert_at_caret(ctx, &text);
+        }
+        self.reference.show(ctx);
+        if let Some(latex) = self.batch.show(ctx) {
+            self.copy_to_clipboard(latex);
+        }
+        // While an editor has focus, Ctrl+Z is its own undo.
+        if !ctx.wants_keyboard_input() {
+            let shift_command = egui::Modifiers::COMMAND | egui::Modifiers::SHIFT;
+            if ctx.input_mut(|i| i.consume_key(shift_command, egui::Key::Z)) {
+                self.run_command(ctx, Command::Redo);
+            } else if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z)) {
+                self.run_command(ctx, Command::Undo);
+            }
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::F)) {
+            self.search.open(false);
+        }
+        if ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::H)) {
+            self.search.open(true);
+        }
+        if self.slow_conversion().is_some()
+            && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape))
+        {
+            self.stop_conversion(ctx);
+        }
+        if self.search.open
+            && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Escape))
+        {
+            self.search.open = false;
+        }
+
+        if let Some(release) = self.release.as_ref().filter(|_| self.show_release_notes) {
+            egui::Window::new(i18n::tr("release-notes"))
+                .open(&mut self.show_release_notes)
+                .default_width(420.0)
+                .show(ctx, |ui| {
+                    ui.heading(i18n::tr_args(
+                        "release-version",
+                        &[
+                            ("version", &release.version),
+                            ("current", env!("CARGO_PKG_VERSION")),
+                        ],
+                    ));
+                    egui::ScrollArea::vertical()
+                        .max_height(300.0)
+                        .show(ui, |ui| {
+                            ui.label(release.notes.as_str());
+                        });
+                    ui.separator();
+                    ui.hyperlink_to(i18n::tr("release-download"), &release.url);
+                });
+        }
+
+        if self.show_theme_editor {
+            let mut open = true;
+            let mut changed = false;
+            egui::Window::new(i18n::tr("theme-editor"))
+                .open(&mut open)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    let visuals = ctx.style().visuals.clone();
+                    let preview = theme::preview_color(ctx, &self.settings);
+                    let settings = &mut self.settings;
+                    egui::Grid::new("theme_colors").show(ui, |ui| {
+                        for (label, setting, current) in [
+                            (
+                                i18n::tr("color-accent"),
+                                &mut settings.accent_color,
+                                visuals.selection.bg_fill,
+                            ),
+                            (
+                                i18n::tr("color-background"),
+                                &mut settings.background_color,
+                                visuals.panel_fill,
+                            ),
+                            (
+                                i18n::tr("color-text"),
+                                &mut settings.text_color,
+                                visuals.text_color(),
+                            ),
+                            (
+                                i18n::tr("color-preview"),
+                                &mut settings.preview_color,
+                                preview,
+                            ),
+                        ] {
+                            ui.label(label);
+                            let mut color = current;
+                            if egui::color_picker::color_edit_button_srgba(
+                                ui,
+                                &mut color,
+                                egui::color_picker::Alpha::Opaque,
+                            )
+                            .changed()
+                            {
+                                *setting = theme::color_hex(color);
+                                changed = true;
+                            }
+                            if ui
+                                .add_enabled(
+                                    !setting.is_empty(),
+                                    egui::Button::new(i18n::tr("color-reset")),
+                                )
+                                .clicked()
+                            {
+                                setting.clear();
+                                changed = true;
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            if changed {
+                self.apply_theme(ctx);
+            }
+            self.show_theme_editor = open;
+        }
+
+        if self.show_rules {
+            let mut open = true;
+            let mut changed = false;
+            egui::Window::new(i18n::tr("rules"))
+                .open(&mut open)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    changed |= ui
+                        .checkbox(
+                            &mut self.settings.input_rules_enabled,
+                            i18n::tr("rules-input"),
+                        )
+                        .changed();
+                    ui.label(i18n::tr("rules-input-hint"));
+                    ui.add_enabled_ui(self.settings.input_rules_enabled, |ui| {
+                        changed |= rules::editor(ui, "input_rules", &mut self.settings.input_rules);
+                    });
+                    ui.separator();
+                    ui.strong(i18n::tr("rules-output"));
+                    ui.label(i18n::tr("rules-output-hint"));
+                    changed |= rules::editor(ui, "output_rules", &mut self.settings.output_rules);
+                    ui.separator();
+                    ui.strong(i18n::tr("hooks"));
+                    ui.label(i18n::tr("hooks-hint"));
+                    egui::Grid::new("hooks").num_columns(2).show(ui, |ui| {
+                        for (label, hook) in [
+                            ("hooks-pre", &mut self.settings.pre_hook),
+                            ("hooks-post", &mut self.settings.post_hook),
+                        ] {
+                            ui.label(i18n::tr(label));
+                            // Half-typed commands aren't run.
+                            changed |= ui
+                                .add(
+                                    egui::TextEdit::singleline(hook)
+                                        .code_editor()
+                                        .hint_text("sed 's/foo/bar/'")
+                                        .desired_width(280.0),
+                                )
+                                .lost_focus();
+                            ui.end_row();
+                        }
+                    });
+                    ui.separator();
+                    ui.strong(i18n::tr("macros"));
+                    ui.label(i18n::tr("macros-hint"));
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::multiline(&mut self.settings.latex_macros)
+                                .code_editor()
+                                .hint_text("\\newcommand{\\R}{\\mathbb{R}}")
+                                .desired_rows(4)
+                                .desired_width(f32::INFINITY),
+                        )
+                        .lost_focus();
+                    ui.separator();
+                    ui.strong(i18n::tr("theorems"));
+                    ui.label(i18n::tr("theorems-hint"));
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::multiline(&mut self.settings.theorem_environments)
+                                .code_editor()
+                                .hint_text("theorem = thm")
+                                .desired_rows(3)
+                                .desired_width(f32::INFINITY),
+                        )
+                        .lost_focus();
+                });
+            if changed {
+                if self.settings.save().is_err() {
+                    self.toasts.error(i18n::tr("error-save-settings"));
+                }
+                self.convert(ctx);
+            }
+            self.show_rules = open;
+        }
+
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button(i18n::tr("menu-file"), |ui| {
+                    if ui.button(i18n::tr("menu-open")).clicked() {
+                        self.run_command(ctx, Command::Open);
+                        ui.close_menu();
+                    }
+                    ui.add_enabled_ui(!self.settings.recent_files.is_empty(), |ui| {
+                        ui.menu_button(i18n::tr("menu-open-recent"), |ui| {
+                            let mut opened = None;
+                            for path in &self.settings.recent_files {
+                                if ui.button(path).clicked() {
+                                    opened = Some(PathBuf::from(path));
+                                }
+                            }
+                            if let Some(path) = opened {
+                                self.open_file(ctx, &path);
+                                ui.close_menu();
+                            }
+                        });
+                    });
+                    if ui
+                        .add_enabled(
+                            self.copy_enabled,
+                            egui::Button::new(i18n::tr("menu-save-output")),
+                        )
+                        .clicked()
+                    {
+                        self.run_command(ctx, Command::SaveOutput);
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    ui.menu_button(i18n::tr("menu-from-image"), |ui| {
+                        if ui.button(i18n::tr("menu-open-image")).clicked() {
+                            self.run_command(ctx, Command::OpenImage);
+                            ui.close_menu();
+                        }
+                        if ui.button(i18n::tr("menu-paste-image")).clicked() {
+                            self.run_command(ctx, Command::PasteImage);
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        ui.label(i18n::tr("menu-ocr-command"));
+                        if ui
+                            .text_edit_singleline(&mut self.settings.ocr_command)
+                            .changed()
+                            && self.settings.save().is_err()
+                        {
+                            self.toasts.error(i18n::tr("error-save-settings"));
+                        }
+                    });
+                    if ui
+                        .add_enabled(
+                            !self.latex.is_empty(),
+                            egui::Button::new(i18n::tr("menu-export-standalone")),
+                        )
+                        .clicked()
+                    {
+                        self.run_command(ctx, Command::ExportStandalone);
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.svg.is_some(),
+                            egui::Button::new(i18n::tr("menu-export-png")),
+                        )
+                        .clicked()
+                    {
+                        self.run_command(ctx, Command::ExportPng);
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.svg.is_some(),
+                            egui::Button::new(i18n::tr("menu-export-svg")),
+                        )
+                        .clicked()
+                    {
+                        self.run_command(ctx, Command::ExportSvg);
+                        ui.close_menu();
+                    }
+                    ui.menu_button(i18n::tr("menu-export-colors"), |ui| {
+                        let preview = theme::preview_color(ctx, &self.settings);
+                        let settings = &mut self.settings;
+                        let mut changed = false;
+                        egui::Grid::new("export_colors").show(ui, |ui| {
+                            for (label, setting, default, reset) in [
+                                (
+                                    i18n::tr("export-foreground"),
+                                    &mut settings.export_foreground,
+                                    preview,
+                                    i18n::tr("export-use-preview-color"),
+                                ),
+                                (
+                                    i18n::tr("export-background"),
+                                    &mut settings.export_background,
+                                    egui::Color32::WHITE,
+                                    i18n::tr("export-transparent"),
+                                ),
+                            ] {
+                                ui.label(label);
+                                let mut color = theme::parse_color(setting).unwrap_or(default);
+                                if egui::color_picker::color_edit_button_srgba(
+                                    ui,
+                                    &mut color,
+                                    egui::color_picker::Alpha::Opaque,
+                                )
+                                .changed()
+                                {
+                                    *setting = theme::color_hex(color);
+                                    changed = true;
+                                }
+                                if ui.radio(setting.is_empty(), reset).clicked() {
+                                    setting.clear();
+                                    changed = true;
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        if changed && settings.save().is_err() {
+                            self.toasts.error(i18n::tr("error-save-settings"));
+                        }
+                    });
+                    ui.separator();
+                    if ui.button(i18n::tr("menu-export-settings")).clicked() {
+                        ui.close_menu();
+                        if let Err(err) = self.export_settings() {
+                            self.toasts.error(err);
+                        }
+                    }
+                    if ui.button(i18n::tr("menu-import-settings")).clicked() {
+                        ui.close_menu();
+                        if let Err(err) = self.import_settings(ctx) {
+                            self.toasts.error(err);
+                        }
+                    }
+                });
+                ui.menu_button(i18n::tr("menu-edit"), |ui| {
+                    for (command, enabled) in [
+                        (Command::Undo, self.undo.can_undo()),
+                        (Command::Redo, self.undo.can_redo()),
+                        (Command::Find, true),
+                        (Command::Replace, true),
+                    ] {
+                        let button = egui::Button::new(command.label())
+                            .shortcut_text(command.shortcut().unwrap_or_default());
+                        if ui.add_enabled(enabled, button).clicked() {
+                            self.run_command(ctx, command);
+                            ui.close_menu();
+                        }
+                    }
+                    ui.separator();
+                    let vim = ui
+                        .checkbox(&mut self.settings.vim_mode, i18n::tr("menu-vim"))
+                        .changed();
+                    let auto_close = ui
+                        .checkbox(
+                            &mut self.settings.auto_close_brackets,
+                            i18n::tr("menu-auto-close"),
+                        )
+                        .changed();
+                    let strip = ui
+                        .checkbox(
+                            &mut self.settings.strip_wrappers,
+                            i18n::tr("menu-strip-wrappers"),
+                        )
+                        .on_hover_text(i18n::tr("strip-wrappers-hint"))
+                        .changed();
+                    if strip {
+                        self.convert(ctx);
+                    }
+                    if (vim || auto_close || strip) && self.settings.save().is_err() {
+                        self.toasts.error(i18n::tr("error-save-settings"));
+                    }
+                    if ui.button(i18n::tr("menu-rules")).clicked() {
+                        self.show_rules = true;
+                        ui.close_menu();
+                    }
+                    ui.menu_button(i18n::tr("menu-backend"), |ui| {
+                        if self.backends.is_empty() {
+                            self.backends = backend::names();
+                        }
+                        let current = backend::selected();
+                        for name in self.backends.clone() {
+                            if ui.radio(current.name() == name, &name).clicked() {
+                                self.set_backend(ctx, &name);
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
+                        ui.weak(i18n::tr("menu-backend-hint"));
+                    });
+                    ui.menu_button(i18n::tr("menu-typst-style"), |ui| {
+                        let mut changed = false;
+                        for (setting, heading, options) in [
+                            (
+                                &mut self.settings.typst_shorthands,
+                                "typst-style-shorthands",
+                                [
+                                    ("shorthand", "typst-style-shorthand"),
+                                    ("explicit", "typst-style-explicit"),
+                                ],
+                            ),
+                            (
+                                &mut self.settings.typst_symbols,
+                                "typst-style-symbols",
+                                [
+                                    ("names", "typst-style-names"),
+                                    ("unicode", "typst-style-unicode"),
+                                ],
+                            ),
+                            (
+                                &mut self.settings.typst_spacing,
+                                "typst-style-spacing",
+                                [
+                                    ("spaced", "typst-style-spaced"),
+                                    ("compact", "typst-style-compact"),
+                                ],
+                            ),
+                        ] {
+                            ui.weak(i18n::tr(heading));
+                            for (value, label) in
+                                [("", "typst-style-pandoc")].into_iter().chain(options)
+                            {
+                                if ui.radio(*setting == value, i18n::tr(label)).clicked() {
+                                    *setting = value.to_string();
+                                    changed = true;
+                                }
+                            }
+                            ui.separator();
+                        }
+                        ui.weak(i18n::tr("menu-typst-style-hint"));
+                        if changed {
+                            if self.settings.save().is_err() {
+                                self.toasts.error(i18n::tr("error-save-settings"));
+                            }
+                            self.convert(ctx);
+                        }
+                    });
+                    if cfg!(target_os = "linux")
+                        && ui
+                            .checkbox(
+                                &mut self.settings.primary_selection,
+                                i18n::tr("menu-primary-selection"),
+                            )
+                            .changed()
+                        && self.settings.save().is_err()
+                    {
+                        self.toasts.error(i18n::tr("error-save-settings"));
+                    }
+                });
+                ui.menu_button(i18n::tr("menu-insert"), |ui| {
+                    for (i, template) in templates::ALL.iter().enumerate() {
+                        if ui
+                            .button(template.label())
+                            .on_hover_text(egui::RichText::new(template.typst).monospace())
+                            .clicked()
+                        {
+                            self.run_command(ctx, Command::InsertTemplate(i));
+                            ui.close_menu();
+                        }
+                    }
+                });
+                ui.menu_button(i18n::tr("menu-view"), |ui| {
+                    if ui
+                        .checkbox(
+                            &mut self.settings.tectonic_preview,
+                            i18n::tr("menu-tectonic-preview"),
+                        )
+                        .changed()
+                    {
+                        if self.settings.save().is_err() {
+                            self.toasts.error(i18n::tr("error-save-settings"));
+                        }
+                        self.update_texture(ctx);
+                    }
+                    ui.menu_button(i18n::tr("menu-renderer"), |ui| {
+                        let mut chosen = None;
+                        if ui
+                            .radio(
+                                self.settings.render_backend.is_empty(),
+                                i18n::tr("renderer-builtin"),
+                            )
+                            .clicked()
+                        {
+                            chosen = Some("");
+                        }
+                        for name in render_plugin::names() {
+                            if ui
+                                .radio(self.settings.render_backend == name, name)
+                                .clicked()
+                            {
+                                chosen = Some(name);
+                            }
+                        }
+                        ui.separator();
+                        ui.weak(i18n::tr("renderer-hint"));
+                        if let Some(name) = chosen {
+                            self.settings.render_backend = name.to_string();
+                            if self.settings.save().is_err() {
+                                self.toasts.error(i18n::tr("error-save-settings"));
+                            }
+                            self.update_texture(ctx);
+                            ui.close_menu();
+                        }
+                    });
+                    if ui
+                        .checkbox(
+                            &mut self.settings.validate_with_tex,
+                            i18n::tr("menu-validate"),
+                        )
+                        .changed()
+                    {
+                        if self.settings.save().is_err() {
+                            self.toasts.error(i18n::tr("error-save-settings"));
+                        }
+                        self.start_validation(ctx);
+                    }
+                    if ui
+                        .checkbox(
+                            &mut self.settings.preview_checkerboard,
+                            i18n::tr("menu-checkerboard"),
+                        )
+                        .changed()
+                        && self.settings.save().is_err()
+                    {
+                        self.toasts.error(i18n::tr("error-save-settings"));
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.show_diagnostics, i18n::tr("menu-diagnostics"));
+                    ui.checkbox(&mut self.show_mapping, i18n::tr("menu-mapping"))
+                        .on_hover_text(i18n::tr("menu-mapping-hint"));
+                    ui.checkbox(&mut self.show_diff, i18n::tr("menu-diff"));
+                    ui.menu_button(i18n::tr("menu-theme"), |ui| {
+                        let current = ThemeMode::from_name(&self.settings.theme);
+                        for mode in ThemeMode::ALL {
+                            if ui.radio(current == Some(mode), mode.label()).clicked() {
+                                self.settings.theme = mode.name().to_string();
+                                self.apply_theme(ctx);
+                                ui.close_menu();
+                            }
+                        }
+                        ui.separator();
+                        if ui.button(i18n::tr("menu-customize-colors")).clicked() {
+                            self.show_theme_editor = true;
+                            ui.close_menu();
+                        }
+                    });
+                    ui.menu_button(i18n::tr("menu-layout"), |ui| {
+                        for (layout, label) in [
+                            ("stacked", i18n::tr("layout-stacked")),
+                            ("side-by-side", i18n::tr("layout-side-by-side")),
+                        ] {
+                            if ui.radio(self.settings.layout == layout, label).clicked() {
+                                self.settings.layout = layout.to_string();
+                                if self.settings.save().is_err() {
+                                    self.toasts.error(i18n::tr("error-save-settings"));
+                                }
+                                // Make room for the panes next to each other.
+                                let size = ctx.screen_rect().size();
+                                let width = window_size(&self.settings)[0];
+                                if size.x < width {
+                                    ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(
+                                        egui::vec2(width, size.y),
+                                    ));
+                                }
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.menu_button(i18n::tr("menu-wrap"), |ui| {
+                        let input = ui
+                            .checkbox(&mut self.settings.input_wrap, i18n::tr("wrap-input"))
+                            .changed();
+                        let output = ui
+                            .checkbox(&mut self.settings.output_wrap, i18n::tr("wrap-output"))
+                            .changed();
+                        if (input || output) && self.settings.save().is_err() {
+                            self.toasts.error(i18n::tr("error-save-settings"));
+                        }
+                    });
+                    ui.menu_button(i18n::tr("menu-learning"), |ui| {
+                        for (mode, label) in [
+                            ("", i18n::tr("learning-off")),
+                            ("comments", i18n::tr("learning-comments")),
+                            ("breakdown", i18n::tr("learning-table")),
+                        ] {
+                            if ui
+                                .radio(self.settings.learning_mode == mode, label)
+                                .clicked()
+                            {
+                                self.settings.learning_mode = mode.to_string();
+                                if self.settings.save().is_err() {
+                                    self.toasts.error(i18n::tr("error-save-settings"));
+                                }
+                                self.convert(ctx);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.menu_button(i18n::tr("menu-math-font"), |ui| {
+                        let fonts = std::iter::once((i18n::tr("math-font-default"), ""))
+                            .chain(tex::MATH_FONTS);
+                        for (label, file) in fonts {
+                            if ui.radio(self.settings.math_font == file, label).clicked() {
+                                self.settings.math_font = file.to_string();
+                                if self.settings.save().is_err() {
+                                    self.toasts.error(i18n::tr("error-save-settings"));
+                                }
+                                self.update_texture(ctx);
+                                ui.close_menu();
+                            }
+                        }
+                        ui.weak(i18n::tr("math-font-hint"));
+                    });
+                    ui.menu_button(i18n::tr("menu-preview-cache"), |ui| {
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut self.settings.preview_cache_mb, 0.0..=512.0)
+                                    .suffix(" MB"),
+                            )
+                            .changed()
+                        {
+                            let budget = self.preview_cache_budget();
+                            self.previews.shrink(budget);
+                            if self.settings.save().is_err() {
+                                self.toasts.error(i18n::tr("error-save-settings"));
+                            }
+                        }
+                        ui.weak(i18n::tr_args(
+                            "preview-cache-usage",
+                            &[
+                                ("count", &self.previews.len().to_string()),
+                                (
+                                    "size",
+                                    &format!("{:.1}", self.previews.bytes() as f64 / 1048576.0),
+                                ),
+                            ],
+                        ));
+                        ui.weak(i18n::tr("preview-cache-hint"));
+                    });
+                    ui.menu_button(i18n::tr("menu-editor-font"), |ui| {
+                        let mut changed = false;
+                        for (name, label) in [
+                            ("proportional", i18n::tr("font-proportional")),
+                            ("monospace", i18n::tr("font-monospace")),
+                        ] {
+                            if ui.radio(self.settings.editor_font == name, label).clicked() {
+                                self.settings.editor_font = name.to_string();
+                                changed = true;
+                            }
+                        }
+                        changed |= ui
+                            .add(
+                                egui::Slider::new(&mut self.settings.editor_font_size, 8.0..=40.0)
+                                    .text(i18n::tr("font-size")),
+                            )
+                            .changed();
+                        changed |= ui
+                            .add(
+                                egui::Slider::new(
+                                    &mut self.settings.editor_line_spacing,
+                                    1.0..=2.5,
+                                )
+                                .text(i18n::tr("line-spacing")),
+                            )
+                            .changed();
+                        if changed && self.settings.save().is_err() {
+                            self.toasts.error(i18n::tr("error-save-settings"));
+                        }
+                    });
+                    ui.menu_button(i18n::tr("menu-language"), |ui| {
+                        for language in Language::ALL {
+                            if ui
+                                .selectable_label(i18n::language() == language, language.label())
+                                .clicked()
+                            {
+                                self.set_language(ctx, language);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    ui.separator();
+                    if ui.button(i18n::tr("menu-symbols")).clicked() {
+                        self.run_command(ctx, Command::Symbols);
+                        ui.close_menu();
+                    }
+                    if ui.button(i18n::tr("menu-history")).clicked() {
+                        self.run_command(ctx, Command::History);
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.pandoc_reads_typst,
+                            egui::Button::new(i18n::tr("menu-batch")),
+                        )
+                        .clicked()
+                    {
+                        self.run_command(ctx, Command::Batch);
+                        ui.close_menu();
+                    }
+                    if ui.button(i18n::tr("menu-command-palette")).clicked() {
+                        self.palette.toggle();
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button(i18n::tr("menu-help"), |ui| {
+                    let mut online = !self.settings.offline;
+                    let mut changed = ui
+                        .checkbox(&mut online, i18n::tr("menu-allow-network"))
+                        .on_hover_text(i18n::tr("menu-allow-network-hint"))
+                        .changed();
+                    self.settings.offline = !online;
+                    changed |= ui
+                        .add_enabled(
+                            online,
+                            egui::Checkbox::new(
+                                &mut self.settings.check_updates,
+                                i18n::tr("menu-check-updates"),
+                            ),
+                        )
+                        .changed();
+                    if changed && self.settings.save().is_err() {
+                        self.toasts.error(i18n::tr("error-save-settings"));
+                    }
+                    if ui
+                        .add_enabled(
+                            online && self.update_rx.is_none(),
+                            egui::Button::new(i18n::tr("menu-check-updates-now")),
+                        )
+                        .clicked()
+                    {
+                        self.start_update_check(ctx, true);
+                        ui.close_menu();
+                    }
+                    if ui
+                        .add_enabled(
+                            self.release.is_some(),
+                            egui::Button::new(i18n::tr("menu-release-notes")),
+                        )
+                        .clicked()
+                    {
+                        self.show_release_notes = true;
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button(i18n::tr("menu-reference")).clicked() {
+                        self.run_command(ctx, Command::Reference);
+                        ui.close_menu();
+                    }
+                    if ui.button(i18n::tr("menu-usage")).clicked() {
+                        self.run_command(ctx, Command::Usage);
+                        ui.close_menu();
+                    }
+                    if ui.button(i18n::tr("menu-onboarding")).clicked() {
+                        self.onboarding.start();
+                        ui.close_menu();
+                    }
+                    ui.menu_button(i18n::tr("menu-load-example"), |ui| {
+                        for (i, example) in examples::ALL.iter().enumerate() {
+                            if ui
+                                .button(example.label())
+                                .on_hover_text(egui::RichText::new(example.typst).monospace())
+                                .clicked()
+                            {
+                                self.run_command(ctx, Command::LoadExample(i));
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                });
+            });
+        });
+
+        egui::TopBottomPanel::bottom("status_bar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                // `pandoc_version` waits for the probe, so it's asked once that's done.
+                let probed = self.pandoc_rx.is_none();
+                match probed.then(convert::pandoc_version) {
+                    None => ui.weak("pandoc …"),
+                    Some(Some(version)) if !self.pandoc_reads_typst => ui
+                        .colored_label(ui.visuals().warn_fg_color, format!("pandoc {}", version))
+                        .on_hover_text(i18n::tr_args(
+                            "pandoc-too-old",
+                            &[
+                                ("version", version),
+                                ("required", convert::TYPST_READER_VERSION),
+                            ],
+                        )),
+                    Some(Some(version)) => ui.weak(format!("pandoc {}", version)),
+                    Some(None) => ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        i18n::tr("status-pandoc-missing"),
+                    ),
+                };
+                if self.settings.vim_mode {
+                    ui.separator();
+                    ui.monospace(self.vim.label());
+                }
+                ui.separator();
+                ui.weak(if !self.settings.render_backend.is_empty() {
+                    self.settings.render_backend.as_str()
+                } else if self.settings.tectonic_preview {
+                    "tectonic"
+                } else {
+                    "MathJax"
+                });
+                if let Some(ms) = self.conversion_ms {
+                    ui.separator();
+                    ui.weak(i18n::tr_args(
+                        "status-convert-time",
+                        &[("ms", &ms.to_string())],
+                    ));
+                }
+                if let Some(ms) = self.render_ms {
+                    ui.separator();
+                    ui.weak(i18n::tr_args(
+                        "status-render-time",
+                        &[("ms", &ms.to_string())],
+                    ));
+                }
+                if let Some(release) = &self.release {
+                    ui.separator();
+                    if ui
+                        .link(i18n::tr_args(
+                            "update-available",
+                            &[("version", &release.version)],
+                        ))
+                        .clicked()
+                    {
+                        self.show_release_notes = true;
+                    }
+                }
+                if let Some(err) = self.clipboard_error.clone() {
+                    ui.separator();
+                    ui.colored_label(ui.visuals().error_fg_color, i18n::tr("error-clipboard"))
+                        .on_hover_text(err);
+                    if ui.small_button(i18n::tr("retry")).clicked() {
+                        self.clipboard().ok();
+                    }
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let response = ui.weak(&self.announcement);
+                    // A live region, so that screen readers announce every change.
+                    ctx.accesskit_node_builder(response.id, |builder| {
+                        builder.set_live(egui::accesskit::Live::Polite);
+                    });
+                });
+            });
+        });
+
+        if self.show_diagnostics {
+            egui::TopBottomPanel::bottom("diagnostics")
+                .resizable(true)
+                .default_height(120.0)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.strong(i18n::tr("diagnostics"));
+                        if ui.button(i18n::tr("diagnostics-copy")).clicked() {
+                            self.copy_to_clipboard(diagnostics::report());
+                        }
+                        if ui.button(i18n::tr("clear")).clicked() {
+                            diagnostics::clear();
+                        }
+                        // Stays put while idle, which shows that nothing repaints.
+                        ui.weak(i18n::tr_args(
+                            "diagnostics-frames",
+                            &[("count", &ctx.cumulative_pass_nr().to_string())],
+                        ));
+                        if let Some(ms) = self.startup_ms {
+                            ui.weak(i18n::tr_args(
+                                "diagnostics-startup",
+                                &[("ms", &ms.to_string())],
+                            ));
+                        }
+                    });
+                    egui::ScrollArea::vertical()
+                        .auto_shrink([false, false])
+                        .stick_to_bottom(true)
+                        .show(ui, |ui| {
+                            for entry in diagnostics::entries() {
+                                ui.monospace(entry);
+                            }
+                        });
+                });
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // Set font size.
+            ui.style_mut().override_font_id = Some(egui::FontId {
+                size: 16.0,
+                family: egui::FontFamily::Proportional,
+            });
+
+            ui.add_space(10.0);
+            if !self.pandoc_reads_typst {
+                ui.horizontal_wrapped(|ui| {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        i18n::tr_args(
+                            "pandoc-too-old",
+                            &[
+                                ("version", convert::pandoc_version().unwrap_or_default()),
+                                ("required", convert::TYPST_READER_VERSION),
+                            ],
+                        ),
+                    );
+                    ui.hyperlink_to(
+                        i18n::tr("pandoc-install"),
+                        "https://pandoc.org/installing.html",
+                    );
+                });
+                ui.add_space(5.0);
+            }
+            ui.horizontal(|ui| {
+                let mut changed = false;
+                for direction in [
+                    Direction::TypstToLatex,
+                    Direction::LatexToTypst,
+                    Direction::Auto,
+                ] {
+                    // Only LaTeX to Typst works without the Typst reader.
+                    let enabled = self.pandoc_reads_typst || direction == Direction::LatexToTypst;
+                    changed |= ui
+                        .add_enabled_ui(enabled, |ui| {
+                            ui.selectable_value(&mut self.direction, direction, direction.label())
+                                .changed()
+                        })
+                        .inner;
+                }
+                // Show which direction was picked for the current input.
+                if self.direction == Direction::Auto && !self.input.trim().is_empty() {
+                    ui.weak(i18n::tr_args(
+                        "detected-direction",
+                        &[("direction", self.resolved_direction.label())],
+                    ));
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.add_enabled_ui(self.resolved_direction != Direction::LatexToTypst, |ui| {
+                        egui::ComboBox::from_id_salt("target")
+                            .selected_text(self.target.label())
+                            .show_ui(ui, |ui| {
+                                for target in Target::ALL {
+                                    changed |= ui
+                                        .selectable_value(&mut self.target, target, target.label())
+                                        .changed();
+                                }
+                            });
+                    });
+                });
+                if changed {
+                    self.convert(ctx);
+                }
+            });
+
+            if self.settings.layout == "side-by-side" {
+                ui.columns(2, |columns| {
+                    self.input_pane(ctx, &mut columns[0]);
+                    self.output_pane(ctx, &mut columns[1]);
+                });
+                ui.separator();
+            } else {
+                self.input_pane(ctx, ui);
+                ui.add_space(8.0);
+                ui.separator();
+                ui.add_space(5.0);
+                self.output_pane(ctx, ui);
+            }
+            self.preview_pane(ui);
+        });
+
+        self.toasts.show(ctx);
+    }
+}
+
+pub fn run(args: &[String]) -> eframe::Result {
+    // Launching again brings the open window forward instead of opening another.
+    let request = instance::Request::from_args(args);
+    if instance::forward(&request) {
+        return Ok(());
+    }
+
+    let settings = Settings::load();
+    let language = Language::from_name(&settings.language).unwrap_or_else(Language::detect);
+    i18n::set_language(language);
+    crash::install_hook();
+    if let Some(dir) = settings::config_dir() {
+        diagnostics::log(format!("Settings and data in {}", dir.display()));
+    }
+    let cjk_font = (language == Language::Chinese).then(|| std::thread::spawn(read_cjk_font));
+
+    let native_options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_min_inner_size([450.0, 485.0])
+            .with_inner_size(window_size(&settings)),
+        ..Default::default()
+    };
+    eframe::run_native(
+        i18n::tr("app-title"),
+        native_options,
+        Box::new(|cc| {
+            Ok(Box::new(MyApp::new(
+                &cc.egui_ctx,
+                request,
+                settings,
+                cjk_font,
+            )))
+        }),
+    )
+}
@@ -0,0 +1,599 @@
+use crate::latex::{self, Token};
+
+// pandoc drops the lines of augmented matrices both ways, so they go through it
+// as a marker string in front of the matrix, like `augment:v2;h1` for a line
+// after the second column and one after the first row.
+const MARKER: &str = "augment:";
+
+// The matrix environments and their delimiters.
+const MATRICES: [(&str, &str, &str); 6] = [
+    ("matrix", "", ""),
+    ("pmatrix", "(", ")"),
+    ("bmatrix", "[", "]"),
+    ("Bmatrix", "\\{", "\\}"),
+    ("vmatrix", "|", "|"),
+    ("Vmatrix", "\\|", "\\|"),
+];
+
+// The matrix environment for an array between `\left` and `\right` with a
+// delimiter.
+const LEFT_DELIMITERS: [(&str, &str); 10] = [
+    (".", "matrix"),
+    ("(", "pmatrix"),
+    ("[", "bmatrix"),
+    ("\\{", "Bmatrix"),
+    ("\\lbrace", "Bmatrix"),
+    ("|", "vmatrix"),
+    ("\\vert", "vmatrix"),
+    ("\\lvert", "vmatrix"),
+    ("\\|", "Vmatrix"),
+    ("\\Vert", "Vmatrix"),
+];
+
+// The lines drawn through a matrix, after the columns and rows counted from 1 or,
+// when negative, from the end, and how its columns are aligned: `l`, `c` or `r`.
+struct Rules {
+    vlines: Vec<i32>,
+    hlines: Vec<i32>,
+    align: char,
+}
+
+fn numbers(text: &str) -> Option<Vec<i32>> {
+    text.split(',')
+        .map(|number| number.trim().parse().ok())
+        .collect()
+}
+
+fn list(numbers: &[i32]) -> String {
+    let numbers: Vec<String> = numbers.iter().map(i32::to_string).collect();
+    match &numbers[..] {
+        [number] => number.clone(),
+        numbers => format!("({})", numbers.join(", ")),
+    }
+}
+
+impl Rules {
+    fn marker(&self) -> String {
+        let joined = |lines: &[i32]| {
+            let lines: Vec<String> = lines.iter().map(i32::to_string).collect();
+            lines.join(",")
+        };
+        let mut fields = Vec::new();
+        if !self.vlines.is_empty() {
+            fields.push(format!("v{}", joined(&self.vlines)));
+        }
+        if !self.hlines.is_empty() {
+            fields.push(format!("h{}", joined(&self.hlines)));
+        }
+        if self.align != 'c' {
+            fields.push(format!("a{}", self.align));
+        }
+        format!("{}{}", MARKER, fields.join(";"))
+    }
+
+    fn from_marker(marker: &str) -> Option<Rules> {
+        let mut rules = Rules {
+            vlines: Vec::new(),
+            hlines: Vec::new(),
+            align: 'c',
+        };
+        for field in marker.strip_prefix(MARKER)?.split(';') {
+            match field.split_at_checked(1)? {
+                ("v", vlines) => rules.vlines = numbers(vlines)?,
+                ("h", hlines) => rules.hlines = numbers(hlines)?,
+                ("a", align) => rules.align = align.chars().next()?,
+                _ => return None,
+            }
+        }
+        Some(rules)
+    }
+
+    // The value of Typst's `augment` argument, like `#2` or
+    // `#(hline: 1, vline: (1, 2))`.
+    fn typst(&self) -> String {
+        if let ([vline], []) = (&self.vlines[..], &self.hlines[..]) {
+            return format!("#{}", vline);
+        }
+        let mut fields = Vec::new();
+        if !self.hlines.is_empty() {
+            fields.push(format!("hline: {}", list(&self.hlines)));
+        }
+        if !self.vlines.is_empty() {
+            fields.push(format!("vline: {}", list(&self.vlines)));
+        }
+        format!("#({})", fields.join(", "))
+    }
+
+    fn from_typst(augment: &str, align: Option<&str>) -> Option<Rules> {
+        let align = match align.map(|align| align.trim().trim_start_matches('#')) {
+            Some("left") => 'l',
+            Some("right") => 'r',
+            _ => 'c',
+        };
+        let augment = augment.trim().trim_start_matches('#');
+        if let Ok(vline) = augment.parse() {
+            return Some(Rules {
+                vlines: vec![vline],
+                hlines: Vec::new(),
+                align,
+            });
+        }
+        let mut rules = Rules {
+            vlines: Vec::new(),
+            hlines: Vec::new(),
+            align,
+        };
+        for (start, end) in argument_spans(augment) {
+            let (key, value) = augment[start..end].split_once(':')?;
+            let value = value.trim().trim_start_matches('#');
+            let value = value.trim_start_matches('(').trim_end_matches(')');
+            match key.trim() {
+                "hline" => rules.hlines = numbers(value)?,
+                "vline" => rules.vlines = numbers(value)?,
+                // Strokes are LaTeX's `|` and `\hline` either way.
+                _ => {}
+            }
+        }
+        Some(rules)
+    }
+}
+
+// The byte spans of the arguments of the call whose parenthesis opens at the start
+// of `text`, split on commas and semicolons, or none unless it's closed.
+fn argument_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = 1;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' if previous != '\\' => quoted = !quoted,
+            _ if quoted => {}
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    spans.push((start, i));
+                    return spans;
+                }
+            }
+            ',' | ';' if depth == 1 => {
+                spans.push((start, i));
+                start = i + 1;
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    Vec::new()
+}
+
+// The value of the named argument `name` among `spans` of `call`, and the span
+// to remove for it, up to the next argument.
+fn named_argument<'a>(
+    call: &'a str,
+    spans: &[(usize, usize)],
+    name: &str,
+) -> Option<(&'a str, (usize, usize))> {
+    let spaces = |at: usize| call[at..].len() - call[at..].trim_start().len();
+    spans.iter().enumerate().find_map(|(n, &(start, end))| {
+        let value = call[start..end]
+            .trim_start()
+            .strip_prefix(name)?
+            .trim_start()
+            .strip_prefix(':')?;
+        let end = spans
+            .get(n + 1)
+            .map_or(end, |&(next, _)| next + spaces(next));
+        Some((value, (start + spaces(start), end)))
+    })
+}
+
+// Move the `augment` argument of Typst matrices into a marker in front of them,
+// for `to_arrays` to draw once pandoc has converted them.
+pub fn mark_augmented(typst: &str) -> String {
+    let mut out = String::with_capacity(typst.len());
+    // The `augment` arguments to leave out.
+    let mut removed: Vec<(usize, usize)> = Vec::new();
+    let mut quoted = false;
+    let mut i = 0;
+    while let Some(c) = typst[i..].chars().next() {
+        if let Some(&(_, end)) = removed.iter().find(|(start, _)| *start == i) {
+            i = end;
+            continue;
+        }
+        if c == '"' && !typst[..i].ends_with('\\') {
+            quoted = !quoted;
+        }
+        let call = &typst[i..];
+        let name_start = !typst[..i].ends_with(|c: char| c.is_alphanumeric() || c == '.');
+        if !quoted && name_start && call.starts_with("mat(") {
+            let arguments = &call[3..];
+            let spans = argument_spans(arguments);
+            let align = named_argument(arguments, &spans, "align").map(|(align, _)| align);
+            if let Some((augment, (start, end))) = named_argument(arguments, &spans, "augment") {
+                if let Some(rules) = Rules::from_typst(augment, align) {
+                    out.push_str(&format!("\"{}\" ", rules.marker()));
+                    removed.push((i + 3 + start, i + 3 + end));
+                }
+            }
+        }
+        out.push(c);
+        i += c.len_utf8();
+    }
+    out
+}
+
+// The array for a marker and the matrix environment after it at `i`, and the
+// index after them.
+fn marked_matrix(tokens: &[Token], i: usize) -> Option<(String, usize)> {
+    let Token::Command(command) = &tokens[i] else {
+        return None;
+    };
+    if !matches!(command.as_str(), "text" | "textrm" | "mathrm" | "mbox") {
+        return None;
+    }
+    let (marker, j) = latex::argument(tokens, i + 1);
+    let rules = Rules::from_marker(&latex::to_string(marker))?;
+    let j = latex::skip_spaces(tokens, j);
+    if tokens.get(j) != Some(&Token::Command("begin".to_string())) {
+        return None;
+    }
+    let (name, body, next) = latex::environment(tokens, j + 1);
+    let (_, open, close) = MATRICES.iter().find(|(matrix, _, _)| *matrix == name)?;
+    let rows = latex::split_rows(body);
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0) as i32;
+    let from_end = |line: i32, count: i32| if line < 0 { count + line } else { line };
+    let vlines: Vec<i32> = rules
+        .vlines
+        .iter()
+        .map(|&line| from_end(line, columns))
+        .collect();
+    let hlines: Vec<i32> = rules
+        .hlines
+        .iter()
+        .map(|&line| from_end(line, rows.len() as i32))
+        .collect();
+    let mut spec = String::new();
+    for column in 1..=columns {
+        spec.push(rules.align);
+        if vlines.contains(&column) && column < columns {
+            spec.push('|');
+        }
+    }
+    let rows: Vec<String> = rows
+        .iter()
+        .enumerate()
+        .map(|(row, cells)| {
+            let cells: Vec<String> = cells
+                .iter()
+                .map(|cell| arrays(cell).trim().to_string())
+                .collect();
+            let hline = if row > 0 && hlines.contains(&(row as i32)) {
+                "\\hline "
+            } else {
+                ""
+            };
+            format!("{}{}", hline, cells.join(" & "))
+        })
+        .collect();
+    let array = format!(
+        "\\begin{{array}}{{{}}}\n{}\n\\end{{array}}",
+        spec,
+        rows.join(" \\\\\n")
+    );
+    if open.is_empty() {
+        Some((array, next))
+    } else {
+        Some((format!("\\left{} {} \\right{}", open, array, close), next))
+    }
+}
+
+fn arrays(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some((array, next)) = marked_matrix(tokens, i) {
+            out.push_str(&array);
+            i = next;
+            continue;
+        }
+        match &tokens[i] {
+            Token::Group(group) => {
+                out.push('{');
+                out.push_str(&arrays(group));
+                out.push('}');
+            }
+            token => out.push_str(&latex::to_string(std::slice::from_ref(token))),
+        }
+        // Keep a command name from running into a following letter.
+        if let (Token::Command(name), Some(Token::Char(c))) = (&tokens[i], tokens.get(i + 1)) {
+            if name.chars().all(|c| c.is_ascii_alphabetic()) && c.is_ascii_alphabetic() {
+                out.push(' ');
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+// Turn the matrices marked by `mark_augmented` into arrays with their lines, like
+// `\left( \begin{array}{cc|c} ... \end{array} \right)`.
+pub fn to_arrays(latex: &str) -> String {
+    if !latex.contains(MARKER) {
+        return latex.to_string();
+    }
+    let tokens = latex::parse(latex);
+    let out = arrays(&tokens);
+    if out == latex::to_string(&tokens) {
+        latex.to_string()
+    } else {
+        out
+    }
+}
+
+// The rules of an array's column spec, like `{cc|c}`, and its column count, or
+// `None` for specs a Typst matrix can't take, like `p{2cm}`.
+fn column_rules(spec: &[Token]) -> Option<(Rules, i32)> {
+    let mut rules = Rules {
+        vlines: Vec::new(),
+        hlines: Vec::new(),
+        align: 'c',
+    };
+    let mut aligns = Vec::new();
+    for token in spec {
+        match token {
+            Token::Char(align @ ('l' | 'c' | 'r')) => aligns.push(*align),
+            Token::Char('|') => rules.vlines.push(aligns.len() as i32),
+            Token::Space => {}
+            _ => return None,
+        }
+    }
+    let columns = aligns.len() as i32;
+    // Outer lines are the delimiters' job.
+    rules.vlines.retain(|&line| line > 0 && line < columns);
+    rules.vlines.dedup();
+    if let Some(&align) = aligns
+        .first()
+        .filter(|&&align| aligns.iter().all(|a| *a == align))
+    {
+        rules.align = align;
+    }
+    Some((rules, columns))
+}
+
+// The matrix with a marker for an array with lines, whose `\begin` is at `i`
+// and which is in the matrix environment `matrix`, and the index after it.
+fn marked_array(tokens: &[Token], i: usize, matrix: &str) -> Option<(String, usize)> {
+    if tokens.get(i) != Some(&Token::Command("begin".to_string())) {
+        return None;
+    }
+    let (name, body, next) = latex::environment(tokens, i + 1);
+    if name != "array" {
+        return None;
+    }
+    let (_, start) = latex::optional_argument(body, 0);
+    let (spec, start) = latex::argument(body, start);
+    let (mut rules, _) = column_rules(spec)?;
+    let mut rows = Vec::new();
+    for row in body[start..].split(|token| *token == Token::Command("\\".to_string())) {
+        let mut j = latex::skip_spaces(row, 0);
+        let mut hline = false;
+        while row.get(j) == Some(&Token::Command("hline".to_string())) {
+            hline = true;
+            j = latex::skip_spaces(row, j + 1);
+        }
+        if j == row.len() {
+            continue;
+        }
+        // A line above the first row is the delimiters' job too.
+        if hline && !rows.is_empty() {
+            rules.hlines.push(rows.len() as i32);
+        }
+        rows.push(augmented(&row[j..]).trim().to_string());
+    }
+    if rules.vlines.is_empty() && rules.hlines.is_empty() {
+        return None;
+    }
+    let matrix = format!(
+        "\\text{{{}}}\\begin{{{}}}\n{}\n\\end{{{}}}",
+        rules.marker(),
+        matrix,
+        rows.join(" \\\\\n"),
+        matrix
+    );
+    Some((matrix, next))
+}
+
+// The marked matrix for an array between `\left` and `\right`, whose `\left` is at
+// `i`, and the index after its `\right` delimiter.
+fn delimited_array(tokens: &[Token], i: usize) -> Option<(String, usize)> {
+    if tokens[i] != Token::Command("left".to_string()) {
+        return None;
+    }
+    let j = latex::skip_spaces(tokens, i + 1);
+    let open = latex::to_string(tokens.get(j..j + 1)?);
+    let (_, matrix) = LEFT_DELIMITERS.iter().find(|(left, _)| *left == open)?;
+    let (array, next) = marked_array(tokens, latex::skip_spaces(tokens, j + 1), matrix)?;
+    let right = latex::skip_spaces(tokens, next);
+    if tokens.get(right) != Some(&Token::Command("right".to_string())) {
+        return None;
+    }
+    let close = latex::skip_spaces(tokens, right + 1);
+    Some((array, (close + 1).min(tokens.len())))
+}
+
+fn augmented(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let marked = delimited_array(tokens, i).or_else(|| marked_array(tokens, i, "matrix"));
+        if let Some((matrix, next)) = marked {
+            out.push_str(&matrix);
+            i = next;
+            continue;
+        }
+        match &tokens[i] {
+            Token::Group(group) => {
+                out.push('{');
+                out.push_str(&augmented(group));
+                out.push('}');
+            }
+            token => out.push_str(&latex::to_string(std::slice::from_ref(token))),
+        }
+        if let (Token::Command(name), Some(Token::Char(c))) = (&tokens[i], tokens.get(i + 1)) {
+            if name.chars().all(|c| c.is_ascii_alphabetic()) && c.is_ascii_alphabetic() {
+                out.push(' ');
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+// Turn arrays with lines, like `\left( \begin{array}{c|c} ... \end{array} \right)`,
+// into matrices with a marker, for `to_augmented` to add the lines back once
+// pandoc has converted them.
+pub fn mark_arrays(latex: &str) -> String {
+    if !latex.contains("{array}") {
+        return latex.to_string();
+    }
+    let tokens = latex::parse(latex);
+    let out = augmented(&tokens);
+    if out == latex::to_string(&tokens) {
+        latex.to_string()
+    } else {
+        out
+    }
+}
+
+// Turn the markers of `mark_arrays` into the `augment` and `align` arguments of
+// the Typst matrices after them.
+pub fn to_augmented(typst: &str) -> String {
+    let quoted_marker = format!("\"{}", MARKER);
+    let mut out = String::with_capacity(typst.len());
+    let mut rest = typst;
+    while let Some(at) = rest.find(&quoted_marker) {
+        out.push_str(&rest[..at]);
+        rest = &rest[at + 1..];
+        let Some(end) = rest.find('"') else {
+            break;
+        };
+        let rules = Rules::from_marker(&rest[..end]);
+        rest = rest[end + 1..].trim_start();
+        // Without a matrix after it, the marker is dropped.
+        if let (Some(rules), Some(arguments)) = (rules, rest.strip_prefix("mat(")) {
+            out.push_str(&format!("mat(augment: {}, ", rules.typst()));
+            match rules.align {
+                'l' => out.push_str("align: #left, "),
+                'r' => out.push_str("align: #right, "),
+                _ => {}
+            }
+            rest = arguments.trim_start();
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn augment_becomes_a_marker() {
+        assert_eq!(
+            mark_augmented("mat(1, 2; 3, 4; augment: #1)"),
+            r#""augment:v1" mat(1, 2; 3, 4; )"#
+        );
+        assert_eq!(
+            mark_augmented("mat(1, 2, 3; 4, 5, 6; augment: #(hline: 1, vline: 2))"),
+            r#""augment:v2;h1" mat(1, 2, 3; 4, 5, 6; )"#
+        );
+        assert_eq!(
+            mark_augmented(r#"mat(delim: "[", 1, 0; 0, 1; augment: #(vline: -1), align: #left)"#),
+            r#""augment:v-1;al" mat(delim: "[", 1, 0; 0, 1; align: #left)"#
+        );
+        assert_eq!(mark_augmented("x + mat(a; b)"), "x + mat(a; b)");
+    }
+
+    #[test]
+    fn markers_become_arrays() {
+        assert_eq!(
+            to_arrays(r"\text{augment:v1}\begin{pmatrix} 1 & 2 \\ 3 & 4 \end{pmatrix}"),
+            "\\left( \\begin{array}{c|c}\n1 & 2 \\\\\n3 & 4\n\\end{array} \\right)"
+        );
+        assert_eq!(
+            to_arrays(r"\text{augment:v-1;al}\begin{bmatrix} 1 & 0 \\ 0 & 1 \end{bmatrix}"),
+            "\\left[ \\begin{array}{l|l}\n1 & 0 \\\\\n0 & 1\n\\end{array} \\right]"
+        );
+        // A marker without a matrix after it is left alone.
+        assert_eq!(to_arrays(r"\text{augment:v1}x"), r"\text{augment:v1}x");
+    }
+
+    #[test]
+    fn arrays_round_trip_through_markers() {
+        let latex =
+            to_arrays(r"\text{augment:v2;h1}\begin{pmatrix} 1 & 2 & 3 \\ 4 & 5 & 6 \end{pmatrix}");
+        assert_eq!(
+            mark_arrays(&latex),
+            "\\text{augment:v2;h1}\\begin{pmatrix}\n1 & 2 & 3 \\\\\n4 & 5 & 6\n\\end{pmatrix}"
+        );
+        assert_eq!(
+            to_augmented(r#""augment:v2;h1" mat(1, 2, 3; 4, 5, 6)"#),
+            "mat(augment: #(hline: 1, vline: 2), 1, 2, 3; 4, 5, 6)"
+        );
+        assert_eq!(
+            to_augmented(r#""augment:v-1;al" mat(delim: "[", 1, 0; 0, 1)"#),
+            r#"mat(augment: #-1, align: #left, delim: "[", 1, 0; 0, 1)"#
+        );
+    }
+
+    #[test]
+    fn arrays_typst_cannot_draw_are_kept() {
+        let latex = r"\begin{array}{p{2cm}|c} a & b \end{array}";
+        assert_eq!(mark_arrays(latex), latex);
+        assert_eq!(to_augmented("mat(1, 2)"), "mat(1, 2)");
+    }
+
+    #[test]
+    fn nested_and_unfinished_matrices() {
+        assert_eq!(
+            mark_augmented("mat(1, mat(2; 3; augment: #1); 4, 5)"),
+            r#"mat(1, "augment:v1" mat(2; 3; ); 4, 5)"#
+        );
+        assert_eq!(
+            mark_augmented("mat(1, 2; 3, 4; augment: #(hline: (1, 2)))"),
+            r#""augment:h1,2" mat(1, 2; 3, 4; )"#
+        );
+        assert_eq!(
+            mark_augmented("mat(1, 2; augment: #1"),
+            "mat(1, 2; augment: #1"
+        );
+        // Inside a string it's text.
+        assert_eq!(
+            mark_augmented(r#""mat(augment: #1)""#),
+            r#""mat(augment: #1)""#
+        );
+    }
+
+    #[test]
+    fn rules_alignment_and_delimiters_of_arrays() {
+        assert_eq!(
+            mark_arrays(r"\begin{array}{cc|c} 1 & 2 & 3 \\ \hline 4 & 5 & 6 \end{array}"),
+            "\\text{augment:v2;h1}\\begin{matrix}\n1 & 2 & 3 \\\\\n4 & 5 & 6\n\\end{matrix}"
+        );
+        assert_eq!(
+            mark_arrays(r"\begin{array}{r|r} 1 & 2 \end{array}"),
+            "\\text{augment:v1;ar}\\begin{matrix}\n1 & 2\n\\end{matrix}"
+        );
+        assert_eq!(
+            mark_arrays(r"\left( \begin{array}{c|c} a & b \end{array} \right)"),
+            "\\text{augment:v1}\\begin{pmatrix}\na & b\n\\end{pmatrix}"
+        );
+        // Double rules have no counterpart.
+        let double = r"\begin{array}{||c} a \end{array}";
+        assert_eq!(mark_arrays(double), double);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::convert::{self, Direction};
+use crate::diagnostics;
+use crate::json::Value;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+// Something that converts math between Typst and LaTeX. Pandoc is built in; others
+// come from plugins.
+pub trait ConversionBackend: Send + Sync {
+    fn name(&self) -> &str;
+    // Convert `input`, with `direction` already resolved.
+    fn convert(&self, input: &str, direction: Direction) -> Result<String, String>;
+}
+
+pub const PANDOC: &str = "pandoc";
+
+pub struct Pandoc;
+
+impl ConversionBackend for Pandoc {
+    fn name(&self) -> &str {
+        PANDOC
+    }
+
+    fn convert(&self, input: &str, direction: Direction) -> Result<String, String> {
+        match direction {
+            Direction::LatexToTypst => convert::convert_latex_to_typst(input),
+            _ => convert::convert_typst_to_latex(input),
+        }
+    }
+}
+
+// Plugins are executables named `typst2latex-backend-<name>`, found in the
+// directories of `TYPST2LATEX_PLUGIN_PATH` and then of `PATH`.
+const PREFIX: &str = "typst2latex-backend-";
+
+// A converter in an executable of its own, run once per conversion. It reads one
+// JSON request on stdin and writes one JSON response on stdout, like the native
+// messaging host but without the length prefix:
+//
+// Request:  {"input": "...", "direction": "typst-to-latex" | "latex-to-typst"}
+// Response: {"output": "..."} or {"error": "..."}
+pub struct Plugin {
+    name: String,
+    path: PathBuf,
+}
+
+impl ConversionBackend for Plugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn convert(&self, input: &str, direction: Direction) -> Result<String, String> {
+        diagnostics::log(format!(
+            "{} {} ({} bytes)",
+            self.path.display(),
+            direction.name(),
+            input.len()
+        ));
+        if convert::cancelled() {
+            return Err(convert::CANCELLED.to_string());
+        }
+        let mut child = Command::new(&self.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|_| format!("Failed to run the {} plugin", self.name))?;
+        let request = Value::object([
+            ("input", input.into()),
+            ("direction", direction.name().into()),
+        ]);
+        child
+            .stdin
+            .take()
+            .ok_or("Failed to open stdin")?
+            .write_all(request.to_string().as_bytes())
+            .map_err(|_| "Failed to write to stdin")?;
+        let output = convert::wait(child, &self.name)?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let Ok(response) = Value::parse(stdout.trim()) else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            diagnostics::log(format!("{} stderr: {}", self.name, stderr.trim()));
+            return Err(format!(
+                "The {} plugin didn't answer in JSON: {}",
+                self.name,
+                stderr.trim()
+            ));
+        };
+        if let Some(error) = response.get("error").and_then(Value::as_str) {
+            return Err(error.to_string());
+        }
+        response
+            .get("output")
+            .and_then(Value::as_str)
+            .map(|output| output.trim().to_string())
+            .ok_or_else(|| format!("The {} plugin gave no output", self.name))
+    }
+}
+
+fn plugin_dirs() -> Vec<PathBuf> {
+    ["TYPST2LATEX_PLUGIN_PATH", "PATH"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .flat_map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+        .collect()
+}
+
+// The executables named `<prefix><name>` in the plugin directories, by name. Where
+// two share a name, the first found wins.
+pub fn executables(prefix: &str) -> Vec<(String, PathBuf)> {
+    let mut executables: Vec<(String, PathBuf)> = Vec::new();
+    for dir in plugin_dirs() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut found: Vec<(String, PathBuf)> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| {
+                let file = entry.file_name().to_string_lossy().to_string();
+                let name = file.strip_prefix(prefix)?;
+                let name = if cfg!(windows) {
+                    name.strip_suffix(".exe")?
+                } else {
+                    name
+                };
+                Some((name.to_string(), entry.path()))
+            })
+            .filter(|(name, _)| !name.is_empty())
+            .collect();
+        found.sort();
+        for (name, path) in found {
+            if !executables.iter().any(|(known, _)| *known == name) {
+                executables.push((name, path));
+            }
+        }
+    }
+    executables
+}
+
+// The conversion plugins installed, by name.
+pub fn discover() -> Vec<Plugin> {
+    executables(PREFIX)
+        .into_iter()
+        .filter(|(name, _)| name != PANDOC)
+        .map(|(name, path)| Plugin { name, path })
+        .collect()
+}
+
+// The names of every backend, pandoc first.
+pub fn names() -> Vec<String> {
+    let mut names = vec![PANDOC.to_string()];
+    names.extend(discover().into_iter().map(|plugin| plugin.name));
+    names
+}
+
+static SELECTED: Mutex<Option<Arc<dyn ConversionBackend>>> = Mutex::new(None);
+
+// Convert with the backend called `name` from now on, or with pandoc when it's
+// empty.
+pub fn select(name: &str) -> Result<(), String> {
+    let backend: Option<Arc<dyn ConversionBackend>> = if name.is_empty() || name == PANDOC {
+        None
+    } else {
+        let plugin = discover()
+            .into_iter()
+            .find(|plugin| plugin.name == name)
+            .ok_or_else(|| format!("No backend named '{}'", name))?;
+        Some(Arc::new(plugin))
+    };
+    if let Ok(mut selected) = SELECTED.lock() {
+        *selected = backend;
+    }
+    Ok(())
+}
+
+// Tells apart what backends made in caches: empty for pandoc, so that what was
+// cached before there were plugins still counts.
+pub fn tag() -> String {
+    let backend = selected();
+    if backend.name() == PANDOC {
+        String::new()
+    } else {
+        format!("{}\n", backend.name())
+    }
+}
+
+pub fn selected() -> Arc<dyn ConversionBackend> {
+    SELECTED
+        .lock()
+        .ok()
+        .and_then(|selected| selected.clone())
+        .unwrap_or_else(|| Arc::new(Pandoc))
+}
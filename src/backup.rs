@@ -0,0 +1,49 @@
+use crate::json::Value;
+use crate::settings::config_dir;
+use std::fs;
+use std::path::Path;
+
+// Marks a file as a bundle, with the version of its layout.
+const FORMAT: &str = "typst2latex-backup";
+
+// The files moved between machines, by their key in the bundle. Settings carry the
+// rules and profiles. Caches are left out, since they fill up again by themselves.
+const FILES: [(&str, &str); 3] = [
+    ("settings", "settings.json"),
+    ("history", "history.json"),
+    ("usage", "usage.json"),
+];
+
+// Bundle the settings and data into one JSON file at `path`.
+pub fn export(path: &Path) -> Result<(), String> {
+    let dir = config_dir().ok_or("Failed to locate config directory")?;
+    let mut bundle = Value::object([(FORMAT, Value::from(1.0))]);
+    for (key, file) in FILES {
+        let Ok(text) = fs::read_to_string(dir.join(file)) else {
+            continue;
+        };
+        let value = Value::parse(&text).map_err(|_| format!("{} is damaged", file))?;
+        bundle.insert(key, value);
+    }
+    fs::write(path, bundle.pretty(2)).map_err(|_| format!("Failed to write {}", path.display()))
+}
+
+// Replace the settings and data with those bundled in `path`. What the bundle
+// doesn't have is kept.
+pub fn import(path: &Path) -> Result<(), String> {
+    let text =
+        fs::read_to_string(path).map_err(|_| format!("Failed to read {}", path.display()))?;
+    let bundle = Value::parse(&text)
+        .ok()
+        .filter(|bundle| bundle.get(FORMAT).is_some())
+        .ok_or("Not a typst2latex settings export")?;
+    let dir = config_dir().ok_or("Failed to locate config directory")?;
+    fs::create_dir_all(&dir).map_err(|_| "Failed to create config directory")?;
+    for (key, file) in FILES {
+        if let Some(value) = bundle.get(key) {
+            fs::write(dir.join(file), value.pretty(2))
+                .map_err(|_| format!("Failed to write {}", file))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use crate::cache::Cache;
+use crate::convert::Direction;
+use crate::i18n;
+use crate::pool;
+use crate::settings::config_dir;
+use eframe::egui;
+
+struct Row {
+    typst: String,
+    latex: Result<String, String>,
+}
+
+impl Row {
+    fn new(typst: String, cache: &mut Cache) -> Self {
+        let latex = cache.convert(&typst, Direction::TypstToLatex);
+        Self { typst, latex }
+    }
+}
+
+// A table of expressions converted one per row, e.g. for a homework set.
+#[derive(Default)]
+pub struct Batch {
+    pub open: bool,
+    rows: Vec<Row>,
+    paste: String,
+    // Loaded when the table is first shown.
+    cache: Option<Cache>,
+}
+
+// Split pasted text into expressions: one per paragraph if there are blank lines,
+// otherwise one per line.
+fn split_expressions(text: &str) -> Vec<String> {
+    let paragraphs = text.contains("\n\n") || text.contains("\r\n\r\n");
+    let pieces: Vec<&str> = if paragraphs {
+        text.split("\n\n")
+            .flat_map(|piece| piece.split("\r\n\r\n"))
+            .collect()
+    } else {
+        text.lines().collect()
+    };
+    pieces
+        .into_iter()
+        .map(str::trim)
+        .filter(|piece| !piece.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+impl Batch {
+    // Draw the table and return the LaTeX of a row whose copy button was clicked.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<String> {
+        let cache = self.cache.get_or_insert_with(|| match config_dir() {
+            Some(dir) => Cache::load(&dir.join("cache.json")),
+            None => Cache::memory(),
+        });
+        let mut copied = None;
+        let mut open = self.open;
+        egui::Window::new(i18n::tr("batch"))
+            .open(&mut open)
+            .default_width(600.0)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.paste)
+                        .hint_text(i18n::tr("batch-paste-hint"))
+                        .desired_rows(3)
+                        .desired_width(f32::INFINITY),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button(i18n::tr("batch-add-rows")).clicked() {
+                        let expressions = split_expressions(&std::mem::take(&mut self.paste));
+                        let latex = cache.convert_all(
+                            &expressions,
+                            Direction::TypstToLatex,
+                            pool::default_jobs(),
+                        );
+                        self.rows.extend(
+                            expressions
+                                .into_iter()
+                                .zip(latex)
+                                .map(|(typst, latex)| Row { typst, latex }),
+                        );
+                    }
+                    if ui.button(i18n::tr("batch-add-row")).clicked() {
+                        self.rows.push(Row::new(String::new(), cache));
+                    }
+                    if ui
+                        .add_enabled(!self.rows.is_empty(), egui::Button::new(i18n::tr("clear")))
+                        .clicked()
+                    {
+                        self.rows.clear();
+                    }
+                    let failed = self.rows.iter().filter(|row| row.latex.is_err()).count();
+                    ui.weak(i18n::tr_args(
+                        "batch-summary",
+                        &[
+                            ("rows", &self.rows.len().to_string()),
+                            ("failed", &failed.to_string()),
+                        ],
+                    ));
+                });
+                ui.separator();
+                let mut removed = None;
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("batch_grid")
+                            .num_columns(4)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (i, row) in self.rows.iter_mut().enumerate() {
+                                    match &row.latex {
+                                        Ok(_) => ui.label("✔"),
+                                        Err(err) => ui
+                                            .colored_label(ui.visuals().error_fg_color, "✖")
+                                            .on_hover_text(err),
+                                    };
+                                    let edited = ui
+                                        .add(
+                                            egui::TextEdit::multiline(&mut row.typst)
+                                                .desired_rows(1)
+                                                .desired_width(240.0),
+                                        )
+                                        .changed();
+                                    if edited {
+                                        row.latex =
+                                            cache.convert(&row.typst, Direction::TypstToLatex);
+                                    }
+                                    let mut latex = match &row.latex {
+                                        Ok(latex) => latex.as_str(),
+                                        Err(_) => "",
+                                    };
+                                    ui.add(
+                                        egui::TextEdit::multiline(&mut latex)
+                                            .desired_rows(1)
+                                            .desired_width(240.0),
+                                    );
+                                    ui.horizontal(|ui| {
+                                        if ui
+                                            .add_enabled(
+                                                row.latex.is_ok(),
+                                                egui::Button::new(i18n::tr("batch-copy")),
+                                            )
+                                            .clicked()
+                                        {
+                                            copied = row.latex.clone().ok();
+                                        }
+                                        if ui.small_button("×").clicked() {
+                                            removed = Some(i);
+                                        }
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                if let Some(i) = removed {
+                    self.rows.remove(i);
+                }
+            });
+        self.open = open;
+        if let Err(err) = cache.save() {
+            crate::diagnostics::log(err);
+        }
+        copied
+    }
+}
@@ -0,0 +1,296 @@
+use crate::i18n;
+use eframe::egui;
+use std::ops::Range;
+
+fn closing(open: char) -> Option<char> {
+    match open {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        _ => None,
+    }
+}
+
+// A bracket that doesn't pair up, at a character index.
+pub struct Unbalanced {
+    index: usize,
+    pub line: usize,
+    pub column: usize,
+    found: char,
+    // The bracket it should have closed, if it's a closing one.
+    open: Option<char>,
+}
+
+impl Unbalanced {
+    pub fn message(&self) -> String {
+        let found = self.found.to_string();
+        let position = [
+            ("bracket", found.as_str()),
+            ("line", &self.line.to_string()),
+            ("column", &self.column.to_string()),
+        ];
+        match self.open {
+            _ if closing(self.found).is_some() => i18n::tr_args("brackets-unclosed", &position),
+            Some(open) => {
+                let open = open.to_string();
+                i18n::tr_args(
+                    "brackets-mismatched",
+                    &[position.as_slice(), &[("open", open.as_str())]].concat(),
+                )
+            }
+            None => i18n::tr_args("brackets-unexpected", &position),
+        }
+    }
+
+    // `text` with the bracket closed, replaced by the right one, or removed.
+    pub fn fix(&self, text: &str) -> String {
+        let mut chars: Vec<char> = text.chars().collect();
+        match (closing(self.found), self.open.and_then(closing)) {
+            (Some(close), _) => chars.push(close),
+            (None, Some(close)) => chars[self.index] = close,
+            (None, None) => {
+                chars.remove(self.index);
+            }
+        }
+        chars.into_iter().collect()
+    }
+}
+
+// Pair up the round, square and curly brackets of `text`, skipping escaped ones,
+// comments and, in Typst, strings. LaTeX's `\left.` and `\right.` stand in for a
+// bracket of either kind. Returns the pairs as character indices and the first
+// bracket that doesn't pair up.
+fn scan(text: &str, typst: bool) -> (Vec<(usize, usize)>, Option<Unbalanced>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut pairs = Vec::new();
+    let mut stack: Vec<(usize, char)> = Vec::new();
+    let mut problem = None;
+    let mut in_string = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let rest = &chars[i..];
+        if in_string {
+            match c {
+                '\\' => i += 1,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '\\' {
+            let command: String = rest.iter().skip(1).take(6).collect();
+            if !typst && command.starts_with("left.") {
+                stack.push((i, '.'));
+                i += 5;
+            } else if !typst && command.starts_with("right.") {
+                if let Some((open, _)) = stack.pop() {
+                    pairs.push((open, i));
+                }
+                i += 6;
+            } else {
+                i += 1;
+            }
+        } else if (typst && rest.starts_with(&['/', '/'])) || (!typst && c == '%') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if typst && c == '"' {
+            in_string = true;
+        } else if closing(c).is_some() {
+            stack.push((i, c));
+        } else if matches!(c, ')' | ']' | '}') {
+            match stack.pop() {
+                Some((open, bracket)) if bracket == '.' || closing(bracket) == Some(c) => {
+                    pairs.push((open, i))
+                }
+                popped => {
+                    if problem.is_none() {
+                        problem = Some((i, c, popped.map(|(_, bracket)| bracket)));
+                    }
+                    if let Some(popped) = popped {
+                        stack.push(popped);
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+    let problem = problem.or_else(|| stack.first().map(|&(i, c)| (i, c, None)));
+    let problem = problem.map(|(index, found, open)| {
+        let before = &chars[..index];
+        Unbalanced {
+            index,
+            line: before.iter().filter(|&&c| c == '\n').count() + 1,
+            column: before.iter().rev().take_while(|&&c| c != '\n').count() + 1,
+            found: if found == '.' { '(' } else { found },
+            open,
+        }
+    });
+    (pairs, problem)
+}
+
+pub fn unbalanced(text: &str, typst: bool) -> Option<Unbalanced> {
+    scan(text, typst).1
+}
+
+// Byte ranges of the bracket next to the caret and its partner, for highlighting.
+pub fn matching(text: &str, caret: usize, typst: bool) -> Vec<Range<usize>> {
+    let (pairs, _) = scan(text, typst);
+    let touches = |index: usize| index == caret || index + 1 == caret;
+    let Some(&(open, close)) = pairs
+        .iter()
+        .find(|(open, close)| touches(*open) || touches(*close))
+    else {
+        return Vec::new();
+    };
+    let byte_range = |index: usize| {
+        text.char_indices()
+            .nth(index)
+            .map(|(start, c)| start..start + c.len_utf8())
+    };
+    byte_range(open)
+        .into_iter()
+        .chain(byte_range(close))
+        .collect()
+}
+
+// Type brackets in pairs: an opening bracket brings its closing one along, or wraps
+// the selection, typing a closing bracket steps over the one already there, and
+// Backspace between an empty pair removes both. Only acts on frames with a single
+// keystroke so that the editor's own handling of the rest stays in order. Returns
+// the new selection if the text changed.
+pub fn auto_close(
+    ctx: &egui::Context,
+    text: &mut String,
+    selection: Range<usize>,
+) -> Option<Range<usize>> {
+    let mut chars: Vec<char> = text.chars().collect();
+    let Range { start, end } = selection;
+    if end > chars.len() {
+        return None;
+    }
+    let next = chars.get(end).copied();
+    let previous = start.checked_sub(1).map(|i| chars[i]);
+    let typed = ctx.input(|i| {
+        let mut keystrokes = i.events.iter().filter(|event| match event {
+            egui::Event::Text(_) | egui::Event::Paste(_) => true,
+            egui::Event::Key { pressed, .. } => *pressed,
+            _ => false,
+        });
+        match (keystrokes.next(), keystrokes.next()) {
+            (Some(egui::Event::Text(text)), None) if text.chars().count() == 1 => {
+                text.chars().next()
+            }
+            (
+                Some(egui::Event::Key {
+                    key: egui::Key::Backspace,
+                    modifiers,
+                    ..
+                }),
+                None,
+            ) if modifiers.is_none() => Some('\u{8}'),
+            _ => None,
+        }
+    })?;
+    let selection = match typed {
+        '\u{8}' => {
+            let pair = previous
+                .and_then(closing)
+                .or(previous.filter(|&c| c == '|'));
+            if start != end || pair.is_none() || pair != next {
+                return None;
+            }
+            chars.drain(start - 1..=start);
+            start - 1..start - 1
+        }
+        ')' | ']' | '}' | '|' if start == end && next == Some(typed) => end + 1..end + 1,
+        '(' | '[' | '{' | '|' => {
+            let close = closing(typed).unwrap_or(typed);
+            // Only pair up where the closing bracket can't swallow what follows.
+            let free = next.is_none_or(|c| c.is_whitespace() || ")]},;$".contains(c));
+            if start == end && !free {
+                return None;
+            }
+            chars.insert(end, close);
+            chars.insert(start, typed);
+            start + 1..end + 1
+        }
+        _ => return None,
+    };
+    ctx.input_mut(|i| {
+        i.events.retain(|event| {
+            !matches!(
+                event,
+                egui::Event::Text(_)
+                    | egui::Event::Key {
+                        key: egui::Key::Backspace,
+                        pressed: true,
+                        ..
+                    }
+            )
+        })
+    });
+    *text = chars.into_iter().collect();
+    Some(selection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem(text: &str, typst: bool) -> Option<(usize, usize, char, Option<char>)> {
+        unbalanced(text, typst).map(|u| (u.line, u.column, u.found, u.open))
+    }
+
+    #[test]
+    fn nested_brackets_pair_up() {
+        let (pairs, problem) = scan("f(a[b{c}])", true);
+        assert_eq!(pairs, vec![(5, 7), (3, 8), (1, 9)]);
+        assert!(problem.is_none());
+        assert_eq!(matching("f(a[b{c}])", 4, true), vec![3..4, 8..9]);
+        // Either side of the caret.
+        assert_eq!(matching("(x)", 3, true), vec![0..1, 2..3]);
+        assert_eq!(matching("(x)", 1, true), vec![0..1, 2..3]);
+        assert!(matching("x (y)", 1, true).is_empty());
+    }
+
+    #[test]
+    fn mismatched_and_unclosed() {
+        assert_eq!(problem("(a]", true), Some((1, 3, ']', Some('('))));
+        assert_eq!(problem("a)", true), Some((1, 2, ')', None)));
+        assert_eq!(problem("x\n  [(a)", true), Some((2, 3, '[', None)));
+        // The first problem is reported, not the unclosed bracket before it.
+        assert_eq!(problem("(a] b)", true), Some((1, 3, ']', Some('('))));
+        assert_eq!(
+            unbalanced("(a]", true).unwrap().message(),
+            "] on line 1, column 3 doesn't close ("
+        );
+    }
+
+    #[test]
+    fn fixes() {
+        let fix = |text: &str| unbalanced(text, true).unwrap().fix(text);
+        assert_eq!(fix("(a"), "(a)");
+        assert_eq!(fix("(a]"), "(a)");
+        assert_eq!(fix("a)"), "a");
+    }
+
+    #[test]
+    fn escaped_brackets_and_latex_delimiters() {
+        assert!(unbalanced(r"\{ x \}", false).is_none());
+        assert!(unbalanced(r"\{ x", false).is_none());
+        assert!(unbalanced(r"\left( x \right)", false).is_none());
+        assert!(unbalanced(r"\left. x \right)", false).is_none());
+        assert!(unbalanced(r"\left( x \right.", false).is_none());
+        assert_eq!(problem(r"\left( x", false), Some((1, 6, '(', None)));
+        assert_eq!(problem(r"\left. x", false), Some((1, 1, '(', None)));
+    }
+
+    #[test]
+    fn comments_and_strings_are_skipped() {
+        assert!(unbalanced("x % (\ny", false).is_none());
+        assert!(unbalanced("x // (\ny", true).is_none());
+        assert!(unbalanced(r#""(" + "\")""#, true).is_none());
+        // Only Typst has strings.
+        assert!(unbalanced(r#""(""#, false).is_some());
+    }
+}
@@ -0,0 +1,238 @@
+use crate::backend;
+use crate::convert::{self, convert, Direction};
+use crate::json::Value;
+#[cfg(feature = "gui")]
+use crate::pool;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Beyond this many formulas the oldest are forgotten.
+const MAX_ENTRIES: usize = 20_000;
+
+// FNV-1a, which unlike `DefaultHasher` gives the same hash in every build.
+pub fn hash(bytes: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+// The hash of converting `input`, which takes in the backend and the pandoc
+// version so that switching or upgrading them converts again.
+pub fn key(input: &str, direction: Direction) -> String {
+    let pandoc = convert::pandoc_version().unwrap_or_default();
+    key_for(&backend::tag(), pandoc, input, direction)
+}
+
+// `key` with the backend's tag and the pandoc version given.
+fn key_for(tag: &str, pandoc: &str, input: &str, direction: Direction) -> String {
+    hash(format!("{}{}{}\n{}", tag, pandoc, direction.name(), input).as_bytes())
+}
+
+// Converted formulas by the hash of their input, kept in a file between runs so
+// that only new or edited math goes through pandoc again.
+pub struct Cache {
+    path: Option<PathBuf>,
+    entries: HashMap<String, String>,
+    // Hashes from oldest to newest, for forgetting the oldest.
+    order: Vec<String>,
+    changed: bool,
+}
+
+impl Cache {
+    // A cache that isn't saved anywhere.
+    pub fn memory() -> Cache {
+        Cache {
+            path: None,
+            entries: HashMap::new(),
+            order: Vec::new(),
+            changed: false,
+        }
+    }
+
+    // The cache saved at `path`, or an empty one if there's none or it's unreadable.
+    pub fn load(path: &Path) -> Cache {
+        let mut cache = Cache::memory();
+        cache.path = Some(path.to_path_buf());
+        let text = fs::read_to_string(path).unwrap_or_default();
+        if let Some(Value::Object(entries)) = Value::parse(&text)
+            .ok()
+            .and_then(|value| value.get("entries").cloned())
+        {
+            for (key, value) in entries {
+                if let Value::String(output) = value {
+                    cache.order.push(key.clone());
+                    cache.entries.insert(key, output);
+                }
+            }
+        }
+        cache
+    }
+
+    pub fn save(&mut self) -> Result<(), String> {
+        let Some(path) = self.path.as_ref().filter(|_| self.changed) else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|_| "Failed to create cache directory")?;
+        }
+        let entries = self
+            .order
+            .iter()
+            .filter_map(|key| Some((key.clone(), self.entries.get(key)?.as_str().into())));
+        let value = Value::object([("entries", Value::Object(entries.collect()))]);
+        fs::write(path, value.pretty(1))
+            .map_err(|_| format!("Failed to write {}", path.display()))?;
+        self.changed = false;
+        Ok(())
+    }
+
+    fn lookup(&self, input: &str, direction: Direction) -> Option<String> {
+        self.entries.get(&key(input, direction)).cloned()
+    }
+
+    fn store(&mut self, input: &str, direction: Direction, output: &str) {
+        let key = key(input, direction);
+        if self
+            .entries
+            .insert(key.clone(), output.to_string())
+            .is_none()
+        {
+            self.order.push(key);
+        }
+        if self.order.len() > MAX_ENTRIES {
+            for old in self.order.drain(..self.order.len() - MAX_ENTRIES) {
+                self.entries.remove(&old);
+            }
+        }
+        self.changed = true;
+    }
+
+    // Convert `input`, or take the result of converting it before. Failures aren't
+    // kept, since they may come from a missing or outdated pandoc.
+    #[cfg(feature = "gui")]
+    pub fn convert(&mut self, input: &str, direction: Direction) -> Result<String, String> {
+        if let Some(output) = self.lookup(input, direction) {
+            return Ok(output);
+        }
+        let output = convert(input, direction)?;
+        self.store(input, direction, &output);
+        Ok(output)
+    }
+
+    // `convert` for a cache shared between threads, which isn't locked while pandoc
+    // runs.
+    pub fn convert_shared(
+        cache: &Mutex<Cache>,
+        input: &str,
+        direction: Direction,
+    ) -> Result<String, String> {
+        let hit = cache
+            .lock()
+            .ok()
+            .and_then(|cache| cache.lookup(input, direction));
+        if let Some(output) = hit {
+            return Ok(output);
+        }
+        let output = convert(input, direction)?;
+        if let Ok(mut cache) = cache.lock() {
+            cache.store(input, direction, &output);
+        }
+        Ok(output)
+    }
+
+    // `convert` for many inputs, on up to `jobs` threads.
+    #[cfg(feature = "gui")]
+    pub fn convert_all(
+        &mut self,
+        inputs: &[String],
+        direction: Direction,
+        jobs: usize,
+    ) -> Vec<Result<String, String>> {
+        let shared = Mutex::new(std::mem::replace(self, Cache::memory()));
+        let outputs = pool::map(inputs, jobs, |input| {
+            Cache::convert_shared(&shared, input, direction)
+        });
+        *self = shared.into_inner().unwrap_or_else(|err| err.into_inner());
+        outputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_change_with_the_backend_and_pandoc() {
+        let key = key_for("", "3.2", "x^2", Direction::TypstToLatex);
+        assert_eq!(key, key_for("", "3.2", "x^2", Direction::TypstToLatex));
+        assert_ne!(
+            key,
+            key_for("plugin\n", "3.2", "x^2", Direction::TypstToLatex)
+        );
+        assert_ne!(key, key_for("", "3.5", "x^2", Direction::TypstToLatex));
+        assert_ne!(key, key_for("", "3.2", "x^2", Direction::LatexToTypst));
+        assert_ne!(key, key_for("", "3.2", "x^3", Direction::TypstToLatex));
+        // Pandoc's own key is the one from before there were plugins.
+        assert_eq!(key, hash(b"3.2typst-to-latex\nx^2"));
+    }
+
+    #[test]
+    fn hits_skip_converting() {
+        let cache = Mutex::new(Cache::memory());
+        // Not valid Typst, so that converting it would fail.
+        let input = "#(";
+        cache
+            .lock()
+            .unwrap()
+            .store(input, Direction::TypstToLatex, "cached");
+        assert_eq!(
+            Cache::convert_shared(&cache, input, Direction::TypstToLatex),
+            Ok("cached".to_string())
+        );
+        assert!(cache
+            .lock()
+            .unwrap()
+            .lookup(input, Direction::LatexToTypst)
+            .is_none());
+    }
+
+    #[test]
+    fn entries_are_saved_oldest_first() {
+        let path = std::env::temp_dir().join(format!(
+            "typst2latex-cache-test-{}.json",
+            std::process::id()
+        ));
+        let mut cache = Cache::load(&path);
+        cache.store("a", Direction::TypstToLatex, "1");
+        cache.store("b", Direction::TypstToLatex, "2");
+        cache.store("a", Direction::TypstToLatex, "3");
+        cache.save().unwrap();
+        let loaded = Cache::load(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.order, cache.order);
+        assert_eq!(
+            loaded.lookup("a", Direction::TypstToLatex),
+            Some("3".to_string())
+        );
+        assert_eq!(
+            loaded.lookup("b", Direction::TypstToLatex),
+            Some("2".to_string())
+        );
+    }
+
+    #[test]
+    fn the_oldest_entries_are_forgotten() {
+        let mut cache = Cache::memory();
+        for i in 0..=MAX_ENTRIES {
+            cache.store(&i.to_string(), Direction::TypstToLatex, "");
+        }
+        assert_eq!(cache.entries.len(), MAX_ENTRIES);
+        assert!(cache.lookup("0", Direction::TypstToLatex).is_none());
+        assert!(cache.lookup("1", Direction::TypstToLatex).is_some());
+    }
+}
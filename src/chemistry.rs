@@ -0,0 +1,303 @@
+// mhchem's `\ce{...}` as plain LaTeX math, which pandoc turns into the usual Typst
+// for chemistry, like `"H"_2 "O"` for `\ce{H2O}`.
+
+// Reaction arrows and signs, longest first so that `<=>>` isn't read as `<=>`,
+// with what's drawn for them.
+const ARROWS: [(&str, &str); 9] = [
+    ("<=>>", "\\rightleftharpoons"),
+    ("<<=>", "\\rightleftharpoons"),
+    ("<-->", "\\longleftrightarrow"),
+    ("<=>", "\\rightleftharpoons"),
+    ("<->", "\\leftrightarrow"),
+    ("->", "\\longrightarrow"),
+    ("<-", "\\longleftarrow"),
+    ("=", "="),
+    ("+", "+"),
+];
+
+// Physical states, set upright after a species.
+const STATES: [&str; 5] = ["(s)", "(l)", "(g)", "(aq)", "(cr)"];
+
+// The text in brackets opening at the start of `text`, like `[H2O]` after an
+// arrow, and the rest after it.
+fn bracketed(text: &str, open: char, close: char) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some((&text[1..i], &text[i + 1..]));
+            }
+        }
+    }
+    None
+}
+
+// A script after `^` or `_`, braced or digits with a charge's sign, as in `^2-`,
+// and the rest after it.
+fn script(text: &str) -> (&str, &str) {
+    match bracketed(text, '{', '}') {
+        Some(found) if text.starts_with('{') => found,
+        _ => {
+            let digits = text
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(text.len());
+            let end = text[digits..]
+                .find(|c: char| c != '+' && c != '-')
+                .map_or(text.len(), |end| digits + end);
+            (&text[..end], &text[end..])
+        }
+    }
+}
+
+// A species like `SO4^2-`, `^{14}_{6}C`, `(NH4)2SO4` or `CH3-CH2OH`.
+fn species(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    // A leading number is a stoichiometric coefficient, as in `2H2O`.
+    let coefficient = rest
+        .find(|c: char| !c.is_ascii_digit() && c != '/' && c != '.')
+        .unwrap_or(rest.len());
+    // Alone, as in `2 H2O`, it's the whole word.
+    if coefficient == rest.len() {
+        return rest.to_string();
+    }
+    if coefficient > 0 {
+        out.push_str(&rest[..coefficient]);
+        out.push_str("\\,");
+        rest = &rest[coefficient..];
+    }
+    // A leading superscript and subscript are a nucleus' mass and atomic number.
+    if rest.starts_with(['^', '_']) {
+        out.push_str("{}");
+    }
+    // Where the upright text of the last element ends, for the next one right
+    // after it to join, as in `\text{NaCl}`.
+    let mut text_end = None;
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+        match c {
+            'A'..='Z' => {
+                let end = after
+                    .find(|c: char| !c.is_ascii_lowercase())
+                    .unwrap_or(after.len());
+                if text_end == Some(out.len()) {
+                    out.pop();
+                    out.push_str(&format!("{}{}}}", c, &after[..end]));
+                } else {
+                    out.push_str(&format!("\\text{{{}{}}}", c, &after[..end]));
+                }
+                text_end = Some(out.len());
+                rest = &after[end..];
+            }
+            '0'..='9' => {
+                let end = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                out.push_str(&format!("_{{{}}}", &rest[..end]));
+                rest = &rest[end..];
+            }
+            '^' | '_' => {
+                let (script, next) = script(after);
+                out.push_str(&format!("{}{{{}}}", c, script));
+                rest = next;
+            }
+            // A charge closing the species, as in `Na+` or `OH-`.
+            '+' | '-' if after.is_empty() => {
+                out.push_str(&format!("^{{{}}}", c));
+                rest = after;
+            }
+            '-' => {
+                out.push('-');
+                rest = after;
+            }
+            '#' => {
+                out.push_str("\\equiv ");
+                rest = after;
+            }
+            // An adduct, with a coefficient of its own as in `CuSO4*5H2O`.
+            '*' | '.' => {
+                out.push_str("\\cdot ");
+                out.push_str(&species(after));
+                rest = "";
+            }
+            '(' | '[' => {
+                let close = if c == '(' { ')' } else { ']' };
+                match bracketed(rest, c, close) {
+                    Some((inside, next)) if STATES.contains(&&rest[..inside.len() + 2]) => {
+                        out.push_str(&format!("\\text{{{}{}{}}}", c, inside, close));
+                        rest = next;
+                    }
+                    Some((inside, next)) => {
+                        out.push_str(&format!("{}{}{}", c, species(inside), close));
+                        rest = next;
+                    }
+                    None => {
+                        out.push(c);
+                        rest = after;
+                    }
+                }
+            }
+            '$' => match after.find('$') {
+                Some(end) => {
+                    out.push_str(&after[..end]);
+                    rest = &after[end + 1..];
+                }
+                None => rest = after,
+            },
+            _ => {
+                out.push(c);
+                rest = after;
+            }
+        }
+    }
+    out
+}
+
+// The words of a reaction, split on spaces outside braces and brackets, so that
+// `->[H2O, heat]` stays one.
+fn words(ce: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in ce.char_indices() {
+        match c {
+            '{' | '[' | '(' => depth += 1,
+            '}' | ']' | ')' => depth -= 1,
+            c if c.is_whitespace() && depth == 0 => {
+                words.push(&ce[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    words.push(&ce[start..]);
+    words.into_iter().filter(|word| !word.is_empty()).collect()
+}
+
+// The LaTeX for the contents of `\ce{...}`: species, reaction arrows with their
+// labels, gas and precipitate arrows.
+pub fn to_latex(ce: &str) -> String {
+    let mut out = Vec::new();
+    for word in words(ce) {
+        let arrow = ARROWS.iter().find(|(arrow, _)| word.starts_with(arrow));
+        let text = match (word, arrow) {
+            ("^", _) => "\\uparrow".to_string(),
+            ("v", _) => "\\downarrow".to_string(),
+            (_, Some((arrow, drawn))) => {
+                let rest = &word[arrow.len()..];
+                // Labels above and below, as in `->[H2O][heat]`.
+                match bracketed(rest, '[', ']') {
+                    Some((above, rest)) if rest.is_empty() || rest.starts_with('[') => {
+                        let drawn = format!("\\overset{{{}}}{{{}}}", label(above), drawn);
+                        match bracketed(rest, '[', ']') {
+                            Some((below, _)) => {
+                                format!("\\underset{{{}}}{{{}}}", label(below), drawn)
+                            }
+                            None => drawn,
+                        }
+                    }
+                    _ if rest.is_empty() => drawn.to_string(),
+                    _ => species(word),
+                }
+            }
+            _ => species(word),
+        };
+        out.push(text);
+    }
+    out.join(" ")
+}
+
+// An arrow label: words like `heat` as text, anything else as a formula.
+fn label(text: &str) -> String {
+    let text = text.trim();
+    if text
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || " ,".contains(c))
+    {
+        format!("\\text{{{}}}", text)
+    } else {
+        to_latex(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn species() {
+        assert_eq!(to_latex("H2O"), r"\text{H}_{2}\text{O}");
+        assert_eq!(to_latex("SO4^2-"), r"\text{SO}_{4}^{2-}");
+        assert_eq!(to_latex("Fe^3+"), r"\text{Fe}^{3+}");
+        assert_eq!(to_latex("^{14}_{6}C"), r"{}^{14}_{6}\text{C}");
+        assert_eq!(to_latex("(NH4)2SO4"), r"(\text{NH}_{4})_{2}\text{SO}_{4}");
+        assert_eq!(
+            to_latex("CH3-CH2OH"),
+            r"\text{CH}_{3}-\text{CH}_{2}\text{OH}"
+        );
+        assert_eq!(to_latex("NaCl(aq)"), r"\text{NaCl}\text{(aq)}");
+    }
+
+    #[test]
+    fn reactions() {
+        assert_eq!(
+            to_latex("2H2 + O2 -> 2H2O"),
+            r"2\,\text{H}_{2} + \text{O}_{2} \longrightarrow 2\,\text{H}_{2}\text{O}"
+        );
+        assert_eq!(to_latex("A <=> B"), r"\text{A} \rightleftharpoons \text{B}");
+        // Not read as `<=>` followed by `>`.
+        assert_eq!(
+            to_latex("A <=>> B"),
+            r"\text{A} \rightleftharpoons \text{B}"
+        );
+        assert_eq!(to_latex("CaCO3 v"), r"\text{CaCO}_{3} \downarrow");
+        assert_eq!(to_latex("CO2 ^"), r"\text{CO}_{2} \uparrow");
+        assert_eq!(to_latex("A ->"), r"\text{A} \longrightarrow");
+    }
+
+    #[test]
+    fn arrow_labels() {
+        assert_eq!(
+            to_latex("A ->[heat] B"),
+            r"\text{A} \overset{\text{heat}}{\longrightarrow} \text{B}"
+        );
+        assert_eq!(
+            to_latex("A ->[H2O][heat] B"),
+            r"\text{A} \underset{\text{heat}}{\overset{\text{H}_{2}\text{O}}{\longrightarrow}} \text{B}"
+        );
+    }
+
+    #[test]
+    fn charges_and_complexes() {
+        assert_eq!(to_latex("e-"), "e^{-}");
+        assert_eq!(to_latex("H+"), r"\text{H}^{+}");
+        assert_eq!(
+            to_latex("[Cu(NH3)4]^2+"),
+            r"[\text{Cu}(\text{NH}_{3})_{4}]^{2+}"
+        );
+        assert_eq!(
+            to_latex("CuSO4*5H2O"),
+            r"\text{CuSO}_{4}\cdot 5\,\text{H}_{2}\text{O}"
+        );
+        assert_eq!(to_latex(""), "");
+    }
+
+    #[test]
+    fn coefficients_apart_from_the_species() {
+        assert_eq!(to_latex("2 H2O"), r"2 \text{H}_{2}\text{O}");
+        assert_eq!(to_latex("1/2 O2"), r"1/2 \text{O}_{2}");
+    }
+
+    #[test]
+    fn other_arrows_and_empty_labels() {
+        assert_eq!(to_latex("A <- B"), r"\text{A} \longleftarrow \text{B}");
+        assert_eq!(to_latex("A <-> B"), r"\text{A} \leftrightarrow \text{B}");
+        assert_eq!(
+            to_latex("A ->[][cat] B"),
+            r"\text{A} \underset{\text{cat}}{\overset{\text{}}{\longrightarrow}} \text{B}"
+        );
+    }
+}
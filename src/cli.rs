@@ -0,0 +1,202 @@
+use crate::convert::Direction;
+use crate::project::{self, Status};
+use crate::{backend, native_messaging, notebook, pool, url_scheme};
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+
+pub const USAGE: &str = "Usage:
+  typst2latex [--paste | <expression>]         Start the GUI (unless built without it), or
+                                               bring the open window forward and convert
+                                               the expression or the clipboard in it
+  typst2latex nb convert <notebook.ipynb> [--reverse] [-o <output.ipynb>]
+                                               Convert Typst math in Markdown cells to
+                                               LaTeX (or LaTeX to Typst with --reverse)
+  typst2latex project <dir> --out <dir> [--reverse] [--jobs <n>] [--report <report.json>]
+                                               Mirror the .typ and .md files of a directory
+                                               with their math converted to LaTeX (or the
+                                               .tex and .md files to Typst with --reverse),
+                                               skipping files unchanged since the last run,
+                                               on <n> threads (one per CPU by default), and
+                                               write the status and warnings of every file
+                                               to <report.json>
+  typst2latex typst2latex://convert?src=<text>[&direction=<direction>]
+                                               Start the GUI with the percent-encoded text
+  typst2latex register-url-scheme              Open typst2latex:// links with this program
+  typst2latex backends                         List the backends: pandoc, and the plugins,
+                                               programs named typst2latex-backend-<name>
+                                               in TYPST2LATEX_PLUGIN_PATH or PATH
+  typst2latex --native-messaging               Serve a browser extension over native messaging
+
+Options:
+  --verbose                                    Log backend invocations and timings to stderr
+  --portable                                   Keep settings, history and caches in a
+                                               typst2latex-data folder beside the program
+                                               (the default once that folder exists)
+  --backend <name>                             Convert with a plugin instead of pandoc";
+
+// Run a command-line subcommand. Returns `None` when the arguments do not name
+// one, in which case the GUI starts.
+pub fn run(args: &[String]) -> Option<i32> {
+    if args.first().map(String::as_str) == Some("--native-messaging")
+        || native_messaging::is_browser_launch(args)
+    {
+        return Some(match native_messaging::serve() {
+            Ok(()) => 0,
+            Err(err) => {
+                eprintln!("Error: {}", err);
+                1
+            }
+        });
+    }
+    let result = match args.first().map(String::as_str) {
+        Some("nb") => notebook_command(&args[1..]),
+        Some("project") => project_command(&args[1..]),
+        Some("register-url-scheme") => url_scheme::register().map(|location| {
+            println!("Registered {}:// links in {}", url_scheme::SCHEME, location);
+        }),
+        Some("backends") => {
+            for name in backend::names() {
+                println!("{}", name);
+            }
+            Ok(())
+        }
+        Some("-h" | "--help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => return None,
+    };
+    Some(match result {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            1
+        }
+    })
+}
+
+fn notebook_command(args: &[String]) -> Result<(), String> {
+    if args.first().map(String::as_str) != Some("convert") {
+        return Err(format!("Unknown notebook command\n\n{}", USAGE));
+    }
+    let mut path = None;
+    let mut output = None;
+    let mut direction = Direction::TypstToLatex;
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--reverse" => direction = Direction::LatexToTypst,
+            "-o" | "--output" => output = Some(args.next().ok_or("Missing output path")?),
+            _ if path.is_none() => path = Some(arg),
+            _ => return Err(format!("Unexpected argument '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+    let path = path.ok_or(format!("Missing notebook path\n\n{}", USAGE))?;
+
+    let text = fs::read_to_string(path).map_err(|_| format!("Failed to read {}", path))?;
+    let (converted, warnings) = notebook::convert_notebook(&text, direction)?;
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    // Rewrite the notebook in place unless an output path is given.
+    let output = output.unwrap_or(path);
+    fs::write(output, converted).map_err(|_| format!("Failed to write {}", output))
+}
+
+fn project_command(args: &[String]) -> Result<(), String> {
+    let mut source = None;
+    let mut out = None;
+    let mut direction = Direction::TypstToLatex;
+    let mut jobs = pool::default_jobs();
+    let mut report = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--reverse" => direction = Direction::LatexToTypst,
+            "-j" | "--jobs" => {
+                jobs = args
+                    .next()
+                    .and_then(|jobs| jobs.parse().ok())
+                    .filter(|&jobs| jobs > 0)
+                    .ok_or("--jobs needs a number of threads")?
+            }
+            "-o" | "--out" => out = Some(args.next().ok_or("Missing output directory")?),
+            "--report" => report = Some(args.next().ok_or("Missing report path")?),
+            _ if source.is_none() => source = Some(arg),
+            _ => return Err(format!("Unexpected argument '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+    let source = source.ok_or(format!("Missing project directory\n\n{}", USAGE))?;
+    let out = out.ok_or(format!("Missing output directory\n\n{}", USAGE))?;
+
+    let reports = project::convert_project(
+        Path::new(source),
+        Path::new(out),
+        direction,
+        jobs,
+        draw_progress,
+    );
+    if std::io::stderr().is_terminal() {
+        eprint!("\r\x1b[K");
+    }
+    let reports = reports?;
+    if let Some(report) = report {
+        fs::write(report, project::report_json(&reports).pretty(2))
+            .map_err(|_| format!("Failed to write {}", report))?;
+    }
+    let (mut converted, mut unchanged, mut failed, mut warned) = (0, 0, 0, 0);
+    for report in &reports {
+        match &report.status {
+            Status::Converted => {
+                converted += 1;
+                eprintln!("{:<9}  {}", report.status.name(), report.path);
+            }
+            Status::Unchanged => unchanged += 1,
+            Status::Failed(err) => {
+                failed += 1;
+                eprintln!("{:<9}  {}: {}", report.status.name(), report.path, err);
+            }
+        }
+        if !report.warnings.is_empty() {
+            warned += 1;
+            for warning in &report.warnings {
+                eprintln!("warning    {}: {}", report.path, warning);
+            }
+        }
+    }
+    println!(
+        "{} files: {} converted, {} unchanged, {} with warnings, {} failed",
+        reports.len(),
+        converted,
+        unchanged,
+        warned,
+        failed
+    );
+    if failed > 0 {
+        return Err(format!("{} files could not be converted", failed));
+    }
+    Ok(())
+}
+
+// `[######--------------] 12/40  chapters/intro.typ` on one line of stderr. The
+// bar is redrawn in place, so it's left out when stderr goes to a file or a pipe.
+fn draw_progress(done: usize, total: usize, path: &str) {
+    const WIDTH: usize = 20;
+    let mut stderr = std::io::stderr().lock();
+    if !stderr.is_terminal() {
+        return;
+    }
+    let filled = (done * WIDTH).checked_div(total).unwrap_or(WIDTH);
+    let _ = write!(
+        stderr,
+        "\r\x1b[K[{}{}] {:>w$}/{}  {}",
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        done,
+        total,
+        path,
+        w = total.to_string().len()
+    );
+    let _ = stderr.flush();
+}
@@ -0,0 +1,75 @@
+use crate::diagnostics;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// On X11 and Wayland the clipboard is served by the app that copied, so its content
+// goes away when the app quits. These tools fork a small process that keeps serving
+// the text until something else is copied.
+const HANDOFF_COMMANDS: [(&str, &[&str]); 3] = [
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+// The same tools set the primary selection, which the clipboard crate can't.
+const PRIMARY_COMMANDS: [(&str, &[&str]); 3] = [
+    ("wl-copy", &["--primary"]),
+    ("xclip", &["-selection", "primary"]),
+    ("xsel", &["--primary", "--input"]),
+];
+
+// Pipe `text` into the first of `commands` that runs, returning its name.
+fn pipe_to_first(commands: &[(&'static str, &[&str])], text: &str) -> Option<&'static str> {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    for &(program, args) in commands {
+        // wl-copy only works under Wayland; the X11 tools also work through
+        // XWayland.
+        if program == "wl-copy" && !wayland {
+            continue;
+        }
+        let Ok(mut child) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        let written = child
+            .stdin
+            .take()
+            .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        if written {
+            return Some(program);
+        }
+    }
+    None
+}
+
+// Hand the copied text over to a process that outlives the app. Does nothing on
+// platforms where the clipboard keeps its content by itself.
+pub fn hand_off(text: &str) -> Result<(), String> {
+    if !cfg!(target_os = "linux") {
+        return Ok(());
+    }
+    let program = pipe_to_first(&HANDOFF_COMMANDS, text).ok_or_else(|| {
+        "No tool found to keep the clipboard after quitting (install wl-clipboard, xclip or xsel)"
+            .to_string()
+    })?;
+    diagnostics::log(format!("Handed the clipboard off to {}", program));
+    Ok(())
+}
+
+// Put `text` in the primary selection, for pasting with the middle mouse button.
+pub fn set_primary(text: &str) -> Result<(), String> {
+    if !cfg!(target_os = "linux") {
+        return Ok(());
+    }
+    pipe_to_first(&PRIMARY_COMMANDS, text)
+        .map(|_| ())
+        .ok_or_else(|| {
+            "No tool found to set the primary selection (install wl-clipboard, xclip or xsel)"
+                .to_string()
+        })
+}
@@ -0,0 +1,211 @@
+use crate::latex::{self, Token};
+
+// Translate LaTeX math into a ConTeXt display formula. Most math commands are the
+// same; environments become `\start...`/`\stop...` pairs with `\NC` and `\NR`
+// marking cells and rows, and a few commands have ConTeXt names.
+pub fn from_latex(latex: &str) -> String {
+    let mut out = String::new();
+    emit(&latex::parse(latex), &mut out);
+    format!("\\startformula\n{}\n\\stopformula", out.trim())
+}
+
+fn emit(tokens: &[Token], out: &mut String) {
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Command(name) => {
+                i = emit_command(name, tokens, i + 1, out);
+                continue;
+            }
+            Token::Group(group) => emit_group(group, out),
+            Token::Char(c) => out.push(*c),
+            Token::Space => out.push(' '),
+        }
+        i += 1;
+    }
+}
+
+fn emit_group(tokens: &[Token], out: &mut String) {
+    out.push('{');
+    emit(tokens, out);
+    out.push('}');
+}
+
+// Emit the argument starting at `i` as a braced group and return the index after it.
+fn emit_argument(tokens: &[Token], i: usize, out: &mut String) -> usize {
+    let (argument, next) = latex::argument(tokens, i);
+    emit_group(argument, out);
+    next
+}
+
+// The ConTeXt environment for a LaTeX one, with its options.
+fn environment(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "matrix" | "smallmatrix" | "array" => "matrix",
+        "pmatrix" => "pmatrix",
+        "bmatrix" => "bmatrix",
+        "Bmatrix" => "Bmatrix",
+        "vmatrix" => "vmatrix",
+        "Vmatrix" => "Vmatrix",
+        "cases" | "dcases" => "cases",
+        "aligned" | "align" | "align*" | "alignedat" | "split" => "align",
+        "gathered" | "gather" | "gather*" => "align[align=middle]",
+        _ => return None,
+    })
+}
+
+// Rows of cells as `\NC cell \NC cell \NR`, one row per line.
+fn emit_rows(body: &[Token], out: &mut String) {
+    for row in latex::split_rows(body) {
+        for cell in row {
+            let mut cell_out = String::new();
+            emit(cell, &mut cell_out);
+            out.push_str(&format!("\\NC {} ", cell_out.trim()));
+        }
+        out.push_str("\\NR\n");
+    }
+}
+
+fn emit_command(name: &str, tokens: &[Token], i: usize, out: &mut String) -> usize {
+    match name {
+        "begin" => {
+            let (name, mut body, after) = latex::environment(tokens, i);
+            let Some(context) = environment(&name) else {
+                out.push_str(&format!(
+                    "\\begin{{{name}}}{}\\end{{{name}}}",
+                    latex::to_string(body)
+                ));
+                return after;
+            };
+            // Column specifications, as of `array` and `alignedat`, have no
+            // counterpart.
+            if let Some(Token::Group(_)) = body.get(latex::skip_spaces(body, 0)) {
+                body = &body[latex::skip_spaces(body, 0) + 1..];
+            }
+            let stop = context.split('[').next().unwrap_or(context);
+            out.truncate(out.trim_end().len());
+            out.push_str(&format!("\n\\start{}\n", context));
+            emit_rows(body, out);
+            out.push_str(&format!("\\stop{}\n", stop));
+            after
+        }
+        "substack" => {
+            let (body, i) = latex::argument(tokens, i);
+            out.push_str("\\startsubstack ");
+            let rows: Vec<String> = latex::split_rows(body)
+                .iter()
+                .map(|row| {
+                    let mut row_out = String::new();
+                    for cell in row {
+                        emit(cell, &mut row_out);
+                    }
+                    row_out.trim().to_string()
+                })
+                .collect();
+            out.push_str(&rows.join(" \\NR "));
+            out.push_str(" \\stopsubstack");
+            i
+        }
+        "text" | "textrm" | "mbox" => {
+            out.push_str("\\mtext");
+            emit_argument(tokens, i, out)
+        }
+        "operatorname" => {
+            out.push_str("\\mfunction");
+            emit_argument(tokens, i, out)
+        }
+        "mathbb" | "mathfrak" | "mathcal" => {
+            out.push_str(match name {
+                "mathbb" => "\\mathblackboard",
+                "mathfrak" => "\\mathfraktur",
+                _ => "\\mathscript",
+            });
+            emit_argument(tokens, i, out)
+        }
+        // Plain TeX commands, which ConTeXt shares, keep their name.
+        name => {
+            out.push('\\');
+            out.push_str(name);
+            // Keep a command name from running into a following letter.
+            if let Some(Token::Char(c)) = tokens.get(i) {
+                if c.is_ascii_alphabetic() && name.chars().all(|c| c.is_ascii_alphabetic()) {
+                    out.push(' ');
+                }
+            }
+            i
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_stay_latex() {
+        assert_eq!(
+            from_latex(r"\frac{a}{b} + x^{2}"),
+            "\\startformula\n\\frac{a}{b} + x^{2}\n\\stopformula"
+        );
+        assert_eq!(
+            from_latex(r"\left( x \right)"),
+            "\\startformula\n\\left( x \\right)\n\\stopformula"
+        );
+    }
+
+    #[test]
+    fn environments_become_start_stop_pairs() {
+        assert_eq!(
+            from_latex(r"\begin{aligned} a &= b \\ c &= d \end{aligned}"),
+            "\\startformula\n\\startalign\n\\NC a \\NC = b \\NR\n\\NC c \\NC = d \\NR\n\\stopalign\n\\stopformula"
+        );
+        assert_eq!(
+            from_latex(r"\begin{pmatrix} 1 & 2 \\ 3 & 4 \end{pmatrix}"),
+            "\\startformula\n\\startpmatrix\n\\NC 1 \\NC 2 \\NR\n\\NC 3 \\NC 4 \\NR\n\\stoppmatrix\n\\stopformula"
+        );
+        assert_eq!(
+            from_latex(r"\begin{gathered} a \\ b \end{gathered}"),
+            "\\startformula\n\\startalign[align=middle]\n\\NC a \\NR\n\\NC b \\NR\n\\stopalign\n\\stopformula"
+        );
+    }
+
+    #[test]
+    fn context_names() {
+        assert_eq!(
+            from_latex(r"\mathbb{R} \mathcal{F}"),
+            "\\startformula\n\\mathblackboard{R} \\mathscript{F}\n\\stopformula"
+        );
+        assert_eq!(
+            from_latex(r"\text{if } x"),
+            "\\startformula\n\\mtext{if } x\n\\stopformula"
+        );
+    }
+
+    #[test]
+    fn column_specifications_are_dropped() {
+        assert_eq!(
+            from_latex(r"\begin{array}{c|c} a & b \end{array}"),
+            "\\startformula\n\\startmatrix\n\\NC a \\NC b \\NR\n\\stopmatrix\n\\stopformula"
+        );
+    }
+
+    #[test]
+    fn unknown_environments_are_kept() {
+        assert_eq!(
+            from_latex(r"\begin{foo} a \end{foo}"),
+            "\\startformula\n\\begin{foo} a \\end{foo}\n\\stopformula"
+        );
+    }
+
+    #[test]
+    fn operators_and_stacks() {
+        assert_eq!(
+            from_latex(r"\operatorname{rank} A"),
+            "\\startformula\n\\mfunction{rank} A\n\\stopformula"
+        );
+        assert_eq!(
+            from_latex(r"\sum_{\substack{i \\ j}} a"),
+            "\\startformula\n\\sum_{\\startsubstack i \\NR j \\stopsubstack} a\n\\stopformula"
+        );
+    }
+}
@@ -0,0 +1,761 @@
+use crate::arrays;
+use crate::backend;
+use crate::diagnostics;
+use crate::packages;
+use crate::units;
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    TypstToLatex,
+    LatexToTypst,
+    Auto,
+}
+
+impl Direction {
+    #[cfg(feature = "gui")]
+    pub fn label(self) -> &'static str {
+        match self {
+            Direction::TypstToLatex => "Typst → LaTeX",
+            Direction::LatexToTypst => "LaTeX → Typst",
+            Direction::Auto => "Auto",
+        }
+    }
+
+    // Machine-readable name used by the command line and the native messaging host.
+    pub fn name(self) -> &'static str {
+        match self {
+            Direction::TypstToLatex => "typst-to-latex",
+            Direction::LatexToTypst => "latex-to-typst",
+            Direction::Auto => "auto",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Direction> {
+        [
+            Direction::TypstToLatex,
+            Direction::LatexToTypst,
+            Direction::Auto,
+        ]
+        .into_iter()
+        .find(|direction| direction.name() == name)
+    }
+
+    // Resolve `Auto` into a concrete direction by looking at the input.
+    pub fn resolve(self, input: &str) -> Direction {
+        match self {
+            Direction::Auto => detect_direction(input),
+            direction => direction,
+        }
+    }
+}
+
+// Guess the input language: LaTeX is full of backslash commands and braces, while
+// Typst uses bare function names with parentheses, dotted symbol names and strings.
+pub fn detect_direction(input: &str) -> Direction {
+    let chars: Vec<char> = input.chars().collect();
+    let mut latex_score = 0;
+    let mut typst_score = 0;
+    for (i, &c) in chars.iter().enumerate() {
+        let next = chars.get(i + 1).copied().unwrap_or(' ');
+        let prev = if i > 0 { chars[i - 1] } else { ' ' };
+        match c {
+            '\\' if next.is_ascii_alphabetic() => latex_score += 2,
+            '{' | '}' if prev != '\\' => latex_score += 1,
+            '&' => latex_score += 1,
+            '"' => typst_score += 1,
+            '(' if prev.is_ascii_alphabetic() => typst_score += 2,
+            '.' if prev.is_ascii_alphabetic() && next.is_ascii_alphabetic() => typst_score += 1,
+            _ => {}
+        }
+    }
+    // A backslash command followed by a parenthesis (`\left(`) is no sign of Typst.
+    typst_score -= input.matches("\\left(").count() as i32 * 2;
+    if latex_score > typst_score {
+        Direction::LatexToTypst
+    } else {
+        Direction::TypstToLatex
+    }
+}
+
+// Convert with the backend chosen with `backend::select`, pandoc by default.
+pub fn convert(input: &str, direction: Direction) -> Result<String, String> {
+    backend::selected().convert(input, direction.resolve(input))
+}
+
+// Older versions of pandoc fail with a bare "Unknown input format typst".
+fn check_typst_reader() -> Result<(), String> {
+    if pandoc_reads_typst() {
+        return Ok(());
+    }
+    Err(format!(
+        "pandoc {} can't read Typst, which needs pandoc {} or newer. \
+         Install a newer version from https://pandoc.org/installing.html",
+        pandoc_version().unwrap_or_default(),
+        TYPST_READER_VERSION
+    ))
+}
+
+pub fn convert_typst_to_latex(input: &str) -> Result<String, String> {
+    check_typst_reader()?;
+    let input = prepare_typst(input);
+    // Add delimiters to treat input as math.
+    let latex = run_pandoc("typst", "latex", &[], &format!("$\n{}\n$", input))?;
+    let latex = latex
+        .trim_start_matches(r"\[")
+        .trim_end_matches(r"\]") // Remove LaTeX math delimiters.
+        .trim();
+    Ok(arrays::to_arrays(&substack_attachments(latex)))
+}
+
+// What pandoc is given of Typst input, with the constructs it misreads rewritten.
+fn prepare_typst(input: &str) -> String {
+    let input = units::expand_unit_calls(input);
+    stack_multiline_attachments(&arrays::mark_augmented(&input))
+}
+
+// What pandoc is given of LaTeX input, with package commands expanded.
+fn prepare_latex(input: &str) -> String {
+    arrays::mark_arrays(&packages::expand(input))
+}
+
+// Whether the rewriting before pandoc moves lines, e.g. by collapsing a multi-line
+// subscript, so that the lines pandoc reports aren't the input's.
+#[cfg(feature = "gui")]
+pub fn moves_lines(input: &str, direction: Direction) -> bool {
+    let prepared = match direction.resolve(input) {
+        Direction::LatexToTypst => prepare_latex(input),
+        _ => prepare_typst(input),
+    };
+    prepared.lines().count() != input.lines().count()
+}
+
+pub fn convert_latex_to_typst(input: &str) -> Result<String, String> {
+    let input = prepare_latex(input);
+    let typst = run_pandoc("latex", "typst", &[], &format!("\\[\n{}\n\\]", input))?;
+    Ok(arrays::to_augmented(
+        typst
+            .trim_start_matches('$')
+            .trim_end_matches('$') // Remove Typst math delimiters.
+            .trim(),
+    ))
+}
+
+// A whole Typst or LaTeX document, prose and all, in the other language, as for
+// the `.typ` and `.tex` files of a project. Always through pandoc, since backends
+// only take math.
+pub fn convert_document(input: &str, direction: Direction) -> Result<String, String> {
+    match direction.resolve(input) {
+        Direction::LatexToTypst => run_pandoc("latex", "typst", &[], input),
+        _ => {
+            check_typst_reader()?;
+            run_pandoc("typst", "latex", &[], input)
+        }
+    }
+}
+
+// The installed pandoc version, e.g. "3.2", queried once and cached.
+pub fn pandoc_version() -> Option<&'static str> {
+    static VERSION: OnceLock<Option<String>> = OnceLock::new();
+    VERSION
+        .get_or_init(|| {
+            let output = Command::new("pandoc").arg("--version").output().ok()?;
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            // The first line reads `pandoc 3.2`.
+            let version = stdout.lines().next()?.split_whitespace().nth(1)?;
+            Some(version.to_string())
+        })
+        .as_deref()
+}
+
+// The first pandoc release with a Typst reader.
+pub const TYPST_READER_VERSION: &str = "3.1.2";
+
+fn version_numbers(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+// Whether the installed pandoc can read Typst. A missing pandoc is reported on its
+// own, and a version string we can't make sense of gets the benefit of the doubt.
+pub fn pandoc_reads_typst() -> bool {
+    let Some(version) = pandoc_version() else {
+        return true;
+    };
+    let numbers = version_numbers(version);
+    numbers.is_empty() || numbers >= version_numbers(TYPST_READER_VERSION)
+}
+
+// MathML for LaTeX math, which screen readers and speech engines understand.
+#[cfg(feature = "gui")]
+pub fn convert_latex_to_mathml(input: &str) -> Result<String, String> {
+    let html = run_pandoc(
+        "latex",
+        "html",
+        &["--mathml"],
+        &format!("\\[\n{}\n\\]", input),
+    )?;
+    let start = html.find("<math").ok_or("pandoc produced no MathML")?;
+    let end = html.rfind("</math>").ok_or("pandoc produced no MathML")?;
+    Ok(html[start..end + "</math>".len()].to_string())
+}
+
+// Windows-1252 characters for bytes 0x80 to 0x9f; the rest of the code page matches
+// Latin-1. Unassigned bytes map to U+FFFD.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{fffd}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{fffd}', 'Ž',
+    '\u{fffd}', '\u{fffd}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{fffd}',
+    'ž', 'Ÿ',
+];
+
+// Decode the output of a subprocess. Pandoc writes UTF-8, but on some Windows setups
+// the text comes out as UTF-16 or in the ANSI code page, which a lossy UTF-8 decode
+// would silently turn into garbage.
+pub fn decode_output(bytes: &[u8]) -> Result<String, String> {
+    let utf8_error = match String::from_utf8(bytes.to_vec()) {
+        Ok(text) => return Ok(text),
+        Err(err) => err.utf8_error(),
+    };
+    if let Some(rest) = bytes.strip_prefix(&[0xff, 0xfe]) {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16(&units).map_err(|_| "Output is not valid UTF-16".to_string());
+    }
+    if cfg!(windows) {
+        return Ok(bytes
+            .iter()
+            .map(|&byte| match byte {
+                0x80..=0x9f => WINDOWS_1252[byte as usize - 0x80],
+                _ => byte as char,
+            })
+            .collect());
+    }
+    Err(format!(
+        "Output is not valid UTF-8 (invalid byte at offset {})",
+        utf8_error.valid_up_to()
+    ))
+}
+
+// The error of a conversion stopped with `cancel_on`.
+pub const CANCELLED: &str = "Conversion cancelled";
+
+thread_local! {
+    static CANCEL: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+// Kill pandoc, and fail every conversion after, once `flag` is set. Applies to
+// conversions on the calling thread, which is meant to be a worker of its own.
+#[cfg(feature = "gui")]
+pub fn cancel_on(flag: Arc<AtomicBool>) {
+    CANCEL.with(|cancel| *cancel.borrow_mut() = Some(flag));
+}
+
+fn cancel_flag() -> Option<Arc<AtomicBool>> {
+    CANCEL.with(|cancel| cancel.borrow().clone())
+}
+
+pub fn cancelled() -> bool {
+    cancel_flag().is_some_and(|flag| flag.load(Ordering::Relaxed))
+}
+
+// `wait_with_output`, except that the converter, `program`, is killed when the
+// conversion is cancelled.
+pub fn wait(mut child: Child, program: &str) -> Result<Output, String> {
+    let Some(flag) = cancel_flag() else {
+        return child
+            .wait_with_output()
+            .map_err(|_| "Failed to read stdout and stderr".to_string());
+    };
+    // Read both pipes while waiting, or pandoc stalls once one of them fills up.
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            if let Some(mut pipe) = pipe {
+                pipe.read_to_end(&mut bytes).ok();
+            }
+            bytes
+        })
+    };
+    let stdout = read(child.stdout.take().map(|pipe| Box::new(pipe) as _));
+    let stderr = read(child.stderr.take().map(|pipe| Box::new(pipe) as _));
+    let status = loop {
+        if flag.load(Ordering::Relaxed) {
+            child.kill().ok();
+            child.wait().ok();
+            diagnostics::log(format!("Killed {}", program));
+            return Err(CANCELLED.to_string());
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => thread::sleep(Duration::from_millis(10)),
+            Err(_) => return Err(format!("Failed to wait for {}", program)),
+        }
+    };
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn run_pandoc(from: &str, to: &str, args: &[&str], input: &str) -> Result<String, String> {
+    diagnostics::log(format!(
+        "pandoc -f {} -t {} {}({} bytes)",
+        from,
+        to,
+        args.iter()
+            .map(|arg| format!("{} ", arg))
+            .collect::<String>(),
+        input.len()
+    ));
+    if cancelled() {
+        return Err(CANCELLED.to_string());
+    }
+    let start = Instant::now();
+    let mut child = Command::new("pandoc")
+        .arg("-f")
+        .arg(from)
+        .arg("-t")
+        .arg(to)
+        .args(args)
+        .arg("--")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            diagnostics::log(format!("Failed to spawn pandoc: {}", err));
+            "Failed to execute pandoc. Do you have it installed?"
+        })?;
+
+    child
+        .stdin
+        .take()
+        .ok_or("Failed to open stdin")?
+        .write_all(input.as_bytes())
+        .map_err(|_| "Failed to write to stdin")?;
+    let output = wait(child, "pandoc")?;
+    diagnostics::log(format!(
+        "pandoc exited with {} after {} ms",
+        output.status,
+        start.elapsed().as_millis()
+    ));
+    // Messages are still worth showing when their encoding is off.
+    let error = decode_output(&output.stderr)
+        .unwrap_or_else(|_| String::from_utf8_lossy(&output.stderr).to_string());
+    if !error.is_empty() {
+        diagnostics::log(format!("pandoc stderr: {}", error.trim()));
+    }
+
+    if output.status.success() {
+        let stdout = decode_output(&output.stdout).map_err(|err| {
+            diagnostics::log(format!("Failed to decode pandoc output: {}", err));
+            format!("Failed to read pandoc's output: {}", err)
+        })?;
+        Ok(stdout.trim().to_string())
+    } else {
+        Err(Diagnostic::parse(&error).to_string())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ErrorClass {
+    // The reader rejected the input at a known position.
+    Syntax,
+    // This pandoc doesn't know one of the formats, usually because it's too old.
+    UnknownFormat,
+    Other,
+}
+
+impl ErrorClass {
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorClass::Syntax => "syntax",
+            ErrorClass::UnknownFormat => "unknown-format",
+            ErrorClass::Other => "other",
+        }
+    }
+}
+
+// A conversion error split into its parts. Line and column are 1-based and count
+// from the start of the input.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub class: ErrorClass,
+}
+
+// Read a number at the start of `text`.
+fn leading_number(text: &str) -> Option<usize> {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    text[..end].parse().ok()
+}
+
+fn classify(message: &str, line: Option<usize>) -> ErrorClass {
+    if message.starts_with("Unknown input format") || message.starts_with("Unknown output format") {
+        ErrorClass::UnknownFormat
+    } else if line.is_some() {
+        ErrorClass::Syntax
+    } else {
+        ErrorClass::Other
+    }
+}
+
+impl Diagnostic {
+    // Parse pandoc's stderr. Reader errors look like
+    //
+    //     Error at "source" (line 2, column 5):
+    //     unexpected "}"
+    //     expecting expression
+    //
+    // with the message on the lines after the header.
+    fn parse(stderr: &str) -> Self {
+        let stderr = stderr.trim();
+        let (header, rest) = stderr.split_once('\n').unwrap_or((stderr, ""));
+        let position = header.find("(line ").and_then(|start| {
+            let position = &header[start + "(line ".len()..];
+            let line = leading_number(position)?;
+            let column =
+                leading_number(&position[position.find(", column ")? + ", column ".len()..])?;
+            Some((line, column))
+        });
+        let message = match position {
+            Some(_) if !rest.trim().is_empty() => rest
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            // Everything is on one line: drop the header up to the position.
+            Some(_) => header
+                .rsplit_once("):")
+                .map_or(header, |(_, message)| message)
+                .trim()
+                .to_string(),
+            None => stderr.to_string(),
+        };
+        // Pandoc counts from the line with the opening math delimiter, which every
+        // caller puts on a line of its own, so the input starts on line 2.
+        let (line, column) = match position {
+            Some((line, column)) if line > 1 => (Some(line - 1), Some(column)),
+            _ => (None, None),
+        };
+        Self {
+            class: classify(&message, position.map(|(line, _)| line)),
+            message,
+            line,
+            column,
+        }
+    }
+
+    // Recover the parts of an error returned by `convert`.
+    pub fn from_error(error: &str) -> Self {
+        let position = error.strip_prefix("line ").and_then(|rest| {
+            let (position, message) = rest.split_once(": ")?;
+            let (line, column) = position.split_once(", column ")?;
+            Some((line.parse().ok()?, column.parse().ok()?, message))
+        });
+        let (line, column, message) = match position {
+            Some((line, column, message)) => (Some(line), Some(column), message),
+            None => (None, None, error),
+        };
+        Self {
+            message: message.to_string(),
+            line,
+            column,
+            class: classify(message, line),
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "line {}, column {}: {}", line, column, self.message)
+            }
+            _ => f.write_str(&self.message),
+        }
+    }
+}
+
+// The 1-based input line a conversion error points at, if pandoc reported one.
+#[cfg(feature = "gui")]
+pub fn error_line(error: &str) -> Option<usize> {
+    Diagnostic::from_error(error).line
+}
+
+// Index of the delimiter closing the group opened at `open`. Typst math allows
+// mismatched pairs such as `[0, 1)`, so every opening and closing delimiter counts.
+fn find_closing_delimiter(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1, // Skip escaped characters.
+            '"' => i = skip_string(chars, i),
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+// Index of the quote closing the string literal opened at `open`.
+fn skip_string(chars: &[char], open: usize) -> usize {
+    let mut i = open + 1;
+    while i < chars.len() && chars[i] != '"' {
+        if chars[i] == '\\' {
+            i += 1;
+        }
+        i += 1;
+    }
+    i
+}
+
+// Split the content of an attachment on Typst linebreaks (`\` followed by whitespace)
+// and literal newlines that are not nested inside another group.
+fn split_math_lines(content: &str) -> Vec<String> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut depth = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if depth == 0 && chars.get(i + 1).is_none_or(|c| c.is_whitespace()) => {
+                lines.push(chars[start..i].iter().collect::<String>());
+                start = i + 1;
+            }
+            '\\' => i += 1,
+            '"' => i = skip_string(&chars, i),
+            '\n' if depth == 0 => {
+                lines.push(chars[start..i].iter().collect::<String>());
+                start = i + 1;
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    lines.push(chars[start.min(chars.len())..].iter().collect::<String>());
+    lines
+        .into_iter()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+// Marks the matrices of `stack_multiline_attachments`, so that `substack_attachments`
+// leaves matrices written as subscripts alone. It comes out of pandoc as
+// `\text{substack:}`.
+const SUBSTACK_MARKER: &str = "substack:";
+
+// Rewrite multi-line subscripts and superscripts such as `sum_(i = 0 \ j = 1)` into
+// marked matrices, so that pandoc keeps the lines apart instead of flattening them.
+fn stack_multiline_attachments(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut output = String::with_capacity(input.len());
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                output.push(chars[i]);
+                output.push(chars[i + 1]);
+                i += 2;
+            }
+            '"' => {
+                let end = skip_string(&chars, i).min(chars.len() - 1);
+                output.extend(&chars[i..=end]);
+                i = end + 1;
+            }
+            c @ ('_' | '^') if chars.get(i + 1) == Some(&'(') => {
+                output.push(c);
+                i += 1;
+                if let Some(close) = find_closing_delimiter(&chars, i) {
+                    let content: String = chars[i + 1..close].iter().collect();
+                    let lines = split_math_lines(&content);
+                    if lines.len() > 1 {
+                        let rows: Vec<String> = lines
+                            .iter()
+                            .map(|line| stack_multiline_attachments(line))
+                            .collect();
+                        output.push_str(&format!(
+                            "(\"{}\" mat({}))",
+                            SUBSTACK_MARKER,
+                            rows.join("; ")
+                        ));
+                        i = close + 1;
+                    }
+                }
+            }
+            c => {
+                output.push(c);
+                i += 1;
+            }
+        }
+    }
+    output
+}
+
+// Turn the marked matrices of `stack_multiline_attachments`, which make up a whole
+// subscript or superscript, into `\substack{...}`.
+fn substack_attachments(latex: &str) -> String {
+    const BEGIN: &str = r"\begin{pmatrix}";
+    const END: &str = r"\end{pmatrix}";
+
+    if !latex.contains(SUBSTACK_MARKER) {
+        return latex.to_string();
+    }
+    let mut output = String::with_capacity(latex.len());
+    let mut rest = latex;
+    while let Some(pos) = rest.find(['_', '^']) {
+        let (before, after) = rest.split_at(pos + 1);
+        output.push_str(before);
+        rest = after;
+        if !rest.starts_with('{') || before.ends_with("\\_") || before.ends_with("\\^") {
+            continue;
+        }
+        let Some(close) = find_closing_brace(rest) else {
+            continue;
+        };
+        let group = rest[1..close].trim();
+        let Some(body) = ["text", "textrm", "mathrm", "mbox"]
+            .iter()
+            .find_map(|command| {
+                group.strip_prefix(&format!("\\{}{{{}}}", command, SUBSTACK_MARKER))
+            })
+            .map(str::trim_start)
+            .and_then(|body| body.strip_prefix(BEGIN))
+            .and_then(|body| body.strip_suffix(END))
+            .filter(|body| !body.contains(BEGIN))
+        else {
+            continue;
+        };
+        let rows: Vec<String> = body
+            .split(r"\\")
+            .map(|row| substack_attachments(&join_cells(row)))
+            .filter(|row| !row.is_empty())
+            .collect();
+        if rows.len() > 1 {
+            output.push_str(&format!(r"{{\substack{{{}}}}}", rows.join(r" \\ ")));
+            rest = &rest[close + 1..];
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+// Byte index of the brace closing the group opened at the start of `latex`.
+fn find_closing_brace(latex: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in latex.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Matrix cells in a stacked line come from commas in the Typst source, so join
+// them back together with commas.
+fn join_cells(row: &str) -> String {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut escaped = false;
+    for c in row.chars() {
+        match c {
+            '&' if !escaped => cells.push(std::mem::take(&mut cell)),
+            _ => cell.push(c),
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    cells.push(cell);
+    cells
+        .iter()
+        .map(|cell| cell.trim())
+        .collect::<Vec<_>>()
+        .join(", ")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiline_attachments_become_marked_matrices() {
+        assert_eq!(
+            stack_multiline_attachments(r"sum_(i = 0 \ j = 1) x"),
+            r#"sum_("substack:" mat(i = 0; j = 1)) x"#
+        );
+        assert_eq!(stack_multiline_attachments("x_(i, j)"), "x_(i, j)");
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn collapsed_attachments_move_lines() {
+        assert!(moves_lines(
+            "sum_(i = 0 \\\n j = 1) x",
+            Direction::TypstToLatex
+        ));
+        assert!(!moves_lines("x^2 +\n y", Direction::TypstToLatex));
+    }
+
+    #[test]
+    fn marked_matrices_become_substacks() {
+        assert_eq!(
+            substack_attachments(
+                r"\sum_{\text{substack:}\begin{pmatrix} i = 0 \\ j & 1 \end{pmatrix}} x"
+            ),
+            r"\sum_{\substack{i = 0 \\ j, 1}} x"
+        );
+    }
+
+    #[test]
+    fn matrices_written_as_attachments_are_kept() {
+        let latex = r"x_{\begin{pmatrix} 1 \\ 2 \end{pmatrix}}";
+        assert_eq!(substack_attachments(latex), latex);
+        let marked = format!(
+            r"{} + y_{{\text{{substack:}}\begin{{pmatrix}} a \\ b \end{{pmatrix}}}}",
+            latex
+        );
+        assert_eq!(
+            substack_attachments(&marked),
+            format!(r"{} + y_{{\substack{{a \\ b}}}}", latex)
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use crate::convert;
+use crate::diagnostics;
+use crate::i18n;
+use crate::settings::config_dir;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+static LAST_INPUT: Mutex<String> = Mutex::new(String::new());
+// What a panic on a worker thread left for the UI to tell the user.
+static PENDING: Mutex<Option<String>> = Mutex::new(None);
+
+// Remember the input being converted, for the crash report.
+pub fn record_input(input: &str) {
+    if let Ok(mut last) = LAST_INPUT.lock() {
+        last.clear();
+        last.push_str(input);
+    }
+}
+
+fn report(info: &PanicHookInfo) -> String {
+    let input = LAST_INPUT
+        .lock()
+        .map(|input| input.clone())
+        .unwrap_or_default();
+    format!(
+        "{}\n\npandoc: {}\n\nPanic: {}\n\nBacktrace:\n{}\n\nLast input:\n{}\n",
+        diagnostics::report(),
+        convert::pandoc_version().unwrap_or("not found"),
+        info,
+        Backtrace::force_capture(),
+        input
+    )
+}
+
+fn write_report(report: &str) -> Option<PathBuf> {
+    let dir = config_dir()?.join("crashes");
+    fs::create_dir_all(&dir).ok()?;
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let path = dir.join(format!("crash-{}.txt", seconds));
+    fs::write(&path, report).ok()?;
+    Some(path)
+}
+
+// The report left by a panic on another thread, which the UI shows once.
+pub fn take_pending() -> Option<String> {
+    PENDING.lock().ok()?.take()
+}
+
+// Write a crash report and tell the user where it is, instead of the window just
+// disappearing. The default hook still prints the panic to stderr. Dialogs only
+// open on the main thread, so a worker's panic is left for the UI's next frame.
+pub fn install_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let main = std::thread::current().name() == Some("main");
+        let (saved, not_saved) = if main {
+            ("crash-saved", "crash-not-saved")
+        } else {
+            ("crash-background-saved", "crash-background-not-saved")
+        };
+        let description = match write_report(&report(info)) {
+            Some(path) => i18n::tr_args(saved, &[("path", &path.display().to_string())]),
+            None => i18n::tr_args(not_saved, &[("error", &info.to_string())]),
+        };
+        if !main {
+            if let Ok(mut pending) = PENDING.lock() {
+                *pending = Some(description);
+            }
+            return;
+        }
+        rfd::MessageDialog::new()
+            .set_level(rfd::MessageLevel::Error)
+            .set_title(i18n::tr("crash-title"))
+            .set_description(description)
+            .set_buttons(rfd::MessageButtons::Ok)
+            .show();
+    }));
+}
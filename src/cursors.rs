@@ -0,0 +1,207 @@
+use eframe::egui;
+
+enum Edit {
+    Insert(String),
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+}
+
+// Line and column of a character index.
+pub fn line_column(chars: &[char], index: usize) -> (usize, usize) {
+    let before = &chars[..index.min(chars.len())];
+    let line = before.iter().filter(|&&c| c == '\n').count();
+    let column = before.iter().rev().take_while(|&&c| c != '\n').count();
+    (line, column)
+}
+
+// Character index of a column on a line, clamped to the line's end. None past the
+// last line.
+pub fn index_of(chars: &[char], line: usize, column: usize) -> Option<usize> {
+    let start = if line == 0 {
+        0
+    } else {
+        chars
+            .iter()
+            .enumerate()
+            .filter(|(_, &c)| c == '\n')
+            .nth(line - 1)?
+            .0
+            + 1
+    };
+    let length = chars[start..].iter().take_while(|&&c| c != '\n').count();
+    Some(start + column.min(length))
+}
+
+// Apply an edit at every caret, front to back, and return where the carets end up.
+fn apply(chars: &mut Vec<char>, carets: &[usize], edit: &Edit) -> Vec<usize> {
+    let mut moved = Vec::new();
+    let mut shift = 0isize;
+    for &caret in carets {
+        let at = ((caret as isize + shift).max(0) as usize).min(chars.len());
+        let line_start = chars[..at]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |i| i + 1);
+        let line_end = at + chars[at..].iter().take_while(|&&c| c != '\n').count();
+        moved.push(match edit {
+            Edit::Insert(text) => {
+                let count = text.chars().count();
+                chars.splice(at..at, text.chars());
+                shift += count as isize;
+                at + count
+            }
+            Edit::Backspace if at > 0 => {
+                chars.remove(at - 1);
+                shift -= 1;
+                at - 1
+            }
+            Edit::Delete if at < chars.len() => {
+                chars.remove(at);
+                shift -= 1;
+                at
+            }
+            Edit::Backspace | Edit::Delete => at,
+            Edit::Left => at.saturating_sub(1),
+            Edit::Right => (at + 1).min(chars.len()),
+            Edit::Home => line_start,
+            Edit::End => line_end,
+        });
+    }
+    moved
+}
+
+// Extra carets in the input editor, as character indices. egui's text editor has a
+// single caret, so the others are kept here and typing is replayed at each of them.
+#[derive(Default)]
+pub struct Cursors {
+    extra: Vec<usize>,
+}
+
+impl Cursors {
+    pub fn is_empty(&self) -> bool {
+        self.extra.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.extra.clear();
+    }
+
+    pub fn add(&mut self, index: usize, primary: usize) {
+        if index != primary && !self.extra.contains(&index) {
+            self.extra.push(index);
+        }
+    }
+
+    // A caret on every line from `from` to `to`, in the column of `to`, for editing
+    // a block such as the rows of a matrix.
+    pub fn add_column(&mut self, text: &str, from: usize, to: usize) {
+        let chars: Vec<char> = text.chars().collect();
+        let (first, _) = line_column(&chars, from);
+        let (last, column) = line_column(&chars, to);
+        for line in first.min(last)..=first.max(last) {
+            if let Some(index) = index_of(&chars, line, column) {
+                self.add(index, to);
+            }
+        }
+    }
+
+    // A caret on the line above the topmost caret or below the bottommost one, in
+    // the same column.
+    pub fn add_vertical(&mut self, text: &str, primary: usize, down: bool) {
+        let chars: Vec<char> = text.chars().collect();
+        let carets = self.extra.iter().chain([&primary]).copied();
+        let outermost = if down { carets.max() } else { carets.min() };
+        let (line, column) = line_column(&chars, outermost.unwrap_or(primary));
+        let target = if down {
+            Some(line + 1)
+        } else {
+            line.checked_sub(1)
+        };
+        if let Some(index) = target.and_then(|line| index_of(&chars, line, column)) {
+            self.add(index, primary);
+        }
+    }
+
+    // Take this frame's typing out of the input so the editor doesn't also apply it,
+    // and replay it at every caret. Returns the new primary caret if anything was
+    // typed. Escape drops the extra carets.
+    pub fn edit(
+        &mut self,
+        ctx: &egui::Context,
+        text: &mut String,
+        primary: usize,
+    ) -> Option<usize> {
+        let mut edits = Vec::new();
+        let mut escape = false;
+        ctx.input_mut(|i| {
+            i.events.retain(|event| {
+                let edit = match event {
+                    egui::Event::Text(text) | egui::Event::Paste(text) => {
+                        Edit::Insert(text.clone())
+                    }
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } if modifiers.is_none() || modifiers.shift_only() => match key {
+                        egui::Key::Enter => Edit::Insert("\n".into()),
+                        egui::Key::Tab => Edit::Insert("\t".into()),
+                        egui::Key::Backspace => Edit::Backspace,
+                        egui::Key::Delete => Edit::Delete,
+                        egui::Key::ArrowLeft => Edit::Left,
+                        egui::Key::ArrowRight => Edit::Right,
+                        egui::Key::Home => Edit::Home,
+                        egui::Key::End => Edit::End,
+                        egui::Key::Escape => {
+                            escape = true;
+                            return false;
+                        }
+                        _ => return true,
+                    },
+                    _ => return true,
+                };
+                edits.push(edit);
+                false
+            });
+        });
+        if escape {
+            self.clear();
+        }
+        if edits.is_empty() {
+            return None;
+        }
+        let mut chars: Vec<char> = text.chars().collect();
+        let mut carets: Vec<usize> = self.extra.iter().chain([&primary]).copied().collect();
+        carets.sort_unstable();
+        carets.dedup();
+        let mut primary_slot = carets.iter().position(|&caret| caret == primary)?;
+        for edit in &edits {
+            carets = apply(&mut chars, &carets, edit);
+            // Carets that ran into each other become one.
+            let primary_caret = carets[primary_slot];
+            carets.dedup();
+            primary_slot = carets.iter().position(|&caret| caret == primary_caret)?;
+        }
+        *text = chars.into_iter().collect();
+        let primary = carets.remove(primary_slot);
+        self.extra = carets;
+        Some(primary)
+    }
+
+    // Draw the extra carets over the editor.
+    pub fn paint(&self, ui: &egui::Ui, output: &egui::text_edit::TextEditOutput) {
+        let stroke = ui.visuals().text_cursor.stroke;
+        for &index in &self.extra {
+            let rect = output
+                .galley
+                .pos_from_ccursor(egui::text::CCursor::new(index))
+                .translate(output.galley_pos.to_vec2());
+            ui.painter().vline(rect.min.x, rect.y_range(), stroke);
+        }
+    }
+}
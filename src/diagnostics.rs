@@ -0,0 +1,73 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+const MAX_ENTRIES: usize = 1000;
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+static LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn start() -> Instant {
+    static START: OnceLock<Instant> = OnceLock::new();
+    *START.get_or_init(Instant::now)
+}
+
+// Called first thing on launch, so that log times and the startup time count from
+// then.
+pub fn mark_start() {
+    start();
+}
+
+#[cfg(feature = "gui")]
+pub fn since_start() -> std::time::Duration {
+    start().elapsed()
+}
+
+// Also echo log entries to stderr (`--verbose`).
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+    start();
+}
+
+// Record a diagnostics entry, e.g. a backend invocation or its timing.
+pub fn log(message: impl AsRef<str>) {
+    let entry = format!(
+        "[{:>9.3}s] {}",
+        start().elapsed().as_secs_f64(),
+        message.as_ref()
+    );
+    if VERBOSE.load(Ordering::Relaxed) {
+        eprintln!("{}", entry);
+    }
+    if let Ok(mut log) = LOG.lock() {
+        if log.len() >= MAX_ENTRIES {
+            log.remove(0);
+        }
+        log.push(entry);
+    }
+}
+
+#[cfg(feature = "gui")]
+pub fn entries() -> Vec<String> {
+    LOG.lock().map(|log| log.clone()).unwrap_or_default()
+}
+
+#[cfg(feature = "gui")]
+pub fn clear() {
+    if let Ok(mut log) = LOG.lock() {
+        log.clear();
+    }
+}
+
+// The whole log with version information, for pasting into bug reports.
+#[cfg(feature = "gui")]
+pub fn report() -> String {
+    format!(
+        "{} {} ({} {})\n{}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        entries().join("\n")
+    )
+}
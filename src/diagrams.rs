@@ -0,0 +1,410 @@
+use crate::latex::{self, Token};
+
+const MATRICES: [&str; 6] = [
+    "matrix", "pmatrix", "bmatrix", "Bmatrix", "vmatrix", "Vmatrix",
+];
+
+// Arrow commands and the way they point: `r`ight, `l`eft, `d`own or `u`p.
+const ARROWS: [(&str, char); 11] = [
+    ("rightarrow", 'r'),
+    ("to", 'r'),
+    ("longrightarrow", 'r'),
+    ("mapsto", 'r'),
+    ("longmapsto", 'r'),
+    ("leftarrow", 'l'),
+    ("gets", 'l'),
+    ("longleftarrow", 'l'),
+    ("downarrow", 'd'),
+    ("uparrow", 'u'),
+    ("Vert", 'd'),
+];
+
+#[derive(Default)]
+struct Arrow {
+    direction: char,
+    // An equals sign, drawn as a double line.
+    equal: bool,
+    maps_to: bool,
+    // Labels above or left of the arrow, and below or right of it.
+    first: String,
+    second: String,
+}
+
+impl Arrow {
+    fn is_vertical(&self) -> bool {
+        matches!(self.direction, 'd' | 'u')
+    }
+}
+
+enum Cell {
+    Empty,
+    Object(String),
+    Arrow(Arrow),
+}
+
+// The arrow written with `tokens`, like `\rightarrow^{f}`, `\xrightarrow{f}`,
+// `\overset{f}{\to}` or `g \downarrow`.
+fn arrow(tokens: &[Token]) -> Option<Arrow> {
+    let tokens: Vec<Token> = tokens
+        .iter()
+        .filter(|token| **token != Token::Space)
+        .cloned()
+        .collect();
+    let at = tokens.iter().position(|token| match token {
+        Token::Command(name) => {
+            ARROWS.iter().any(|(arrow, _)| arrow == name)
+                || matches!(
+                    name.as_str(),
+                    "|" | "parallel"
+                        | "xrightarrow"
+                        | "xleftarrow"
+                        | "overset"
+                        | "underset"
+                        | "stackrel"
+                )
+        }
+        Token::Char('=') => true,
+        _ => false,
+    })?;
+    let mut arrow = Arrow::default();
+    let mut next = at + 1;
+    match &tokens[at] {
+        Token::Char('=') => {
+            arrow.direction = 'r';
+            arrow.equal = true;
+        }
+        Token::Command(name) if name == "|" || name == "parallel" => {
+            arrow.direction = 'd';
+            arrow.equal = true;
+        }
+        Token::Command(name) if name == "xrightarrow" || name == "xleftarrow" => {
+            arrow.direction = if name == "xrightarrow" { 'r' } else { 'l' };
+            let (below, j) = latex::optional_argument(&tokens, next);
+            let (above, j) = latex::argument(&tokens, j);
+            arrow.first = latex::to_string(above);
+            arrow.second = below.map(latex::to_string).unwrap_or_default();
+            next = j;
+        }
+        Token::Command(name) if matches!(name.as_str(), "overset" | "underset" | "stackrel") => {
+            let (label, j) = latex::argument(&tokens, next);
+            let (base, j) = latex::argument(&tokens, j);
+            arrow = self::arrow(base).filter(|arrow| !arrow.is_vertical())?;
+            if name == "underset" {
+                arrow.second = latex::to_string(label);
+            } else {
+                arrow.first = latex::to_string(label);
+            }
+            next = j;
+        }
+        Token::Command(name) => {
+            arrow.direction = ARROWS.iter().find(|(arrow, _)| arrow == name)?.1;
+            arrow.equal = name == "Vert";
+            arrow.maps_to = name.ends_with("mapsto");
+        }
+        _ => return None,
+    }
+    // Labels attached as scripts, and for vertical arrows written beside them.
+    let mut rest = &tokens[next..];
+    if tokens.get(next) == Some(&Token::Command("limits".to_string())) {
+        rest = &rest[1..];
+    }
+    let mut after = Vec::new();
+    let mut i = 0;
+    while i < rest.len() {
+        match &rest[i] {
+            Token::Char(script @ ('^' | '_')) => {
+                let (label, j) = latex::argument(rest, i + 1);
+                let label = latex::to_string(label);
+                if *script == '^' && !arrow.is_vertical() {
+                    arrow.first = label;
+                } else {
+                    arrow.second = label;
+                }
+                i = j;
+            }
+            token => {
+                after.push(token.clone());
+                i += 1;
+            }
+        }
+    }
+    let before = &tokens[..at];
+    if arrow.is_vertical() {
+        if !before.is_empty() {
+            arrow.first = latex::to_string(before);
+        }
+        if !after.is_empty() {
+            arrow.second = latex::to_string(&after);
+        }
+    } else if !before.is_empty() || !after.is_empty() {
+        return None;
+    }
+    Some(arrow)
+}
+
+fn cell(tokens: &[Token]) -> Cell {
+    let text = latex::to_string(tokens).trim().to_string();
+    if text.is_empty() {
+        Cell::Empty
+    } else if let Some(arrow) = arrow(tokens) {
+        Cell::Arrow(arrow)
+    } else {
+        Cell::Object(text)
+    }
+}
+
+// The cells of the matrix whose `\begin` is at `i` if it's laid out like a
+// commutative diagram, objects with arrows between them, and the index after it.
+fn diagram(tokens: &[Token], i: usize) -> Option<(Vec<Vec<Cell>>, usize)> {
+    if tokens[i] != Token::Command("begin".to_string()) {
+        return None;
+    }
+    let (name, body, next) = latex::environment(tokens, i + 1);
+    if !MATRICES.contains(&name.as_str()) {
+        return None;
+    }
+    let mut rows: Vec<Vec<Cell>> = latex::split_rows(body)
+        .iter()
+        .map(|row| row.iter().map(|tokens| cell(tokens)).collect())
+        .collect();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    for row in &mut rows {
+        row.resize_with(columns, || Cell::Empty);
+    }
+    let mut arrows = 0;
+    for (r, row) in rows.iter().enumerate() {
+        for (c, cell) in row.iter().enumerate() {
+            let fits = match (cell, r % 2, c % 2) {
+                (Cell::Empty, _, _) => true,
+                (Cell::Object(_), 0, 0) => true,
+                (Cell::Arrow(arrow), 0, 1) => !arrow.is_vertical(),
+                (Cell::Arrow(arrow), 1, 0) => arrow.is_vertical(),
+                _ => false,
+            };
+            if !fits {
+                return None;
+            }
+            arrows += matches!(cell, Cell::Arrow(_)) as usize;
+        }
+    }
+    (arrows > 0).then_some((rows, next))
+}
+
+// Whether `latex` has a matrix of arrows that `emit` would write as a diagram.
+pub fn is_diagram(latex: &str) -> bool {
+    if !latex.contains("\\begin") {
+        return false;
+    }
+    let tokens = latex::parse(latex);
+    (0..tokens.len()).any(|i| diagram(&tokens, i).is_some())
+}
+
+fn amscd(rows: &[Vec<Cell>]) -> String {
+    let lines: Vec<String> = rows
+        .iter()
+        .enumerate()
+        .map(|(r, row)| {
+            let mut items: Vec<String> = row
+                .iter()
+                .enumerate()
+                // Arrow rows have their arrows under the objects only.
+                .filter(|(c, _)| r % 2 == 0 || c % 2 == 0)
+                .map(|(c, cell)| match cell {
+                    Cell::Object(object) => object.clone(),
+                    Cell::Empty if r % 2 == 0 && c % 2 == 0 => String::new(),
+                    Cell::Empty => "@.".to_string(),
+                    Cell::Arrow(arrow) => match (arrow.direction, arrow.equal) {
+                        ('d' | 'u', true) => "@|".to_string(),
+                        (_, true) => "@=".to_string(),
+                        (direction, _) => {
+                            let sign = match direction {
+                                'r' => '>',
+                                'l' => '<',
+                                'd' => 'V',
+                                _ => 'A',
+                            };
+                            format!("@{0}{1}{0}{2}{0}", sign, arrow.first, arrow.second)
+                        }
+                    },
+                })
+                .collect();
+            while items
+                .last()
+                .is_some_and(|item| item.is_empty() || item == "@.")
+            {
+                items.pop();
+            }
+            items.join(" ")
+        })
+        .collect();
+    format!("\\begin{{CD}}\n{}\n\\end{{CD}}", lines.join(" \\\\\n"))
+}
+
+fn tikzcd(rows: &[Vec<Cell>]) -> String {
+    let columns = rows.first().map_or(0, Vec::len).div_ceil(2);
+    let mut grid = vec![vec![String::new(); columns]; rows.len().div_ceil(2)];
+    for (r, row) in rows.iter().enumerate() {
+        for (c, cell) in row.iter().enumerate() {
+            match cell {
+                Cell::Empty => {}
+                Cell::Object(object) => grid[r / 2][c / 2].insert_str(0, object),
+                Cell::Arrow(arrow) => {
+                    // Arrows go out from the object they start at.
+                    let (source_row, source_column) = match arrow.direction {
+                        'l' => (r / 2, c.div_ceil(2)),
+                        'u' => (r.div_ceil(2), c / 2),
+                        _ => (r / 2, c / 2),
+                    };
+                    // Labels go left of the way an arrow points, or right with `'`.
+                    let (first, second) = match arrow.direction {
+                        'r' | 'u' => ("", "'"),
+                        _ => ("'", ""),
+                    };
+                    let mut options = vec![arrow.direction.to_string()];
+                    for (label, swap) in [(&arrow.first, first), (&arrow.second, second)] {
+                        if label.contains(',') {
+                            options.push(format!("\"{{{}}}\"{}", label, swap));
+                        } else if !label.is_empty() {
+                            options.push(format!("\"{}\"{}", label, swap));
+                        }
+                    }
+                    if arrow.equal {
+                        options.push("equal".to_string());
+                    } else if arrow.maps_to {
+                        options.push("mapsto".to_string());
+                    }
+                    let object = &mut grid[source_row][source_column];
+                    object.push_str(&format!(" \\arrow[{}]", options.join(", ")));
+                }
+            }
+        }
+    }
+    let lines: Vec<String> = grid
+        .iter()
+        .map(|row| {
+            let mut cells: Vec<&str> = row.iter().map(|cell| cell.trim()).collect();
+            while cells.last() == Some(&"") {
+                cells.pop();
+            }
+            cells.join(" & ")
+        })
+        .collect();
+    format!(
+        "\\begin{{tikzcd}}\n{}\n\\end{{tikzcd}}",
+        lines.join(" \\\\\n")
+    )
+}
+
+// Write the matrices of arrows in `latex` as commutative diagrams in `style`,
+// amscd's "cd" or "tikz-cd", or leave them as they are for "". Their delimiters
+// go, so the result may need adjusting by hand.
+pub fn emit(latex: &str, style: &str) -> String {
+    if style.is_empty() || !is_diagram(latex) {
+        return latex.to_string();
+    }
+    let tokens = latex::parse(latex);
+    let mut out = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some((rows, next)) = diagram(&tokens, i) {
+            out.push_str(&if style == "cd" {
+                amscd(&rows)
+            } else {
+                tikzcd(&rows)
+            });
+            i = next;
+            continue;
+        }
+        out.push_str(&latex::to_string(&tokens[i..i + 1]));
+        if let (Token::Command(name), Some(Token::Char(c))) = (&tokens[i], tokens.get(i + 1)) {
+            if name.chars().all(|c| c.is_ascii_alphabetic()) && c.is_ascii_alphabetic() {
+                out.push(' ');
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: &str = r"\begin{matrix} A & \xrightarrow{f} & B \\ \downarrow & & \downarrow g \\ C & \rightarrow & D \end{matrix}";
+
+    #[test]
+    fn matrices_of_arrows_are_diagrams() {
+        assert!(is_diagram(SQUARE));
+        assert!(is_diagram(r"\begin{matrix} A & \to & B \end{matrix}"));
+        assert!(!is_diagram(r"\begin{pmatrix} 1 & 2 \\ 3 & 4 \end{pmatrix}"));
+    }
+
+    #[test]
+    fn amscd() {
+        assert_eq!(
+            emit(SQUARE, "cd"),
+            "\\begin{CD}\nA @>f>> B \\\\\n@VVV @VVgV \\\\\nC @>>> D\n\\end{CD}"
+        );
+        // Labels on the left of a vertical arrow, and arrows written as `\overset`.
+        assert_eq!(
+            emit(
+                r"\begin{matrix} A & \overset{f}{\to} & B \\ {h}\downarrow & & \downarrow \\ C & \xrightarrow{k} & D \end{matrix}",
+                "cd"
+            ),
+            "\\begin{CD}\nA @>f>> B \\\\\n@V{h}VV @VVV \\\\\nC @>k>> D\n\\end{CD}"
+        );
+    }
+
+    #[test]
+    fn tikzcd() {
+        assert_eq!(
+            emit(SQUARE, "tikz-cd"),
+            "\\begin{tikzcd}\nA \\arrow[r, \"f\"] \\arrow[d] & B \\arrow[d, \"g\"] \\\\\nC \\arrow[r] & D\n\\end{tikzcd}"
+        );
+    }
+
+    #[test]
+    fn other_matrices_and_styles_are_kept() {
+        assert_eq!(emit(SQUARE, ""), SQUARE);
+        let matrix = r"\begin{pmatrix} 1 & 2 \\ 3 & 4 \end{pmatrix}";
+        assert_eq!(emit(matrix, "cd"), matrix);
+    }
+
+    #[test]
+    fn other_arrows() {
+        let equal = r"\begin{matrix} A & = & B \end{matrix}";
+        assert!(is_diagram(equal));
+        assert_eq!(emit(equal, "cd"), "\\begin{CD}\nA @= B\n\\end{CD}");
+        assert_eq!(
+            emit(equal, "tikz-cd"),
+            "\\begin{tikzcd}\nA \\arrow[r, equal] & B\n\\end{tikzcd}"
+        );
+        let left = r"\begin{matrix} A & \xleftarrow{u} & B \end{matrix}";
+        assert_eq!(emit(left, "cd"), "\\begin{CD}\nA @<u<< B\n\\end{CD}");
+        assert_eq!(
+            emit(left, "tikz-cd"),
+            "\\begin{tikzcd}\nA & B \\arrow[l, \"u\"']\n\\end{tikzcd}"
+        );
+    }
+
+    #[test]
+    fn arrows_starting_at_the_target() {
+        // An upward arrow starts at the object below it.
+        let up = r"\begin{matrix} A & \leftarrow & B \\ \uparrow & & \\ C & & \end{matrix}";
+        assert_eq!(
+            emit(up, "cd"),
+            "\\begin{CD}\nA @<<< B \\\\\n@AAA \\\\\nC\n\\end{CD}"
+        );
+        assert_eq!(
+            emit(up, "tikz-cd"),
+            "\\begin{tikzcd}\nA & B \\arrow[l] \\\\\nC \\arrow[u]\n\\end{tikzcd}"
+        );
+    }
+
+    #[test]
+    fn matrices_without_arrows_are_not_diagrams() {
+        let matrix = r"\begin{matrix} A & B \\ C & D \end{matrix}";
+        assert!(!is_diagram(matrix));
+        assert_eq!(emit(matrix, "tikz-cd"), matrix);
+    }
+}
@@ -0,0 +1,94 @@
+// Beyond this many tokens a side the table of the longest common subsequence gets
+// too big, and the whole text is shown as replaced.
+const MAX_TOKENS: usize = 2000;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Change {
+    Same,
+    Removed,
+    Added,
+}
+
+// Split LaTeX or Typst into commands, words, runs of whitespace and single symbols,
+// so that a diff lines up with what was edited.
+fn tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let same_kind = |next: char| {
+            if c == '\\' || c.is_alphanumeric() {
+                next.is_alphanumeric()
+            } else if c.is_whitespace() {
+                next.is_whitespace()
+            } else {
+                false
+            }
+        };
+        // A backslash followed by a symbol is a command of its own, like `\{`.
+        if c == '\\'
+            && chars
+                .peek()
+                .is_some_and(|(_, next)| !next.is_alphanumeric())
+        {
+            if let Some((i, next)) = chars.next() {
+                end = i + next.len_utf8();
+            }
+        } else {
+            while let Some((i, next)) = chars.peek().copied() {
+                if !same_kind(next) {
+                    break;
+                }
+                end = i + next.len_utf8();
+                chars.next();
+            }
+        }
+        tokens.push(&text[start..end]);
+    }
+    tokens
+}
+
+// The changes that turn `old` into `new`, with adjacent pieces of the same kind
+// merged.
+pub fn diff(old: &str, new: &str) -> Vec<(Change, String)> {
+    let old_tokens = tokens(old);
+    let new_tokens = tokens(new);
+    let mut changes: Vec<(Change, String)> = Vec::new();
+    let mut push = |change: Change, token: &str| match changes.last_mut() {
+        Some((last, text)) if *last == change => text.push_str(token),
+        _ => changes.push((change, token.to_string())),
+    };
+    if old_tokens.len() > MAX_TOKENS || new_tokens.len() > MAX_TOKENS {
+        push(Change::Removed, old);
+        push(Change::Added, new);
+        return changes;
+    }
+    // lengths[i][j] is the length of the longest common subsequence of the
+    // tokens from old_tokens[i..] and new_tokens[j..].
+    let (n, m) = (old_tokens.len(), new_tokens.len());
+    let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if old_tokens[i] == new_tokens[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old_tokens[i] == new_tokens[j] {
+            push(Change::Same, old_tokens[i]);
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            push(Change::Removed, old_tokens[i]);
+            i += 1;
+        } else {
+            push(Change::Added, new_tokens[j]);
+            j += 1;
+        }
+    }
+    changes
+}
@@ -0,0 +1,513 @@
+use crate::convert::{convert, Direction};
+use std::ops::Range;
+
+// A math span inside prose, e.g. `$x^2$` in a Markdown cell or a Typst document.
+pub struct MathRegion {
+    // Byte range of the whole span including its delimiters.
+    pub range: Range<usize>,
+    // Byte range of the math between the delimiters.
+    pub content: Range<usize>,
+    pub display: bool,
+}
+
+// Byte index just past the code span or fence starting at `start`, which is
+// skipped so that `$` inside code is left alone. A backtick run without a
+// closing run of the same length is literal text, so only the run is skipped.
+fn skip_code(text: &str, start: usize) -> usize {
+    let ticks = text[start..].bytes().take_while(|&b| b == b'`').count();
+    let mut i = start + ticks;
+    while let Some(pos) = text[i..].find('`') {
+        let run = text[i + pos..].bytes().take_while(|&b| b == b'`').count();
+        if run == ticks {
+            return i + pos + run;
+        }
+        i += pos + run;
+    }
+    start + ticks
+}
+
+// Typst math: `$...$`, which is display math when padded with whitespace on both
+// sides (`$ x $`).
+pub fn typst_math_regions(text: &str) -> Vec<MathRegion> {
+    let mut regions = Vec::new();
+    let mut i = 0;
+    while let Some(pos) = text[i..].find(['$', '`', '\\']) {
+        let start = i + pos;
+        match text.as_bytes()[start] {
+            b'`' => i = skip_code(text, start),
+            b'\\' => i = start + 1 + text[start + 1..].chars().next().map_or(0, char::len_utf8),
+            _ => {
+                let Some(end) = find_unescaped(text, start + 1, "$") else {
+                    break;
+                };
+                let content = &text[start + 1..end];
+                let display = content.starts_with(char::is_whitespace)
+                    && content.ends_with(char::is_whitespace);
+                regions.push(MathRegion {
+                    range: start..end + 1,
+                    content: start + 1..end,
+                    display,
+                });
+                i = end + 1;
+            }
+        }
+    }
+    regions
+}
+
+// LaTeX math as written in Markdown and TeX documents: `$$...$$`, `$...$`,
+// `\[...\]` and `\(...\)`, and mhchem's `\ce{...}` in text, which is inline math
+// in Typst. Inline `$` follows pandoc's `tex_math_dollars`, so prices such as
+// `$5 and $10` stay text.
+pub fn latex_math_regions(text: &str) -> Vec<MathRegion> {
+    let mut regions = Vec::new();
+    let mut i = 0;
+    while let Some(pos) = text[i..].find(['$', '`', '\\']) {
+        let start = i + pos;
+        let rest = &text[start..];
+        let delimiters = if rest.starts_with("$$") {
+            Some(("$$", "$$", true))
+        } else if rest.starts_with('$') {
+            Some(("$", "$", false))
+        } else if rest.starts_with("\\[") {
+            Some(("\\[", "\\]", true))
+        } else if rest.starts_with("\\(") {
+            Some(("\\(", "\\)", false))
+        } else {
+            None
+        };
+        match delimiters {
+            Some(("$", _, _)) => match inline_dollar_end(text, start) {
+                Some(end) => {
+                    regions.push(MathRegion {
+                        range: start..end + 1,
+                        content: start + 1..end,
+                        display: false,
+                    });
+                    i = end + 1;
+                }
+                None => i = start + 1,
+            },
+            Some((open, close, display)) => {
+                let Some(end) = find_unescaped(text, start + open.len(), close) else {
+                    break;
+                };
+                regions.push(MathRegion {
+                    range: start..end + close.len(),
+                    content: start + open.len()..end,
+                    display,
+                });
+                i = end + close.len();
+            }
+            None if rest.starts_with('`') => i = skip_code(text, start),
+            None if rest.starts_with("\\ce{") => {
+                let Some(end) = closing_brace(text, start + 3) else {
+                    break;
+                };
+                regions.push(MathRegion {
+                    range: start..end + 1,
+                    content: start..end + 1,
+                    display: false,
+                });
+                i = end + 1;
+            }
+            // Skip escaped characters such as `\$`.
+            None => i = start + 1 + rest[1..].chars().next().map_or(0, char::len_utf8),
+        }
+    }
+    regions
+}
+
+// Byte index of the `$` closing the inline math opened at `start`: the opening
+// `$` must not be followed by whitespace, and the closing one must not follow
+// whitespace or be followed by a digit.
+fn inline_dollar_end(text: &str, start: usize) -> Option<usize> {
+    if text[start + 1..].starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut from = start + 1;
+    while let Some(end) = find_unescaped(text, from, "$") {
+        let valid = end > start + 1
+            && !text[..end].ends_with(char::is_whitespace)
+            && !text[end + 1..].starts_with(|c: char| c.is_ascii_digit());
+        if valid {
+            return Some(end);
+        }
+        from = end + 1;
+    }
+    None
+}
+
+// Byte index of the brace closing the one at `open`.
+fn closing_brace(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in text[open..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Byte index of the next `delimiter` at or after `from` that is not escaped.
+fn find_unescaped(text: &str, from: usize, delimiter: &str) -> Option<usize> {
+    let mut i = from;
+    while i < text.len() {
+        let rest = &text[i..];
+        if rest.starts_with(delimiter) {
+            return Some(i);
+        }
+        let c = rest.chars().next()?;
+        i += c.len_utf8();
+        if c == '\\' && delimiter != "\\]" && delimiter != "\\)" {
+            i += text[i..].chars().next().map_or(0, char::len_utf8);
+        }
+    }
+    None
+}
+
+// 1-based line number of a byte offset, for warnings.
+pub fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+// Convert every math region in `text`, leaving the surrounding prose untouched.
+// Regions that fail to convert are kept as they are and reported as warnings.
+pub fn convert_math_regions(text: &str, direction: Direction) -> (String, Vec<String>) {
+    convert_math_regions_with(text, direction, convert)
+}
+
+// `convert_math_regions` with another way to convert each region, e.g. through a
+// cache.
+pub fn convert_math_regions_with(
+    text: &str,
+    direction: Direction,
+    mut convert: impl FnMut(&str, Direction) -> Result<String, String>,
+) -> (String, Vec<String>) {
+    let regions = match direction {
+        Direction::LatexToTypst => latex_math_regions(text),
+        _ => typst_math_regions(text),
+    };
+    let mut output = String::with_capacity(text.len());
+    let mut warnings = Vec::new();
+    let mut last = 0;
+    for region in regions {
+        output.push_str(&text[last..region.range.start]);
+        let math = &text[region.content.clone()];
+        match convert(math.trim(), direction) {
+            Ok(converted) => output.push_str(&wrap_math(&converted, direction, region.display)),
+            Err(err) => {
+                warnings.push(format!(
+                    "line {}: {}",
+                    line_of(text, region.range.start),
+                    err
+                ));
+                output.push_str(&text[region.range.clone()]);
+            }
+        }
+        last = region.range.end;
+    }
+    output.push_str(&text[last..]);
+    (output, warnings)
+}
+
+// The math of prose with math spans in it, e.g. a paragraph copied from a document,
+// joined up for telling the language. None when `text` is math and nothing else;
+// backslashes or braces outside the spans mean it's LaTeX math with `$` in it.
+#[cfg(feature = "gui")]
+pub fn prose_math(text: &str) -> Option<String> {
+    let regions = latex_math_regions(text);
+    let mut prose = String::new();
+    let mut last = 0;
+    for region in &regions {
+        prose.push_str(&text[last..region.range.start]);
+        last = region.range.end;
+    }
+    prose.push_str(&text[last..]);
+    // `\ce{...}` alone is LaTeX math, as in `K = \ce{[H+]}`.
+    let delimited = regions.iter().any(|region| region.range != region.content);
+    if !delimited || prose.trim().is_empty() || prose.contains(['\\', '{', '}']) {
+        return None;
+    }
+    Some(
+        regions
+            .iter()
+            .map(|region| text[region.content.clone()].trim())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+// Delimit converted math in the syntax of the target language.
+pub fn wrap_math(math: &str, direction: Direction, display: bool) -> String {
+    match (direction, display) {
+        (Direction::LatexToTypst, true) => format!("$ {} $", math),
+        (Direction::LatexToTypst, false) => format!("${}$", math),
+        (_, true) => format!("$$\n{}\n$$", math),
+        (_, false) => format!("${}$", math),
+    }
+}
+
+// The equations of Typst input that holds several display equations and nothing
+// else, e.g. a run of `$ ... $` blocks copied from a document.
+#[cfg(feature = "gui")]
+pub fn display_equations(text: &str) -> Option<Vec<String>> {
+    let regions = typst_math_regions(text);
+    if regions.len() < 2 || !regions.iter().all(|region| region.display) {
+        return None;
+    }
+    let mut last = 0;
+    for region in &regions {
+        if !text[last..region.range.start].trim().is_empty() {
+            return None;
+        }
+        last = region.range.end;
+    }
+    if !text[last..].trim().is_empty() {
+        return None;
+    }
+    Some(
+        regions
+            .iter()
+            .map(|region| text[region.content.clone()].trim().to_string())
+            .collect(),
+    )
+}
+
+// A wrapper around pasted math, taken off before converting and put back around
+// the output in the other language.
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Wrapper {
+    Inline,
+    Display,
+    // A Markdown code fence.
+    Fence,
+    // Typst's `#math.equation(..)` or LaTeX's `equation` environment.
+    Equation,
+}
+
+#[cfg(feature = "gui")]
+fn unwrap_once(text: &str) -> Option<(&str, Wrapper)> {
+    if text.starts_with("```") || text.starts_with("~~~") {
+        let fence = &text[..3];
+        let (_, rest) = text.split_once('\n')?;
+        let inner = rest.trim_end().strip_suffix(fence)?;
+        return Some((inner, Wrapper::Fence));
+    }
+    if let Some(arguments) = text
+        .strip_prefix("#math.equation(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        // The math is the `$...$` argument, after options such as `block: true`.
+        let start = arguments.find('$')?;
+        let end = arguments.rfind('$').filter(|&end| end > start)?;
+        return Some((&arguments[start + 1..end], Wrapper::Equation));
+    }
+    for (name, wrapper) in [
+        ("equation", Wrapper::Equation),
+        ("equation*", Wrapper::Display),
+        ("displaymath", Wrapper::Display),
+    ] {
+        let inner = text
+            .strip_prefix(&format!("\\begin{{{}}}", name))
+            .and_then(|rest| rest.strip_suffix(&format!("\\end{{{}}}", name)));
+        if let Some(inner) = inner {
+            return Some((inner, wrapper));
+        }
+    }
+    // `$$ ... $$`, `\[ ... \]`, `\( ... \)` or `$ ... $`, the whole text and nothing else.
+    match latex_math_regions(text).as_slice() {
+        [region] if region.range == (0..text.len()) => {
+            let inner = &text[region.content.clone()];
+            // Typst's `$ x $` is display math too.
+            let display = region.display
+                || (text.starts_with('$')
+                    && inner.starts_with(char::is_whitespace)
+                    && inner.ends_with(char::is_whitespace));
+            let wrapper = if display {
+                Wrapper::Display
+            } else {
+                Wrapper::Inline
+            };
+            Some((inner, wrapper))
+        }
+        _ => None,
+    }
+}
+
+// Take math delimiters, code fences and equation wrappers off pasted `text`,
+// outermost first.
+#[cfg(feature = "gui")]
+pub fn unwrap_math(text: &str) -> (&str, Vec<Wrapper>) {
+    let mut text = text.trim();
+    let mut wrappers = Vec::new();
+    while let Some((inner, wrapper)) = unwrap_once(text) {
+        wrappers.push(wrapper);
+        text = inner.trim();
+    }
+    (text, wrappers)
+}
+
+// Put converted `math` back in the `wrappers` it came in, written in the language
+// `direction` converts to.
+#[cfg(feature = "gui")]
+pub fn rewrap(math: &str, wrappers: &[Wrapper], direction: Direction) -> String {
+    let to_typst = direction == Direction::LatexToTypst;
+    wrappers
+        .iter()
+        .rev()
+        .fold(math.to_string(), |math, wrapper| match wrapper {
+            Wrapper::Inline => wrap_math(&math, direction, false),
+            Wrapper::Display => wrap_math(&math, direction, true),
+            Wrapper::Fence if to_typst => format!("```typst\n{}\n```", math),
+            Wrapper::Fence => format!("```latex\n{}\n```", math),
+            Wrapper::Equation if to_typst => {
+                format!(
+                    "#math.equation(block: true, numbering: \"(1)\", $ {} $)",
+                    math
+                )
+            }
+            Wrapper::Equation => format!("\\begin{{equation}}\n{}\n\\end{{equation}}", math),
+        })
+}
+
+// Display math environments in TeX documents. `equation` and `equation*` give
+// their body, the others are kept whole for their alignment.
+#[cfg(feature = "gui")]
+const MATH_ENVIRONMENTS: [(&str, bool); 8] = [
+    ("equation", false),
+    ("equation*", false),
+    ("align", true),
+    ("align*", true),
+    ("gather", true),
+    ("gather*", true),
+    ("multline", true),
+    ("multline*", true),
+];
+
+// The math in a TeX document: the delimited spans of `latex_math_regions` and the
+// display math environments, in order.
+#[cfg(feature = "gui")]
+fn tex_math_regions(text: &str) -> Vec<MathRegion> {
+    let mut environments = Vec::new();
+    let mut i = 0;
+    while let Some(pos) = text[i..].find("\\begin{") {
+        let start = i + pos;
+        let name_start = start + "\\begin{".len();
+        let Some(name_len) = text[name_start..].find('}') else {
+            break;
+        };
+        let name = &text[name_start..name_start + name_len];
+        let body = name_start + name_len + 1;
+        let end_tag = format!("\\end{{{}}}", name);
+        let environment = MATH_ENVIRONMENTS.iter().find(|(known, _)| *known == name);
+        match (environment, text[body..].find(&end_tag)) {
+            (Some(&(_, whole)), Some(len)) => {
+                let end = body + len + end_tag.len();
+                environments.push(MathRegion {
+                    range: start..end,
+                    content: if whole { start..end } else { body..body + len },
+                    display: true,
+                });
+                i = end;
+            }
+            _ => i = body,
+        }
+    }
+    let mut regions: Vec<MathRegion> = latex_math_regions(text)
+        .into_iter()
+        .filter(|region| {
+            !environments.iter().any(|environment| {
+                region.range.start < environment.range.end
+                    && environment.range.start < region.range.end
+            })
+        })
+        .collect();
+    regions.append(&mut environments);
+    regions.sort_by_key(|region| region.range.start);
+    regions
+}
+
+#[cfg(feature = "gui")]
+pub const SOURCE_EXTENSIONS: [&str; 3] = ["typ", "tex", "md"];
+
+// The math blocks in a source file, and the direction to convert them in. Markdown
+// may hold either language, so its direction is detected from the math itself.
+#[cfg(feature = "gui")]
+pub fn math_blocks(text: &str, extension: &str) -> (Vec<String>, Direction) {
+    let (regions, direction) = match extension {
+        "typ" => (typst_math_regions(text), Direction::TypstToLatex),
+        "tex" => (tex_math_regions(text), Direction::LatexToTypst),
+        _ => (latex_math_regions(text), Direction::Auto),
+    };
+    let blocks = regions
+        .iter()
+        .map(|region| text[region.content.clone()].trim().to_string())
+        .filter(|block| !block.is_empty())
+        .collect();
+    (blocks, direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(text: &str) -> Vec<&str> {
+        latex_math_regions(text)
+            .into_iter()
+            .map(|region| &text[region.content])
+            .collect()
+    }
+
+    #[test]
+    fn unmatched_backticks_are_literal() {
+        assert_eq!(contents("a ` tick, $x$"), ["x"]);
+        assert_eq!(contents("`$a$` and $b$"), ["b"]);
+        assert_eq!(contents("``a ` $c$`` $d$"), ["d"]);
+        assert_eq!(typst_math_regions("` $x$").len(), 1);
+    }
+
+    #[test]
+    fn inline_dollars_follow_pandoc() {
+        assert_eq!(contents("costs $5 and $10"), Vec::<&str>::new());
+        assert_eq!(contents("$ x$ and $y $"), Vec::<&str>::new());
+        assert_eq!(contents("$x$5 then $y$"), ["x$5 then $y"]);
+        assert_eq!(contents("$a $b$"), ["a $b"]);
+        assert_eq!(contents(r"\$ and $x^2$"), ["x^2"]);
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn tex_environments_are_math_blocks() {
+        let text = "See $x$.\n\\begin{equation}\n  a = b \\label{eq}\n\\end{equation}\n\\begin{align*}\n  c &= d\n\\end{align*}\n\\begin{itemize} $y$ \\end{itemize}";
+        let (blocks, direction) = math_blocks(text, "tex");
+        assert!(direction == Direction::LatexToTypst);
+        assert_eq!(
+            blocks,
+            [
+                "x",
+                "a = b \\label{eq}",
+                "\\begin{align*}\n  c &= d\n\\end{align*}",
+                "y"
+            ]
+        );
+    }
+
+    #[test]
+    fn display_delimiters() {
+        let text = r"$$ a $$ \[b\] \(c\) \ce{H2O}";
+        assert_eq!(contents(text), [" a ", "b", "c", r"\ce{H2O}"]);
+        assert!(latex_math_regions(text)[0].display);
+    }
+}
@@ -0,0 +1,444 @@
+use crate::latex::{self, Token};
+
+// Translate LaTeX math into eqn, the troff preprocessor, as a display equation
+// for man pages and other troff documents.
+pub fn from_latex(latex: &str) -> String {
+    let mut out = String::new();
+    emit(&latex::parse(latex), &mut out);
+    format!(
+        ".EQ\n{}\n.EN",
+        out.split_whitespace().collect::<Vec<_>>().join(" ")
+    )
+}
+
+fn emit(tokens: &[Token], out: &mut String) {
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Command(name) => {
+                i = emit_command(name, tokens, i + 1, out);
+                continue;
+            }
+            Token::Group(group) => emit_group(group, out),
+            Token::Char('^') => out.push_str(" sup "),
+            Token::Char('_') => out.push_str(" sub "),
+            Token::Char('\'') => out.push_str(" prime "),
+            Token::Char('~') => out.push_str(" ~ "),
+            Token::Char(c) if "<>=+-".contains(*c) => {
+                out.push(' ');
+                out.push(*c);
+                out.push(' ');
+            }
+            Token::Char(c) => out.push(*c),
+            Token::Space => out.push(' '),
+        }
+        i += 1;
+    }
+}
+
+fn emit_group(tokens: &[Token], out: &mut String) {
+    out.push_str(" { ");
+    emit(tokens, out);
+    out.push_str(" } ");
+}
+
+fn emitted(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    emit(tokens, &mut out);
+    out
+}
+
+// Emit the argument starting at `i` as a braced group and return the index after it.
+fn emit_argument(tokens: &[Token], i: usize, out: &mut String) -> usize {
+    let (argument, next) = latex::argument(tokens, i);
+    emit_group(argument, out);
+    next
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => " alpha ",
+        "beta" => " beta ",
+        "gamma" => " gamma ",
+        "delta" => " delta ",
+        "epsilon" | "varepsilon" => " epsilon ",
+        "zeta" => " zeta ",
+        "eta" => " eta ",
+        "theta" | "vartheta" => " theta ",
+        "iota" => " iota ",
+        "kappa" => " kappa ",
+        "lambda" => " lambda ",
+        "mu" => " mu ",
+        "nu" => " nu ",
+        "xi" => " xi ",
+        "pi" | "varpi" => " pi ",
+        "rho" | "varrho" => " rho ",
+        "sigma" | "varsigma" => " sigma ",
+        "tau" => " tau ",
+        "upsilon" => " upsilon ",
+        "phi" | "varphi" => " phi ",
+        "chi" => " chi ",
+        "psi" => " psi ",
+        "omega" => " omega ",
+        "Gamma" => " GAMMA ",
+        "Delta" => " DELTA ",
+        "Theta" => " THETA ",
+        "Lambda" => " LAMBDA ",
+        "Xi" => " XI ",
+        "Pi" => " PI ",
+        "Sigma" => " SIGMA ",
+        "Upsilon" => " UPSILON ",
+        "Phi" => " PHI ",
+        "Psi" => " PSI ",
+        "Omega" => " OMEGA ",
+        "cdot" => " cdot ",
+        "times" => " times ",
+        "div" => " \\(di ",
+        "pm" => " +- ",
+        "mp" => " \\(-+ ",
+        "leq" | "le" => " <= ",
+        "geq" | "ge" => " >= ",
+        "neq" | "ne" => " != ",
+        "ll" => " << ",
+        "gg" => " >> ",
+        "approx" => " approx ",
+        "sim" => " \\(ap ",
+        "equiv" => " == ",
+        "propto" => " \\(pt ",
+        "in" => " \\(mo ",
+        "notin" => " \\(nm ",
+        "subset" => " \\(sb ",
+        "subseteq" => " \\(ib ",
+        "supset" => " \\(sp ",
+        "supseteq" => " \\(ip ",
+        "cup" => " union ",
+        "cap" => " inter ",
+        "emptyset" | "varnothing" => " \\(es ",
+        "forall" => " \\(fa ",
+        "exists" => " \\(te ",
+        "neg" | "lnot" => " \\(no ",
+        "land" | "wedge" => " \\(AN ",
+        "lor" | "vee" => " \\(OR ",
+        "to" | "rightarrow" => " -> ",
+        "leftarrow" | "gets" => " <- ",
+        "Rightarrow" | "implies" => " \\(rA ",
+        "Leftarrow" => " \\(lA ",
+        "Leftrightarrow" | "iff" => " \\(hA ",
+        "infty" => " inf ",
+        "partial" => " partial ",
+        "nabla" => " grad ",
+        "hbar" => " \\(-h ",
+        "aleph" => " \\(Ah ",
+        "ldots" | "dots" => " ... ",
+        "cdots" => " cdots ",
+        "circ" => " \\(ci ",
+        "perp" => " \\(pp ",
+        "mid" => " | ",
+        "langle" => " \\(la ",
+        "rangle" => " \\(ra ",
+        "lfloor" => " \\(lf ",
+        "rfloor" => " \\(rf ",
+        "lceil" => " \\(lc ",
+        "rceil" => " \\(rc ",
+        "{" => " \"{\" ",
+        "}" => " \"}\" ",
+        "|" => " || ",
+        "," | ":" | ";" => " ^ ",
+        "quad" => " ~~ ",
+        "qquad" => " ~~~~ ",
+        " " => " ~ ",
+        "!" => " ",
+        "%" => " \"%\" ",
+        "#" => " \"#\" ",
+        "$" => " \"$\" ",
+        "&" => " \"&\" ",
+        _ => return None,
+    })
+}
+
+// Large operators and the eqn keyword they map to, which takes limits with
+// `from` and `to`.
+fn big_operator(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "sum" => "sum",
+        "prod" => "prod",
+        "int" | "oint" => "int",
+        "iint" => "int int",
+        "iiint" => "int int int",
+        "bigcup" => "union",
+        "bigcap" => "inter",
+        "lim" => "lim",
+        "liminf" => "\"lim inf\"",
+        "limsup" => "\"lim sup\"",
+        "max" => "max",
+        "min" => "min",
+        // `sup` and `inf` are eqn keywords for superscripts and infinity.
+        "sup" => "\"sup\"",
+        "inf" => "\"inf\"",
+        _ => return None,
+    })
+}
+
+// Functions eqn sets upright by itself.
+const FUNCTIONS: [&str; 13] = [
+    "sin", "cos", "tan", "sinh", "cosh", "tanh", "arc", "log", "ln", "exp", "det", "Re", "Im",
+];
+
+// Cells by column, as eqn lays out matrices column by column.
+fn columns(body: &[Token]) -> Vec<Vec<String>> {
+    let rows = latex::split_rows(body);
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    (0..width)
+        .map(|column| {
+            rows.iter()
+                .map(|row| {
+                    row.get(column)
+                        .map_or(String::from("\"\""), |cell| emitted(cell))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn emit_command(name: &str, tokens: &[Token], mut i: usize, out: &mut String) -> usize {
+    if let Some(op) = big_operator(name) {
+        out.push_str(&format!(" {} ", op));
+        // Turn attached limits into `from {...} to {...}`.
+        loop {
+            let j = latex::skip_spaces(tokens, i);
+            match tokens.get(j) {
+                Some(Token::Char('_')) => {
+                    out.push_str(" from ");
+                    i = emit_argument(tokens, j + 1, out);
+                }
+                Some(Token::Char('^')) => {
+                    out.push_str(" to ");
+                    i = emit_argument(tokens, j + 1, out);
+                }
+                Some(Token::Command(c)) if c == "limits" || c == "nolimits" => i = j + 1,
+                _ => return i,
+            }
+        }
+    }
+    if let Some(symbol) = symbol(name) {
+        out.push_str(symbol);
+        return i;
+    }
+    match name {
+        "frac" | "dfrac" | "tfrac" | "cfrac" => {
+            i = emit_argument(tokens, i, out);
+            out.push_str(" over ");
+            emit_argument(tokens, i, out)
+        }
+        "binom" => {
+            let (n, i) = latex::argument(tokens, i);
+            let (k, i) = latex::argument(tokens, i);
+            out.push_str(&format!(
+                " left ( pile {{ {} above {} }} right ) ",
+                emitted(n),
+                emitted(k)
+            ));
+            i
+        }
+        "sqrt" => match latex::optional_argument(tokens, i) {
+            // eqn has no root with an index, so the index is raised before it.
+            (Some(index), i) => {
+                out.push_str(&format!(" {{ \"\" sup {{ {} }} }} sqrt ", emitted(index)));
+                emit_argument(tokens, i, out)
+            }
+            (None, i) => {
+                out.push_str(" sqrt ");
+                emit_argument(tokens, i, out)
+            }
+        },
+        "text" | "textrm" | "mbox" | "operatorname" => {
+            let (text, i) = latex::argument(tokens, i);
+            out.push_str(&format!(
+                " \"{}\" ",
+                latex::to_string(text).replace('"', "")
+            ));
+            i
+        }
+        "mathrm" | "mathup" => {
+            out.push_str(" roman ");
+            emit_argument(tokens, i, out)
+        }
+        // eqn has no blackboard bold, so bold stands in for it.
+        "mathbf" | "boldsymbol" | "bm" | "mathbb" => {
+            out.push_str(" bold ");
+            emit_argument(tokens, i, out)
+        }
+        "mathit" => {
+            out.push_str(" italic ");
+            emit_argument(tokens, i, out)
+        }
+        // Accents follow what they're on in eqn.
+        "hat" | "widehat" | "bar" | "overline" | "vec" | "tilde" | "widetilde" | "dot" | "ddot"
+        | "underline" => {
+            i = emit_argument(tokens, i, out);
+            let accent = match name {
+                "widehat" => "hat",
+                "overline" => "bar",
+                "widetilde" => "tilde",
+                "ddot" => "dotdot",
+                "underline" => "under",
+                name => name,
+            };
+            out.push_str(&format!(" {} ", accent));
+            i
+        }
+        "left" | "right" => {
+            let j = latex::skip_spaces(tokens, i);
+            let delimiter = match tokens.get(j) {
+                Some(Token::Char('.')) => "\"\"".to_string(),
+                Some(Token::Char(c)) => c.to_string(),
+                Some(Token::Command(c)) if c == "{" || c == "}" => c.to_string(),
+                Some(Token::Command(c)) if c == "|" => "||".to_string(),
+                Some(Token::Command(c)) => symbol(c).unwrap_or(c).trim().to_string(),
+                _ => return j,
+            };
+            out.push_str(&format!(" {} {} ", name, delimiter));
+            j + 1
+        }
+        "begin" => {
+            let (environment, mut body, after) = latex::environment(tokens, i);
+            // Column specifications, as of `array`, have no counterpart.
+            if let Some(Token::Group(_)) = body.get(latex::skip_spaces(body, 0)) {
+                body = &body[latex::skip_spaces(body, 0) + 1..];
+            }
+            let (open, close) = match environment.as_str() {
+                "pmatrix" => ("left (", "right )"),
+                "bmatrix" => ("left [", "right ]"),
+                "Bmatrix" => ("left {", "right }"),
+                "vmatrix" => ("left |", "right |"),
+                "Vmatrix" => ("left ||", "right ||"),
+                "cases" | "dcases" => ("left {", "right \"\""),
+                _ => ("", ""),
+            };
+            let columns = columns(body);
+            let layout: fn(usize) -> &'static str = match environment.as_str() {
+                // Aligned equations line up at the `&`: the left side flush right
+                // and the right side flush left.
+                "aligned" | "align" | "align*" | "split" => |column: usize| {
+                    if column % 2 == 1 {
+                        "lcol"
+                    } else {
+                        "rcol"
+                    }
+                },
+                "cases" | "dcases" => |_| "lcol",
+                _ => |_| "ccol",
+            };
+            let columns: Vec<String> = columns
+                .iter()
+                .enumerate()
+                .map(|(column, cells)| {
+                    format!("{} {{ {} }}", layout(column), cells.join(" above "))
+                })
+                .collect();
+            out.push_str(&format!(
+                " {} matrix {{ {} }} {} ",
+                open,
+                columns.join(" "),
+                close
+            ));
+            after
+        }
+        "substack" => {
+            let (body, i) = latex::argument(tokens, i);
+            let rows: Vec<String> = columns(body).into_iter().flatten().collect();
+            out.push_str(&format!(" pile {{ {} }} ", rows.join(" above ")));
+            i
+        }
+        "\\" => i,
+        name if FUNCTIONS.contains(&name) => {
+            out.push_str(&format!(" {} ", name));
+            i
+        }
+        // Unknown commands with an argument, such as `\mathcal{F}`, keep only the
+        // argument.
+        _ if matches!(tokens.get(i), Some(Token::Group(_))) => emit_argument(tokens, i, out),
+        // Other functions, such as `\gcd`, and unknown commands are set upright.
+        name => {
+            out.push_str(&format!(" \"{}\" ", name));
+            i
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eqn(latex: &str) -> String {
+        from_latex(latex)
+            .trim_start_matches(".EQ\n")
+            .trim_end_matches("\n.EN")
+            .to_string()
+    }
+
+    #[test]
+    fn display_equation() {
+        assert_eq!(from_latex("x"), ".EQ\nx\n.EN");
+    }
+
+    #[test]
+    fn fractions_scripts_and_limits() {
+        assert_eq!(eqn(r"\frac{a}{b}"), "{ a } over { b }");
+        assert_eq!(eqn("x^{2} + y_{i}"), "x sup { 2 } + y sub { i }");
+        assert_eq!(eqn(r"\sum_{i=1}^{n} i"), "sum from { i = 1 } to { n } i");
+        assert_eq!(
+            eqn(r"\sqrt{x} \alpha \leq \infty"),
+            "sqrt { x } alpha <= inf"
+        );
+    }
+
+    #[test]
+    fn matrices_go_by_column() {
+        assert_eq!(
+            eqn(r"\begin{pmatrix} 1 & 2 \\ 3 & 4 \end{pmatrix}"),
+            "left ( matrix { ccol { 1 above 3 } ccol { 2 above 4 } } right )"
+        );
+        assert_eq!(
+            eqn(r"\begin{aligned} a &= b \\ c &= d \end{aligned}"),
+            "matrix { rcol { a above c } lcol { = b above = d } }"
+        );
+        assert_eq!(
+            eqn(r"f(x) = \begin{cases} 1 & x > 0 \\ 0 & \text{otherwise} \end{cases}"),
+            r#"f(x) = left { matrix { lcol { 1 above 0 } lcol { x > 0 above "otherwise" } } right """#
+        );
+    }
+
+    #[test]
+    fn text_fonts_and_unknown_commands() {
+        assert_eq!(eqn(r"\text{if } x"), r#""if " x"#);
+        assert_eq!(eqn(r"\mathbb{R} \mathcal{F}"), "bold { R } { F }");
+        assert_eq!(eqn(r"\gcd(a, b)"), r#""gcd" (a, b)"#);
+    }
+
+    #[test]
+    fn roots_and_binomials() {
+        // eqn has no indexed root, so the index is raised in front.
+        assert_eq!(eqn(r"\sqrt[n]{x}"), r#"{ "" sup { n } } sqrt { x }"#);
+        assert_eq!(eqn(r"\binom{n}{k}"), "left ( pile { n above k } right )");
+    }
+
+    #[test]
+    fn delimiters_primes_and_limits() {
+        assert_eq!(eqn(r"\left. x \right|"), r#"left "" x right |"#);
+        assert_eq!(eqn("x''"), "x prime prime");
+        assert_eq!(eqn(r"\sum\limits_{i} i"), "sum from { i } i");
+    }
+
+    #[test]
+    fn column_specifications_are_dropped() {
+        assert_eq!(
+            eqn(r"\begin{array}{cc} a & b \end{array}"),
+            "matrix { ccol { a } ccol { b } }"
+        );
+        assert_eq!(
+            eqn(r"\begin{bmatrix} a \\ b \end{bmatrix}"),
+            "left [ matrix { ccol { a above b } } right ]"
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use crate::i18n;
+
+// A complete formula written in Typst, loaded from the Help menu in place of the
+// input, so that new users can see what the tool does before learning the syntax.
+pub struct Example {
+    name: &'static str,
+    pub typst: &'static str,
+}
+
+impl Example {
+    pub fn label(&self) -> &'static str {
+        i18n::tr(self.name)
+    }
+}
+
+pub const ALL: [Example; 12] = [
+    Example {
+        name: "example-quadratic",
+        typst: "x = (-b plus.minus sqrt(b^2 - 4 a c)) / (2 a)",
+    },
+    Example {
+        name: "example-euler",
+        typst: "e^(i pi) + 1 = 0",
+    },
+    Example {
+        name: "example-gaussian",
+        typst: "integral_(-oo)^oo e^(-x^2) dif x = sqrt(pi)",
+    },
+    Example {
+        name: "example-taylor",
+        typst: "e^x = sum_(n = 0)^oo x^n / n! = 1 + x + x^2 / 2! + x^3 / 3! + dots.c",
+    },
+    Example {
+        name: "example-derivation",
+        typst: "integral x e^x dif x &= x e^x - integral e^x dif x \\\n&= (x - 1) e^x + C",
+    },
+    Example {
+        name: "example-system",
+        typst: "cases(x + y = 3, 2 x - y = 0) quad => quad x = 1, y = 2",
+    },
+    Example {
+        name: "example-eigenvector",
+        typst: "mat(2, 1; 1, 2) vec(1, 1) = 3 vec(1, 1)",
+    },
+    Example {
+        name: "example-bayes",
+        typst: "P(A | B) = (P(B | A) P(A)) / P(B)",
+    },
+    Example {
+        name: "example-fourier",
+        typst: "hat(f)(xi) = integral_(-oo)^oo f(x) e^(-2 pi i x xi) dif x",
+    },
+    Example {
+        name: "example-maxwell",
+        typst: "nabla dot bold(E) &= rho / epsilon_0 \\\nnabla times bold(E) &= -(partial bold(B)) / (partial t)",
+    },
+    Example {
+        name: "example-schrodinger",
+        typst: "i planck.reduce (partial) / (partial t) Psi(bold(r), t) = hat(H) Psi(bold(r), t)",
+    },
+    Example {
+        name: "example-energy",
+        typst: "E^2 = (p c)^2 + (m c^2)^2",
+    },
+];
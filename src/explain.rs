@@ -0,0 +1,370 @@
+use crate::brackets::Unbalanced;
+use crate::convert::Diagnostic;
+use crate::i18n;
+use crate::symbols::SYMBOLS;
+
+// Typst's math functions and operators, for telling a misspelled name from a real one.
+const FUNCTIONS: &[&str] = &[
+    "abs",
+    "accent",
+    "arccos",
+    "arcsin",
+    "arctan",
+    "arg",
+    "attach",
+    "binom",
+    "bold",
+    "bb",
+    "cal",
+    "cancel",
+    "cases",
+    "ceil",
+    "class",
+    "cos",
+    "cosh",
+    "cot",
+    "coth",
+    "csc",
+    "csch",
+    "ctg",
+    "deg",
+    "det",
+    "diag",
+    "dif",
+    "Dif",
+    "dim",
+    "display",
+    "exp",
+    "floor",
+    "frac",
+    "frak",
+    "gcd",
+    "hat",
+    "hom",
+    "id",
+    "im",
+    "inf",
+    "inline",
+    "italic",
+    "ker",
+    "lcm",
+    "lg",
+    "lim",
+    "liminf",
+    "limits",
+    "limsup",
+    "ln",
+    "log",
+    "lr",
+    "mat",
+    "max",
+    "mid",
+    "min",
+    "mod",
+    "mono",
+    "norm",
+    "op",
+    "overbrace",
+    "overbracket",
+    "overline",
+    "overparen",
+    "Pr",
+    "primes",
+    "root",
+    "round",
+    "sans",
+    "scripts",
+    "sec",
+    "sech",
+    "serif",
+    "sgn",
+    "sin",
+    "sinc",
+    "sinh",
+    "sqrt",
+    "stretch",
+    "sup",
+    "tan",
+    "tanh",
+    "tg",
+    "tr",
+    "underbrace",
+    "underbracket",
+    "underline",
+    "underparen",
+    "upright",
+    "vec",
+    "arrow",
+    "tilde",
+    "dot",
+    "dot.double",
+    "breve",
+    "acute",
+    "grave",
+    "macron",
+    "circle",
+    "caron",
+    "text",
+    "quad",
+    "qquad",
+    "wide",
+    "thin",
+    "med",
+    "thick",
+    "space",
+    "oo",
+    "dots",
+];
+
+// A likelier reason for a failed conversion, and the input with it fixed if there's
+// an obvious fix.
+pub struct Hint {
+    pub message: String,
+    pub fix: Option<String>,
+}
+
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
+
+fn is_known(name: &str) -> bool {
+    FUNCTIONS.contains(&name)
+        || SYMBOLS
+            .iter()
+            .any(|symbol| symbol.typst == name || symbol.typst.split('.').next() == Some(name))
+}
+
+// The known name closest to `name`, if it's close enough to be a typo.
+fn suggestion(name: &str) -> Option<&'static str> {
+    FUNCTIONS
+        .iter()
+        .copied()
+        .chain(SYMBOLS.iter().map(|symbol| symbol.typst))
+        .map(|known| (distance(name, known), known))
+        .filter(|&(distance, _)| distance <= 2 && distance < name.chars().count())
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, known)| known)
+}
+
+// Identifiers of Typst math outside strings and comments, as character ranges.
+// Single letters are always variables and left out.
+fn identifiers(chars: &[char]) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            c if c.is_alphabetic() => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric()
+                        || (chars[i] == '.' && chars.get(i + 1).is_some_and(|c| c.is_alphabetic())))
+                {
+                    i += 1;
+                }
+                // Named arguments such as `delim:` aren't looked up.
+                if i - start > 1 && chars.get(i) != Some(&':') {
+                    found.push((start, i));
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    found
+}
+
+// Where the last string that's never closed opens.
+fn unclosed_string(chars: &[char]) -> Option<usize> {
+    let mut open = None;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '"' => open = if open.is_some() { None } else { Some(i) },
+            '/' if open.is_none() && chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    open
+}
+
+// Split `text` on `separator` where it isn't nested in brackets or a string.
+fn split_top_level(text: &[char], separator: char) -> Vec<Vec<char>> {
+    let mut parts = vec![Vec::new()];
+    let mut depth = 0;
+    let mut in_string = false;
+    for &c in text {
+        match c {
+            '"' => in_string = !in_string,
+            '(' | '[' | '{' if !in_string => depth += 1,
+            ')' | ']' | '}' if !in_string => depth -= 1,
+            _ if c == separator && depth == 0 && !in_string => {
+                parts.push(Vec::new());
+                continue;
+            }
+            _ => {}
+        }
+        if let Some(part) = parts.last_mut() {
+            part.push(c);
+        }
+    }
+    parts
+}
+
+// A row of a `mat(..)` with fewer cells than the others where two entries are only
+// separated by a space, e.g. `mat(1, 2; 3 4)`. Returns the row's number and the
+// input with a comma put in.
+fn missing_comma(chars: &[char]) -> Option<(usize, String)> {
+    let text: String = chars.iter().collect();
+    for (start, _) in text.match_indices("mat(") {
+        let open = text[..start].chars().count() + 3;
+        let mut depth = 0;
+        let mut in_string = false;
+        let close = (open..chars.len()).find(|&i| {
+            match chars[i] {
+                '"' => in_string = !in_string,
+                '(' | '[' | '{' if !in_string => depth += 1,
+                ')' | ']' | '}' if !in_string => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        })?;
+        let rows = split_top_level(&chars[open + 1..close], ';');
+        let cells: Vec<Vec<Vec<char>>> = rows.iter().map(|row| split_top_level(row, ',')).collect();
+        // Named arguments such as `delim: "["` aren't cells.
+        let width = |row: &Vec<Vec<char>>| row.iter().filter(|cell| !cell.contains(&':')).count();
+        let widest = cells.iter().map(width).max()?;
+        let mut offset = open + 1;
+        for (row, row_cells) in cells.iter().enumerate() {
+            let mut cell_offset = offset;
+            for cell in row_cells {
+                let trimmed = cell.iter().collect::<String>();
+                let leading = cell.len() - trimmed.trim_start().chars().count();
+                let content: Vec<char> = trimmed.trim().chars().collect();
+                let gap = content.iter().position(|c| c.is_whitespace());
+                if let Some(gap) = gap.filter(|_| width(row_cells) < widest && !cell.contains(&':'))
+                {
+                    let at = cell_offset + leading + gap;
+                    let mut fixed = chars.to_vec();
+                    fixed.insert(at, ',');
+                    return Some((row + 1, fixed.into_iter().collect()));
+                }
+                cell_offset += cell.len() + 1;
+            }
+            offset += rows[row].len() + 1;
+        }
+    }
+    None
+}
+
+// Look for a common mistake behind a failed conversion of `input`.
+pub fn explain(
+    input: &str,
+    error: &str,
+    unbalanced: Option<&Unbalanced>,
+    typst: bool,
+) -> Option<Hint> {
+    let chars: Vec<char> = input.chars().collect();
+    if typst {
+        if let Some(open) = unclosed_string(&chars) {
+            let line = chars[..open].iter().filter(|&&c| c == '\n').count() + 1;
+            let end = open + chars[open..].iter().take_while(|&&c| c != '\n').count();
+            let mut fixed = chars.clone();
+            fixed.insert(end, '"');
+            return Some(Hint {
+                message: i18n::tr_args("explain-unclosed-string", &[("line", &line.to_string())]),
+                fix: Some(fixed.into_iter().collect()),
+            });
+        }
+    }
+    if let Some(unbalanced) = unbalanced {
+        return Some(Hint {
+            message: unbalanced.message(),
+            fix: Some(unbalanced.fix(input)),
+        });
+    }
+    if !typst {
+        return None;
+    }
+    // Prefer the name pandoc's error points at.
+    let diagnostic = Diagnostic::from_error(error);
+    let unknown: Vec<(usize, usize)> = identifiers(&chars)
+        .into_iter()
+        .filter(|&(start, end)| !is_known(&chars[start..end].iter().collect::<String>()))
+        .collect();
+    let position = diagnostic
+        .line
+        .zip(diagnostic.column)
+        .and_then(|(line, column)| {
+            let start = if line == 1 {
+                0
+            } else {
+                chars
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &c)| c == '\n')
+                    .nth(line - 2)?
+                    .0
+                    + 1
+            };
+            Some(start + column.saturating_sub(1))
+        });
+    let named = position
+        .and_then(|at| {
+            unknown
+                .iter()
+                .find(|&&(start, end)| (start..=end).contains(&at))
+        })
+        .or(unknown.first());
+    if let Some(&(start, end)) = named {
+        let name: String = chars[start..end].iter().collect();
+        let (message, replacement) = match suggestion(&name) {
+            Some(known) => (
+                i18n::tr_args("explain-unknown", &[("name", &name), ("suggestion", known)]),
+                known.to_string(),
+            ),
+            None => (
+                i18n::tr_args("explain-unknown-text", &[("name", &name)]),
+                format!("\"{}\"", name),
+            ),
+        };
+        let before: String = chars[..start].iter().collect();
+        let after: String = chars[end..].iter().collect();
+        return Some(Hint {
+            message,
+            fix: Some(format!("{}{}{}", before, replacement, after)),
+        });
+    }
+    missing_comma(&chars).map(|(row, fixed)| Hint {
+        message: i18n::tr_args("explain-mat-comma", &[("row", &row.to_string())]),
+        fix: Some(fixed),
+    })
+}
@@ -0,0 +1,130 @@
+use crate::i18n;
+use eframe::egui;
+use regex::{NoExpand, Regex};
+use std::ops::Range;
+
+pub enum Action {
+    Replace,
+    ReplaceAll,
+}
+
+// Find and replace state for the input editor.
+#[derive(Default)]
+pub struct Search {
+    pub open: bool,
+    replace: bool,
+    query: String,
+    replacement: String,
+    regex: bool,
+    focus: bool,
+}
+
+impl Search {
+    pub fn open(&mut self, replace: bool) {
+        self.open = true;
+        self.replace = replace;
+        self.focus = true;
+    }
+
+    fn pattern(&self) -> Result<Regex, String> {
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        Regex::new(&pattern).map_err(|err| err.to_string())
+    }
+
+    // Byte ranges of the matches in `text`, for highlighting.
+    pub fn matches(&self, text: &str) -> Result<Vec<Range<usize>>, String> {
+        if !self.open || self.query.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(self
+            .pattern()?
+            .find_iter(text)
+            .map(|m| m.range())
+            .filter(|range| !range.is_empty())
+            .collect())
+    }
+
+    // Replace the first match, or all of them. With the regex option the
+    // replacement may refer to groups as `$1`.
+    pub fn replace(&self, text: &str, all: bool) -> Result<String, String> {
+        if self.query.is_empty() {
+            return Ok(text.to_string());
+        }
+        let pattern = self.pattern()?;
+        let limit = if all { 0 } else { 1 };
+        let replaced = if self.regex {
+            pattern.replacen(text, limit, self.replacement.as_str())
+        } else {
+            pattern.replacen(text, limit, NoExpand(&self.replacement))
+        };
+        Ok(replaced.into_owned())
+    }
+
+    // Draw the find bar; `matches` is the result of `matches` for the input.
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        matches: &Result<Vec<Range<usize>>, String>,
+    ) -> Option<Action> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.query)
+                    .hint_text(i18n::tr("find-hint"))
+                    .desired_width(140.0),
+            );
+            if std::mem::take(&mut self.focus) {
+                response.request_focus();
+            }
+            ui.checkbox(&mut self.regex, i18n::tr("find-regex"));
+            match matches {
+                Ok(matches) if !self.query.is_empty() => {
+                    ui.weak(i18n::tr_args(
+                        "find-matches",
+                        &[("count", &matches.len().to_string())],
+                    ));
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    ui.colored_label(ui.visuals().error_fg_color, i18n::tr("find-invalid"))
+                        .on_hover_text(err);
+                }
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.small_button("×").clicked() {
+                    self.open = false;
+                }
+                if !self.replace && ui.small_button(i18n::tr("find-show-replace")).clicked() {
+                    self.replace = true;
+                }
+            });
+        });
+        if self.replace {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.replacement)
+                        .hint_text(i18n::tr("replace-hint"))
+                        .desired_width(140.0),
+                );
+                let found = matches.as_ref().is_ok_and(|matches| !matches.is_empty());
+                if ui
+                    .add_enabled(found, egui::Button::new(i18n::tr("replace")))
+                    .clicked()
+                {
+                    action = Some(Action::Replace);
+                }
+                if ui
+                    .add_enabled(found, egui::Button::new(i18n::tr("replace-all")))
+                    .clicked()
+                {
+                    action = Some(Action::ReplaceAll);
+                }
+            });
+        }
+        action
+    }
+}
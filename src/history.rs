@@ -0,0 +1,198 @@
+use crate::i18n;
+use crate::json::Value;
+use crate::settings::config_dir;
+use eframe::egui;
+use std::fs;
+use std::path::PathBuf;
+
+const MAX_ENTRIES: usize = 500;
+
+#[derive(Clone, PartialEq)]
+pub struct Entry {
+    pub typst: String,
+    pub latex: String,
+}
+
+pub enum Action {
+    Restore(Entry),
+    Export(Vec<Entry>),
+}
+
+// Conversions that were copied or saved, newest first, kept across runs.
+#[derive(Default)]
+pub struct History {
+    pub open: bool,
+    entries: Vec<Entry>,
+    query: String,
+    // Parallel to `entries`: whether the entry is ticked for export.
+    selected: Vec<bool>,
+}
+
+impl History {
+    fn path() -> Option<PathBuf> {
+        Some(config_dir()?.join("history.json"))
+    }
+
+    pub fn load() -> Self {
+        let entries: Vec<Entry> = Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| Value::parse(&text).ok())
+            .and_then(|value| {
+                let entries = value.as_array()?.iter().filter_map(|entry| {
+                    Some(Entry {
+                        typst: entry.get("typst")?.as_str()?.to_string(),
+                        latex: entry.get("latex")?.as_str()?.to_string(),
+                    })
+                });
+                Some(entries.collect())
+            })
+            .unwrap_or_default();
+        Self {
+            selected: vec![false; entries.len()],
+            entries,
+            ..Default::default()
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("Failed to locate config directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|_| "Failed to create config directory")?;
+        }
+        let value = Value::Array(
+            self.entries
+                .iter()
+                .map(|entry| {
+                    Value::object([
+                        ("typst", entry.typst.as_str().into()),
+                        ("latex", entry.latex.as_str().into()),
+                    ])
+                })
+                .collect(),
+        );
+        fs::write(&path, value.pretty(2)).map_err(|_| "Failed to write history".into())
+    }
+
+    // Add a conversion, moving it to the top if it's already there.
+    pub fn record(&mut self, entry: Entry) -> Result<(), String> {
+        if let Some(i) = self.entries.iter().position(|e| *e == entry) {
+            self.entries.remove(i);
+            self.selected.remove(i);
+        }
+        self.entries.insert(0, entry);
+        self.selected.insert(0, false);
+        self.entries.truncate(MAX_ENTRIES);
+        self.selected.truncate(MAX_ENTRIES);
+        self.save()
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        let query = self.query.to_lowercase();
+        query.is_empty()
+            || entry.typst.to_lowercase().contains(&query)
+            || entry.latex.to_lowercase().contains(&query)
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Action> {
+        let mut action = None;
+        let mut open = self.open;
+        egui::Window::new(i18n::tr("history"))
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text(i18n::tr("history-search"))
+                        .desired_width(f32::INFINITY),
+                );
+                let visible: Vec<usize> = (0..self.entries.len())
+                    .filter(|&i| self.matches(&self.entries[i]))
+                    .collect();
+                ui.horizontal(|ui| {
+                    if ui.button(i18n::tr("history-select-all")).clicked() {
+                        for &i in &visible {
+                            self.selected[i] = true;
+                        }
+                    }
+                    if ui.button(i18n::tr("history-select-none")).clicked() {
+                        self.selected.fill(false);
+                    }
+                    let chosen: Vec<Entry> = (0..self.entries.len())
+                        .filter(|&i| self.selected[i])
+                        .map(|i| self.entries[i].clone())
+                        .collect();
+                    if ui
+                        .add_enabled(
+                            !chosen.is_empty(),
+                            egui::Button::new(i18n::tr("history-export")),
+                        )
+                        .clicked()
+                    {
+                        action = Some(Action::Export(chosen));
+                    }
+                });
+                ui.separator();
+                if visible.is_empty() {
+                    ui.weak(i18n::tr("history-empty"));
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for i in visible {
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut self.selected[i], "");
+                                let entry = &self.entries[i];
+                                let label = ui
+                                    .add(
+                                        egui::Label::new(format!(
+                                            "{}  →  {}",
+                                            entry.typst, entry.latex
+                                        ))
+                                        .truncate()
+                                        .sense(egui::Sense::click()),
+                                    )
+                                    .on_hover_text(i18n::tr("history-restore"));
+                                if label.clicked() {
+                                    action = Some(Action::Restore(entry.clone()));
+                                }
+                            });
+                        }
+                    });
+            });
+        self.open = open;
+        action
+    }
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+// A Markdown table with Typst and LaTeX columns, as code spans.
+pub fn markdown(entries: &[Entry]) -> String {
+    let mut table = String::from("| Typst | LaTeX |\n| --- | --- |\n");
+    for entry in entries {
+        table.push_str(&format!(
+            "| `{}` | `{}` |\n",
+            markdown_cell(&entry.typst),
+            markdown_cell(&entry.latex)
+        ));
+    }
+    table
+}
+
+fn csv_field(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+pub fn csv(entries: &[Entry]) -> String {
+    let mut table = String::from("Typst,LaTeX\n");
+    for entry in entries {
+        table.push_str(&format!(
+            "{},{}\n",
+            csv_field(&entry.typst),
+            csv_field(&entry.latex)
+        ));
+    }
+    table
+}
@@ -0,0 +1,48 @@
+use crate::convert::{self, Direction};
+use crate::diagnostics;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// Run the shell command of a hook on `text`, which it reads on stdin, and return
+// what it writes on stdout. `direction` is in `TYPST2LATEX_DIRECTION`, for scripts
+// that only touch one side. Like pandoc, it's killed when the conversion is
+// cancelled.
+pub fn run(command: &str, text: &str, direction: Direction) -> Result<String, String> {
+    diagnostics::log(format!("Hook: {} ({} bytes)", command, text.len()));
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let mut child = shell
+        .arg(command)
+        .env("TYPST2LATEX_DIRECTION", direction.name())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| format!("Failed to run {}", command))?;
+    child
+        .stdin
+        .take()
+        .ok_or("Failed to open stdin")?
+        .write_all(text.as_bytes())
+        .map_err(|_| "Failed to write to stdin")?;
+    let output = convert::wait(child, "hook")?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        let error = String::from_utf8_lossy(&output.stderr);
+        let error = error.trim();
+        diagnostics::log(format!("Hook exited with {}: {}", output.status, error));
+        if error.is_empty() {
+            Err(format!("{} failed with {}", command, output.status))
+        } else {
+            Err(format!("{} failed: {}", command, error))
+        }
+    }
+}
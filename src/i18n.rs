@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Language {
+    English,
+    Chinese,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Chinese];
+
+    // Name stored in the settings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Chinese => "zh",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Chinese => "中文 (Chinese)",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Language> {
+        Language::ALL
+            .into_iter()
+            .find(|language| language.name() == name)
+    }
+
+    // The system language from the usual locale variables, or English.
+    pub fn detect() -> Language {
+        for var in ["LC_ALL", "LC_MESSAGES", "LANG"] {
+            match std::env::var(var) {
+                Ok(locale) if locale.starts_with("zh") => return Language::Chinese,
+                Ok(locale) if !locale.is_empty() => return Language::English,
+                _ => {}
+            }
+        }
+        Language::English
+    }
+
+    fn messages(self) -> &'static HashMap<&'static str, &'static str> {
+        static ENGLISH: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+        static CHINESE: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+        match self {
+            Language::English => ENGLISH.get_or_init(|| parse(include_str!("../locales/en.ftl"))),
+            Language::Chinese => CHINESE.get_or_init(|| parse(include_str!("../locales/zh.ftl"))),
+        }
+    }
+}
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+
+pub fn set_language(language: Language) {
+    let index = Language::ALL
+        .iter()
+        .position(|l| *l == language)
+        .unwrap_or(0);
+    CURRENT.store(index, Ordering::Relaxed);
+}
+
+pub fn language() -> Language {
+    Language::ALL[CURRENT.load(Ordering::Relaxed)]
+}
+
+// Parse the subset of Fluent used by the bundled resources: single-line
+// `key = value` messages, with `#` comments and blank lines in between.
+fn parse(resource: &'static str) -> HashMap<&'static str, &'static str> {
+    resource
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(" = "))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+// The message for `key` in the current language, falling back to English and then
+// to the key itself so that a missing translation is visible but harmless.
+pub fn tr(key: &'static str) -> &'static str {
+    language()
+        .messages()
+        .get(key)
+        .or_else(|| Language::English.messages().get(key))
+        .copied()
+        .unwrap_or(key)
+}
+
+// `tr` with `{ $name }` placeables filled in from `args`.
+pub fn tr_args(key: &'static str, args: &[(&str, &str)]) -> String {
+    let mut message = tr(key).to_string();
+    for (name, value) in args {
+        message = message.replace(&format!("{{ ${} }}", name), value);
+    }
+    message
+}
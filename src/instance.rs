@@ -0,0 +1,177 @@
+use crate::convert::Direction;
+use crate::diagnostics;
+use crate::json::Value;
+use crate::settings::config_dir;
+use crate::url_scheme;
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+// What a launch asks of the window: come to the front, and maybe convert something.
+#[derive(Default)]
+pub struct Request {
+    // Text given on the command line.
+    pub input: Option<String>,
+    // Convert what's on the clipboard.
+    pub paste: bool,
+    pub direction: Option<Direction>,
+}
+
+impl Request {
+    pub fn from_args(args: &[String]) -> Self {
+        // Opened from a `typst2latex://` link.
+        if let [link] = args {
+            if url_scheme::is_link(link) {
+                return match url_scheme::parse(link) {
+                    Ok(link) => Self {
+                        input: link.src,
+                        paste: false,
+                        direction: link.direction,
+                    },
+                    Err(err) => {
+                        diagnostics::log(format!("Ignored link {}: {}", link, err));
+                        Self::default()
+                    }
+                };
+            }
+        }
+        let mut request = Self::default();
+        let mut words = Vec::new();
+        for arg in args {
+            match arg.as_str() {
+                "--paste" => request.paste = true,
+                _ => words.push(arg.as_str()),
+            }
+        }
+        if !words.is_empty() {
+            request.input = Some(words.join(" "));
+        }
+        request
+    }
+
+    fn to_json(&self) -> Value {
+        Value::object([
+            (
+                "input",
+                self.input.as_deref().map_or(Value::Null, Value::from),
+            ),
+            ("paste", self.paste.into()),
+            (
+                "direction",
+                self.direction
+                    .map_or(Value::Null, |direction| direction.name().into()),
+            ),
+        ])
+    }
+
+    fn from_json(value: &Value) -> Self {
+        Self {
+            input: value.get("input").and_then(Value::as_str).map(String::from),
+            paste: value.get("paste").and_then(Value::as_bool).unwrap_or(false),
+            direction: value
+                .get("direction")
+                .and_then(Value::as_str)
+                .and_then(Direction::from_name),
+        }
+    }
+}
+
+// Holds the port the running window listens on and a secret, so that a stale file
+// pointing at a port some other program has since taken isn't mistaken for us.
+fn path() -> Option<PathBuf> {
+    Some(config_dir()?.join("instance"))
+}
+
+fn new_token() -> String {
+    // RandomState is seeded randomly per process, which is all this needs.
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    format!("{:016x}", hasher.finish())
+}
+
+// Hand the request to a window that is already running. Returns false if there is
+// none, in which case this launch should open its own.
+pub fn forward(request: &Request) -> bool {
+    let Some(file) = path().and_then(|path| fs::read_to_string(path).ok()) else {
+        return false;
+    };
+    let Some((port, token)) = file
+        .trim()
+        .split_once(' ')
+        .and_then(|(port, token)| Some((port.parse::<u16>().ok()?, token)))
+    else {
+        return false;
+    };
+    let send = || -> std::io::Result<bool> {
+        let address = (Ipv4Addr::LOCALHOST, port).into();
+        let mut stream = TcpStream::connect_timeout(&address, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        let message = Value::object([("token", token.into()), ("request", request.to_json())]);
+        writeln!(stream, "{}", message)?;
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        Ok(reply.trim() == "ok")
+    };
+    let forwarded = send().unwrap_or(false);
+    if forwarded {
+        diagnostics::log(format!("Handed the launch to the window on port {}", port));
+    }
+    forwarded
+}
+
+fn receive(mut stream: TcpStream, token: &str) -> Result<Request, String> {
+    stream
+        .set_read_timeout(Some(TIMEOUT))
+        .map_err(|err| err.to_string())?;
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .map_err(|err| err.to_string())?;
+    let message = Value::parse(&line)?;
+    if message.get("token").and_then(Value::as_str) != Some(token) {
+        return Err("Ignored a connection without the right token".into());
+    }
+    let request = Request::from_json(message.get("request").ok_or("Missing request")?);
+    stream.write_all(b"ok\n").map_err(|err| err.to_string())?;
+    Ok(request)
+}
+
+// Listen for later launches and pass on what they ask for. `notify` is called after
+// each request, to wake the window up.
+pub fn listen(notify: impl Fn() + Send + 'static) -> Result<mpsc::Receiver<Request>, String> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .map_err(|err| format!("Failed to listen for other launches: {}", err))?;
+    let port = listener
+        .local_addr()
+        .map_err(|err| format!("Failed to listen for other launches: {}", err))?
+        .port();
+    let token = new_token();
+    let path = path().ok_or("Failed to locate config directory")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|_| "Failed to create config directory")?;
+    }
+    fs::write(&path, format!("{} {}", port, token)).map_err(|_| "Failed to write instance file")?;
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            match receive(stream, &token) {
+                Ok(request) => {
+                    if tx.send(request).is_err() {
+                        break;
+                    }
+                    notify();
+                }
+                Err(err) => diagnostics::log(format!("Instance request failed: {}", err)),
+            }
+        }
+    });
+    Ok(rx)
+}
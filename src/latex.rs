@@ -0,0 +1,145 @@
+// A lightweight LaTeX math tokenizer shared by the emitters for other targets.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    // A control sequence without the backslash, e.g. `frac`, `{` or `\`.
+    Command(String),
+    Char(char),
+    // A braced group.
+    Group(Vec<Token>),
+    Space,
+}
+
+pub fn parse(latex: &str) -> Vec<Token> {
+    let chars: Vec<char> = latex.chars().collect();
+    let mut pos = 0;
+    parse_until_close(&chars, &mut pos)
+}
+
+fn parse_until_close(chars: &[char], pos: &mut usize) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    while *pos < chars.len() {
+        let c = chars[*pos];
+        *pos += 1;
+        match c {
+            '}' => break,
+            '{' => tokens.push(Token::Group(parse_until_close(chars, pos))),
+            '%' => {
+                // Skip comments up to the end of the line.
+                while *pos < chars.len() && chars[*pos] != '\n' {
+                    *pos += 1;
+                }
+            }
+            '\\' => {
+                let start = *pos;
+                while *pos < chars.len() && chars[*pos].is_ascii_alphabetic() {
+                    *pos += 1;
+                }
+                if *pos == start && *pos < chars.len() {
+                    *pos += 1;
+                }
+                tokens.push(Token::Command(chars[start..*pos].iter().collect()));
+            }
+            c if c.is_whitespace() => {
+                if tokens.last() != Some(&Token::Space) {
+                    tokens.push(Token::Space);
+                }
+            }
+            c => tokens.push(Token::Char(c)),
+        }
+    }
+    tokens
+}
+
+// Serialize tokens back into LaTeX source.
+pub fn to_string(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Command(name) => {
+                out.push('\\');
+                out.push_str(name);
+                // Keep a command name from running into a following letter.
+                let letter_command = name.chars().all(|c| c.is_ascii_alphabetic());
+                if let Some(Token::Char(c)) = tokens.get(i + 1) {
+                    if letter_command && c.is_ascii_alphabetic() {
+                        out.push(' ');
+                    }
+                }
+            }
+            Token::Char(c) => out.push(*c),
+            Token::Group(group) => {
+                out.push('{');
+                out.push_str(&to_string(group));
+                out.push('}');
+            }
+            Token::Space => out.push(' '),
+        }
+    }
+    out
+}
+
+// Index of the next token that is not a space, starting at `i`.
+pub fn skip_spaces(tokens: &[Token], mut i: usize) -> usize {
+    while tokens.get(i) == Some(&Token::Space) {
+        i += 1;
+    }
+    i
+}
+
+// The argument starting at `i` (skipping spaces) as a token slice, plus the index
+// after it. Braced groups yield their content; anything else is a single token.
+pub fn argument(tokens: &[Token], i: usize) -> (&[Token], usize) {
+    let i = skip_spaces(tokens, i);
+    match tokens.get(i) {
+        Some(Token::Group(group)) => (group, i + 1),
+        Some(_) => (&tokens[i..i + 1], i + 1),
+        None => (&[], i),
+    }
+}
+
+// An optional `[...]` argument starting at `i`, plus the index after it.
+pub fn optional_argument(tokens: &[Token], i: usize) -> (Option<&[Token]>, usize) {
+    let start = skip_spaces(tokens, i);
+    if tokens.get(start) != Some(&Token::Char('[')) {
+        return (None, i);
+    }
+    match tokens[start..].iter().position(|t| *t == Token::Char(']')) {
+        Some(end) => (Some(&tokens[start + 1..start + end]), start + end + 1),
+        None => (None, i),
+    }
+}
+
+// The name of the environment whose `\begin` command is at `i - 1`, its body,
+// and the index after the matching `\end{...}`.
+pub fn environment(tokens: &[Token], i: usize) -> (String, &[Token], usize) {
+    let (name, body_start) = argument(tokens, i);
+    let name = to_string(name);
+    let mut depth = 0;
+    let mut j = body_start;
+    while j < tokens.len() {
+        match &tokens[j] {
+            Token::Command(command) if command == "begin" => depth += 1,
+            Token::Command(command) if command == "end" => {
+                if depth == 0 {
+                    let (_, after) = argument(tokens, j + 1);
+                    return (name, &tokens[body_start..j], after);
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+        j += 1;
+    }
+    (name, &tokens[body_start..], tokens.len())
+}
+
+// Split an environment body into rows (on `\\`) and cells (on `&`).
+pub fn split_rows(body: &[Token]) -> Vec<Vec<&[Token]>> {
+    body.split(|t| *t == Token::Command("\\".to_string()))
+        .map(|row| row.split(|t| *t == Token::Char('&')).collect::<Vec<_>>())
+        .filter(|row| {
+            !row.iter()
+                .all(|cell| cell.iter().all(|t| *t == Token::Space))
+        })
+        .collect()
+}
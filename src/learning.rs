@@ -0,0 +1,127 @@
+use crate::symbols::SYMBOLS;
+
+// One construct of the Typst side and what it became on the LaTeX side.
+pub struct Note {
+    pub typst: String,
+    pub latex: String,
+}
+
+// Typst functions, a piece of LaTeX that shows they were converted, and how to
+// present the LaTeX form. Also listed in the quick reference.
+pub const FUNCTIONS: &[(&str, &str, &str)] = &[
+    ("mat", "matrix}", "\\begin{pmatrix} … \\end{pmatrix}"),
+    ("vec", "matrix}", "\\begin{pmatrix} … \\end{pmatrix}"),
+    ("cases", "{cases}", "\\begin{cases} … \\end{cases}"),
+    ("frac", "\\frac", "\\frac{…}{…}"),
+    ("binom", "\\binom", "\\binom{…}{…}"),
+    ("sqrt", "\\sqrt", "\\sqrt{…}"),
+    ("root", "\\sqrt[", "\\sqrt[n]{…}"),
+    ("abs", "|", "\\left| … \\right|"),
+    ("norm", "\\|", "\\left\\| … \\right\\|"),
+    ("floor", "\\lfloor", "\\lfloor … \\rfloor"),
+    ("ceil", "\\lceil", "\\lceil … \\rceil"),
+    ("lr", "\\left", "\\left( … \\right)"),
+    ("hat", "\\hat", "\\hat{…}"),
+    ("tilde", "\\tilde", "\\tilde{…}"),
+    ("dot", "\\dot", "\\dot{…}"),
+    ("arrow", "\\overrightarrow", "\\overrightarrow{…}"),
+    ("overline", "\\overline", "\\overline{…}"),
+    ("underline", "\\underline", "\\underline{…}"),
+    ("overbrace", "\\overbrace", "\\overbrace{…}"),
+    ("underbrace", "\\underbrace", "\\underbrace{…}"),
+    ("upright", "\\mathrm", "\\mathrm{…}"),
+    ("bold", "\\mathbf", "\\mathbf{…}"),
+    ("italic", "\\mathit", "\\mathit{…}"),
+    ("cal", "\\mathcal", "\\mathcal{…}"),
+    ("frak", "\\mathfrak", "\\mathfrak{…}"),
+    ("bb", "\\mathbb", "\\mathbb{…}"),
+    ("op", "\\operatorname", "\\operatorname{…}"),
+];
+
+// Whether `latex` uses the command `\name`, not just one starting with it.
+fn has_command(latex: &str, name: &str) -> bool {
+    let command = format!("\\{}", name);
+    latex
+        .match_indices(&command)
+        .any(|(i, _)| !latex[i + command.len()..].starts_with(|c: char| c.is_ascii_alphabetic()))
+}
+
+// What each construct of a formula turned into, in the order they appear in the
+// Typst. Only pairs whose LaTeX form is actually in `latex` are listed.
+pub fn notes(typst: &str, latex: &str) -> Vec<Note> {
+    let mut notes: Vec<Note> = Vec::new();
+    let mut note = |typst: String, latex: &str| {
+        if !notes.iter().any(|note| note.typst == typst) {
+            notes.push(Note {
+                typst,
+                latex: latex.to_string(),
+            });
+        }
+    };
+    let chars: Vec<char> = typst.chars().collect();
+    let mut in_string = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if in_string {
+            match c {
+                '\\' => i += 1,
+                '"' => in_string = false,
+                _ => {}
+            }
+            i += 1;
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                if latex.contains("\\text") {
+                    note("\"…\"".to_string(), "\\text{…}");
+                }
+            }
+            '/' if latex.contains("\\frac") => note("… / …".to_string(), "\\frac{…}{…}"),
+            c if c.is_alphabetic() => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric()
+                        || (chars[i] == '.' && chars.get(i + 1).is_some_and(|c| c.is_alphabetic())))
+                {
+                    i += 1;
+                }
+                let name: String = chars[start..i].iter().collect();
+                if chars.get(i) == Some(&'(') {
+                    if let Some((_, marker, shown)) =
+                        FUNCTIONS.iter().find(|(function, _, _)| *function == name)
+                    {
+                        if latex.contains(marker) {
+                            note(format!("{}(..)", name), shown);
+                        }
+                    }
+                } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| symbol.typst == name) {
+                    if has_command(latex, symbol.latex) {
+                        note(name, &format!("\\{}", symbol.latex));
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    notes
+}
+
+// Append the notes to the output as comments, one per line, pointing from the input
+// syntax to the output syntax.
+pub fn annotate(output: &str, notes: &[Note], comment: &str, from_latex: bool) -> String {
+    let mut annotated = output.to_string();
+    for note in notes {
+        let (from, to) = if from_latex {
+            (&note.latex, &note.typst)
+        } else {
+            (&note.typst, &note.latex)
+        };
+        annotated.push_str(&format!("\n{} {} -> {}", comment, from, to));
+    }
+    annotated
+}
@@ -0,0 +1,255 @@
+use crate::latex::{self, Token};
+
+// Macros expanding into macros are followed this deep, which stops ones that
+// expand into themselves.
+const DEPTH: usize = 16;
+
+// A macro of a LaTeX preamble, with the default of its first argument if that's
+// optional and a body in which `#1` and on stand for the arguments.
+pub struct Macro {
+    name: String,
+    arguments: usize,
+    default: Option<Vec<Token>>,
+    body: Vec<Token>,
+}
+
+// The name of the command at `i`, braced or not as in `\newcommand\R`, and the
+// index after it.
+fn command_name(tokens: &[Token], i: usize) -> Option<(String, usize)> {
+    match latex::argument(tokens, i) {
+        ([Token::Command(name)], next) => Some((name.clone(), next)),
+        _ => None,
+    }
+}
+
+// The macros defined in `preamble` with `\newcommand` and its variants, `\def`,
+// `\DeclareMathOperator` and `\DeclarePairedDelimiter`. Anything else in it is
+// skipped.
+pub fn parse(preamble: &str) -> Vec<Macro> {
+    let tokens = latex::parse(preamble);
+    let mut macros = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let Token::Command(command) = &tokens[i] else {
+            i += 1;
+            continue;
+        };
+        i += 1;
+        let starred = tokens.get(i) == Some(&Token::Char('*'));
+        if starred {
+            i += 1;
+        }
+        let Some((name, after_name)) = command_name(&tokens, i) else {
+            continue;
+        };
+        let defined = match command.as_str() {
+            "newcommand" | "renewcommand" | "providecommand" => {
+                let (arguments, j) = latex::optional_argument(&tokens, after_name);
+                let (default, j) = match arguments {
+                    Some(_) => latex::optional_argument(&tokens, j),
+                    None => (None, j),
+                };
+                let (body, j) = latex::argument(&tokens, j);
+                let arguments = arguments
+                    .and_then(|arguments| latex::to_string(arguments).trim().parse().ok())
+                    .unwrap_or(0);
+                Some((arguments, default.map(<[Token]>::to_vec), body.to_vec(), j))
+            }
+            // Parameters, as in `\def\pair#1#2{...}`, come before the body.
+            "def" => {
+                let start = after_name;
+                let end = (start..tokens.len())
+                    .find(|&j| matches!(tokens[j], Token::Group(_)))
+                    .unwrap_or(tokens.len());
+                let arguments = tokens[start..end]
+                    .iter()
+                    .filter(|token| **token == Token::Char('#'))
+                    .count();
+                let (body, j) = latex::argument(&tokens, end);
+                Some((arguments, None, body.to_vec(), j))
+            }
+            "DeclareMathOperator" => {
+                let (operator, j) = latex::argument(&tokens, after_name);
+                let command = if starred {
+                    "operatorname*"
+                } else {
+                    "operatorname"
+                };
+                let mut body = vec![Token::Command(command.to_string())];
+                body.push(Token::Group(operator.to_vec()));
+                Some((0, None, body, j))
+            }
+            "DeclarePairedDelimiter" => {
+                let (open, j) = latex::argument(&tokens, after_name);
+                let (close, j) = latex::argument(&tokens, j);
+                let mut body = vec![Token::Command("left".to_string())];
+                body.extend_from_slice(open);
+                body.extend([Token::Char('#'), Token::Char('1')]);
+                body.push(Token::Command("right".to_string()));
+                body.extend_from_slice(close);
+                Some((1, None, body, j))
+            }
+            _ => None,
+        };
+        if let Some((arguments, default, body, next)) = defined {
+            // Later definitions win, as `\renewcommand` does.
+            macros.retain(|defined: &Macro| defined.name != name);
+            macros.push(Macro {
+                name,
+                arguments,
+                default,
+                body,
+            });
+            i = next;
+        }
+    }
+    macros
+}
+
+// `body` with each `#n` replaced by the nth of `arguments`.
+fn substitute(body: &[Token], arguments: &[&[Token]]) -> Vec<Token> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < body.len() {
+        match (&body[i], body.get(i + 1)) {
+            (Token::Char('#'), Some(Token::Char(digit))) if digit.is_ascii_digit() => {
+                let n = digit.to_digit(10).unwrap_or(0) as usize;
+                if let Some(argument) = n.checked_sub(1).and_then(|n| arguments.get(n)) {
+                    out.extend_from_slice(argument);
+                }
+                i += 2;
+                continue;
+            }
+            (Token::Group(group), _) => out.push(Token::Group(substitute(group, arguments))),
+            (token, _) => out.push(token.clone()),
+        }
+        i += 1;
+    }
+    out
+}
+
+fn expanded(tokens: &[Token], macros: &[Macro], depth: usize) -> Vec<Token> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        i += 1;
+        match token {
+            Token::Command(name) if depth < DEPTH => {
+                let Some(found) = macros.iter().find(|found| found.name == *name) else {
+                    out.push(token.clone());
+                    continue;
+                };
+                let mut arguments: Vec<&[Token]> = Vec::new();
+                if let Some(default) = &found.default {
+                    let (argument, next) = latex::optional_argument(tokens, i);
+                    arguments.push(argument.unwrap_or(default));
+                    i = next;
+                }
+                while arguments.len() < found.arguments {
+                    let (argument, next) = latex::argument(tokens, i);
+                    arguments.push(argument);
+                    i = next;
+                }
+                let body = substitute(&found.body, &arguments);
+                out.extend(expanded(&body, macros, depth + 1));
+            }
+            Token::Group(group) => out.push(Token::Group(expanded(group, macros, depth))),
+            token => out.push(token.clone()),
+        }
+    }
+    out
+}
+
+// Expand the user's `macros` in `latex`, which is returned as it is when it uses
+// none of them.
+pub fn expand(latex: &str, macros: &[Macro]) -> String {
+    if macros.is_empty() {
+        return latex.to_string();
+    }
+    let tokens = latex::parse(latex);
+    let out = expanded(&tokens, macros, 0);
+    if out == tokens {
+        latex.to_string()
+    } else {
+        latex::to_string(&out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PREAMBLE: &str = r"\usepackage{amsmath}
+\newcommand{\R}{\mathbb{R}}
+\newcommand\pd[2][x]{\frac{\partial #2}{\partial #1}}
+\def\e{\mathrm{e}}
+\DeclareMathOperator{\tr}{tr}
+\DeclarePairedDelimiter\abs{\lvert}{\rvert}";
+
+    #[test]
+    fn preamble_commands_are_parsed() {
+        assert_eq!(parse(PREAMBLE).len(), 5);
+        assert!(parse(r"\usepackage{amsmath} \section{Intro}").is_empty());
+    }
+
+    #[test]
+    fn macros_expand() {
+        let macros = parse(PREAMBLE);
+        assert_eq!(expand(r"x \in \R^n", &macros), r"x \in \mathbb{R}^n");
+        assert_eq!(expand(r"\e^x", &macros), r"\mathrm{e}^x");
+        assert_eq!(expand(r"\tr A", &macros), r"\operatorname{tr} A");
+        assert_eq!(expand(r"\abs{x}", &macros), r"\left\lvert x\right\rvert");
+        // Longer names aren't mistaken for a macro.
+        assert_eq!(expand(r"\Rx + \Real", &macros), r"\Rx + \Real");
+        assert_eq!(expand("x + y", &macros), "x + y");
+    }
+
+    #[test]
+    fn optional_and_missing_arguments() {
+        let macros = parse(PREAMBLE);
+        assert_eq!(expand(r"\pd{f}", &macros), r"\frac{\partial f}{\partial x}");
+        assert_eq!(
+            expand(r"\pd[t]{f}", &macros),
+            r"\frac{\partial f}{\partial t}"
+        );
+        assert_eq!(expand(r"\pd", &macros), r"\frac{\partial }{\partial x}");
+    }
+
+    #[test]
+    fn recursive_macros_stop() {
+        let macros = parse(r"\newcommand{\loop}{\loop x}");
+        assert_eq!(
+            expand(r"\loop", &macros),
+            format!(r"\loop{}", " x".repeat(DEPTH))
+        );
+    }
+
+    #[test]
+    fn nested_arguments() {
+        let macros = parse(
+            r"\newcommand{\norm}[1]{\lVert #1 \rVert}
+\newcommand{\ip}[2]{\langle #1, #2 \rangle}",
+        );
+        assert_eq!(
+            expand(r"\norm{\frac{a}{b}}", &macros),
+            r"\lVert \frac{a}{b} \rVert"
+        );
+        assert_eq!(
+            expand(r"\ip{x}{\norm{y}}", &macros),
+            r"\langle x, \lVert y \rVert \rangle"
+        );
+        assert_eq!(expand(r"\norm{\{a\}}", &macros), r"\lVert \{a\} \rVert");
+        // An unbraced argument is the next token, and a missing one is empty.
+        assert_eq!(expand(r"\norm x", &macros), r"\lVert x \rVert");
+        assert_eq!(expand(r"\ip{a}", &macros), r"\langle a,  \rangle");
+    }
+
+    #[test]
+    fn arguments_lose_their_braces() {
+        // As in TeX, `\sq{a+b}` is `a+b^2` rather than `(a+b)^2`.
+        let macros = parse(r"\def\sq#1{#1^2} \renewcommand{\half}{\frac12}");
+        assert_eq!(expand(r"\sq{a+b}", &macros), "a+b^2");
+        assert_eq!(expand(r"\half\half", &macros), r"\frac12\frac12");
+    }
+}
@@ -0,0 +1,122 @@
+use crate::symbols::SYMBOLS;
+use std::ops::Range;
+
+// Conversion goes through pandoc, which keeps no source positions, so the mapping
+// between the panes is recovered from the tokens themselves: a command name in one
+// pane is paired with the same name, or its counterpart in the symbol table or
+// below, in the other.
+const ALIASES: &[(&str, &str)] = &[
+    ("mat", "pmatrix"),
+    ("dif", "d"),
+    ("arrow", "to"),
+    ("abs", "lvert"),
+    ("norm", "lVert"),
+    ("floor", "lfloor"),
+    ("ceil", "lceil"),
+];
+
+// The token being hovered in one of the panes.
+#[derive(Clone, PartialEq)]
+pub struct Hover {
+    pub from_input: bool,
+    pub range: Range<usize>,
+    pub name: String,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '.'
+}
+
+// The command, identifier or number at byte offset `index`, without a leading
+// backslash.
+pub fn token_at(text: &str, index: usize) -> Option<(Range<usize>, String)> {
+    if !text.is_char_boundary(index) {
+        return None;
+    }
+    // On the backslash of a command, take the name after it.
+    let index = if text[index..].starts_with('\\') {
+        index + 1
+    } else {
+        index
+    };
+    let start = text[..index]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_name_char(*c))
+        .last()
+        .map_or(index, |(i, _)| i);
+    let end = text[index..]
+        .char_indices()
+        .find(|(_, c)| !is_name_char(*c))
+        .map_or(text.len(), |(i, _)| index + i);
+    let name = text[start..end].trim_matches('.');
+    if name.is_empty() {
+        return None;
+    }
+    let start = start + text[start..end].find(name)?;
+    let end = start + name.len();
+    // Include the backslash of a LaTeX command in the highlighted span.
+    let start = if text[..start].ends_with('\\') {
+        start - 1
+    } else {
+        start
+    };
+    Some((start..end, name.to_string()))
+}
+
+// Names that `name` may appear as in the other pane.
+fn counterparts(name: &str) -> Vec<&str> {
+    let mut names = vec![name];
+    let symbols = SYMBOLS.iter().map(|symbol| (symbol.typst, symbol.latex));
+    for (typst, latex) in ALIASES.iter().copied().chain(symbols) {
+        if typst == latex {
+            continue;
+        }
+        if typst == name {
+            names.push(latex);
+        } else if latex == name {
+            names.push(typst);
+        }
+    }
+    // `alpha.alt` and the like fall back to the base name.
+    if let Some((base, _)) = name.split_once('.') {
+        names.push(base);
+    }
+    names
+}
+
+// Byte ranges of the tokens in `text` that correspond to `name`.
+pub fn find(text: &str, name: &str) -> Vec<Range<usize>> {
+    let names = counterparts(name);
+    let mut ranges = Vec::new();
+    let mut index = 0;
+    while index < text.len() {
+        let Some(c) = text[index..].chars().next() else {
+            break;
+        };
+        if !is_name_char(c) {
+            index += c.len_utf8();
+            continue;
+        }
+        match token_at(text, index) {
+            Some((range, token)) => {
+                if names.contains(&token.as_str()) {
+                    ranges.push(range.clone());
+                }
+                index = range.end.max(index + c.len_utf8());
+            }
+            None => index += c.len_utf8(),
+        }
+    }
+    ranges
+}
+
+// The spans to highlight in a pane given the current hover: the token itself in
+// the pane it's hovered in, its counterparts in the other one.
+pub fn highlights(hover: Option<&Hover>, text: &str, input: bool) -> Vec<Range<usize>> {
+    match hover {
+        Some(hover) if hover.from_input == input => vec![hover.range.clone()],
+        Some(hover) => find(text, &hover.name),
+        None => Vec::new(),
+    }
+}
@@ -0,0 +1,83 @@
+use crate::convert::{convert, Diagnostic, Direction};
+use crate::json::Value;
+use std::io::{self, Read, Write};
+
+// Serve conversion requests from a browser extension over the Chrome/Firefox native
+// messaging protocol: each message is a 32-bit native-endian length followed by
+// that many bytes of UTF-8 JSON, on stdin and stdout alike.
+//
+// Request:  {"input": "...", "direction": "typst-to-latex" | "latex-to-typst" | "auto"}
+// Response: {"output": "...", "direction": "..."} or
+//           {"error": "...", "message": "...", "line": 1, "column": 5, "class": "syntax"},
+//           where line and column are null when pandoc didn't report a position.
+pub fn serve() -> Result<(), String> {
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    loop {
+        let mut length = [0u8; 4];
+        match stdin.read_exact(&mut length) {
+            Ok(()) => {}
+            // The browser closes stdin when the extension disconnects.
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(_) => return Err("Failed to read message length".into()),
+        }
+        let mut message = vec![0; u32::from_ne_bytes(length) as usize];
+        stdin
+            .read_exact(&mut message)
+            .map_err(|_| "Failed to read message")?;
+
+        let response = handle(&String::from_utf8_lossy(&message)).to_string();
+        stdout
+            .write_all(&(response.len() as u32).to_ne_bytes())
+            .and_then(|_| stdout.write_all(response.as_bytes()))
+            .and_then(|_| stdout.flush())
+            .map_err(|_| "Failed to write response")?;
+    }
+}
+
+fn handle(message: &str) -> Value {
+    let request = match Value::parse(message) {
+        Ok(request) => request,
+        Err(err) => return Value::object([("error", format!("Invalid request: {}", err).into())]),
+    };
+    let Some(input) = request.get("input").and_then(Value::as_str) else {
+        return Value::object([("error", "Missing input".into())]);
+    };
+    let direction = match request.get("direction").and_then(Value::as_str) {
+        Some(name) => match Direction::from_name(name) {
+            Some(direction) => direction,
+            None => {
+                return Value::object([("error", format!("Unknown direction '{}'", name).into())])
+            }
+        },
+        None => Direction::TypstToLatex,
+    }
+    .resolve(input);
+    match convert(input, direction) {
+        Ok(output) => Value::object([
+            ("output", output.into()),
+            ("direction", direction.name().into()),
+        ]),
+        Err(err) => {
+            let diagnostic = Diagnostic::from_error(&err);
+            let position = |n: Option<usize>| n.map_or(Value::Null, |n| (n as f64).into());
+            Value::object([
+                ("error", err.as_str().into()),
+                ("message", diagnostic.message.as_str().into()),
+                ("line", position(diagnostic.line)),
+                ("column", position(diagnostic.column)),
+                ("class", diagnostic.class.name().into()),
+            ])
+        }
+    }
+}
+
+// Browsers start native messaging hosts with their own arguments: the caller's
+// origin for Chrome, and the manifest path plus extension id for Firefox.
+pub fn is_browser_launch(args: &[String]) -> bool {
+    match args {
+        [origin, ..] if origin.starts_with("chrome-extension://") => true,
+        [manifest, _] => manifest.ends_with(".json"),
+        _ => false,
+    }
+}
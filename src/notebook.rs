@@ -0,0 +1,42 @@
+use crate::convert::Direction;
+use crate::document::convert_math_regions;
+use crate::json::Value;
+
+// Convert the math in every Markdown cell of a Jupyter notebook, keeping all other
+// cells, outputs and metadata as they are. Returns the notebook and warnings.
+pub fn convert_notebook(text: &str, direction: Direction) -> Result<(String, Vec<String>), String> {
+    let mut notebook = Value::parse(text).map_err(|err| format!("Invalid notebook: {}", err))?;
+    let cells = notebook
+        .get_mut("cells")
+        .and_then(Value::as_array_mut)
+        .ok_or("Invalid notebook: missing cells")?;
+
+    let mut warnings = Vec::new();
+    for (index, cell) in cells.iter_mut().enumerate() {
+        if cell.get("cell_type").and_then(Value::as_str) != Some("markdown") {
+            continue;
+        }
+        let Some(source) = cell.get_mut("source") else {
+            continue;
+        };
+        // Cell sources are either one string or a list of lines.
+        let (text, as_lines) = match source {
+            Value::String(text) => (text.clone(), false),
+            Value::Array(lines) => (lines.iter().filter_map(Value::as_str).collect(), true),
+            _ => continue,
+        };
+        let (converted, cell_warnings) = convert_math_regions(&text, direction);
+        warnings.extend(
+            cell_warnings
+                .into_iter()
+                .map(|warning| format!("cell {}, {}", index + 1, warning)),
+        );
+        *source = if as_lines {
+            Value::Array(converted.split_inclusive('\n').map(Value::from).collect())
+        } else {
+            Value::String(converted)
+        };
+    }
+    // Jupyter writes notebooks with one space of indentation and a final newline.
+    Ok((notebook.pretty(1) + "\n", warnings))
+}
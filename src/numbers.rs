@@ -0,0 +1,288 @@
+use crate::latex::{self, Token};
+
+// Commands whose arguments hold no numbers to format: text, labels, and siunitx's,
+// which formats its own.
+const SKIPPED: [&str; 15] = [
+    "text",
+    "textrm",
+    "mbox",
+    "mathrm",
+    "operatorname",
+    "label",
+    "tag",
+    "begin",
+    "end",
+    "SI",
+    "si",
+    "num",
+    "qty",
+    "unit",
+    "ang",
+];
+
+// How numbers are written: `decimal_comma` for `1{,}5`, `grouping` for thin
+// spaces between groups of three digits in numbers of five digits or more, and
+// `scientific` "times" for `1.5 \times 10^{3}`, "e" for `1.5\mathrm{e}{3}` or ""
+// for either as written.
+pub struct Style<'a> {
+    pub decimal_comma: bool,
+    pub grouping: bool,
+    pub scientific: &'a str,
+}
+
+impl Style<'_> {
+    fn is_plain(&self) -> bool {
+        !self.decimal_comma && !self.grouping && self.scientific.is_empty()
+    }
+}
+
+// Digits in groups of three, counted from the decimal point, like siunitx does.
+fn group(digits: &str, from_left: bool) -> String {
+    if digits.len() < 5 {
+        return digits.to_string();
+    }
+    let chars: Vec<char> = digits.chars().collect();
+    let groups: Vec<String> = if from_left {
+        chars
+            .chunks(3)
+            .map(|chunk| chunk.iter().collect())
+            .collect()
+    } else {
+        chars
+            .rchunks(3)
+            .rev()
+            .map(|chunk| chunk.iter().collect())
+            .collect()
+    };
+    groups.join("\\,")
+}
+
+// The digits at `i`, with a decimal point if there's one, and the index after
+// them.
+fn digits(tokens: &[Token], i: usize) -> Option<(String, usize)> {
+    let mut j = i;
+    let mut number = String::new();
+    while let Some(Token::Char(c)) = tokens.get(j) {
+        let point = *c == '.'
+            && !number.is_empty()
+            && !number.contains('.')
+            && matches!(tokens.get(j + 1), Some(Token::Char(d)) if d.is_ascii_digit());
+        if !c.is_ascii_digit() && !point {
+            break;
+        }
+        number.push(*c);
+        j += 1;
+    }
+    (!number.is_empty()).then_some((number, j))
+}
+
+// The power of ten following a number at `i`, written `\times 10^{3}`,
+// `\cdot 10^{3}` or `e3`, and the index after it.
+fn exponent(tokens: &[Token], i: usize) -> Option<(String, usize)> {
+    if tokens.get(i) == Some(&Token::Char('e')) {
+        let mut j = latex::skip_spaces(tokens, i + 1);
+        let mut sign = "";
+        if let Some(Token::Char(c @ ('-' | '+'))) = tokens.get(j) {
+            sign = if *c == '-' { "-" } else { "" };
+            j = latex::skip_spaces(tokens, j + 1);
+        }
+        let (power, next) = digits(tokens, j).filter(|(power, _)| !power.contains('.'))?;
+        return Some((format!("{}{}", sign, power), next));
+    }
+    let j = latex::skip_spaces(tokens, i);
+    let Some(Token::Command(times)) = tokens.get(j) else {
+        return None;
+    };
+    if times != "times" && times != "cdot" {
+        return None;
+    }
+    let ten = latex::skip_spaces(tokens, j + 1);
+    let base = [Token::Char('1'), Token::Char('0'), Token::Char('^')];
+    if tokens.get(ten..ten + 3) != Some(&base[..]) {
+        return None;
+    }
+    let (power, next) = latex::argument(tokens, ten + 3);
+    Some((latex::to_string(power).trim().to_string(), next))
+}
+
+fn mantissa(number: &str, style: &Style) -> String {
+    let (integer, fraction) = match number.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (number, None),
+    };
+    let (integer, fraction) = if style.grouping {
+        (
+            group(integer, false),
+            fraction.map(|fraction| group(fraction, true)),
+        )
+    } else {
+        (integer.to_string(), fraction.map(str::to_string))
+    };
+    match fraction {
+        Some(fraction) if style.decimal_comma => format!("{}{{,}}{}", integer, fraction),
+        Some(fraction) => format!("{}.{}", integer, fraction),
+        None => integer,
+    }
+}
+
+fn restyled(tokens: &[Token], style: &Style) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        // Not the digits of a name, as in `x2`, or of an unbraced script, as in
+        // `x_12`, whose script is the first digit alone.
+        let after_name = i > 0
+            && matches!(&tokens[i - 1], Token::Char(c) if c.is_alphanumeric() || *c == '_' || *c == '^');
+        if let Some((number, next)) = digits(tokens, i).filter(|_| !after_name) {
+            out.push_str(&mantissa(&number, style));
+            i = next;
+            if !style.scientific.is_empty() {
+                if let Some((power, next)) = exponent(tokens, i) {
+                    if style.scientific == "e" {
+                        out.push_str(&format!("\\mathrm{{e}}{{{}}}", power));
+                    } else {
+                        out.push_str(&format!(" \\times 10^{{{}}}", power));
+                    }
+                    i = next;
+                }
+            }
+            continue;
+        }
+        match &tokens[i] {
+            Token::Command(name) if SKIPPED.contains(&name.as_str()) => {
+                let mut end = i + 1;
+                while matches!(tokens.get(end), Some(Token::Group(_) | Token::Char('*'))) {
+                    end += 1;
+                }
+                out.push_str(&latex::to_string(&tokens[i..end]));
+                i = end;
+                continue;
+            }
+            Token::Group(group) => {
+                out.push('{');
+                out.push_str(&restyled(group, style));
+                out.push('}');
+            }
+            token => out.push_str(&latex::to_string(std::slice::from_ref(token))),
+        }
+        // Keep a command name from running into a following letter.
+        if let (Token::Command(name), Some(Token::Char(c))) = (&tokens[i], tokens.get(i + 1)) {
+            if name.chars().all(|c| c.is_ascii_alphabetic()) && c.is_ascii_alphabetic() {
+                out.push(' ');
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+// Write the numbers of LaTeX output in `style`, to match a journal's requirements.
+pub fn restyle(latex: &str, style: &Style) -> String {
+    if style.is_plain() {
+        return latex.to_string();
+    }
+    let tokens = latex::parse(latex);
+    let out = restyled(&tokens, style);
+    if out == latex::to_string(&tokens) {
+        latex.to_string()
+    } else {
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUPING: Style = Style {
+        decimal_comma: false,
+        grouping: true,
+        scientific: "",
+    };
+
+    #[test]
+    fn digits_are_grouped_from_the_decimal_point() {
+        assert_eq!(restyle("12345.678901", &GROUPING), r"12\,345.678\,901");
+        // Four digits stay together.
+        assert_eq!(restyle("1234", &GROUPING), "1234");
+        assert_eq!(restyle("a^{100000}", &GROUPING), r"a^{100\,000}");
+    }
+
+    #[test]
+    fn grouping_inside_subscripts() {
+        assert_eq!(restyle("x_{12345}", &GROUPING), r"x_{12\,345}");
+        // Unbraced, the subscript is the first digit alone.
+        assert_eq!(restyle("x_12345", &GROUPING), "x_12345");
+        assert_eq!(restyle("x2 + y_{3}", &GROUPING), "x2 + y_{3}");
+    }
+
+    #[test]
+    fn decimal_comma() {
+        let style = Style {
+            decimal_comma: true,
+            grouping: false,
+            scientific: "",
+        };
+        assert_eq!(restyle("1.5", &style), "1{,}5");
+        assert_eq!(
+            restyle(r"1.5 \times 10^{3}", &style),
+            r"1{,}5 \times 10^{3}"
+        );
+    }
+
+    #[test]
+    fn scientific_notation() {
+        let e = Style {
+            decimal_comma: false,
+            grouping: false,
+            scientific: "e",
+        };
+        let times = Style {
+            scientific: "times",
+            ..e
+        };
+        assert_eq!(restyle(r"1.5 \times 10^{3}", &e), r"1.5\mathrm{e}{3}");
+        assert_eq!(restyle(r"1.5 \cdot 10^{-3}", &e), r"1.5\mathrm{e}{-3}");
+        assert_eq!(restyle(r"1.5 \cdot 10^{-3}", &times), r"1.5 \times 10^{-3}");
+    }
+
+    #[test]
+    fn text_and_labels_are_kept() {
+        for latex in [r"\text{12345}", r"\label{12345}", r"\SI{12345}{\meter}"] {
+            assert_eq!(restyle(latex, &GROUPING), latex);
+        }
+    }
+
+    #[test]
+    fn grouping_with_a_decimal_comma() {
+        let style = Style {
+            decimal_comma: true,
+            grouping: true,
+            scientific: "",
+        };
+        assert_eq!(restyle("12345.6", &style), r"12\,345{,}6");
+        assert_eq!(restyle("0.12345", &style), r"0{,}123\,45");
+        assert_eq!(restyle("1234567", &style), r"1\,234\,567");
+        // A comma already there isn't a decimal point.
+        assert_eq!(restyle("1,000", &style), "1,000");
+    }
+
+    #[test]
+    fn numbers_in_arguments_and_upright_text() {
+        assert_eq!(restyle(r"\frac{10000}{3}", &GROUPING), r"\frac{10\,000}{3}");
+        assert_eq!(restyle(r"\mathrm{10000}", &GROUPING), r"\mathrm{10000}");
+        assert_eq!(restyle("a.b", &GROUPING), "a.b");
+    }
+
+    #[test]
+    fn only_powers_of_ten_are_scientific() {
+        let e = Style {
+            decimal_comma: false,
+            grouping: false,
+            scientific: "e",
+        };
+        assert_eq!(restyle(r"2\times10^3", &e), r"2\mathrm{e}{3}");
+        assert_eq!(restyle(r"x \cdot 10^{3}", &e), r"x \cdot 10^{3}");
+        assert_eq!(restyle("10^{3}", &e), "10^{3}");
+    }
+}
@@ -0,0 +1,97 @@
+use crate::diagnostics;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+pub const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "bmp", "webp"];
+
+// Run the configured OCR command (e.g. `pix2tex {image}` or `texify {image}`) on an
+// image of a formula and return the recognized LaTeX. `{image}` is replaced with the
+// image path, which is appended when the placeholder is missing. Arguments with
+// spaces, such as the executable's path, can be quoted.
+pub fn recognize(command: &str, image: &Path) -> Result<String, String> {
+    let image = image.to_string_lossy();
+    let mut args: Vec<String> = split_command(command);
+    if args.is_empty() {
+        return Err("No OCR command configured".into());
+    }
+    if !args.iter().any(|arg| arg.contains("{image}")) {
+        args.push("{image}".into());
+    }
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| arg.replace("{image}", &image))
+        .collect();
+
+    diagnostics::log(format!("OCR: {}", args.join(" ")));
+    let output = Command::new(&args[0])
+        .args(&args[1..])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|_| format!("Failed to execute {}. Do you have it installed?", args[0]))?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        diagnostics::log(format!("OCR stderr: {}", error.trim()));
+        return Err(error
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("OCR command failed")
+            .trim()
+            .to_string());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let latex = stdout.trim();
+    // pix2tex prefixes its result with the image path.
+    let latex = latex
+        .strip_prefix(image.as_ref())
+        .map(|rest| rest.trim_start_matches(':').trim())
+        .unwrap_or(latex);
+    if latex.is_empty() {
+        Err("OCR command returned no LaTeX".into())
+    } else {
+        Ok(latex.to_string())
+    }
+}
+
+// Split a command line into arguments at whitespace outside single or double
+// quotes, which are removed.
+fn split_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut quote = None;
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => arg.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                arg.get_or_insert_with(String::new);
+            }
+            (None, _) if c.is_whitespace() => args.extend(arg.take()),
+            (None, _) => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(arg);
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_arguments_keep_their_spaces() {
+        assert_eq!(
+            split_command(r#""C:\Program Files\pix2tex\pix2tex.exe" --model 'base 2' {image}"#),
+            [
+                r"C:\Program Files\pix2tex\pix2tex.exe",
+                "--model",
+                "base 2",
+                "{image}"
+            ]
+        );
+        assert_eq!(split_command("  texify   {image} "), ["texify", "{image}"]);
+        assert_eq!(split_command(r#"cmd "" x"#), ["cmd", "", "x"]);
+        assert!(split_command("   ").is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::convert;
+use crate::i18n;
+use crate::output::{Delimiters, Target};
+use crate::settings::Settings;
+use crate::theme::{self, ThemeMode};
+use eframe::egui;
+
+const STEPS: usize = 4;
+
+// What the app knows about pandoc, which the first step reports.
+pub enum Pandoc<'a> {
+    Probing,
+    Missing,
+    // The version, and whether it reads Typst.
+    Found(&'a str, bool),
+}
+
+// A walk through the few choices that matter on first launch: whether pandoc is
+// there, a profile, delimiters and the theme. Shown until finished or skipped,
+// and again from the Help menu.
+#[derive(Default)]
+pub struct Onboarding {
+    pub open: bool,
+    step: usize,
+}
+
+impl Onboarding {
+    pub fn start(&mut self) {
+        self.open = true;
+        self.step = 0;
+    }
+
+    // Returns whether the settings changed; choices take effect right away.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        settings: &mut Settings,
+        target: &mut Target,
+        pandoc: Pandoc,
+    ) -> bool {
+        if !self.open {
+            return false;
+        }
+        let mut changed = false;
+        let mut done = false;
+        egui::Window::new(i18n::tr("onboarding-title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.set_width(360.0);
+                ui.weak(i18n::tr_args(
+                    "onboarding-step",
+                    &[
+                        ("step", &(self.step + 1).to_string()),
+                        ("count", &STEPS.to_string()),
+                    ],
+                ));
+                ui.add_space(4.0);
+                match self.step {
+                    0 => pandoc_step(ui, pandoc),
+                    1 => {
+                        ui.strong(i18n::tr("onboarding-profile"));
+                        ui.label(i18n::tr("onboarding-profile-hint"));
+                        if ui
+                            .radio(settings.profile.is_empty(), i18n::tr("profile-none"))
+                            .clicked()
+                        {
+                            settings.profile.clear();
+                            changed = true;
+                        }
+                        let mut chosen = None;
+                        for profile in &settings.profiles {
+                            if ui
+                                .radio(settings.profile == profile.name, &profile.name)
+                                .clicked()
+                            {
+                                chosen = Some(profile.clone());
+                            }
+                        }
+                        if let Some(profile) = chosen {
+                            profile.apply(settings, target);
+                            changed = true;
+                        }
+                    }
+                    2 => {
+                        ui.strong(i18n::tr("onboarding-delimiters"));
+                        ui.label(i18n::tr("onboarding-delimiters-hint"));
+                        let current = Delimiters::from_name(&settings.delimiters);
+                        for delimiters in Delimiters::ALL {
+                            if ui
+                                .radio(current == delimiters, delimiters.label())
+                                .clicked()
+                            {
+                                settings.delimiters = delimiters.name().to_string();
+                                changed = true;
+                            }
+                        }
+                    }
+                    _ => {
+                        ui.strong(i18n::tr("onboarding-theme"));
+                        let current = ThemeMode::from_name(&settings.theme);
+                        for mode in ThemeMode::ALL {
+                            if ui.radio(current == Some(mode), mode.label()).clicked() {
+                                settings.theme = mode.name().to_string();
+                                theme::apply(ctx, settings);
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.link(i18n::tr("onboarding-skip")).clicked() {
+                        done = true;
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if self.step + 1 < STEPS {
+                            if ui.button(i18n::tr("onboarding-next")).clicked() {
+                                self.step += 1;
+                            }
+                        } else if ui.button(i18n::tr("onboarding-finish")).clicked() {
+                            done = true;
+                        }
+                        if self.step > 0 && ui.button(i18n::tr("onboarding-back")).clicked() {
+                            self.step -= 1;
+                        }
+                    });
+                });
+            });
+        if done {
+            self.open = false;
+            settings.onboarded = true;
+            changed = true;
+        }
+        changed
+    }
+}
+
+fn pandoc_step(ui: &mut egui::Ui, pandoc: Pandoc) {
+    ui.strong(i18n::tr("onboarding-welcome"));
+    ui.label(i18n::tr("onboarding-pandoc"));
+    ui.add_space(4.0);
+    match pandoc {
+        Pandoc::Probing => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(i18n::tr("onboarding-pandoc-probing"));
+            });
+        }
+        Pandoc::Found(version, true) => {
+            ui.label(i18n::tr_args(
+                "onboarding-pandoc-found",
+                &[("version", version)],
+            ));
+        }
+        Pandoc::Found(version, false) => {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                i18n::tr_args(
+                    "pandoc-too-old",
+                    &[
+                        ("version", version),
+                        ("required", convert::TYPST_READER_VERSION),
+                    ],
+                ),
+            );
+            ui.hyperlink_to(
+                i18n::tr("pandoc-install"),
+                "https://pandoc.org/installing.html",
+            );
+        }
+        Pandoc::Missing => {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                i18n::tr("onboarding-pandoc-missing"),
+            );
+            ui.hyperlink_to(
+                i18n::tr("onboarding-pandoc-get"),
+                "https://pandoc.org/installing.html",
+            );
+        }
+    }
+}
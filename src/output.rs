@@ -0,0 +1,726 @@
+use crate::{context, eqn, plaintex, starmath};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Target {
+    Latex,
+    StarMath,
+    Context,
+    PlainTex,
+    Eqn,
+}
+
+impl Target {
+    pub const ALL: [Target; 5] = [
+        Target::Latex,
+        Target::PlainTex,
+        Target::StarMath,
+        Target::Context,
+        Target::Eqn,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Target::Latex => "LaTeX",
+            Target::StarMath => "StarMath",
+            Target::Context => "ConTeXt",
+            Target::PlainTex => "Plain TeX",
+            Target::Eqn => "eqn (troff)",
+        }
+    }
+
+    // Machine-readable name used in saved sessions and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::Latex => "latex",
+            Target::StarMath => "starmath",
+            Target::Context => "context",
+            Target::PlainTex => "plain-tex",
+            Target::Eqn => "eqn",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Target> {
+        Target::ALL.into_iter().find(|target| target.name() == name)
+    }
+
+    // Emit converted LaTeX in this target's syntax.
+    pub fn emit(self, latex: &str) -> String {
+        match self {
+            Target::Latex => latex.to_string(),
+            Target::StarMath => starmath::from_latex(latex),
+            Target::Context => context::from_latex(latex),
+            Target::PlainTex => plaintex::from_latex(latex),
+            Target::Eqn => eqn::from_latex(latex),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Environment {
+    Equation,
+    EquationStar,
+    AlignStar,
+    Gathered,
+}
+
+impl Environment {
+    pub const ALL: [Environment; 4] = [
+        Environment::Equation,
+        Environment::EquationStar,
+        Environment::AlignStar,
+        Environment::Gathered,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Environment::Equation => "equation",
+            Environment::EquationStar => "equation*",
+            Environment::AlignStar => "align*",
+            Environment::Gathered => "gathered",
+        }
+    }
+
+    pub fn wrap(self, latex: &str) -> String {
+        let latex = match self {
+            Environment::Equation | Environment::EquationStar => multiline_body(latex.trim()),
+            _ => latex.trim().to_string(),
+        };
+        let wrapped = format!(
+            "\\begin{{{name}}}\n{}\n\\end{{{name}}}",
+            latex,
+            name = self.name()
+        );
+        // `gathered` only works in math mode, and is pasted into the document body.
+        match self {
+            Environment::Gathered => format!("\\[\n{}\n\\]", wrapped),
+            _ => wrapped,
+        }
+    }
+}
+
+// Several lines need an inner environment to share one number.
+fn multiline_body(latex: &str) -> String {
+    if !latex.contains(r"\\") || latex.contains(r"\begin") {
+        latex.to_string()
+    } else if latex.contains('&') {
+        format!("\\begin{{aligned}}\n{}\n\\end{{aligned}}", latex)
+    } else {
+        format!("\\begin{{gathered}}\n{}\n\\end{{gathered}}", latex)
+    }
+}
+
+// How a copied formula is numbered when pasted into a LaTeX document.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Numbering {
+    // Copied as converted, without an environment.
+    AsIs,
+    // In `equation`, numbered by LaTeX.
+    Numbered,
+    // In `equation*`, with any `\tag` and `\label` taken out.
+    Unnumbered,
+    // In `equation`, with a `\tag` following a Typst numbering pattern.
+    Tag,
+}
+
+impl Numbering {
+    pub const ALL: [Numbering; 4] = [
+        Numbering::AsIs,
+        Numbering::Numbered,
+        Numbering::Unnumbered,
+        Numbering::Tag,
+    ];
+
+    // Name used in the settings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Numbering::AsIs => "",
+            Numbering::Numbered => "numbered",
+            Numbering::Unnumbered => "unnumbered",
+            Numbering::Tag => "tag",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Numbering {
+        Numbering::ALL
+            .into_iter()
+            .find(|numbering| numbering.name() == name)
+            .unwrap_or(Numbering::AsIs)
+    }
+
+    // Wrap `latex` for pasting. `number` is the equation's number for `Tag`, put in
+    // `pattern` the way Typst's `numbering` does.
+    pub fn apply(self, latex: &str, pattern: &str, number: usize) -> String {
+        let latex = latex.trim();
+        match self {
+            Numbering::AsIs => latex.to_string(),
+            Numbering::Numbered => Environment::Equation.wrap(latex),
+            Numbering::Unnumbered => Environment::EquationStar.wrap(&strip_numbering(latex)),
+            Numbering::Tag => {
+                let number = typst_number(pattern, number);
+                // `\tag` adds parentheses itself, `\tag*` leaves the number as is.
+                let tag = match number.strip_prefix('(').and_then(|n| n.strip_suffix(')')) {
+                    Some(inner) => format!("\\tag{{{}}}", inner),
+                    None => format!("\\tag*{{{}}}", number),
+                };
+                let latex = strip_numbering(latex);
+                // The tag goes after the inner environment, not in its last line.
+                Environment::Equation.wrap(&format!("{}\n{}", multiline_body(&latex), tag))
+            }
+        }
+    }
+}
+
+// Math delimiters put around LaTeX copied without a numbering environment.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Delimiters {
+    None,
+    Dollars,
+    DoubleDollars,
+    Parentheses,
+    Brackets,
+}
+
+impl Delimiters {
+    pub const ALL: [Delimiters; 5] = [
+        Delimiters::None,
+        Delimiters::Dollars,
+        Delimiters::DoubleDollars,
+        Delimiters::Parentheses,
+        Delimiters::Brackets,
+    ];
+
+    // Name used in the settings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Delimiters::None => "",
+            Delimiters::Dollars => "dollars",
+            Delimiters::DoubleDollars => "double-dollars",
+            Delimiters::Parentheses => "parentheses",
+            Delimiters::Brackets => "brackets",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Delimiters {
+        Delimiters::ALL
+            .into_iter()
+            .find(|delimiters| delimiters.name() == name)
+            .unwrap_or(Delimiters::None)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Delimiters::None => "—",
+            Delimiters::Dollars => "$ … $",
+            Delimiters::DoubleDollars => "$$ … $$",
+            Delimiters::Parentheses => r"\( … \)",
+            Delimiters::Brackets => r"\[ … \]",
+        }
+    }
+
+    pub fn wrap(self, latex: &str) -> String {
+        let latex = latex.trim();
+        match self {
+            Delimiters::None => latex.to_string(),
+            Delimiters::Dollars => format!("${}$", latex),
+            Delimiters::DoubleDollars => format!("$$\n{}\n$$", latex),
+            Delimiters::Parentheses => format!("\\({}\\)", latex),
+            Delimiters::Brackets => format!("\\[\n{}\n\\]", latex),
+        }
+    }
+}
+
+// How several display equations converted together are put in the output.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Grouping {
+    // One `\[ ... \]` block each.
+    #[default]
+    Separate,
+    // One `gather*`, centered lines.
+    Gather,
+    // One `align*`, lined up at each equation's first relation.
+    Align,
+}
+
+impl Grouping {
+    pub const ALL: [Grouping; 3] = [Grouping::Separate, Grouping::Gather, Grouping::Align];
+
+    pub fn join(self, equations: &[String]) -> String {
+        let environment = |name: &str, lines: Vec<String>| {
+            format!(
+                "\\begin{{{name}}}\n{}\n\\end{{{name}}}",
+                lines.join(" \\\\\n"),
+                name = name
+            )
+        };
+        match self {
+            Grouping::Separate => equations
+                .iter()
+                .map(|latex| format!("\\[\n{}\n\\]", latex.trim()))
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            Grouping::Gather => environment(
+                "gather*",
+                equations
+                    .iter()
+                    .map(|latex| latex.trim().to_string())
+                    .collect(),
+            ),
+            Grouping::Align => environment(
+                "align*",
+                equations
+                    .iter()
+                    .map(|latex| align_at_relation(latex.trim()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// Put an alignment point before the first relation outside braces, unless the
+// equation has its own.
+fn align_at_relation(latex: &str) -> String {
+    const RELATIONS: [&str; 12] = [
+        "=",
+        "<",
+        ">",
+        r"\leq",
+        r"\geq",
+        r"\le",
+        r"\ge",
+        r"\neq",
+        r"\approx",
+        r"\equiv",
+        r"\sim",
+        r"\coloneqq",
+    ];
+    if latex.contains('&') {
+        return latex.to_string();
+    }
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in latex.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ if depth == 0 => {
+                let rest = &latex[i..];
+                let relation = RELATIONS.iter().find(|relation| {
+                    rest.starts_with(*relation)
+                        && !rest[relation.len()..].starts_with(|c: char| {
+                            relation.starts_with('\\') && c.is_ascii_alphabetic()
+                        })
+                });
+                if relation.is_some() {
+                    return format!("{}&{}", &latex[..i], rest);
+                }
+                escaped = c == '\\';
+            }
+            '\\' => escaped = true,
+            _ => {}
+        }
+    }
+    latex.to_string()
+}
+
+// Remove `\tag`, `\label`, `\notag` and `\nonumber` from LaTeX.
+pub fn strip_numbering(latex: &str) -> String {
+    let mut output = String::with_capacity(latex.len());
+    let mut rest = latex;
+    while let Some(pos) = rest.find('\\') {
+        output.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let name_len = rest[1..]
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len() - 1);
+        let name = &rest[1..1 + name_len];
+        let after = rest[1 + name_len..]
+            .strip_prefix('*')
+            .filter(|_| name == "tag");
+        let after = after.unwrap_or(&rest[1 + name_len..]);
+        match name {
+            "notag" | "nonumber" => rest = after,
+            "tag" | "label" if after.trim_start().starts_with('{') => {
+                let after = after.trim_start();
+                let mut depth = 0;
+                let end = after
+                    .char_indices()
+                    .find(|&(_, c)| {
+                        match c {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            _ => {}
+                        }
+                        depth == 0
+                    })
+                    .map_or(after.len(), |(i, _)| i + 1);
+                rest = &after[end..];
+            }
+            _ => {
+                let len = match name_len {
+                    0 => 1 + rest[1..].chars().next().map_or(0, char::len_utf8),
+                    name_len => 1 + name_len,
+                };
+                output.push_str(&rest[..len]);
+                rest = &rest[len..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output.trim_end().to_string()
+}
+
+fn roman(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut numeral = String::new();
+    for (value, letters) in NUMERALS {
+        while n >= value {
+            numeral.push_str(letters);
+            n -= value;
+        }
+    }
+    numeral
+}
+
+// Letters counting like Typst's `a`: a, ..., z, aa, ab, ...
+fn letters(mut n: usize) -> String {
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    letters.into_iter().rev().collect()
+}
+
+// Format `number` with a Typst numbering pattern such as "(1)", "1." or "(i)": the
+// first counting symbol is replaced, what's around it kept. Patterns with several
+// levels, like "(1.a)", are cut after the first.
+pub fn typst_number(pattern: &str, number: usize) -> String {
+    let Some(at) = pattern.find(['1', 'a', 'A', 'i', 'I', '*']) else {
+        return number.to_string();
+    };
+    let formatted = match &pattern[at..at + 1] {
+        "a" => letters(number),
+        "A" => letters(number).to_uppercase(),
+        "i" => roman(number),
+        "I" => roman(number).to_uppercase(),
+        "*" => "*".repeat(number),
+        _ => number.to_string(),
+    };
+    // Keep the closing part after the last counting symbol, e.g. the ")" of "(1.a)".
+    let suffix_at = pattern
+        .rfind(['1', 'a', 'A', 'i', 'I', '*'])
+        .map_or(pattern.len(), |i| i + 1);
+    format!("{}{}{}", &pattern[..at], formatted, &pattern[suffix_at..])
+}
+
+// Format LaTeX for pasting into Obsidian notes. Table cells get their separators
+// and row breaks escaped, since Obsidian's Markdown parser eats them otherwise.
+fn obsidian(latex: &str, in_table: bool) -> String {
+    let latex = strip_left_right(latex.trim());
+    if in_table {
+        let latex = latex
+            .replace(r"\\", r"\\\\")
+            .replace(r"\|", r"\Vert ")
+            .replace('|', r"\vert ")
+            .replace('\n', " ");
+        format!("${}$", latex)
+    } else if is_display(&latex) {
+        format!("$$\n{}\n$$", latex)
+    } else {
+        format!("${}$", latex)
+    }
+}
+
+// Markdown engines that render LaTeX between dollars, each mangling different
+// characters of it.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Markdown {
+    GitHub,
+    Obsidian,
+    Jupyter,
+    Pandoc,
+}
+
+impl Markdown {
+    pub const ALL: [Markdown; 4] = [
+        Markdown::GitHub,
+        Markdown::Obsidian,
+        Markdown::Jupyter,
+        Markdown::Pandoc,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Markdown::GitHub => "GitHub",
+            Markdown::Obsidian => "Obsidian",
+            Markdown::Jupyter => "Jupyter",
+            Markdown::Pandoc => "Pandoc Markdown",
+        }
+    }
+
+    // Name used in the settings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Markdown::GitHub => "github",
+            Markdown::Obsidian => "obsidian",
+            Markdown::Jupyter => "jupyter",
+            Markdown::Pandoc => "pandoc",
+        }
+    }
+
+    // `latex` delimited for this engine. With `sanitize`, what the engine would take
+    // for Markdown is adjusted so that the math renderer gets the formula intact.
+    pub fn wrap(self, latex: &str, in_table: bool, sanitize: bool) -> String {
+        let latex = latex.trim();
+        if !sanitize {
+            return if in_table {
+                format!("${}$", latex.replace('\n', " "))
+            } else if is_display(latex) {
+                format!("$$\n{}\n$$", latex)
+            } else {
+                format!("${}$", latex)
+            };
+        }
+        // A `|` ends the table cell even inside math.
+        let cell = |latex: &str| {
+            latex
+                .replace(r"\|", r"\Vert ")
+                .replace('|', r"\vert ")
+                .replace('\n', " ")
+        };
+        match self {
+            Markdown::Obsidian => obsidian(latex, in_table),
+            // GitHub leaves math in a code span or a `math` fence alone.
+            Markdown::GitHub if in_table => format!("$`{}`$", cell(latex)),
+            Markdown::GitHub if is_display(latex) => format!("```math\n{}\n```", latex),
+            Markdown::GitHub => format!("$`{}`$", latex),
+            // Jupyter's Markdown renderer can pair up `*` and `_` as emphasis. With
+            // spaces around them they can neither open nor close it.
+            Markdown::Jupyter => {
+                let mut spaced = String::with_capacity(latex.len());
+                let mut escaped = false;
+                for c in latex.chars() {
+                    match c {
+                        '*' | '_' if !escaped => {
+                            spaced.push(' ');
+                            spaced.push(c);
+                            spaced.push(' ');
+                        }
+                        _ => spaced.push(c),
+                    }
+                    escaped = c == '\\' && !escaped;
+                }
+                let latex = spaced;
+                if in_table {
+                    format!("${}$", cell(&latex))
+                } else if is_display(&latex) {
+                    format!("$$\n{}\n$$", latex)
+                } else {
+                    format!("${}$", latex)
+                }
+            }
+            // Pandoc only reads `$...$` as math without spaces inside the dollars, and
+            // its pipe tables hold a single line.
+            Markdown::Pandoc if in_table => format!("${}$", cell(latex).trim()),
+            Markdown::Pandoc if is_display(latex) => format!("$$\n{}\n$$", latex),
+            Markdown::Pandoc => format!("${}$", latex),
+        }
+    }
+}
+
+// Remove `\left`, `\middle` and `\right` sizing commands while keeping their
+// delimiters. Null delimiters (`\left.`) are dropped entirely.
+pub fn strip_left_right(latex: &str) -> String {
+    let mut output = String::with_capacity(latex.len());
+    let mut rest = latex;
+    while let Some(pos) = rest.find('\\') {
+        output.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let command = [r"\left", r"\middle", r"\right"]
+            .into_iter()
+            .find(|command| {
+                rest.starts_with(command)
+                    && !rest[command.len()..].starts_with(|c: char| c.is_ascii_alphabetic())
+            });
+        match command {
+            Some(command) => {
+                rest = rest[command.len()..].trim_start();
+                if let Some(after) = rest.strip_prefix('.') {
+                    rest = after;
+                }
+            }
+            None => {
+                // Copy the backslash and the escaped character or command name.
+                let name = rest[1..]
+                    .find(|c: char| !c.is_ascii_alphabetic())
+                    .unwrap_or(rest.len() - 1);
+                let len = match name {
+                    0 => 1 + rest[1..].chars().next().map_or(0, char::len_utf8),
+                    name => 1 + name,
+                };
+                output.push_str(&rest[..len]);
+                rest = &rest[len..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// LaTeX for a quoted HTML attribute, e.g. `alt` or `data-latex`, with everything
+// that would end the attribute or start markup turned into entities.
+pub fn html_attribute(latex: &str) -> String {
+    escape_html(latex.trim()).replace('\'', "&#39;")
+}
+
+// A MathJax `<script type="math/tex">` block. Its content is raw text, where
+// entities aren't decoded, so `<` and `>` become TeX's `\lt` and `\gt` instead:
+// otherwise a `</script` in the formula would end the block early.
+pub fn html_script(latex: &str) -> String {
+    let latex = latex.trim().replace('<', r"\lt ").replace('>', r"\gt ");
+    let mode = if is_display(&latex) {
+        "; mode=display"
+    } else {
+        ""
+    };
+    format!("<script type=\"math/tex{}\">{}</script>", mode, latex)
+}
+
+fn is_display(latex: &str) -> bool {
+    latex.contains('\n') || latex.contains(r"\\") || latex.contains(r"\begin")
+}
+
+// Self-contained HTML snippet with the rendered SVG inlined. The LaTeX is kept as
+// the accessible label so the formula survives copy-paste and screen readers.
+pub fn html_svg(svg: &str, latex: &str) -> String {
+    let svg = svg.trim();
+    let svg = match svg.find("<svg") {
+        Some(start) => &svg[start..], // Drop any XML prolog.
+        None => svg,
+    };
+    let style = if is_display(latex) {
+        "display:block;text-align:center"
+    } else {
+        "display:inline-block"
+    };
+    format!(
+        "<span class=\"math\" role=\"img\" aria-label=\"{}\" style=\"{}\">{}</span>",
+        escape_html(latex.trim()),
+        style,
+        svg
+    )
+}
+
+// The offset of the baseline from the bottom of a rendered formula, e.g. "-0.566ex",
+// which MathJax puts in the `vertical-align` of the root element.
+pub fn baseline(svg: &str) -> Option<&str> {
+    let start = svg.find("<svg")?;
+    let root = &svg[start..start + svg[start..].find('>')?];
+    let value = &root[root.find("vertical-align:")? + "vertical-align:".len()..];
+    let end = value.find([';', '"', '\''])?;
+    Some(value[..end].trim())
+}
+
+// A standalone SVG file that keeps the baseline both as the `vertical-align` style
+// and as a `data-baseline` attribute, for tools that align inline formulas with
+// the surrounding text.
+pub fn svg_file(svg: &str) -> String {
+    let svg = svg.trim();
+    let svg = match svg.find("<svg") {
+        Some(start) => &svg[start..],
+        None => svg,
+    };
+    let mut file = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    match baseline(svg) {
+        Some(baseline) if !svg.contains("data-baseline=") => {
+            file.push_str(&format!("<svg data-baseline=\"{}\"", baseline));
+            file.push_str(&svg["<svg".len()..]);
+        }
+        _ => file.push_str(svg),
+    }
+    file.push('\n');
+    file
+}
+
+// HTML snippet with the LaTeX source plus the KaTeX auto-render scripts.
+pub fn html_katex(latex: &str) -> String {
+    const KATEX: &str = "https://cdn.jsdelivr.net/npm/katex@0.16.11/dist";
+    let math = if is_display(latex) {
+        format!(
+            "<div class=\"math\">\\[{}\\]</div>",
+            escape_html(latex.trim())
+        )
+    } else {
+        format!(
+            "<span class=\"math\">\\({}\\)</span>",
+            escape_html(latex.trim())
+        )
+    };
+    format!(
+        "<link rel=\"stylesheet\" href=\"{KATEX}/katex.min.css\">\n\
+         <script defer src=\"{KATEX}/katex.min.js\"></script>\n\
+         <script defer src=\"{KATEX}/contrib/auto-render.min.js\" \
+         onload=\"renderMathInElement(document.body)\"></script>\n\
+         {math}"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gathered_is_put_in_display_math() {
+        assert_eq!(
+            Environment::Gathered.wrap(r"a \\ b"),
+            "\\[\n\\begin{gathered}\na \\\\ b\n\\end{gathered}\n\\]"
+        );
+    }
+
+    #[test]
+    fn equations_of_several_lines_get_an_inner_environment() {
+        assert_eq!(
+            Environment::Equation.wrap(r"a &= b \\ c &= d"),
+            "\\begin{equation}\n\\begin{aligned}\na &= b \\\\ c &= d\n\\end{aligned}\n\\end{equation}"
+        );
+        assert_eq!(
+            Environment::EquationStar.wrap(r"a \\ b"),
+            "\\begin{equation*}\n\\begin{gathered}\na \\\\ b\n\\end{gathered}\n\\end{equation*}"
+        );
+        assert_eq!(
+            Environment::Equation.wrap("x"),
+            "\\begin{equation}\nx\n\\end{equation}"
+        );
+    }
+
+    #[test]
+    fn tags_follow_the_inner_environment() {
+        assert_eq!(
+            Numbering::Tag.apply(r"a \\ b", "(1)", 3),
+            "\\begin{equation}\n\\begin{gathered}\na \\\\ b\n\\end{gathered}\n\\tag{3}\n\\end{equation}"
+        );
+    }
+}
@@ -0,0 +1,634 @@
+use crate::chemistry;
+use crate::latex::{self, Token};
+
+// Commands of the physics, mathtools, siunitx, bm and mhchem packages, which pandoc
+// doesn't know, rewritten into LaTeX it turns into the Typst for them before
+// converting, e.g. `\abs{x}` into `\left| x \right|` for `lr(|x|)`.
+pub fn expand(latex: &str) -> String {
+    let tokens = latex::parse(latex);
+    let out = expanded(&tokens);
+    // Left as it is without any, comments and spacing included.
+    if out == latex::to_string(&tokens) {
+        latex.to_string()
+    } else {
+        out
+    }
+}
+
+fn expanded(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Command(name) => {
+                if let Some(next) = expand_command(name, tokens, i + 1, &mut out) {
+                    i = next;
+                    continue;
+                }
+                out.push('\\');
+                out.push_str(name);
+            }
+            Token::Group(group) => {
+                out.push('{');
+                out.push_str(&expanded(group));
+                out.push('}');
+            }
+            Token::Char(c) => out.push(*c),
+            Token::Space => out.push(' '),
+        }
+        // Keep a command name from running into a following letter.
+        if let (Some(Token::Char(c)), Some(last)) = (tokens.get(i + 1), out.chars().last()) {
+            let letter_command = matches!(&tokens[i], Token::Command(name)
+                if name.chars().all(|c| c.is_ascii_alphabetic()));
+            if letter_command && last.is_ascii_alphabetic() && c.is_ascii_alphabetic() {
+                out.push(' ');
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+// The argument at `i`, expanded, and the index after it.
+fn argument(tokens: &[Token], i: usize) -> (String, usize) {
+    let (argument, next) = latex::argument(tokens, i);
+    (expanded(argument), next)
+}
+
+// The braced arguments that follow at `i`, as many as there are up to `most`.
+fn groups(tokens: &[Token], mut i: usize, most: usize) -> (Vec<String>, usize) {
+    let mut groups = Vec::new();
+    while groups.len() < most {
+        let j = latex::skip_spaces(tokens, i);
+        let Some(Token::Group(group)) = tokens.get(j) else {
+            break;
+        };
+        groups.push(expanded(group));
+        i = j + 1;
+    }
+    (groups, i)
+}
+
+// Skip the `*` of a starred variant, which only changes sizing.
+fn skip_star(tokens: &[Token], i: usize) -> usize {
+    match tokens.get(i) {
+        Some(Token::Char('*')) => i + 1,
+        _ => i,
+    }
+}
+
+// What's between `open` at `i` and its matching `close`, and the index after it.
+fn delimited(tokens: &[Token], i: usize, open: char, close: char) -> Option<(&[Token], usize)> {
+    let start = latex::skip_spaces(tokens, i);
+    if tokens.get(start) != Some(&Token::Char(open)) {
+        return None;
+    }
+    let mut depth = 1;
+    for (j, token) in tokens.iter().enumerate().skip(start + 1) {
+        match token {
+            Token::Char(c) if *c == close => depth -= 1,
+            Token::Char(c) if *c == open => depth += 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some((&tokens[start + 1..j], j + 1));
+        }
+    }
+    None
+}
+
+fn paired(open: &str, body: &str, close: &str) -> String {
+    format!("\\left{} {} \\right{}", open, body.trim(), close)
+}
+
+// A derivative of `function` by each variable, upright `d` or `\partial` as
+// `symbol`, with `order` for one variable, e.g. `\pdv[2]{f}{x}`.
+fn derivative(symbol: &str, order: Option<String>, function: &str, variables: &[String]) -> String {
+    let mut numerator = symbol.to_string();
+    let denominator = match (order, variables) {
+        (Some(order), [variable]) => {
+            numerator.push_str(&format!("^{{{}}}", order));
+            format!("{} {}^{{{}}}", symbol, variable, order)
+        }
+        (None, [variable]) => format!("{} {}", symbol, variable),
+        // Mixed partial derivatives, like `\pdv{f}{x}{y}`.
+        (_, variables) => {
+            numerator.push_str(&format!("^{{{}}}", variables.len()));
+            variables
+                .iter()
+                .map(|variable| format!("{} {}", symbol, variable))
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+    };
+    if !function.is_empty() {
+        numerator.push_str(&format!(" {}", function));
+    }
+    format!("\\frac{{{}}}{{{}}}", numerator, denominator)
+}
+
+// siunitx units and their symbols.
+pub const UNITS: [(&str, &str); 39] = [
+    ("meter", "m"),
+    ("metre", "m"),
+    ("second", "s"),
+    ("kilogram", "kg"),
+    ("gram", "g"),
+    ("ampere", "A"),
+    ("kelvin", "K"),
+    ("mole", "mol"),
+    ("candela", "cd"),
+    ("hertz", "Hz"),
+    ("newton", "N"),
+    ("pascal", "Pa"),
+    ("joule", "J"),
+    ("watt", "W"),
+    ("coulomb", "C"),
+    ("volt", "V"),
+    ("ohm", "Ω"),
+    ("farad", "F"),
+    ("tesla", "T"),
+    ("henry", "H"),
+    ("weber", "Wb"),
+    ("siemens", "S"),
+    ("becquerel", "Bq"),
+    ("gray", "Gy"),
+    ("lumen", "lm"),
+    ("lux", "lx"),
+    ("liter", "L"),
+    ("litre", "L"),
+    ("minute", "min"),
+    ("hour", "h"),
+    ("day", "d"),
+    ("electronvolt", "eV"),
+    ("bar", "bar"),
+    ("angstrom", "Å"),
+    ("degree", "°"),
+    ("celsius", "°C"),
+    ("degreeCelsius", "°C"),
+    ("percent", "\\%"),
+    ("decibel", "dB"),
+];
+
+// siunitx prefixes and their symbols.
+pub const PREFIXES: [(&str, &str); 14] = [
+    ("yocto", "y"),
+    ("femto", "f"),
+    ("pico", "p"),
+    ("nano", "n"),
+    ("micro", "μ"),
+    ("milli", "m"),
+    ("centi", "c"),
+    ("deci", "d"),
+    ("deca", "da"),
+    ("hecto", "h"),
+    ("kilo", "k"),
+    ("mega", "M"),
+    ("giga", "G"),
+    ("tera", "T"),
+];
+
+// A siunitx unit such as `\kilo\meter\per\second\squared` as upright text with
+// powers, or a literal one such as `m/s` set upright.
+fn unit(tokens: &[Token]) -> String {
+    if !tokens
+        .iter()
+        .any(|token| matches!(token, Token::Command(_)))
+    {
+        return format!("\\mathrm{{{}}}", expanded(tokens).trim());
+    }
+    let mut units: Vec<(String, String)> = Vec::new();
+    let mut prefix = "";
+    let mut power: Option<String> = None;
+    let mut per = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        let Token::Command(name) = &tokens[i] else {
+            i += 1;
+            continue;
+        };
+        i += 1;
+        match name.as_str() {
+            "per" => per = true,
+            "square" => power = Some("2".to_string()),
+            "cubic" => power = Some("3".to_string()),
+            "raiseto" => {
+                let (argument, next) = argument(tokens, i);
+                power = Some(argument);
+                i = next;
+            }
+            "squared" | "cubed" | "tothe" => {
+                let exponent = match name.as_str() {
+                    "squared" => "2".to_string(),
+                    "cubed" => "3".to_string(),
+                    _ => {
+                        let (argument, next) = argument(tokens, i);
+                        i = next;
+                        argument
+                    }
+                };
+                if let Some((_, last)) = units.last_mut() {
+                    *last = match last.strip_prefix('-') {
+                        Some("1") => format!("-{}", exponent),
+                        _ => exponent,
+                    };
+                }
+            }
+            name => {
+                if let Some((_, symbol)) = PREFIXES.iter().find(|(found, _)| *found == name) {
+                    prefix = symbol;
+                } else if let Some((_, symbol)) = UNITS.iter().find(|(found, _)| *found == name) {
+                    let power = power.take().unwrap_or_else(|| "1".to_string());
+                    let power = if per { format!("-{}", power) } else { power };
+                    units.push((format!("{}{}", prefix, symbol), power));
+                    prefix = "";
+                    per = false;
+                } else {
+                    units.push((format!("\\{}", name), "1".to_string()));
+                }
+            }
+        }
+    }
+    units
+        .iter()
+        .map(|(symbol, power)| match power.as_str() {
+            "1" => format!("\\text{{{}}}", symbol),
+            power => format!("\\text{{{}}}^{{{}}}", symbol, power),
+        })
+        .collect::<Vec<_>>()
+        .join("\\,")
+}
+
+// A siunitx number: `1.5e3` as `1.5 \times 10^{3}` and `+-` as `\pm`.
+fn number(tokens: &[Token]) -> String {
+    let number = latex::to_string(tokens).replace(' ', "");
+    let number = number.replace("+-", "\\pm ");
+    match number.split_once(['e', 'E']) {
+        Some(("", exponent)) => format!("10^{{{}}}", exponent),
+        Some((mantissa, exponent)) => format!("{} \\times 10^{{{}}}", mantissa, exponent),
+        None => number,
+    }
+}
+
+// A quantity: a number and its unit, with a thin space between.
+fn quantity(number: &str, unit: &str) -> String {
+    match unit {
+        "\\text{°}" | "\\text{\\%}" => format!("{}{}", number, unit),
+        unit => format!("{}\\,{}", number, unit),
+    }
+}
+
+// Expand a package command whose arguments start at `i` into `out`, and return
+// the index after them, or `None` for other commands.
+fn expand_command(name: &str, tokens: &[Token], i: usize, out: &mut String) -> Option<usize> {
+    let expansion = |out: &mut String, text: String, next: usize| {
+        out.push_str(&text);
+        Some(next)
+    };
+    match name {
+        // physics
+        "abs" | "norm" | "bra" | "ket" | "expval" | "pqty" | "bqty" | "Bqty" | "vqty" => {
+            let i = skip_star(tokens, i);
+            let (body, next) = argument(tokens, i);
+            let (open, close) = match name {
+                "abs" | "vqty" => ("|", "|"),
+                "norm" => ("\\|", "\\|"),
+                "bra" => ("\\langle", "|"),
+                "ket" => ("|", "\\rangle"),
+                "expval" => ("\\langle", "\\rangle"),
+                "pqty" => ("(", ")"),
+                "bqty" => ("[", "]"),
+                _ => ("\\{", "\\}"),
+            };
+            expansion(out, paired(open, &body, close), next)
+        }
+        // siunitx's `\qty` is a quantity with braced arguments, physics' brackets
+        // anything.
+        "qty"
+            if matches!(
+                tokens.get(latex::skip_spaces(tokens, i)),
+                Some(Token::Char('(' | '[' | '|'))
+            ) =>
+        {
+            let (open, close) = match tokens.get(latex::skip_spaces(tokens, i)) {
+                Some(Token::Char('(')) => ('(', ')'),
+                Some(Token::Char('[')) => ('[', ']'),
+                _ => ('|', '|'),
+            };
+            let (body, next) = delimited(tokens, i, open, close)?;
+            let text = paired(&open.to_string(), &expanded(body), &close.to_string());
+            expansion(out, text, next)
+        }
+        "braket" | "ketbra" | "comm" | "acomm" => {
+            let (arguments, next) = groups(tokens, i, 2);
+            let [a, b] = match &arguments[..] {
+                [a] => [a.clone(), a.clone()],
+                [a, b] => [a.clone(), b.clone()],
+                _ => return None,
+            };
+            let text = match name {
+                "braket" => format!("\\left\\langle {} \\middle| {} \\right\\rangle", a, b),
+                "ketbra" => format!(
+                    "\\left| {} \\right\\rangle\\!\\left\\langle {} \\right|",
+                    a, b
+                ),
+                "comm" => paired("[", &format!("{}, {}", a, b), "]"),
+                _ => paired("\\{", &format!("{}, {}", a, b), "\\}"),
+            };
+            expansion(out, text, next)
+        }
+        "mel" => {
+            let (arguments, next) = groups(tokens, i, 3);
+            let [a, b, c] = &arguments[..] else {
+                return None;
+            };
+            let text = format!(
+                "\\left\\langle {} \\middle| {} \\middle| {} \\right\\rangle",
+                a, b, c
+            );
+            expansion(out, text, next)
+        }
+        "dv" | "pdv" | "odv" => {
+            let symbol = if name == "pdv" {
+                "\\partial"
+            } else {
+                "\\mathrm{d}"
+            };
+            let i = skip_star(tokens, i);
+            let (order, i) = latex::optional_argument(tokens, i);
+            let (arguments, next) = groups(tokens, i, 3);
+            let order = order.map(expanded);
+            let text = match &arguments[..] {
+                [] => return None,
+                // An operator on what follows, like `\dv{x}`.
+                [variable] => derivative(symbol, order, "", std::slice::from_ref(variable)),
+                [function, variables @ ..] => derivative(symbol, order, function, variables),
+            };
+            expansion(out, text, next)
+        }
+        "dd" => {
+            let (order, i) = latex::optional_argument(tokens, i);
+            let mut text = String::from("\\mathrm{d}");
+            if let Some(order) = order {
+                text.push_str(&format!("^{{{}}}", expanded(order)));
+            }
+            // Only a braced variable belongs to it, as in `\dd{x}`.
+            match tokens.get(i) {
+                Some(Token::Group(variable)) => {
+                    text.push_str(&expanded(variable));
+                    expansion(out, text, i + 1)
+                }
+                _ => expansion(out, text + " ", i),
+            }
+        }
+        "eval" => {
+            let i = skip_star(tokens, i);
+            let (body, next) = argument(tokens, i);
+            expansion(out, format!("\\left. {} \\right|", body.trim()), next)
+        }
+        "vb" | "va" | "vu" => {
+            let i = skip_star(tokens, i);
+            let (body, next) = argument(tokens, i);
+            let text = match name {
+                "vb" => format!("\\mathbf{{{}}}", body),
+                "va" => format!("\\vec{{{}}}", body),
+                _ => format!("\\hat{{\\mathbf{{{}}}}}", body),
+            };
+            expansion(out, text, next)
+        }
+        "order" => {
+            let (body, next) = argument(tokens, i);
+            expansion(
+                out,
+                format!("\\mathcal{{O}}{}", paired("(", &body, ")")),
+                next,
+            )
+        }
+        "qq" => {
+            let (body, next) = argument(tokens, i);
+            expansion(out, format!("\\quad\\text{{{}}}\\quad ", body), next)
+        }
+        "grad" => expansion(out, "\\nabla ".to_string(), i),
+        "curl" => expansion(out, "\\nabla\\times ".to_string(), i),
+        "divergence" => expansion(out, "\\nabla\\cdot ".to_string(), i),
+        "laplacian" => expansion(out, "\\nabla^{2} ".to_string(), i),
+        "Tr" | "tr" | "Res" | "rank" | "erf" | "sgn" => {
+            expansion(out, format!("\\operatorname{{{}}} ", name), i)
+        }
+        "qc" => expansion(out, ",\\quad ".to_string(), i),
+        // mathtools
+        "coloneqq" | "vcentcolon" | "eqqcolon" | "Coloneqq" | "dblcolon" => {
+            let text = match name {
+                "coloneqq" => ":=",
+                "vcentcolon" => ":",
+                "eqqcolon" => "=:",
+                "Coloneqq" => "::=",
+                _ => "::",
+            };
+            expansion(out, text.to_string(), i)
+        }
+        "ceil" | "floor" => {
+            let i = skip_star(tokens, i);
+            let (body, next) = argument(tokens, i);
+            let (open, close) = if name == "ceil" {
+                ("\\lceil", "\\rceil")
+            } else {
+                ("\\lfloor", "\\rfloor")
+            };
+            expansion(out, paired(open, &body, close), next)
+        }
+        "mathclap" | "mathllap" | "mathrlap" | "clap" | "smashoperator" | "mathmakebox" => {
+            let (_, i) = latex::optional_argument(tokens, i);
+            let (body, next) = argument(tokens, i);
+            expansion(out, format!("{{{}}}", body), next)
+        }
+        "prescript" => {
+            let (arguments, next) = groups(tokens, i, 3);
+            let [sup, sub, base] = &arguments[..] else {
+                return None;
+            };
+            expansion(
+                out,
+                format!("{{}}^{{{}}}_{{{}}}{{{}}}", sup, sub, base),
+                next,
+            )
+        }
+        "shortintertext" | "intertext" => {
+            let (body, next) = argument(tokens, i);
+            expansion(out, format!("\\text{{{}}}", body), next)
+        }
+        "adjustlimits" => Some(i),
+        // bm
+        "bm" => {
+            let (body, next) = argument(tokens, i);
+            expansion(out, format!("\\boldsymbol{{{}}}", body), next)
+        }
+        // siunitx
+        "num" => {
+            let (_, i) = latex::optional_argument(tokens, i);
+            let (body, next) = latex::argument(tokens, i);
+            expansion(out, number(body), next)
+        }
+        "si" | "unit" => {
+            let (_, i) = latex::optional_argument(tokens, i);
+            let (body, next) = latex::argument(tokens, i);
+            expansion(out, unit(body), next)
+        }
+        "SI" | "qty" => {
+            let (_, i) = latex::optional_argument(tokens, i);
+            let (value, i) = latex::argument(tokens, i);
+            // A pre-unit, as in `\SI{10}[\$]{}`.
+            let (before, i) = latex::optional_argument(tokens, i);
+            let (units, next) = latex::argument(tokens, i);
+            let before = before.map(expanded).unwrap_or_default();
+            let text = quantity(&format!("{}{}", before, number(value)), &unit(units));
+            expansion(out, text, next)
+        }
+        "numrange" | "SIrange" | "qtyrange" => {
+            let (_, i) = latex::optional_argument(tokens, i);
+            let (from, i) = latex::argument(tokens, i);
+            let (to, i) = latex::argument(tokens, i);
+            let (from, to) = (number(from), number(to));
+            if name == "numrange" {
+                return expansion(out, format!("{} \\text{{ to }} {}", from, to), i);
+            }
+            let (units, next) = latex::argument(tokens, i);
+            let units = unit(units);
+            let text = format!(
+                "{} \\text{{ to }} {}",
+                quantity(&from, &units),
+                quantity(&to, &units)
+            );
+            expansion(out, text, next)
+        }
+        "numlist" => {
+            let (_, i) = latex::optional_argument(tokens, i);
+            let (body, next) = latex::argument(tokens, i);
+            let numbers: Vec<String> = body
+                .split(|token| *token == Token::Char(';'))
+                .map(number)
+                .collect();
+            expansion(out, numbers.join(", "), next)
+        }
+        "ang" => {
+            let (_, i) = latex::optional_argument(tokens, i);
+            let (body, next) = latex::argument(tokens, i);
+            let parts: Vec<String> = body
+                .split(|token| *token == Token::Char(';'))
+                .map(number)
+                .collect();
+            let text: String = parts
+                .iter()
+                .zip(["^{\\circ}", "'", "''"])
+                .filter(|(part, _)| !part.is_empty())
+                .map(|(part, mark)| format!("{}{}", part, mark))
+                .collect();
+            expansion(out, text, next)
+        }
+        // mhchem
+        "ce" => {
+            let (body, next) = latex::argument(tokens, i);
+            let text = chemistry::to_latex(&latex::to_string(body));
+            expansion(out, format!("{{{}}}", text), next)
+        }
+        "pu" => {
+            let (body, next) = latex::argument(tokens, i);
+            let text = match body.iter().position(|token| *token == Token::Space) {
+                Some(space) => quantity(&number(&body[..space]), &unit(&body[space + 1..])),
+                None => unit(body),
+            };
+            expansion(out, text, next)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn other_latex_is_kept_as_it_is() {
+        let latex = "\\frac{a}{b} % note\n  + \\alpha";
+        assert_eq!(expand(latex), latex);
+    }
+
+    #[test]
+    fn physics_brackets() {
+        assert_eq!(expand(r"\abs{x}"), r"\left| x \right|");
+        assert_eq!(expand(r"\norm*{v}"), r"\left\| v \right\|");
+        assert_eq!(expand(r"\qty(a+b)"), r"\left( a+b \right)");
+        assert_eq!(
+            expand(r"\braket{\psi}"),
+            r"\left\langle \psi \middle| \psi \right\rangle"
+        );
+        assert_eq!(expand(r"\comm{A}{B}"), r"\left[ A, B \right]");
+    }
+
+    #[test]
+    fn nested_arguments() {
+        assert_eq!(
+            expand(r"\abs{\frac{a}{\norm{b}}}"),
+            r"\left| \frac{a}{\left\| b \right\|} \right|"
+        );
+        // Brackets inside `\qty(...)` pair up with their own.
+        assert_eq!(
+            expand(r"\qty(f(x) + \abs{g(y)})"),
+            r"\left( f(x) + \left| g(y) \right| \right)"
+        );
+        assert_eq!(expand(r"\ket{\vb{k}}"), r"\left| \mathbf{k} \right\rangle");
+    }
+
+    #[test]
+    fn derivatives() {
+        assert_eq!(expand(r"\dv{f}{x}"), r"\frac{\mathrm{d} f}{\mathrm{d} x}");
+        assert_eq!(
+            expand(r"\pdv[2]{f}{x}"),
+            r"\frac{\partial^{2} f}{\partial x^{2}}"
+        );
+        assert_eq!(
+            expand(r"\pdv{f}{x}{y}"),
+            r"\frac{\partial^{2} f}{\partial x \partial y}"
+        );
+        assert_eq!(expand(r"\dv{x}"), r"\frac{\mathrm{d}}{\mathrm{d} x}");
+        assert_eq!(expand(r"\int f \dd{x}"), r"\int f \mathrm{d}x");
+        // A derivative without arguments is left alone.
+        assert_eq!(expand(r"\dv"), r"\dv");
+    }
+
+    #[test]
+    fn command_names_stay_apart_from_letters() {
+        assert_eq!(expand(r"\grad f"), r"\nabla  f");
+        assert_eq!(expand(r"\tr A"), r"\operatorname{tr}  A");
+        assert_eq!(expand(r"a \coloneqq b"), "a := b");
+    }
+
+    #[test]
+    fn siunitx() {
+        assert_eq!(expand(r"\num{1.5e3}"), r"1.5 \times 10^{3}");
+        assert_eq!(expand(r"\num{1+-0.1}"), r"1\pm 0.1");
+        assert_eq!(
+            expand(r"\SI{3}{\kilo\meter\per\second\squared}"),
+            r"3\,\text{km}\,\text{s}^{-2}"
+        );
+        assert_eq!(expand(r"\qty{90}{\degree}"), r"90\text{°}");
+        assert_eq!(expand(r"\si{m/s}"), r"\mathrm{m/s}");
+        assert_eq!(
+            expand(r"\SIrange{1}{2}{\metre}"),
+            r"1\,\text{m} \text{ to } 2\,\text{m}"
+        );
+        assert_eq!(expand(r"\ang{1;2;3}"), r"1^{\circ}2'3''");
+    }
+
+    #[test]
+    fn bm_mathtools_and_mhchem() {
+        assert_eq!(expand(r"\bm{x}"), r"\boldsymbol{x}");
+        assert_eq!(
+            expand(r"\ceil{\frac{n}{2}}"),
+            r"\left\lceil \frac{n}{2} \right\rceil"
+        );
+        assert_eq!(expand(r"\prescript{14}{6}{C}"), "{}^{14}_{6}{C}");
+        assert_eq!(expand(r"\ce{H2O}"), r"{\text{H}_{2}\text{O}}");
+        assert_eq!(expand(r"\pu{5 kg}"), r"5\,\mathrm{kg}");
+    }
+}
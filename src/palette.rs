@@ -0,0 +1,289 @@
+use crate::convert::Direction;
+use crate::examples;
+use crate::i18n;
+use crate::output::{Environment, Markdown, Target};
+use crate::templates;
+use eframe::egui;
+
+// Every action the app offers, so that it can be run from the command palette.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Command {
+    Convert,
+    CancelConversion,
+    Undo,
+    Redo,
+    Find,
+    Replace,
+    SwapDirection,
+    SetDirection(Direction),
+    SetTarget(Target),
+    Copy,
+    CopyAs(Environment),
+    // For a Markdown engine, in a table cell or not.
+    CopyMarkdown(Markdown, bool),
+    CopyHtmlSvg,
+    CopyHtmlKatex,
+    CopyHtmlAttribute,
+    CopyHtmlScript,
+    CopySpeech,
+    SendToAnki,
+    Open,
+    SaveOutput,
+    OpenImage,
+    PasteImage,
+    ExportStandalone,
+    ExportPng,
+    ExportSvg,
+    Clear,
+    ToggleTectonicPreview,
+    ToggleValidation,
+    ToggleDiagnostics,
+    ToggleMapping,
+    ToggleDiff,
+    History,
+    Batch,
+    Usage,
+    Symbols,
+    Reference,
+    // An index into `templates::ALL`.
+    InsertTemplate(usize),
+    // An index into `examples::ALL`.
+    LoadExample(usize),
+}
+
+impl Command {
+    pub fn all() -> Vec<Command> {
+        let mut commands = vec![
+            Command::Convert,
+            Command::CancelConversion,
+            Command::Undo,
+            Command::Redo,
+            Command::Find,
+            Command::Replace,
+            Command::SwapDirection,
+        ];
+        commands.extend(
+            [
+                Direction::TypstToLatex,
+                Direction::LatexToTypst,
+                Direction::Auto,
+            ]
+            .map(Command::SetDirection),
+        );
+        commands.extend(Target::ALL.map(Command::SetTarget));
+        commands.push(Command::Copy);
+        commands.extend(Environment::ALL.map(Command::CopyAs));
+        for engine in Markdown::ALL {
+            commands.extend([
+                Command::CopyMarkdown(engine, false),
+                Command::CopyMarkdown(engine, true),
+            ]);
+        }
+        commands.extend([
+            Command::CopyHtmlSvg,
+            Command::CopyHtmlKatex,
+            Command::CopyHtmlAttribute,
+            Command::CopyHtmlScript,
+            Command::CopySpeech,
+            Command::SendToAnki,
+            Command::Open,
+            Command::SaveOutput,
+            Command::OpenImage,
+            Command::PasteImage,
+            Command::ExportStandalone,
+            Command::ExportPng,
+            Command::ExportSvg,
+            Command::Clear,
+            Command::ToggleTectonicPreview,
+            Command::ToggleValidation,
+            Command::ToggleDiagnostics,
+            Command::ToggleMapping,
+            Command::ToggleDiff,
+            Command::History,
+            Command::Batch,
+            Command::Usage,
+            Command::Symbols,
+            Command::Reference,
+        ]);
+        commands.extend((0..templates::ALL.len()).map(Command::InsertTemplate));
+        commands.extend((0..examples::ALL.len()).map(Command::LoadExample));
+        commands
+    }
+
+    pub fn shortcut(self) -> Option<&'static str> {
+        Some(match self {
+            Command::CancelConversion => "Esc",
+            Command::Undo => "Ctrl+Z",
+            Command::Redo => "Ctrl+Shift+Z",
+            Command::Find => "Ctrl+F",
+            Command::Replace => "Ctrl+H",
+            _ => return None,
+        })
+    }
+
+    pub fn label(self) -> String {
+        let copy_as = |format: &str| i18n::tr_args("command-copy-as", &[("format", format)]);
+        match self {
+            Command::SetDirection(direction) => {
+                i18n::tr_args("command-direction", &[("direction", direction.label())])
+            }
+            Command::SetTarget(target) => {
+                i18n::tr_args("command-target", &[("target", target.label())])
+            }
+            Command::CopyAs(environment) => copy_as(environment.name()),
+            Command::CopyMarkdown(engine, false) => copy_as(engine.label()),
+            Command::CopyMarkdown(engine, true) => copy_as(&i18n::tr_args(
+                "copy-markdown-table-cell",
+                &[("engine", engine.label())],
+            )),
+            Command::CopyHtmlSvg => copy_as(i18n::tr("copy-html-svg")),
+            Command::CopyHtmlKatex => copy_as(i18n::tr("copy-html-katex")),
+            Command::CopyHtmlAttribute => copy_as(i18n::tr("copy-html-attribute")),
+            Command::CopyHtmlScript => copy_as(i18n::tr("copy-html-script")),
+            Command::CopySpeech => i18n::tr("command-copy-speech").to_string(),
+            Command::Convert => i18n::tr("command-convert").to_string(),
+            Command::CancelConversion => i18n::tr("command-cancel-conversion").to_string(),
+            Command::Undo => i18n::tr("command-undo").to_string(),
+            Command::Redo => i18n::tr("command-redo").to_string(),
+            Command::Find => i18n::tr("command-find").to_string(),
+            Command::Replace => i18n::tr("command-replace").to_string(),
+            Command::SwapDirection => i18n::tr("command-swap-direction").to_string(),
+            Command::Copy => i18n::tr("command-copy").to_string(),
+            Command::SendToAnki => i18n::tr("command-send-to-anki").to_string(),
+            Command::Open => i18n::tr("command-open").to_string(),
+            Command::SaveOutput => i18n::tr("command-save-output").to_string(),
+            Command::OpenImage => i18n::tr("command-open-image").to_string(),
+            Command::PasteImage => i18n::tr("command-paste-image").to_string(),
+            Command::ExportStandalone => i18n::tr("command-export-standalone").to_string(),
+            Command::ExportPng => i18n::tr("command-export-png").to_string(),
+            Command::ExportSvg => i18n::tr("command-export-svg").to_string(),
+            Command::Clear => i18n::tr("command-clear").to_string(),
+            Command::ToggleTectonicPreview => i18n::tr("command-tectonic-preview").to_string(),
+            Command::ToggleValidation => i18n::tr("command-validate").to_string(),
+            Command::ToggleDiagnostics => i18n::tr("command-diagnostics").to_string(),
+            Command::ToggleMapping => i18n::tr("command-mapping").to_string(),
+            Command::ToggleDiff => i18n::tr("command-diff").to_string(),
+            Command::History => i18n::tr("command-history").to_string(),
+            Command::Batch => i18n::tr("command-batch").to_string(),
+            Command::Usage => i18n::tr("command-usage").to_string(),
+            Command::Symbols => i18n::tr("command-symbols").to_string(),
+            Command::Reference => i18n::tr("command-reference").to_string(),
+            Command::InsertTemplate(i) => i18n::tr_args(
+                "command-insert-template",
+                &[("template", templates::ALL[i].label())],
+            ),
+            Command::LoadExample(i) => i18n::tr_args(
+                "command-load-example",
+                &[("example", examples::ALL[i].label())],
+            ),
+        }
+    }
+}
+
+// Score how well `query` matches `label` as a case-insensitive subsequence, or
+// `None` if it doesn't. Consecutive characters and word starts score higher.
+pub fn fuzzy_score(query: &str, label: &str) -> Option<i32> {
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous_match: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = next + label[next..].iter().position(|&c| c == q)?;
+        score += 1;
+        if previous_match == Some(i.wrapping_sub(1)) {
+            score += 3;
+        }
+        if i == 0 || !label[i - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous_match = Some(i);
+        next = i + 1;
+    }
+    // Prefer shorter labels among equal matches, but keep the listed order when
+    // nothing has been typed.
+    if score == 0 {
+        return Some(0);
+    }
+    Some(score * 100 - label.len() as i32)
+}
+
+#[derive(Default)]
+pub struct Palette {
+    open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl Palette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    // Draw the palette and return the command picked with Enter or a click.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<Command> {
+        if !self.open {
+            return None;
+        }
+        let mut matches: Vec<(i32, Command)> = Command::all()
+            .into_iter()
+            .filter_map(|command| Some((fuzzy_score(&self.query, &command.label())?, command)))
+            .collect();
+        matches.sort_by_key(|(score, _)| -score);
+
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if down {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let mut chosen = None;
+        egui::Window::new("Command palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 30.0))
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text(i18n::tr("palette-hint"))
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.selected = 0;
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(250.0)
+                    .show(ui, |ui| {
+                        for (i, (_, command)) in matches.iter().enumerate() {
+                            let item = ui.selectable_label(i == self.selected, command.label());
+                            if i == self.selected && (up || down) {
+                                item.scroll_to_me(None);
+                            }
+                            if item.clicked() {
+                                chosen = Some(*command);
+                            }
+                        }
+                    });
+            });
+        if enter {
+            chosen = matches.get(self.selected).map(|(_, command)| *command);
+        }
+        if chosen.is_some() || escape {
+            self.open = false;
+        }
+        chosen
+    }
+}
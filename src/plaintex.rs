@@ -0,0 +1,289 @@
+use crate::latex::{self, Token};
+
+// Translate LaTeX math into a plain TeX display formula, for systems without
+// amsmath: fractions become `\over`, environments `\eqalign`, `\matrix` and
+// `\cases`, and text `\hbox`.
+pub fn from_latex(latex: &str) -> String {
+    let mut out = String::new();
+    emit(&latex::parse(latex), &mut out);
+    format!("$$ {} $$", out.trim())
+}
+
+fn emit(tokens: &[Token], out: &mut String) {
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Command(name) => {
+                i = emit_command(name, tokens, i + 1, out);
+                continue;
+            }
+            Token::Group(group) => emit_group(group, out),
+            Token::Char(c) => out.push(*c),
+            Token::Space => out.push(' '),
+        }
+        i += 1;
+    }
+}
+
+fn emit_group(tokens: &[Token], out: &mut String) {
+    out.push('{');
+    emit(tokens, out);
+    out.push('}');
+}
+
+fn emitted(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    emit(tokens, &mut out);
+    out.trim().to_string()
+}
+
+// Emit the argument starting at `i` as a braced group and return the index after it.
+fn emit_argument(tokens: &[Token], i: usize, out: &mut String) -> usize {
+    let (argument, next) = latex::argument(tokens, i);
+    emit_group(argument, out);
+    next
+}
+
+// Rows of cells joined with `&`, each ended by `\cr`. In `\cases` the second
+// column is set as text, so it's put back in math.
+fn rows(body: &[Token], cases: bool) -> String {
+    latex::split_rows(body)
+        .iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .enumerate()
+                .map(|(column, cell)| match emitted(cell) {
+                    cell if cases && column > 0 && !cell.is_empty() => format!("${}$", cell),
+                    cell => cell,
+                })
+                .collect();
+            format!("{} \\cr", cells.join(" & "))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn emit_command(name: &str, tokens: &[Token], i: usize, out: &mut String) -> usize {
+    match name {
+        "begin" => {
+            let (name, mut body, after) = latex::environment(tokens, i);
+            // Column specifications, as of `array` and `alignedat`, have no
+            // counterpart.
+            if let Some(Token::Group(_)) = body.get(latex::skip_spaces(body, 0)) {
+                body = &body[latex::skip_spaces(body, 0) + 1..];
+            }
+            let (open, command, close) = match name.as_str() {
+                "aligned" | "align" | "align*" | "alignedat" | "split" | "eqnarray" => {
+                    ("", "\\eqalign", "")
+                }
+                "gathered" | "gather" | "gather*" | "matrix" | "smallmatrix" | "array" => {
+                    ("", "\\matrix", "")
+                }
+                "pmatrix" => ("", "\\pmatrix", ""),
+                "bmatrix" => ("\\left[", "\\matrix", "\\right]"),
+                "Bmatrix" => ("\\left\\{", "\\matrix", "\\right\\}"),
+                "vmatrix" => ("\\left|", "\\matrix", "\\right|"),
+                "Vmatrix" => ("\\left\\|", "\\matrix", "\\right\\|"),
+                "cases" | "dcases" => ("", "\\cases", ""),
+                _ => {
+                    out.push_str(&format!(
+                        "\\begin{{{name}}}{}\\end{{{name}}}",
+                        latex::to_string(body)
+                    ));
+                    return after;
+                }
+            };
+            out.push_str(&format!(
+                "{}{}{{{}}}{}",
+                open,
+                command,
+                rows(body, command == "\\cases"),
+                close
+            ));
+            after
+        }
+        "frac" | "dfrac" | "tfrac" | "cfrac" | "binom" => {
+            let (numerator, i) = latex::argument(tokens, i);
+            let (denominator, i) = latex::argument(tokens, i);
+            let style = match name {
+                "dfrac" => "\\displaystyle ",
+                "tfrac" => "\\textstyle ",
+                _ => "",
+            };
+            let operator = if name == "binom" {
+                "\\choose"
+            } else {
+                "\\over"
+            };
+            out.push_str(&format!(
+                "{{{}{} {} {}}}",
+                style,
+                emitted(numerator),
+                operator,
+                emitted(denominator)
+            ));
+            i
+        }
+        "sqrt" => match latex::optional_argument(tokens, i) {
+            (Some(index), i) => {
+                out.push_str(&format!("\\root {} \\of ", emitted(index)));
+                emit_argument(tokens, i, out)
+            }
+            (None, i) => {
+                out.push_str("\\sqrt");
+                emit_argument(tokens, i, out)
+            }
+        },
+        "substack" => {
+            let (body, i) = latex::argument(tokens, i);
+            let rows: Vec<String> = latex::split_rows(body)
+                .iter()
+                .map(|row| row.iter().map(|cell| emitted(cell)).collect())
+                .collect();
+            out.push_str(&rows.join(" \\atop "));
+            i
+        }
+        "text" | "textrm" | "mbox" | "textnormal" => {
+            out.push_str("\\hbox");
+            let (text, i) = latex::argument(tokens, i);
+            out.push_str(&format!("{{{}}}", latex::to_string(text)));
+            i
+        }
+        "operatorname" => {
+            let (text, i) = latex::argument(tokens, i);
+            out.push_str(&format!(
+                "\\mathop{{\\rm {}}}\\nolimits",
+                latex::to_string(text)
+            ));
+            i
+        }
+        // Font commands are switches in plain TeX. It has no blackboard bold, so
+        // bold stands in for it.
+        "mathrm" | "mathup" | "mathbf" | "boldsymbol" | "bm" | "mathbb" | "mathit" | "mathcal"
+        | "mathtt" => {
+            let switch = match name {
+                "mathrm" | "mathup" => "\\rm",
+                "mathit" => "\\it",
+                "mathcal" => "\\cal",
+                "mathtt" => "\\tt",
+                _ => "\\bf",
+            };
+            let (argument, i) = latex::argument(tokens, i);
+            out.push_str(&format!("{{{} {}}}", switch, emitted(argument)));
+            i
+        }
+        "overset" | "underset" => {
+            let (over, i) = latex::argument(tokens, i);
+            let (base, i) = latex::argument(tokens, i);
+            let attach = if name == "overset" { '^' } else { '_' };
+            out.push_str(&format!(
+                "\\mathop{{{}}}\\limits{}{{{}}}",
+                emitted(base),
+                attach,
+                emitted(over)
+            ));
+            i
+        }
+        "tag" => {
+            let (tag, i) = latex::argument(tokens, i);
+            out.push_str(&format!("\\eqno({})", latex::to_string(tag)));
+            i
+        }
+        // Commands plain TeX shares keep their name.
+        name => {
+            out.push('\\');
+            out.push_str(name);
+            // Keep a command name from running into a following letter.
+            if let Some(Token::Char(c)) = tokens.get(i) {
+                if c.is_ascii_alphabetic() && name.chars().all(|c| c.is_ascii_alphabetic()) {
+                    out.push(' ');
+                }
+            }
+            i
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fractions_become_over() {
+        assert_eq!(from_latex(r"\frac{a}{b}"), r"$$ {a \over b} $$");
+        assert_eq!(
+            from_latex(r"\frac{1}{\frac{a}{b}}"),
+            r"$$ {1 \over {a \over b}} $$"
+        );
+    }
+
+    #[test]
+    fn environments() {
+        assert_eq!(
+            from_latex(r"\begin{aligned} a &= b \\ c &= d \end{aligned}"),
+            r"$$ \eqalign{a & = b \cr c & = d \cr} $$"
+        );
+        assert_eq!(
+            from_latex(r"\begin{pmatrix} 1 & 2 \\ 3 & 4 \end{pmatrix}"),
+            r"$$ \pmatrix{1 & 2 \cr 3 & 4 \cr} $$"
+        );
+        assert_eq!(
+            from_latex(r"\begin{gathered} a \\ b \end{gathered}"),
+            r"$$ \matrix{a \cr b \cr} $$"
+        );
+    }
+
+    #[test]
+    fn cases_have_text_conditions() {
+        assert_eq!(
+            from_latex(r"f(x) = \begin{cases} 1 & x > 0 \\ 0 & \text{otherwise} \end{cases}"),
+            r"$$ f(x) = \cases{1 & $x > 0$ \cr 0 & $\hbox{otherwise}$ \cr} $$"
+        );
+    }
+
+    #[test]
+    fn text_and_fonts() {
+        assert_eq!(from_latex(r"\text{if } x"), r"$$ \hbox{if } x $$");
+        assert_eq!(
+            from_latex(r"\mathbb{R} \mathcal{F}"),
+            r"$$ {\bf R} {\cal F} $$"
+        );
+        assert_eq!(from_latex(r"\sum_{i=1}^{n} i"), r"$$ \sum_{i=1}^{n} i $$");
+    }
+
+    #[test]
+    fn roots_binomials_and_display_fractions() {
+        assert_eq!(from_latex(r"\sqrt[n]{x}"), r"$$ \root n \of {x} $$");
+        assert_eq!(from_latex(r"\binom{n}{k}"), r"$$ {n \choose k} $$");
+        assert_eq!(
+            from_latex(r"\dfrac{a}{b}"),
+            r"$$ {\displaystyle a \over b} $$"
+        );
+    }
+
+    #[test]
+    fn delimited_and_unknown_environments() {
+        assert_eq!(
+            from_latex(r"\begin{bmatrix} a \\ b \end{bmatrix}"),
+            r"$$ \left[\matrix{a \cr b \cr}\right] $$"
+        );
+        assert_eq!(
+            from_latex(r"\begin{foo} a \end{foo}"),
+            r"$$ \begin{foo} a \end{foo} $$"
+        );
+    }
+
+    #[test]
+    fn operators_accents_and_tags() {
+        assert_eq!(
+            from_latex(r"\operatorname{rank} A"),
+            r"$$ \mathop{\rm rank}\nolimits A $$"
+        );
+        assert_eq!(
+            from_latex(r"\overset{a}{b}"),
+            r"$$ \mathop{b}\limits^{a} $$"
+        );
+        assert_eq!(from_latex(r"x \tag{1}"), r"$$ x \eqno(1) $$");
+    }
+}
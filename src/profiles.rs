@@ -0,0 +1,114 @@
+use crate::json::Value;
+use crate::output::{Delimiters, Numbering, Target};
+use crate::rules::Rule;
+use crate::settings::Settings;
+
+// Named settings for one destination, e.g. a paper on Overleaf or Anki cards,
+// switched between as a whole.
+#[derive(Clone)]
+pub struct Profile {
+    pub name: String,
+    pub target: Target,
+    // See `Settings::delimiters`, `Settings::numbering` and the rest.
+    pub delimiters: String,
+    pub numbering: String,
+    pub numbering_pattern: String,
+    pub input_rules: Vec<Rule>,
+    pub output_rules: Vec<Rule>,
+    pub export_foreground: String,
+    pub export_background: String,
+}
+
+impl Profile {
+    // A profile holding what's set now.
+    pub fn capture(name: &str, settings: &Settings, target: Target) -> Profile {
+        Profile {
+            name: name.to_string(),
+            target,
+            delimiters: settings.delimiters.clone(),
+            numbering: settings.numbering.clone(),
+            numbering_pattern: settings.numbering_pattern.clone(),
+            input_rules: settings.input_rules.clone(),
+            output_rules: settings.output_rules.clone(),
+            export_foreground: settings.export_foreground.clone(),
+            export_background: settings.export_background.clone(),
+        }
+    }
+
+    // Switch `settings` and `target` over to this profile.
+    pub fn apply(&self, settings: &mut Settings, target: &mut Target) {
+        *target = self.target;
+        settings.delimiters = self.delimiters.clone();
+        settings.numbering = self.numbering.clone();
+        settings.numbering_pattern = self.numbering_pattern.clone();
+        settings.input_rules = self.input_rules.clone();
+        settings.output_rules = self.output_rules.clone();
+        settings.export_foreground = self.export_foreground.clone();
+        settings.export_background = self.export_background.clone();
+        settings.profile = self.name.clone();
+    }
+
+    pub fn from_json(value: &Value) -> Option<Profile> {
+        let string = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let rules = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_array)
+                .map(|rules| rules.iter().filter_map(Rule::from_json).collect())
+                .unwrap_or_default()
+        };
+        Some(Profile {
+            name: value.get("name")?.as_str()?.to_string(),
+            target: Target::from_name(&string("target")).unwrap_or(Target::Latex),
+            delimiters: string("delimiters"),
+            numbering: string("numbering"),
+            numbering_pattern: string("numbering_pattern"),
+            input_rules: rules("input_rules"),
+            output_rules: rules("output_rules"),
+            export_foreground: string("export_foreground"),
+            export_background: string("export_background"),
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        let rules = |rules: &[Rule]| Value::Array(rules.iter().map(Rule::to_json).collect());
+        Value::object([
+            ("name", self.name.as_str().into()),
+            ("target", self.target.name().into()),
+            ("delimiters", self.delimiters.as_str().into()),
+            ("numbering", self.numbering.as_str().into()),
+            ("numbering_pattern", self.numbering_pattern.as_str().into()),
+            ("input_rules", rules(&self.input_rules)),
+            ("output_rules", rules(&self.output_rules)),
+            ("export_foreground", self.export_foreground.as_str().into()),
+            ("export_background", self.export_background.as_str().into()),
+        ])
+    }
+}
+
+// The profiles a fresh install starts with.
+pub fn defaults() -> Vec<Profile> {
+    let profile = |name: &str, delimiters: Delimiters, numbering: Numbering| Profile {
+        name: name.to_string(),
+        target: Target::Latex,
+        delimiters: delimiters.name().to_string(),
+        numbering: numbering.name().to_string(),
+        numbering_pattern: "(1)".to_string(),
+        input_rules: Vec::new(),
+        output_rules: Vec::new(),
+        export_foreground: String::new(),
+        export_background: String::new(),
+    };
+    vec![
+        profile("Overleaf paper", Delimiters::None, Numbering::Numbered),
+        profile("Obsidian notes", Delimiters::DoubleDollars, Numbering::AsIs),
+        // Anki's MathJax reads `\(...\)` and `\[...\]`, not dollars.
+        profile("Anki cards", Delimiters::Parentheses, Numbering::AsIs),
+    ]
+}
@@ -0,0 +1,402 @@
+use crate::cache::{self, Cache};
+use crate::convert::{self, Direction};
+use crate::document::convert_math_regions_with;
+use crate::json::Value;
+use crate::pool;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+// Hashes of the inputs of the last run, and the formulas converted so far, kept
+// in the output directory.
+const MANIFEST: &str = ".typst2latex-project.json";
+const CACHE: &str = ".typst2latex-cache.json";
+
+pub enum Status {
+    Converted,
+    Unchanged,
+    Failed(String),
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Converted => "converted",
+            Status::Unchanged => "unchanged",
+            Status::Failed(_) => "failed",
+        }
+    }
+}
+
+// What happened to one file, by its path relative to the project.
+pub struct FileReport {
+    pub path: String,
+    pub status: Status,
+    pub warnings: Vec<String>,
+}
+
+// The files to convert under `dir`, sorted, leaving out hidden directories and
+// `skip`, where the output goes when it's inside the project.
+fn sources(dir: &Path, extensions: &[&str], skip: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    let entries = fs::read_dir(dir).map_err(|_| format!("Failed to read {}", dir.display()))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if path.is_dir() {
+            if !hidden && path.canonicalize().ok().as_deref() != Some(skip) {
+                files.extend(sources(&path, extensions, skip)?);
+            }
+        } else if path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| extensions.contains(&extension))
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// The extension of the documents to mirror, and the one they get once converted.
+fn document_extensions(direction: Direction) -> (&'static str, &'static str) {
+    match direction {
+        Direction::LatexToTypst => ("tex", "typ"),
+        _ => ("typ", "tex"),
+    }
+}
+
+// Where the converted `relative` goes under `out`, and whether it's a document
+// rather than Markdown, which keeps its name.
+fn mirror(out: &Path, relative: &Path, direction: Direction) -> (PathBuf, bool) {
+    let (from, to) = document_extensions(direction);
+    let target = out.join(relative);
+    if relative
+        .extension()
+        .is_some_and(|extension| extension == from)
+    {
+        (target.with_extension(to), true)
+    } else {
+        (target, false)
+    }
+}
+
+fn load_manifest(out: &Path) -> Vec<(String, Value)> {
+    let text = fs::read_to_string(out.join(MANIFEST)).unwrap_or_default();
+    match Value::parse(&text)
+        .ok()
+        .and_then(|value| value.get("files").cloned())
+    {
+        Some(Value::Object(files)) => files,
+        _ => Vec::new(),
+    }
+}
+
+// Mirror the Typst and Markdown files under `source` into `out`, in the same
+// places. Markdown keeps its name and its text, with the math converted; a Typst
+// file is converted whole, as it can't hold LaTeX math, into a `.tex` file of the
+// same name. With `Direction::LatexToTypst`, LaTeX files become `.typ` files instead.
+// Files whose content hasn't changed since a run that converted all their math are
+// skipped, and in the files that did change only new or edited math is converted.
+// Up to `jobs` files are converted at a time, and `progress` is told how many files
+// of how many are done, and which one finished last, as they finish.
+pub fn convert_project(
+    source: &Path,
+    out: &Path,
+    direction: Direction,
+    jobs: usize,
+    progress: impl Fn(usize, usize, &str) + Sync,
+) -> Result<Vec<FileReport>, String> {
+    fs::create_dir_all(out).map_err(|_| format!("Failed to create {}", out.display()))?;
+    let skip = out
+        .canonicalize()
+        .map_err(|_| format!("Failed to locate {}", out.display()))?;
+    let (documents, _) = document_extensions(direction);
+    let files = sources(source, &[documents, "md"], &skip)?;
+    let mut manifest = load_manifest(out);
+    let cache = Mutex::new(Cache::load(&out.join(CACHE)));
+    let mut reports = Vec::new();
+    // Files to convert: their place in `reports`, where they go, whether they're
+    // documents, their content and its hash.
+    let mut pending = Vec::new();
+    for file in files {
+        let relative = file.strip_prefix(source).unwrap_or(&file);
+        let path = relative.to_string_lossy().replace('\\', "/");
+        let (target, document) = mirror(out, relative, direction);
+        let text = match fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(_) => {
+                reports.push(FileReport {
+                    path,
+                    status: Status::Failed("Failed to read the file".to_string()),
+                    warnings: Vec::new(),
+                });
+                continue;
+            }
+        };
+        // The direction, backend and pandoc version are part of the hash so that
+        // switching them converts again.
+        let digest = cache::key(&text, direction);
+        let previous = manifest.iter().find(|(name, _)| *name == path);
+        if previous.and_then(|(_, value)| value.as_str()) == Some(digest.as_str())
+            && target.exists()
+        {
+            reports.push(FileReport {
+                path,
+                status: Status::Unchanged,
+                warnings: Vec::new(),
+            });
+            continue;
+        }
+        pending.push((reports.len(), target, document, text, digest));
+        reports.push(FileReport {
+            path,
+            status: Status::Converted,
+            warnings: Vec::new(),
+        });
+    }
+    let total = reports.len();
+    let done = AtomicUsize::new(total - pending.len());
+    progress(done.load(Ordering::Relaxed), total, "");
+    let converted = pool::map(&pending, jobs, |(index, _, document, text, _)| {
+        let converted = if *document {
+            convert::convert_document(text, direction).map(|converted| (converted, Vec::new()))
+        } else {
+            Ok(convert_math_regions_with(
+                text,
+                direction,
+                |math, direction| Cache::convert_shared(&cache, math, direction),
+            ))
+        };
+        let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+        progress(done, total, &reports[*index].path);
+        converted
+    });
+    for ((index, target, _, _, digest), converted) in pending.into_iter().zip(converted) {
+        let report = &mut reports[index];
+        let path = &report.path;
+        let (converted, warnings) = match converted {
+            Ok(converted) => converted,
+            Err(err) => {
+                report.status = Status::Failed(err);
+                continue;
+            }
+        };
+        let written = target
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&target, converted));
+        match written {
+            Ok(()) => {
+                manifest.retain(|(name, _)| name != path);
+                // Files with math left unconverted are tried again next time.
+                if warnings.is_empty() {
+                    manifest.push((path.clone(), digest.into()));
+                }
+            }
+            Err(_) => {
+                report.status = Status::Failed(format!("Failed to write {}", target.display()))
+            }
+        }
+        report.warnings = warnings;
+    }
+    cache
+        .into_inner()
+        .unwrap_or_else(|err| err.into_inner())
+        .save()?;
+    let manifest = Value::object([("files", Value::Object(manifest))]);
+    fs::write(out.join(MANIFEST), manifest.pretty(2))
+        .map_err(|_| format!("Failed to write {}", out.join(MANIFEST).display()))?;
+    Ok(reports)
+}
+
+// The outcome of a run for tools and CI: every file with its status, and the
+// warnings with the lines they point at.
+pub fn report_json(reports: &[FileReport]) -> Value {
+    let files = reports.iter().map(|report| {
+        let mut file = Value::object([
+            ("path", report.path.as_str().into()),
+            ("status", report.status.name().into()),
+        ]);
+        if let Status::Failed(err) = &report.status {
+            file.insert("error", err.as_str().into());
+        }
+        let warnings = report.warnings.iter().map(|warning| {
+            // Warnings read "line <n>: <message>".
+            let located = warning.strip_prefix("line ").and_then(|rest| {
+                let (line, message) = rest.split_once(": ")?;
+                Some((line.parse::<f64>().ok()?, message))
+            });
+            match located {
+                Some((line, message)) => {
+                    Value::object([("line", line.into()), ("message", message.into())])
+                }
+                None => Value::object([("message", warning.as_str().into())]),
+            }
+        });
+        file.insert("warnings", Value::Array(warnings.collect()));
+        file
+    });
+    let count = |name: &str| {
+        let count = reports
+            .iter()
+            .filter(|report| report.status.name() == name)
+            .count();
+        (name.to_string(), Value::from(count as f64))
+    };
+    let warned = reports
+        .iter()
+        .filter(|report| !report.warnings.is_empty())
+        .count();
+    Value::object([
+        (
+            "summary",
+            Value::Object(vec![
+                ("files".to_string(), (reports.len() as f64).into()),
+                count("converted"),
+                count("unchanged"),
+                ("warnings".to_string(), (warned as f64).into()),
+                count("failed"),
+            ]),
+        ),
+        ("files", Value::Array(files.collect())),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An empty directory of its own under the system's temporary one.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("typst2latex-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn statuses(reports: &[FileReport]) -> Vec<(&str, &str)> {
+        reports
+            .iter()
+            .map(|report| (report.path.as_str(), report.status.name()))
+            .collect()
+    }
+
+    #[test]
+    fn documents_are_renamed() {
+        let out = Path::new("out");
+        assert_eq!(
+            mirror(out, Path::new("a/b.typ"), Direction::TypstToLatex),
+            (out.join("a/b.tex"), true)
+        );
+        assert_eq!(
+            mirror(out, Path::new("b.tex"), Direction::LatexToTypst),
+            (out.join("b.typ"), true)
+        );
+        assert_eq!(
+            mirror(out, Path::new("a/b.md"), Direction::LatexToTypst),
+            (out.join("a/b.md"), false)
+        );
+    }
+
+    #[test]
+    fn projects_are_mirrored() {
+        let source = scratch("project");
+        // The output inside the project isn't mirrored into itself.
+        let out = source.join("out");
+        fs::create_dir_all(source.join("chapters")).unwrap();
+        fs::create_dir_all(source.join(".git")).unwrap();
+        fs::write(source.join("README.md"), "No math here.\n").unwrap();
+        fs::write(source.join("chapters/one.md"), "Let $x^2$ be.\n").unwrap();
+        fs::write(source.join("main.typ"), "= Title\n").unwrap();
+        fs::write(source.join("notes.txt"), "Not mirrored.\n").unwrap();
+        fs::write(source.join(".git/hidden.md"), "Not mirrored.\n").unwrap();
+        // The formula is in the cache already, so that pandoc isn't needed for it.
+        fs::create_dir_all(&out).unwrap();
+        let cached = Value::object([(
+            "entries",
+            Value::object([(cache::key("x^2", Direction::TypstToLatex), "x^{2}".into())]),
+        )]);
+        fs::write(out.join(CACHE), cached.to_string()).unwrap();
+
+        let run =
+            || convert_project(&source, &out, Direction::TypstToLatex, 2, |_, _, _| {}).unwrap();
+        let reports = run();
+        assert_eq!(
+            statuses(&reports)
+                .into_iter()
+                .filter(|(path, _)| *path != "main.typ")
+                .collect::<Vec<_>>(),
+            [("README.md", "converted"), ("chapters/one.md", "converted")]
+        );
+        assert_eq!(
+            fs::read_to_string(out.join("chapters/one.md")).unwrap(),
+            "Let $x^{2}$ be.\n"
+        );
+        assert_eq!(
+            fs::read_to_string(out.join("README.md")).unwrap(),
+            "No math here.\n"
+        );
+        assert!(!out.join("notes.txt").exists() && !out.join(".git").exists());
+        // Whether the Typst document converts depends on pandoc being installed,
+        // but it's tried, and into a `.tex` file.
+        let main = reports
+            .iter()
+            .find(|report| report.path == "main.typ")
+            .unwrap();
+        assert_eq!(
+            out.join("main.tex").exists(),
+            matches!(main.status, Status::Converted)
+        );
+        assert!(!out.join("main.typ").exists());
+
+        let manifest = load_manifest(&out);
+        let text = fs::read_to_string(source.join("chapters/one.md")).unwrap();
+        assert!(manifest.contains(&(
+            "chapters/one.md".to_string(),
+            cache::key(&text, Direction::TypstToLatex).into()
+        )));
+
+        // Only what changed is converted again.
+        fs::write(source.join("README.md"), "Still no math.\n").unwrap();
+        let reports = run();
+        assert_eq!(statuses(&reports)[0], ("README.md", "converted"));
+        assert_eq!(statuses(&reports)[1], ("chapters/one.md", "unchanged"));
+        assert_eq!(
+            fs::read_to_string(out.join("README.md")).unwrap(),
+            "Still no math.\n"
+        );
+        // And so is what's gone from the output.
+        fs::remove_file(out.join("chapters/one.md")).unwrap();
+        assert_eq!(statuses(&run())[1], ("chapters/one.md", "converted"));
+
+        let _ = fs::remove_dir_all(&source);
+    }
+
+    #[test]
+    fn reports() {
+        let reports = [
+            FileReport {
+                path: "a.md".to_string(),
+                status: Status::Converted,
+                warnings: vec!["line 3: unknown command".to_string()],
+            },
+            FileReport {
+                path: "b.md".to_string(),
+                status: Status::Unchanged,
+                warnings: Vec::new(),
+            },
+            FileReport {
+                path: "c.typ".to_string(),
+                status: Status::Failed("Failed to write c.tex".to_string()),
+                warnings: vec!["no line".to_string()],
+            },
+        ];
+        assert_eq!(
+            report_json(&reports).to_string(),
+            r#"{"summary":{"files":3,"converted":1,"unchanged":1,"warnings":2,"failed":1},"files":[{"path":"a.md","status":"converted","warnings":[{"line":3,"message":"unknown command"}]},{"path":"b.md","status":"unchanged","warnings":[]},{"path":"c.typ","status":"failed","error":"Failed to write c.tex","warnings":[{"message":"no line"}]}]}"#
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use crate::i18n;
+use crate::learning;
+use crate::symbols::SYMBOLS;
+use eframe::egui;
+
+// The syntax that isn't a function or a symbol: a Typst example, the LaTeX it
+// becomes, and what it's for.
+const SYNTAX: &[(&str, &str, &str)] = &[
+    ("x^2", "x^{2}", "reference-superscript"),
+    ("x_i", "x_{i}", "reference-subscript"),
+    ("x_(i j)", "x_{ij}", "reference-group"),
+    ("a / b", "\\frac{a}{b}", "reference-fraction"),
+    (
+        "(a + b) / c",
+        "\\frac{a + b}{c}",
+        "reference-fraction-group",
+    ),
+    ("a b", "ab", "reference-product"),
+    ("\"if\"", "\\text{if}", "reference-text"),
+    ("x'", "x'", "reference-prime"),
+    ("lim_(x -> 0)", "\\lim_{x \\to 0}", "reference-limits"),
+    ("a &= b", "a & = b", "reference-align"),
+    ("a \\ b", "a \\\\ b", "reference-linebreak"),
+    ("$ x $", "\\[ x \\]", "reference-display"),
+];
+
+// A cheat sheet of Typst math, built from the tables the converter already keeps
+// so it lists exactly what the app knows how to pair up.
+#[derive(Default)]
+pub struct Reference {
+    pub open: bool,
+    query: String,
+}
+
+impl Reference {
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        egui::Window::new(i18n::tr("reference"))
+            .open(&mut open)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text(i18n::tr("reference-search"))
+                        .desired_width(f32::INFINITY),
+                );
+                ui.separator();
+                let query = self.query.trim().to_lowercase();
+                let matches = |fields: &[&str]| {
+                    fields
+                        .iter()
+                        .any(|field| field.to_lowercase().contains(&query))
+                };
+                let syntax: Vec<_> = SYNTAX
+                    .iter()
+                    .filter(|(typst, latex, note)| matches(&[*typst, *latex, i18n::tr(note)]))
+                    .collect();
+                let functions: Vec<_> = learning::FUNCTIONS
+                    .iter()
+                    .filter(|(function, _, latex)| matches(&[*function, *latex]))
+                    .collect();
+                let symbols: Vec<_> = SYMBOLS
+                    .iter()
+                    .filter(|symbol| matches(&[symbol.typst, symbol.latex, symbol.glyph]))
+                    .collect();
+                if syntax.is_empty() && functions.is_empty() && symbols.is_empty() {
+                    ui.weak(i18n::tr("reference-none"));
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(420.0)
+                    .show(ui, |ui| {
+                        // While searching, every section with a match is shown open.
+                        let searching = !query.is_empty();
+                        if !syntax.is_empty() {
+                            section(ui, "reference-syntax", searching, |ui| {
+                                for (typst, latex, note) in syntax {
+                                    ui.monospace(*typst);
+                                    ui.monospace(*latex);
+                                    ui.label(i18n::tr(note));
+                                    ui.end_row();
+                                }
+                            });
+                        }
+                        if !functions.is_empty() {
+                            section(ui, "reference-functions", searching, |ui| {
+                                for (function, _, latex) in functions {
+                                    ui.monospace(format!("{}(…)", function));
+                                    ui.monospace(*latex);
+                                    ui.end_row();
+                                }
+                            });
+                        }
+                        if !symbols.is_empty() {
+                            section(ui, "reference-symbols", searching, |ui| {
+                                for symbol in symbols {
+                                    ui.monospace(symbol.typst);
+                                    ui.monospace(format!("\\{}", symbol.latex));
+                                    ui.label(egui::RichText::new(symbol.glyph).size(18.0));
+                                    ui.end_row();
+                                }
+                            });
+                        }
+                    });
+            });
+        self.open = open;
+    }
+}
+
+fn section(
+    ui: &mut egui::Ui,
+    title: &'static str,
+    searching: bool,
+    rows: impl FnOnce(&mut egui::Ui),
+) {
+    egui::CollapsingHeader::new(i18n::tr(title))
+        .default_open(true)
+        .open(searching.then_some(true))
+        .show(ui, |ui| {
+            egui::Grid::new(title)
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Typst");
+                    ui.strong("LaTeX");
+                    ui.end_row();
+                    rows(ui);
+                });
+        });
+}
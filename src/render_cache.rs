@@ -0,0 +1,66 @@
+use tiny_skia::Pixmap;
+
+// A rendered preview: the SVG, and the pixels rasterized from it before tinting.
+#[derive(Clone)]
+pub struct Rendered {
+    pub svg: String,
+    pub pixmap: Pixmap,
+    // The scale the pixels were rasterized at.
+    pub scale: f32,
+}
+
+impl Rendered {
+    fn bytes(&self) -> usize {
+        self.svg.len() + self.pixmap.data().len()
+    }
+}
+
+// Previews rendered before, so that going back to a formula, e.g. by undoing or
+// from history, shows it without running MathJax and resvg again. The least
+// recently shown are forgotten to stay within a memory budget.
+#[derive(Default)]
+pub struct RenderCache {
+    // From least to most recently shown.
+    entries: Vec<(String, Rendered)>,
+    bytes: usize,
+}
+
+impl RenderCache {
+    pub fn get(&mut self, key: &str) -> Option<&Rendered> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(index);
+        self.entries.push(entry);
+        self.entries.last().map(|(_, rendered)| rendered)
+    }
+
+    // Keep `rendered` under `key`, unless it alone is over `budget` bytes.
+    pub fn insert(&mut self, key: String, rendered: Rendered, budget: usize) {
+        if rendered.bytes() > budget {
+            return;
+        }
+        if let Some(index) = self.entries.iter().position(|(k, _)| *k == key) {
+            self.bytes -= self.entries.remove(index).1.bytes();
+        }
+        self.bytes += rendered.bytes();
+        self.entries.push((key, rendered));
+        self.shrink(budget);
+    }
+
+    // Forget the least recently shown previews until the rest fit in `budget` bytes.
+    pub fn shrink(&mut self, budget: usize) {
+        let mut evict = 0;
+        while self.bytes > budget && evict < self.entries.len() {
+            self.bytes -= self.entries[evict].1.bytes();
+            evict += 1;
+        }
+        self.entries.drain(..evict);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
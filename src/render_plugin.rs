@@ -0,0 +1,68 @@
+use crate::backend;
+use crate::convert;
+use crate::diagnostics;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+// Render plugins are executables named `typst2latex-render-<name>`, found where
+// conversion plugins are. One reads LaTeX math on stdin and writes an SVG of it on
+// stdout, or fails with a message on stderr, e.g. to preview with a local LaTeX
+// installation or KaTeX on Node instead of MathJax.
+const PREFIX: &str = "typst2latex-render-";
+
+// Looked for once, since the preview renders often.
+fn installed() -> &'static [(String, PathBuf)] {
+    static INSTALLED: OnceLock<Vec<(String, PathBuf)>> = OnceLock::new();
+    INSTALLED.get_or_init(|| backend::executables(PREFIX))
+}
+
+pub fn names() -> Vec<&'static str> {
+    installed().iter().map(|(name, _)| name.as_str()).collect()
+}
+
+// Render `latex` to an SVG with the plugin called `name`.
+pub fn render(name: &str, latex: &str) -> Result<String, String> {
+    let (_, path) = installed()
+        .iter()
+        .find(|(found, _)| found == name)
+        .ok_or_else(|| format!("No render plugin named '{}'", name))?;
+    diagnostics::log(format!("{} ({} bytes)", path.display(), latex.len()));
+    let start = Instant::now();
+    let mut child = Command::new(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| format!("Failed to run the {} render plugin", name))?;
+    child
+        .stdin
+        .take()
+        .ok_or("Failed to open stdin")?
+        .write_all(latex.as_bytes())
+        .map_err(|_| "Failed to write to stdin")?;
+    // Killed when the preview it's for is replaced.
+    let output = convert::wait(child, name)?;
+    diagnostics::log(format!(
+        "{} exited with {} after {} ms",
+        name,
+        output.status,
+        start.elapsed().as_millis()
+    ));
+    let svg = String::from_utf8_lossy(&output.stdout);
+    if output.status.success() && svg.contains("<svg") {
+        Ok(svg.trim().to_string())
+    } else {
+        let error = String::from_utf8_lossy(&output.stderr);
+        diagnostics::log(format!("{} stderr: {}", name, error.trim()));
+        Err(error
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("The render plugin gave no SVG")
+            .trim()
+            .to_string())
+    }
+}
@@ -0,0 +1,253 @@
+use crate::i18n;
+use crate::json::Value;
+use eframe::egui;
+use regex::Regex;
+
+// A find-and-replace run over the input or the output of every conversion, for
+// shorthands such as `RR` for `bb(R)` or house style such as `\varepsilon`.
+#[derive(Clone)]
+pub struct Rule {
+    pub find: String,
+    pub replace: String,
+    // `find` is a regular expression and `replace` may refer to its groups as `$1`.
+    pub regex: bool,
+    pub enabled: bool,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            find: String::new(),
+            replace: String::new(),
+            regex: false,
+            enabled: true,
+        }
+    }
+}
+
+impl Rule {
+    pub fn from_json(value: &Value) -> Option<Rule> {
+        Some(Rule {
+            find: value.get("find")?.as_str()?.to_string(),
+            replace: value
+                .get("replace")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            regex: value.get("regex").and_then(Value::as_bool).unwrap_or(false),
+            enabled: value
+                .get("enabled")
+                .and_then(Value::as_bool)
+                .unwrap_or(true),
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::object([
+            ("find", self.find.as_str().into()),
+            ("replace", self.replace.as_str().into()),
+            ("regex", self.regex.into()),
+            ("enabled", self.enabled.into()),
+        ])
+    }
+
+    // Why the rule can't run, if it can't.
+    pub fn error(&self) -> Option<String> {
+        if !self.regex {
+            return None;
+        }
+        Regex::new(&self.find).err().map(|err| err.to_string())
+    }
+
+    fn run(&self, text: &str) -> String {
+        if self.regex {
+            return match Regex::new(&self.find) {
+                Ok(pattern) => pattern
+                    .replace_all(text, self.replace.as_str())
+                    .into_owned(),
+                Err(_) => text.to_string(),
+            };
+        }
+        // Literal names match whole names only: `\to` leaves `\top` alone and `eps`
+        // leaves `epsilon`.
+        let starts_word = self.find.starts_with(|c: char| c.is_alphabetic());
+        let ends_word = self.find.ends_with(|c: char| c.is_alphabetic());
+        let mut output = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, found) in text.match_indices(self.find.as_str()) {
+            let end = start + found.len();
+            if (starts_word && text[..start].ends_with(|c: char| c.is_alphabetic()))
+                || (ends_word && text[end..].starts_with(|c: char| c.is_alphabetic()))
+            {
+                continue;
+            }
+            output.push_str(&text[last..start]);
+            output.push_str(&self.replace);
+            last = end;
+        }
+        output.push_str(&text[last..]);
+        output
+    }
+}
+
+// Run the enabled rules over `text` in order, each on what the one before left.
+// Rules with an invalid pattern are skipped; the editor points them out.
+pub fn apply(rules: &[Rule], text: &str) -> String {
+    rules
+        .iter()
+        .filter(|rule| rule.enabled && !rule.find.is_empty())
+        .fold(text.to_string(), |text, rule| rule.run(&text))
+}
+
+// Edit an ordered list of rules. Returns whether anything changed.
+pub fn editor(ui: &mut egui::Ui, id_salt: &str, rules: &mut Vec<Rule>) -> bool {
+    let mut changed = false;
+    let mut remove = None;
+    let mut raise = None;
+    egui::Grid::new(id_salt).striped(true).show(ui, |ui| {
+        ui.label("");
+        ui.strong(i18n::tr("rules-find"));
+        ui.strong(i18n::tr("rules-replace"));
+        ui.label("");
+        ui.label("");
+        ui.end_row();
+        let count = rules.len();
+        for (i, rule) in rules.iter_mut().enumerate() {
+            changed |= ui
+                .checkbox(&mut rule.enabled, "")
+                .on_hover_text(i18n::tr("rules-enabled"))
+                .changed();
+            let find = ui.add(egui::TextEdit::singleline(&mut rule.find).desired_width(140.0));
+            changed |= find.changed();
+            if let Some(err) = rule.error() {
+                find.on_hover_text(err);
+            }
+            changed |= ui
+                .add(egui::TextEdit::singleline(&mut rule.replace).desired_width(140.0))
+                .changed();
+            changed |= ui
+                .checkbox(&mut rule.regex, i18n::tr("find-regex"))
+                .changed();
+            ui.horizontal(|ui| {
+                if ui.add_enabled(i > 0, egui::Button::new("↑")).clicked() {
+                    raise = Some(i);
+                }
+                if ui
+                    .add_enabled(i + 1 < count, egui::Button::new("↓"))
+                    .clicked()
+                {
+                    raise = Some(i + 1);
+                }
+                if ui.button("×").clicked() {
+                    remove = Some(i);
+                }
+                if rule.error().is_some() {
+                    ui.colored_label(ui.visuals().error_fg_color, i18n::tr("find-invalid"));
+                }
+            });
+            ui.end_row();
+        }
+    });
+    if let Some(i) = raise {
+        rules.swap(i - 1, i);
+        changed = true;
+    }
+    if let Some(i) = remove {
+        rules.remove(i);
+        changed = true;
+    }
+    if ui.button(i18n::tr("rules-add")).clicked() {
+        rules.push(Rule::default());
+        changed = true;
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(find: &str, replace: &str) -> Rule {
+        Rule {
+            find: find.to_string(),
+            replace: replace.to_string(),
+            ..Rule::default()
+        }
+    }
+
+    fn regex(find: &str, replace: &str) -> Rule {
+        Rule {
+            regex: true,
+            ..literal(find, replace)
+        }
+    }
+
+    #[test]
+    fn rules_run_in_order() {
+        let rules = [literal("RR", "bb(R)"), literal("bb(R)", "RR_+")];
+        assert_eq!(apply(&rules, "x in RR"), "x in RR_+");
+        let rules = [literal("bb(R)", "RR_+"), literal("RR", "bb(R)")];
+        assert_eq!(apply(&rules, "x in RR"), "x in bb(R)");
+    }
+
+    #[test]
+    fn disabled_and_empty_rules_are_skipped() {
+        let rules = [
+            Rule {
+                enabled: false,
+                ..literal("a", "b")
+            },
+            literal("", "c"),
+        ];
+        assert_eq!(apply(&rules, "a"), "a");
+    }
+
+    #[test]
+    fn literal_names_match_whole_names() {
+        let rules = [literal(r"\to", r"\rightarrow"), literal("eps", "epsilon")];
+        assert_eq!(
+            apply(&rules, r"x \to y \top eps epsilon"),
+            r"x \rightarrow y \top epsilon epsilon"
+        );
+        // Symbols match anywhere.
+        assert_eq!(apply(&[literal("->", "→")], "a->b"), "a→b");
+        // A regex character in a literal rule is itself.
+        assert_eq!(apply(&[literal("a.", "x")], "a. ab"), "x ab");
+    }
+
+    #[test]
+    fn regex_rules_replace_groups() {
+        let rules = [regex(r"\\mathbf\{(\w)\}", r"\bm{$1}")];
+        assert_eq!(
+            apply(&rules, r"\mathbf{v} + \mathbf{w}"),
+            r"\bm{v} + \bm{w}"
+        );
+        assert!(rules[0].error().is_none());
+    }
+
+    #[test]
+    fn invalid_regexes_are_skipped() {
+        let rules = [regex("(", "x"), literal("a", "b")];
+        assert!(rules[0].error().is_some());
+        // The same text as a literal rule is fine.
+        assert!(literal("(", "x").error().is_none());
+        assert_eq!(apply(&rules, "(a"), "(b");
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let rule = regex("a+", "b");
+        let back = Rule::from_json(&rule.to_json()).unwrap();
+        assert_eq!(
+            (back.find, back.replace, back.regex, back.enabled),
+            ("a+".to_string(), "b".to_string(), true, true)
+        );
+        // Only `find` is required.
+        let rule = Rule::from_json(&Value::parse(r#"{"find": "x"}"#).unwrap()).unwrap();
+        assert_eq!(
+            (rule.replace.as_str(), rule.regex, rule.enabled),
+            ("", false, true)
+        );
+        assert!(Rule::from_json(&Value::parse("{}").unwrap()).is_none());
+    }
+}
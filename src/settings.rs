@@ -0,0 +1,495 @@
+use crate::convert::Direction;
+use crate::json::Value;
+use crate::output::Target;
+use crate::profiles::{self, Profile};
+use crate::rules::Rule;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static PORTABLE: AtomicBool = AtomicBool::new(false);
+
+// Keep settings and data beside the executable (`--portable`).
+pub fn set_portable() {
+    PORTABLE.store(true, Ordering::Relaxed);
+}
+
+// Where portable mode keeps everything. Its existence turns portable mode on, so
+// that a copy on a USB stick stays portable without the flag.
+fn portable_dir() -> Option<PathBuf> {
+    Some(
+        std::env::current_exe()
+            .ok()?
+            .parent()?
+            .join("typst2latex-data"),
+    )
+}
+
+// Configuration directory for the app: the platform one, e.g.
+// `~/.config/typst2latex`, or in portable mode the one beside the executable.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(dir) = portable_dir().filter(|dir| PORTABLE.load(Ordering::Relaxed) || dir.is_dir())
+    {
+        return Some(dir);
+    }
+    let base = if cfg!(windows) {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        PathBuf::from(std::env::var_os("HOME")?).join("Library/Application Support")
+    } else {
+        match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        }
+    };
+    Some(base.join("typst2latex"))
+}
+
+pub struct Settings {
+    pub anki_url: String,
+    pub anki_deck: String,
+    pub anki_model: String,
+    // How the copy button numbers LaTeX equations, see `output::Numbering`.
+    pub numbering: String,
+    // Typst numbering pattern for `\tag`s, e.g. "(1)".
+    pub numbering_pattern: String,
+    // Math delimiters the copy button puts around unnumbered LaTeX, see
+    // `output::Delimiters`.
+    pub delimiters: String,
+    // How quantities and units in LaTeX output are written, see `units::restyle`.
+    pub units: String,
+    // How numbers in LaTeX output are written, see `numbers::Style`.
+    pub decimal_comma: bool,
+    pub digit_grouping: bool,
+    pub scientific: String,
+    // How matrices of arrows are written in LaTeX output: "" as they are, "cd" for
+    // amscd or "tikz-cd".
+    pub diagrams: String,
+    // Characters in LaTeX output: "" as they are, "ascii" for commands only or
+    // "unicode" for symbols as characters, for unicode-math.
+    pub latex_characters: String,
+    // Saved bundles of the settings above and the rules, and the one last chosen.
+    pub profiles: Vec<Profile>,
+    pub profile: String,
+    pub anki_image_front: bool,
+    pub tectonic_preview: bool,
+    // Megabytes kept for previews rendered before, reshown without rendering.
+    pub preview_cache_mb: f32,
+    pub validate_with_tex: bool,
+    pub ocr_command: String,
+    pub recent_files: Vec<String>,
+    // Markdown engines whose copies skip the sanitizer, by `output::Markdown::name`.
+    pub raw_markdown: Vec<String>,
+    // Rewrites of the input before every conversion and of the output after it, in
+    // order. The input ones can be switched off together.
+    pub input_rules: Vec<Rule>,
+    pub input_rules_enabled: bool,
+    pub output_rules: Vec<Rule>,
+    // Shell commands the input goes through before converting and the output
+    // after, reading on stdin and writing on stdout, or "" for none.
+    pub pre_hook: String,
+    pub post_hook: String,
+    // `\newcommand`s and the like of the user's LaTeX preamble, expanded in LaTeX
+    // input before converting.
+    pub latex_macros: String,
+    // amsthm names for Typst theorem blocks in documents, lines like `theorem = thm`.
+    pub theorem_environments: String,
+    // Empty to follow the system language.
+    pub language: String,
+    // "system", "light" or "dark".
+    pub theme: String,
+    // Custom `#rrggbb` colors, empty for the theme's own.
+    pub accent_color: String,
+    pub background_color: String,
+    pub text_color: String,
+    pub preview_color: String,
+    // "proportional" or "monospace".
+    pub editor_font: String,
+    pub editor_font_size: f32,
+    // Line height as a multiple of the font size.
+    pub editor_line_spacing: f32,
+    // "stacked" or "side-by-side".
+    pub layout: String,
+    // Heights of the input and output editors, set with the splitters below them.
+    pub input_height: f32,
+    pub output_height: f32,
+    // Soft-wrap long lines at the pane width instead of scrolling horizontally.
+    pub input_wrap: bool,
+    pub output_wrap: bool,
+    // Draw the preview over a checkerboard, to show what is transparent.
+    pub preview_checkerboard: bool,
+    // Colors of exported images, "" for the preview color and a transparent
+    // background.
+    pub export_foreground: String,
+    pub export_background: String,
+    // Font file of the OpenType math font the preview is typeset in with tectonic,
+    // "" for MathJax's font.
+    pub math_font: String,
+    // Also put each conversion in the primary selection (Linux).
+    pub primary_selection: bool,
+    // Modal, Vim-style editing in the input editor.
+    pub vim_mode: bool,
+    // Type brackets in pairs in the input editor.
+    pub auto_close_brackets: bool,
+    // Take `$...$`, code fences and `#math.equation(..)` off the input and put the
+    // output back in the same wrapper.
+    pub strip_wrappers: bool,
+    // Explain the conversion for learners: "" for off, "comments" to annotate the
+    // output, "breakdown" for a table under it.
+    pub learning_mode: String,
+    // Look for a newer release on GitHub at startup.
+    pub check_updates: bool,
+    // The first-run walkthrough was finished or skipped.
+    pub onboarded: bool,
+    // Never touch the network, whatever else is enabled.
+    pub offline: bool,
+    // The plugin that converts instead of pandoc, or "" for pandoc.
+    pub backend: String,
+    // The plugin that renders the preview, or "" for MathJax or tectonic.
+    pub render_backend: String,
+    // How LaTeX → Typst output is written, see `typst_style::restyle`: arrows and
+    // relations as "shorthand" or "explicit", symbols as "names" or "unicode" and
+    // operators "spaced" or "compact", each "" for the way pandoc writes it.
+    pub typst_shorthands: String,
+    pub typst_symbols: String,
+    pub typst_spacing: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            anki_url: "http://127.0.0.1:8765".to_string(),
+            anki_deck: "Default".to_string(),
+            anki_model: "Basic".to_string(),
+            numbering: String::new(),
+            numbering_pattern: "(1)".to_string(),
+            delimiters: String::new(),
+            units: String::new(),
+            decimal_comma: false,
+            digit_grouping: false,
+            scientific: String::new(),
+            diagrams: String::new(),
+            latex_characters: String::new(),
+            profiles: profiles::defaults(),
+            profile: String::new(),
+            anki_image_front: false,
+            tectonic_preview: false,
+            preview_cache_mb: 64.0,
+            validate_with_tex: false,
+            ocr_command: "pix2tex {image}".to_string(),
+            recent_files: Vec::new(),
+            raw_markdown: Vec::new(),
+            input_rules: Vec::new(),
+            input_rules_enabled: true,
+            output_rules: Vec::new(),
+            pre_hook: String::new(),
+            post_hook: String::new(),
+            latex_macros: String::new(),
+            theorem_environments: String::new(),
+            language: String::new(),
+            theme: "system".to_string(),
+            accent_color: String::new(),
+            background_color: String::new(),
+            text_color: String::new(),
+            preview_color: String::new(),
+            editor_font: "proportional".to_string(),
+            editor_font_size: 16.0,
+            editor_line_spacing: 1.2,
+            layout: "stacked".to_string(),
+            input_height: 100.0,
+            output_height: 100.0,
+            input_wrap: false,
+            output_wrap: false,
+            preview_checkerboard: false,
+            export_foreground: String::new(),
+            export_background: String::new(),
+            math_font: String::new(),
+            primary_selection: false,
+            vim_mode: false,
+            auto_close_brackets: true,
+            strip_wrappers: true,
+            learning_mode: String::new(),
+            check_updates: true,
+            onboarded: false,
+            offline: false,
+            backend: String::new(),
+            render_backend: String::new(),
+            typst_shorthands: String::new(),
+            typst_symbols: String::new(),
+            typst_spacing: String::new(),
+        }
+    }
+}
+
+impl Settings {
+    fn path() -> Option<PathBuf> {
+        Some(config_dir()?.join("settings.json"))
+    }
+
+    // Load settings from the config dir, falling back to defaults for anything missing.
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| Value::parse(&text).ok())
+            .map(|value| Self::from_json(&value))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("Failed to locate config directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|_| "Failed to create config directory")?;
+        }
+        fs::write(&path, self.to_json().pretty(2)).map_err(|_| "Failed to write settings".into())
+    }
+
+    fn from_json(value: &Value) -> Self {
+        let defaults = Self::default();
+        let string = |key: &str, default: String| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .map(String::from)
+                .unwrap_or(default)
+        };
+        let boolean =
+            |key: &str, default: bool| value.get(key).and_then(Value::as_bool).unwrap_or(default);
+        let number = |key: &str, default: f32| {
+            value
+                .get(key)
+                .and_then(Value::as_f64)
+                .map_or(default, |number| number as f32)
+        };
+        let strings = |key: &str, default: Vec<String>| {
+            value
+                .get(key)
+                .and_then(Value::as_array)
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or(default)
+        };
+        let rules = |key: &str, default: Vec<Rule>| {
+            value
+                .get(key)
+                .and_then(Value::as_array)
+                .map(|rules| rules.iter().filter_map(Rule::from_json).collect())
+                .unwrap_or(default)
+        };
+        Self {
+            anki_url: string("anki_url", defaults.anki_url),
+            anki_deck: string("anki_deck", defaults.anki_deck),
+            anki_model: string("anki_model", defaults.anki_model),
+            numbering: string("numbering", defaults.numbering),
+            numbering_pattern: string("numbering_pattern", defaults.numbering_pattern),
+            delimiters: string("delimiters", defaults.delimiters),
+            units: string("units", defaults.units),
+            decimal_comma: boolean("decimal_comma", defaults.decimal_comma),
+            digit_grouping: boolean("digit_grouping", defaults.digit_grouping),
+            scientific: string("scientific", defaults.scientific),
+            diagrams: string("diagrams", defaults.diagrams),
+            latex_characters: string("latex_characters", defaults.latex_characters),
+            profiles: value
+                .get("profiles")
+                .and_then(Value::as_array)
+                .map(|profiles| profiles.iter().filter_map(Profile::from_json).collect())
+                .unwrap_or(defaults.profiles),
+            profile: string("profile", defaults.profile),
+            anki_image_front: boolean("anki_image_front", defaults.anki_image_front),
+            tectonic_preview: boolean("tectonic_preview", defaults.tectonic_preview),
+            preview_cache_mb: number("preview_cache_mb", defaults.preview_cache_mb),
+            validate_with_tex: boolean("validate_with_tex", defaults.validate_with_tex),
+            ocr_command: string("ocr_command", defaults.ocr_command),
+            recent_files: strings("recent_files", defaults.recent_files),
+            raw_markdown: strings("raw_markdown", defaults.raw_markdown),
+            input_rules: rules("input_rules", defaults.input_rules),
+            input_rules_enabled: boolean("input_rules_enabled", defaults.input_rules_enabled),
+            output_rules: rules("output_rules", defaults.output_rules),
+            pre_hook: string("pre_hook", defaults.pre_hook),
+            post_hook: string("post_hook", defaults.post_hook),
+            latex_macros: string("latex_macros", defaults.latex_macros),
+            theorem_environments: string("theorem_environments", defaults.theorem_environments),
+            language: string("language", defaults.language),
+            theme: string("theme", defaults.theme),
+            accent_color: string("accent_color", defaults.accent_color),
+            background_color: string("background_color", defaults.background_color),
+            text_color: string("text_color", defaults.text_color),
+            preview_color: string("preview_color", defaults.preview_color),
+            editor_font: string("editor_font", defaults.editor_font),
+            editor_font_size: number("editor_font_size", defaults.editor_font_size),
+            editor_line_spacing: number("editor_line_spacing", defaults.editor_line_spacing),
+            layout: string("layout", defaults.layout),
+            input_height: number("input_height", defaults.input_height),
+            output_height: number("output_height", defaults.output_height),
+            input_wrap: boolean("input_wrap", defaults.input_wrap),
+            output_wrap: boolean("output_wrap", defaults.output_wrap),
+            preview_checkerboard: boolean("preview_checkerboard", defaults.preview_checkerboard),
+            export_foreground: string("export_foreground", defaults.export_foreground),
+            export_background: string("export_background", defaults.export_background),
+            math_font: string("math_font", defaults.math_font),
+            primary_selection: boolean("primary_selection", defaults.primary_selection),
+            vim_mode: boolean("vim_mode", defaults.vim_mode),
+            auto_close_brackets: boolean("auto_close_brackets", defaults.auto_close_brackets),
+            strip_wrappers: boolean("strip_wrappers", defaults.strip_wrappers),
+            learning_mode: string("learning_mode", defaults.learning_mode),
+            check_updates: boolean("check_updates", defaults.check_updates),
+            // Settings saved before the walkthrough existed belong to no new user.
+            onboarded: boolean("onboarded", true),
+            offline: boolean("offline", defaults.offline),
+            backend: string("backend", defaults.backend),
+            render_backend: string("render_backend", defaults.render_backend),
+            typst_shorthands: string("typst_shorthands", defaults.typst_shorthands),
+            typst_symbols: string("typst_symbols", defaults.typst_symbols),
+            typst_spacing: string("typst_spacing", defaults.typst_spacing),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        Value::object([
+            ("anki_url", self.anki_url.as_str().into()),
+            ("anki_deck", self.anki_deck.as_str().into()),
+            ("anki_model", self.anki_model.as_str().into()),
+            ("numbering", self.numbering.as_str().into()),
+            ("numbering_pattern", self.numbering_pattern.as_str().into()),
+            ("delimiters", self.delimiters.as_str().into()),
+            ("units", self.units.as_str().into()),
+            ("decimal_comma", self.decimal_comma.into()),
+            ("digit_grouping", self.digit_grouping.into()),
+            ("scientific", self.scientific.as_str().into()),
+            ("diagrams", self.diagrams.as_str().into()),
+            ("latex_characters", self.latex_characters.as_str().into()),
+            (
+                "profiles",
+                Value::Array(self.profiles.iter().map(Profile::to_json).collect()),
+            ),
+            ("profile", self.profile.as_str().into()),
+            ("anki_image_front", self.anki_image_front.into()),
+            ("tectonic_preview", self.tectonic_preview.into()),
+            ("preview_cache_mb", (self.preview_cache_mb as f64).into()),
+            ("validate_with_tex", self.validate_with_tex.into()),
+            ("ocr_command", self.ocr_command.as_str().into()),
+            (
+                "recent_files",
+                Value::Array(
+                    self.recent_files
+                        .iter()
+                        .map(|path| path.as_str().into())
+                        .collect(),
+                ),
+            ),
+            (
+                "raw_markdown",
+                Value::Array(
+                    self.raw_markdown
+                        .iter()
+                        .map(|name| name.as_str().into())
+                        .collect(),
+                ),
+            ),
+            (
+                "input_rules",
+                Value::Array(self.input_rules.iter().map(Rule::to_json).collect()),
+            ),
+            ("input_rules_enabled", self.input_rules_enabled.into()),
+            (
+                "output_rules",
+                Value::Array(self.output_rules.iter().map(Rule::to_json).collect()),
+            ),
+            ("pre_hook", self.pre_hook.as_str().into()),
+            ("post_hook", self.post_hook.as_str().into()),
+            ("latex_macros", self.latex_macros.as_str().into()),
+            (
+                "theorem_environments",
+                self.theorem_environments.as_str().into(),
+            ),
+            ("language", self.language.as_str().into()),
+            ("theme", self.theme.as_str().into()),
+            ("accent_color", self.accent_color.as_str().into()),
+            ("background_color", self.background_color.as_str().into()),
+            ("text_color", self.text_color.as_str().into()),
+            ("preview_color", self.preview_color.as_str().into()),
+            ("editor_font", self.editor_font.as_str().into()),
+            ("editor_font_size", (self.editor_font_size as f64).into()),
+            (
+                "editor_line_spacing",
+                (self.editor_line_spacing as f64).into(),
+            ),
+            ("layout", self.layout.as_str().into()),
+            ("input_height", (self.input_height as f64).into()),
+            ("output_height", (self.output_height as f64).into()),
+            ("input_wrap", self.input_wrap.into()),
+            ("output_wrap", self.output_wrap.into()),
+            ("preview_checkerboard", self.preview_checkerboard.into()),
+            ("export_foreground", self.export_foreground.as_str().into()),
+            ("export_background", self.export_background.as_str().into()),
+            ("math_font", self.math_font.as_str().into()),
+            ("primary_selection", self.primary_selection.into()),
+            ("vim_mode", self.vim_mode.into()),
+            ("auto_close_brackets", self.auto_close_brackets.into()),
+            ("strip_wrappers", self.strip_wrappers.into()),
+            ("learning_mode", self.learning_mode.as_str().into()),
+            ("check_updates", self.check_updates.into()),
+            ("onboarded", self.onboarded.into()),
+            ("offline", self.offline.into()),
+            ("backend", self.backend.as_str().into()),
+            ("render_backend", self.render_backend.as_str().into()),
+            ("typst_shorthands", self.typst_shorthands.as_str().into()),
+            ("typst_symbols", self.typst_symbols.as_str().into()),
+            ("typst_spacing", self.typst_spacing.as_str().into()),
+        ])
+    }
+}
+
+// The state of the editor, restored on the next start so that an accidental close
+// doesn't lose the formula being worked on.
+pub struct Session {
+    pub input: String,
+    pub output: String,
+    pub latex: String,
+    pub direction: Direction,
+    pub target: Target,
+}
+
+impl Session {
+    fn path() -> Option<PathBuf> {
+        Some(config_dir()?.join("session.json"))
+    }
+
+    pub fn load() -> Option<Self> {
+        let text = fs::read_to_string(Self::path()?).ok()?;
+        let value = Value::parse(&text).ok()?;
+        let string = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        Some(Self {
+            input: string("input"),
+            output: string("output"),
+            latex: string("latex"),
+            direction: Direction::from_name(&string("direction"))?,
+            target: Target::from_name(&string("target"))?,
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("Failed to locate config directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|_| "Failed to create config directory")?;
+        }
+        let value = Value::object([
+            ("input", self.input.as_str().into()),
+            ("output", self.output.as_str().into()),
+            ("latex", self.latex.as_str().into()),
+            ("direction", self.direction.name().into()),
+            ("target", self.target.name().into()),
+        ]);
+        fs::write(&path, value.pretty(2)).map_err(|_| "Failed to write session".into())
+    }
+}
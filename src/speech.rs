@@ -0,0 +1,497 @@
+use crate::convert::convert_latex_to_mathml;
+use crate::diagnostics;
+use crate::latex::{self, Token};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// A natural-language reading of LaTeX math. The Speech Rule Engine reads the
+// MathML that pandoc produces when it is installed (`npm install -g
+// speech-rule-engine`); otherwise a simpler built-in reader is used.
+pub fn describe(latex: &str) -> String {
+    match speech_rule_engine(latex) {
+        Ok(speech) => speech,
+        Err(err) => {
+            diagnostics::log(format!("Speech rule engine unavailable: {}", err));
+            read(latex)
+        }
+    }
+}
+
+fn speech_rule_engine(latex: &str) -> Result<String, String> {
+    let mathml = convert_latex_to_mathml(latex)?;
+    let mut child = Command::new("sre")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| "Failed to execute sre")?;
+    child
+        .stdin
+        .take()
+        .ok_or("Failed to open stdin")?
+        .write_all(mathml.as_bytes())
+        .map_err(|_| "Failed to write to stdin")?;
+    let output = child
+        .wait_with_output()
+        .map_err(|_| "Failed to read stdout and stderr")?;
+    let speech = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || speech.is_empty() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(speech)
+}
+
+// The built-in reader, which covers common notation only.
+pub fn read(latex: &str) -> String {
+    let mut out = String::new();
+    speak(&latex::parse(latex), &mut out);
+    out.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(" ,", ",")
+        .replace(" ;", ";")
+        .replace(",,", ",")
+}
+
+fn speak(tokens: &[Token], out: &mut String) {
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Command(name) => {
+                i = speak_command(name, tokens, i + 1, out);
+                continue;
+            }
+            Token::Group(group) => speak(group, out),
+            Token::Char('^') => {
+                let (exponent, next) = latex::argument(tokens, i + 1);
+                match latex::to_string(exponent).trim() {
+                    "2" => out.push_str(" squared "),
+                    "3" => out.push_str(" cubed "),
+                    "\\prime" | "'" => out.push_str(" prime "),
+                    _ => {
+                        out.push_str(" to the power of ");
+                        speak_argument(exponent, "power", out);
+                    }
+                }
+                i = next;
+                continue;
+            }
+            Token::Char('_') => {
+                let (subscript, next) = latex::argument(tokens, i + 1);
+                out.push_str(" sub ");
+                speak_argument(subscript, "subscript", out);
+                i = next;
+                continue;
+            }
+            // Keep the digits of a number together, e.g. `3.14`.
+            Token::Char(c) if c.is_ascii_digit() => {
+                if !out.ends_with(|c: char| c.is_ascii_digit() || c == '.') {
+                    out.push(' ');
+                }
+                out.push(*c);
+            }
+            Token::Char('.')
+                if out.ends_with(|c: char| c.is_ascii_digit())
+                    && matches!(tokens.get(i + 1), Some(Token::Char(c)) if c.is_ascii_digit()) =>
+            {
+                out.push('.')
+            }
+            Token::Char(c) => out.push_str(&format!(" {} ", char_word(*c))),
+            Token::Space => {}
+        }
+        i += 1;
+    }
+}
+
+// Speak an argument, closing it off when it is longer than a single symbol so that
+// what follows isn't read as part of it.
+fn speak_argument(argument: &[Token], name: &str, out: &mut String) {
+    speak(argument, out);
+    let symbols = argument.iter().filter(|t| **t != Token::Space).count();
+    if symbols > 1 {
+        out.push_str(&format!(" end {}, ", name));
+    }
+}
+
+fn char_word(c: char) -> String {
+    match c {
+        '+' => "plus",
+        '-' => "minus",
+        '=' => "equals",
+        '<' => "is less than",
+        '>' => "is greater than",
+        '(' => "open paren",
+        ')' => "close paren",
+        '[' => "open bracket",
+        ']' => "close bracket",
+        '|' => "vertical bar",
+        '/' => "divided by",
+        '!' => "factorial",
+        ',' => ",",
+        '\'' => "prime",
+        '&' | '~' => "",
+        c => return c.to_string(),
+    }
+    .to_string()
+}
+
+fn symbol_word(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "alpha",
+        "beta" => "beta",
+        "gamma" => "gamma",
+        "delta" => "delta",
+        "epsilon" | "varepsilon" => "epsilon",
+        "zeta" => "zeta",
+        "eta" => "eta",
+        "theta" | "vartheta" => "theta",
+        "iota" => "iota",
+        "kappa" => "kappa",
+        "lambda" => "lambda",
+        "mu" => "mu",
+        "nu" => "nu",
+        "xi" => "xi",
+        "pi" | "varpi" => "pi",
+        "rho" | "varrho" => "rho",
+        "sigma" | "varsigma" => "sigma",
+        "tau" => "tau",
+        "upsilon" => "upsilon",
+        "phi" | "varphi" => "phi",
+        "chi" => "chi",
+        "psi" => "psi",
+        "omega" => "omega",
+        "Gamma" => "capital gamma",
+        "Delta" => "capital delta",
+        "Theta" => "capital theta",
+        "Lambda" => "capital lambda",
+        "Xi" => "capital xi",
+        "Pi" => "capital pi",
+        "Sigma" => "capital sigma",
+        "Upsilon" => "capital upsilon",
+        "Phi" => "capital phi",
+        "Psi" => "capital psi",
+        "Omega" => "capital omega",
+        "cdot" | "times" => "times",
+        "div" => "divided by",
+        "pm" => "plus or minus",
+        "mp" => "minus or plus",
+        "leq" | "le" => "is less than or equal to",
+        "geq" | "ge" => "is greater than or equal to",
+        "neq" | "ne" => "is not equal to",
+        "ll" => "is much less than",
+        "gg" => "is much greater than",
+        "approx" => "is approximately equal to",
+        "sim" => "is similar to",
+        "simeq" => "is asymptotically equal to",
+        "equiv" => "is equivalent to",
+        "propto" => "is proportional to",
+        "in" => "is an element of",
+        "notin" => "is not an element of",
+        "subset" => "is a subset of",
+        "subseteq" => "is a subset of or equal to",
+        "supset" => "is a superset of",
+        "supseteq" => "is a superset of or equal to",
+        "cup" => "union",
+        "cap" => "intersection",
+        "setminus" => "minus",
+        "emptyset" | "varnothing" => "the empty set",
+        "forall" => "for all",
+        "exists" => "there exists",
+        "neg" | "lnot" => "not",
+        "land" | "wedge" => "and",
+        "lor" | "vee" => "or",
+        "to" | "rightarrow" => "goes to",
+        "mapsto" => "maps to",
+        "Rightarrow" | "implies" => "implies",
+        "Leftarrow" => "is implied by",
+        "Leftrightarrow" | "iff" => "if and only if",
+        "infty" => "infinity",
+        "partial" => "partial",
+        "nabla" => "nabla",
+        "hbar" => "h bar",
+        "ell" => "ell",
+        "ldots" | "cdots" | "dots" | "vdots" | "ddots" => "dot dot dot",
+        "circ" => "composed with",
+        "perp" => "is perpendicular to",
+        "parallel" => "is parallel to",
+        "mid" => "divides",
+        "prime" => "prime",
+        "langle" => "open angle bracket",
+        "rangle" => "close angle bracket",
+        "lfloor" => "floor of",
+        "lceil" => "ceiling of",
+        "rfloor" | "rceil" => "end",
+        "{" => "open brace",
+        "}" => "close brace",
+        "|" => "double vertical bar",
+        "sin" => "sine",
+        "cos" => "cosine",
+        "tan" => "tangent",
+        "cot" => "cotangent",
+        "sec" => "secant",
+        "csc" => "cosecant",
+        "sinh" => "hyperbolic sine",
+        "cosh" => "hyperbolic cosine",
+        "tanh" => "hyperbolic tangent",
+        "arcsin" => "arc sine",
+        "arccos" => "arc cosine",
+        "arctan" => "arc tangent",
+        "log" => "log",
+        "ln" => "natural log",
+        "exp" => "exp",
+        "det" => "determinant of",
+        "gcd" => "greatest common divisor of",
+        "," | ":" | ";" | " " | "!" | "quad" | "qquad" => "",
+        _ => return None,
+    })
+}
+
+// Large operators, which read their limits as "from ... to ... of".
+fn big_operator(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "sum" => "the sum",
+        "prod" => "the product",
+        "coprod" => "the coproduct",
+        "int" => "the integral",
+        "iint" => "the double integral",
+        "iiint" => "the triple integral",
+        "oint" => "the contour integral",
+        "bigcup" => "the union",
+        "bigcap" => "the intersection",
+        "lim" => "the limit",
+        "liminf" => "the limit inferior",
+        "limsup" => "the limit superior",
+        "max" => "the maximum",
+        "min" => "the minimum",
+        "sup" => "the supremum",
+        "inf" => "the infimum",
+        _ => return None,
+    })
+}
+
+fn speak_command(name: &str, tokens: &[Token], mut i: usize, out: &mut String) -> usize {
+    if let Some(operator) = big_operator(name) {
+        out.push_str(&format!(" {} ", operator));
+        loop {
+            let j = latex::skip_spaces(tokens, i);
+            match tokens.get(j) {
+                Some(Token::Char('_')) => {
+                    let (lower, next) = latex::argument(tokens, j + 1);
+                    out.push_str(if name.starts_with("lim") {
+                        " as "
+                    } else {
+                        " from "
+                    });
+                    speak(lower, out);
+                    i = next;
+                }
+                Some(Token::Char('^')) => {
+                    let (upper, next) = latex::argument(tokens, j + 1);
+                    out.push_str(" to ");
+                    speak(upper, out);
+                    i = next;
+                }
+                Some(Token::Command(c)) if c == "limits" || c == "nolimits" => i = j + 1,
+                _ => break,
+            }
+        }
+        out.push_str(" of ");
+        return i;
+    }
+    if let Some(word) = symbol_word(name) {
+        out.push_str(&format!(" {} ", word));
+        return i;
+    }
+    match name {
+        "frac" | "dfrac" | "tfrac" | "cfrac" => {
+            let (numerator, i) = latex::argument(tokens, i);
+            let (denominator, i) = latex::argument(tokens, i);
+            let simple =
+                |tokens: &[Token]| tokens.iter().filter(|t| **t != Token::Space).count() <= 1;
+            if simple(numerator) && simple(denominator) {
+                speak(numerator, out);
+                out.push_str(" over ");
+                speak(denominator, out);
+            } else {
+                out.push_str(" the fraction with numerator ");
+                speak(numerator, out);
+                out.push_str(" and denominator ");
+                speak(denominator, out);
+                out.push_str(" end fraction, ");
+            }
+            i
+        }
+        "binom" => {
+            let (n, i) = latex::argument(tokens, i);
+            let (k, i) = latex::argument(tokens, i);
+            speak(n, out);
+            out.push_str(" choose ");
+            speak(k, out);
+            i
+        }
+        "sqrt" => {
+            let (index, i) = latex::optional_argument(tokens, i);
+            let (radicand, i) = latex::argument(tokens, i);
+            match index.map(|index| latex::to_string(index).trim().to_string()) {
+                None => out.push_str(" the square root of "),
+                Some(index) if index == "3" => out.push_str(" the cube root of "),
+                Some(index) => out.push_str(&format!(" the {}-th root of ", index)),
+            }
+            speak_argument(radicand, "root", out);
+            i
+        }
+        "text" | "textrm" | "mbox" | "operatorname" => {
+            let (text, i) = latex::argument(tokens, i);
+            out.push_str(&format!(" {} ", latex::to_string(text)));
+            i
+        }
+        "mathrm" | "mathup" | "mathit" | "mathbf" | "boldsymbol" | "bm" | "mathsf" | "mathtt"
+        | "displaystyle" | "textstyle" => {
+            if name.ends_with("style") {
+                return i;
+            }
+            let (argument, i) = latex::argument(tokens, i);
+            speak(argument, out);
+            i
+        }
+        "mathbb" | "mathcal" | "mathfrak" => {
+            let (letter, i) = latex::argument(tokens, i);
+            let letter = latex::to_string(letter);
+            let word = match (name, letter.trim()) {
+                ("mathbb", "N") => "the natural numbers".to_string(),
+                ("mathbb", "Z") => "the integers".to_string(),
+                ("mathbb", "Q") => "the rationals".to_string(),
+                ("mathbb", "R") => "the reals".to_string(),
+                ("mathbb", "C") => "the complex numbers".to_string(),
+                ("mathbb", letter) => format!("double-struck {}", letter),
+                ("mathcal", letter) => format!("script {}", letter),
+                (_, letter) => format!("fraktur {}", letter),
+            };
+            out.push_str(&format!(" {} ", word));
+            i
+        }
+        "hat" | "widehat" | "bar" | "overline" | "tilde" | "widetilde" | "dot" | "ddot"
+        | "underline" => {
+            let (argument, i) = latex::argument(tokens, i);
+            speak(argument, out);
+            let accent = match name {
+                "hat" | "widehat" => "hat",
+                "bar" | "overline" => "bar",
+                "tilde" | "widetilde" => "tilde",
+                "dot" => "dot",
+                "ddot" => "double dot",
+                _ => "underlined",
+            };
+            out.push_str(&format!(" {} ", accent));
+            i
+        }
+        "vec" => {
+            out.push_str(" vector ");
+            let (argument, i) = latex::argument(tokens, i);
+            speak(argument, out);
+            i
+        }
+        // Delimiters are read by the character or command that follows.
+        "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl" | "Bigr" => {
+            let j = latex::skip_spaces(tokens, i);
+            if tokens.get(j) == Some(&Token::Char('.')) {
+                return j + 1;
+            }
+            i
+        }
+        "begin" => {
+            let (environment, body, after) = latex::environment(tokens, i);
+            let rows = latex::split_rows(body);
+            if environment.ends_with("matrix") {
+                let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+                out.push_str(&format!(" the {} by {} matrix: ", rows.len(), columns));
+            } else if environment == "cases" {
+                out.push_str(" cases: ");
+            }
+            for (n, row) in rows.iter().enumerate() {
+                if rows.len() > 1 {
+                    out.push_str(&format!(" row {}: ", n + 1));
+                }
+                for (k, cell) in row.iter().enumerate() {
+                    if k > 0 {
+                        out.push_str(if environment == "cases" { " if " } else { ", " });
+                    }
+                    speak(cell, out);
+                }
+                out.push(';');
+            }
+            out.push_str(" end ");
+            out.push_str(environment.trim_end_matches('*'));
+            out.push_str(", ");
+            after
+        }
+        "\\" => {
+            out.push_str("; ");
+            i
+        }
+        name => {
+            out.push_str(&format!(" {} ", name));
+            i
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fractions() {
+        assert_eq!(read(r"\frac{1}{2}"), "1 over 2");
+        assert_eq!(
+            read(r"\frac{a+b}{c}"),
+            "the fraction with numerator a plus b and denominator c end fraction,"
+        );
+        assert_eq!(
+            read(r"\frac{\frac{1}{x}}{y}"),
+            "the fraction with numerator 1 over x and denominator y end fraction,"
+        );
+    }
+
+    #[test]
+    fn scripts() {
+        assert_eq!(read("x^2"), "x squared");
+        assert_eq!(read("x^3"), "x cubed");
+        assert_eq!(read(r"f' + x^{\prime}"), "f prime plus x prime");
+        // Longer scripts are closed off so that what follows isn't read into them.
+        assert_eq!(
+            read("x^{n+1} + 1"),
+            "x to the power of n plus 1 end power, plus 1"
+        );
+        assert_eq!(read("x_i"), "x sub i");
+        assert_eq!(read("x_{i+1}"), "x sub i plus 1 end subscript,");
+    }
+
+    #[test]
+    fn big_operators() {
+        assert_eq!(
+            read(r"\sum_{i=1}^{n} i^2"),
+            "the sum from i equals 1 to n of i squared"
+        );
+        assert_eq!(
+            read(r"\lim_{x \to 0} f(x)"),
+            "the limit as x goes to 0 of f open paren x close paren"
+        );
+        assert_eq!(
+            read(r"\int_0^1 x \, dx"),
+            "the integral from 0 to 1 of x d x"
+        );
+        assert_eq!(
+            read(r"\prod\limits_{k} a_k"),
+            "the product from k of a sub k"
+        );
+    }
+
+    #[test]
+    fn roots_numbers_and_sets() {
+        assert_eq!(read(r"\sqrt{x}"), "the square root of x");
+        assert_eq!(read(r"\sqrt[3]{x}"), "the cube root of x");
+        assert_eq!(read("3.14"), "3.14");
+        assert_eq!(read(r"\mathbb{R}"), "the reals");
+    }
+}
@@ -0,0 +1,400 @@
+use crate::latex::{self, Token};
+
+// Translate LaTeX math into LibreOffice Math (StarMath) syntax.
+pub fn from_latex(latex: &str) -> String {
+    let mut out = String::new();
+    emit(&latex::parse(latex), &mut out);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn emit(tokens: &[Token], out: &mut String) {
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            Token::Command(name) => {
+                i = emit_command(name, tokens, i + 1, out);
+                continue;
+            }
+            Token::Group(group) => emit_group(group, out),
+            Token::Char('^') => out.push_str(" ^ "),
+            Token::Char('_') => out.push_str(" _ "),
+            Token::Char('&') => out.push(' '),
+            Token::Char('\'') => out.push_str(" ' "),
+            Token::Char(c) if "<>=+-".contains(*c) => {
+                out.push(' ');
+                out.push(*c);
+                out.push(' ');
+            }
+            Token::Char(c) => out.push(*c),
+            Token::Space => out.push(' '),
+        }
+        i += 1;
+    }
+}
+
+fn emit_group(tokens: &[Token], out: &mut String) {
+    out.push_str(" { ");
+    emit(tokens, out);
+    out.push_str(" } ");
+}
+
+// Emit the argument starting at `i` as a braced group and return the index after it.
+fn emit_argument(tokens: &[Token], i: usize, out: &mut String) -> usize {
+    let (argument, next) = latex::argument(tokens, i);
+    emit_group(argument, out);
+    next
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "%alpha",
+        "beta" => "%beta",
+        "gamma" => "%gamma",
+        "delta" => "%delta",
+        "epsilon" => "%epsilon",
+        "varepsilon" => "%varepsilon",
+        "zeta" => "%zeta",
+        "eta" => "%eta",
+        "theta" => "%theta",
+        "vartheta" => "%vartheta",
+        "iota" => "%iota",
+        "kappa" => "%kappa",
+        "lambda" => "%lambda",
+        "mu" => "%mu",
+        "nu" => "%nu",
+        "xi" => "%xi",
+        "pi" => "%pi",
+        "varpi" => "%varpi",
+        "rho" => "%rho",
+        "varrho" => "%varrho",
+        "sigma" => "%sigma",
+        "varsigma" => "%varsigma",
+        "tau" => "%tau",
+        "upsilon" => "%upsilon",
+        "phi" => "%phi",
+        "varphi" => "%varphi",
+        "chi" => "%chi",
+        "psi" => "%psi",
+        "omega" => "%omega",
+        "Gamma" => "%GAMMA",
+        "Delta" => "%DELTA",
+        "Theta" => "%THETA",
+        "Lambda" => "%LAMBDA",
+        "Xi" => "%XI",
+        "Pi" => "%PI",
+        "Sigma" => "%SIGMA",
+        "Upsilon" => "%UPSILON",
+        "Phi" => "%PHI",
+        "Psi" => "%PSI",
+        "Omega" => "%OMEGA",
+        "cdot" => " cdot ",
+        "times" => " times ",
+        "div" => " div ",
+        "pm" => " +- ",
+        "mp" => " -+ ",
+        "leq" | "le" => " <= ",
+        "geq" | "ge" => " >= ",
+        "neq" | "ne" => " <> ",
+        "ll" => " << ",
+        "gg" => " >> ",
+        "approx" => " approx ",
+        "sim" => " sim ",
+        "simeq" => " simeq ",
+        "equiv" => " equiv ",
+        "propto" => " prop ",
+        "in" => " in ",
+        "notin" => " notin ",
+        "ni" => " owns ",
+        "subset" => " subset ",
+        "subseteq" => " subseteq ",
+        "supset" => " supset ",
+        "supseteq" => " supseteq ",
+        "cup" => " union ",
+        "cap" => " intersection ",
+        "setminus" => " setminus ",
+        "emptyset" | "varnothing" => " emptyset ",
+        "forall" => " forall ",
+        "exists" => " exists ",
+        "neg" | "lnot" => " neg ",
+        "land" | "wedge" => " and ",
+        "lor" | "vee" => " or ",
+        "to" | "rightarrow" => " toward ",
+        "Rightarrow" | "implies" => " drarrow ",
+        "Leftarrow" => " dlarrow ",
+        "Leftrightarrow" | "iff" => " dlrarrow ",
+        "infty" => " infinity ",
+        "partial" => " partial ",
+        "nabla" => " nabla ",
+        "hbar" => " hbar ",
+        "ell" => " ell ",
+        "aleph" => " aleph ",
+        "Re" => " Re ",
+        "Im" => " Im ",
+        "ldots" | "dots" => " dotslow ",
+        "cdots" => " dotsaxis ",
+        "vdots" => " dotsvert ",
+        "ddots" => " dotsdown ",
+        "circ" => " circ ",
+        "perp" => " ortho ",
+        "parallel" => " parallel ",
+        "mid" => " divides ",
+        "langle" => " langle ",
+        "rangle" => " rangle ",
+        "lfloor" => " lfloor ",
+        "rfloor" => " rfloor ",
+        "lceil" => " lceil ",
+        "rceil" => " rceil ",
+        "{" => " lbrace ",
+        "}" => " rbrace ",
+        "|" => " parallel ",
+        "," | ":" | ";" => " ` ",
+        "quad" | "qquad" => " ~ ",
+        " " | "!" => " ",
+        "%" => "\"%\"",
+        "#" => "\"#\"",
+        "$" => "\"$\"",
+        _ => return None,
+    })
+}
+
+// Whether the LaTeX command `name` has a StarMath counterpart.
+pub fn supports(name: &str) -> bool {
+    symbol(name).is_some() || big_operator(name).is_some()
+}
+
+// Functions that StarMath sets upright by name, as LaTeX does.
+const FUNCTIONS: [&str; 22] = [
+    "sin", "cos", "tan", "cot", "sec", "csc", "sinh", "cosh", "tanh", "coth", "arcsin", "arccos",
+    "arctan", "arccot", "exp", "ln", "log", "lg", "det", "dim", "ker", "arg",
+];
+
+// Large operators and the StarMath keyword they map to.
+fn big_operator(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "sum" => "sum",
+        "prod" => "prod",
+        "coprod" => "coprod",
+        "int" => "int",
+        "iint" => "iint",
+        "iiint" => "iiint",
+        "oint" => "lint",
+        "bigcup" => "Union",
+        "bigcap" => "Intersection",
+        "lim" => "lim",
+        "liminf" => "liminf",
+        "limsup" => "limsup",
+        "max" => "max",
+        "min" => "min",
+        "sup" => "sup",
+        "inf" => "inf",
+        _ => return None,
+    })
+}
+
+fn emit_command(name: &str, tokens: &[Token], mut i: usize, out: &mut String) -> usize {
+    if let Some(op) = big_operator(name) {
+        out.push_str(&format!(" {} ", op));
+        // Turn attached limits into `from {...} to {...}`.
+        loop {
+            let j = latex::skip_spaces(tokens, i);
+            match tokens.get(j) {
+                Some(Token::Char('_')) => {
+                    out.push_str(" from ");
+                    i = emit_argument(tokens, j + 1, out);
+                }
+                Some(Token::Char('^')) => {
+                    out.push_str(" to ");
+                    i = emit_argument(tokens, j + 1, out);
+                }
+                Some(Token::Command(c)) if c == "limits" || c == "nolimits" => i = j + 1,
+                _ => return i,
+            }
+        }
+    }
+    if let Some(symbol) = symbol(name) {
+        out.push_str(symbol);
+        return i;
+    }
+    match name {
+        "frac" | "dfrac" | "tfrac" | "cfrac" => {
+            i = emit_argument(tokens, i, out);
+            out.push_str(" over ");
+            emit_argument(tokens, i, out)
+        }
+        "binom" => {
+            out.push_str(" binom ");
+            i = emit_argument(tokens, i, out);
+            emit_argument(tokens, i, out)
+        }
+        "sqrt" => match latex::optional_argument(tokens, i) {
+            (Some(index), i) => {
+                out.push_str(" nroot ");
+                emit_group(index, out);
+                emit_argument(tokens, i, out)
+            }
+            (None, i) => {
+                out.push_str(" sqrt ");
+                emit_argument(tokens, i, out)
+            }
+        },
+        "text" | "textrm" | "mbox" | "operatorname" => {
+            let (text, i) = latex::argument(tokens, i);
+            if name == "operatorname" {
+                out.push_str(&format!(" func {} ", latex::to_string(text)));
+            } else {
+                out.push_str(&format!(
+                    " \"{}\" ",
+                    latex::to_string(text).replace('"', "")
+                ));
+            }
+            i
+        }
+        "mathrm" | "mathup" => {
+            out.push_str(" nitalic ");
+            emit_argument(tokens, i, out)
+        }
+        "mathbf" | "boldsymbol" | "bm" => {
+            out.push_str(" bold ");
+            emit_argument(tokens, i, out)
+        }
+        "mathit" => {
+            out.push_str(" ital ");
+            emit_argument(tokens, i, out)
+        }
+        "mathbb" => {
+            let (letter, i) = latex::argument(tokens, i);
+            match latex::to_string(letter).trim() {
+                set @ ("N" | "Z" | "Q" | "R" | "C") => out.push_str(&format!(" set{} ", set)),
+                other => out.push_str(&format!(" {} ", other)),
+            }
+            i
+        }
+        "hat" | "widehat" | "bar" | "overline" | "vec" | "tilde" | "widetilde" | "dot" | "ddot"
+        | "underline" => {
+            out.push_str(&format!(" {} ", name));
+            emit_argument(tokens, i, out)
+        }
+        "left" | "right" => {
+            let j = latex::skip_spaces(tokens, i);
+            let left = name == "left";
+            // Bars are `lline`/`rline` and `ldline`/`rdline` as delimiters.
+            let delimiter = match tokens.get(j) {
+                Some(Token::Char('.')) => "none".to_string(),
+                Some(Token::Char('|')) => (if left { "lline" } else { "rline" }).to_string(),
+                Some(Token::Command(c)) if matches!(c.as_str(), "vert" | "lvert" | "rvert") => {
+                    (if left { "lline" } else { "rline" }).to_string()
+                }
+                Some(Token::Command(c))
+                    if matches!(c.as_str(), "|" | "Vert" | "lVert" | "rVert") =>
+                {
+                    (if left { "ldline" } else { "rdline" }).to_string()
+                }
+                Some(Token::Char(c)) => c.to_string(),
+                Some(Token::Command(c)) => symbol(c).unwrap_or(c).trim().to_string(),
+                _ => return j,
+            };
+            out.push_str(&format!(" {} {} ", name, delimiter));
+            j + 1
+        }
+        "begin" => {
+            let (environment, body, after) = latex::environment(tokens, i);
+            let (open, close) = match environment.as_str() {
+                "pmatrix" => ("left(", "right)"),
+                "bmatrix" => ("left[", "right]"),
+                "Bmatrix" => ("left lbrace", "right rbrace"),
+                "vmatrix" => ("left lline", "right rline"),
+                "Vmatrix" => ("left ldline", "right rdline"),
+                "cases" => ("left lbrace", "right none"),
+                _ => ("", ""),
+            };
+            let keyword = match environment.as_str() {
+                "aligned" | "align" | "align*" | "gathered" | "gather" | "gather*" => "stack",
+                _ => "matrix",
+            };
+            let rows: Vec<String> = latex::split_rows(body)
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|cell| {
+                            let mut cell_out = String::new();
+                            emit(cell, &mut cell_out);
+                            cell_out
+                        })
+                        .collect::<Vec<_>>()
+                        .join(if keyword == "matrix" { " # " } else { " " })
+                })
+                .collect();
+            let separator = if keyword == "matrix" { " ## " } else { " # " };
+            out.push_str(&format!(
+                " {} {}{{ {} }} {} ",
+                open,
+                keyword,
+                rows.join(separator),
+                close
+            ));
+            after
+        }
+        "\\" => {
+            out.push_str(" newline ");
+            i
+        }
+        // Functions such as `\sin` keep their name.
+        name if FUNCTIONS.contains(&name) => {
+            out.push_str(&format!(" {} ", name));
+            i
+        }
+        // Unknown commands, e.g. `\mathcal{A}`, leave their argument behind, and
+        // those without one their name.
+        name => match tokens.get(latex::skip_spaces(tokens, i)) {
+            Some(Token::Group(_)) => emit_argument(tokens, i, out),
+            _ => {
+                out.push_str(&format!(" {} ", name));
+                i
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_latex;
+
+    #[test]
+    fn escaped_characters_keep_their_own_text() {
+        assert_eq!(from_latex(r"\# \$ 50\%"), r##""#" "$" 50"%""##);
+    }
+
+    #[test]
+    fn bars_after_left_and_right_are_line_delimiters() {
+        assert_eq!(from_latex(r"\left| x \right|"), "left lline x right rline");
+        assert_eq!(
+            from_latex(r"\left\| x \right\|"),
+            "left ldline x right rdline"
+        );
+        assert_eq!(
+            from_latex(r"\left\Vert x \right\Vert"),
+            "left ldline x right rdline"
+        );
+    }
+
+    #[test]
+    fn other_delimiters_after_left_and_right() {
+        assert_eq!(from_latex(r"\left( x \right."), "left ( x right none");
+        assert_eq!(
+            from_latex(r"\left\langle x \right\rangle"),
+            "left langle x right rangle"
+        );
+    }
+
+    #[test]
+    fn unknown_commands_keep_their_argument() {
+        assert_eq!(from_latex(r"\mathcal{A}"), "{ A }");
+        assert_eq!(from_latex(r"\star"), "star");
+    }
+
+    #[test]
+    fn functions_keep_their_name() {
+        assert_eq!(from_latex(r"\sin x"), "sin x");
+        assert_eq!(from_latex(r"\log{x}"), "log { x }");
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::BTreeSet;
+
+// Counts shown under the output, for pasting into places with length limits.
+pub struct Stats {
+    pub commands: usize,
+    // The distinct commands, listed on hover.
+    pub command_names: BTreeSet<String>,
+    // Deepest nesting of `{}` groups and `\left`…`\right` pairs.
+    pub depth: usize,
+}
+
+pub fn latex(text: &str) -> Stats {
+    let mut stats = Stats {
+        commands: 0,
+        command_names: BTreeSet::new(),
+        depth: 0,
+    };
+    let mut depth = 0usize;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let mut name = String::new();
+                while let Some(next) = chars.peek().copied().filter(char::is_ascii_alphabetic) {
+                    name.push(next);
+                    chars.next();
+                }
+                // `\{` and friends are escaped symbols, not commands.
+                if name.is_empty() {
+                    chars.next();
+                    continue;
+                }
+                match name.as_str() {
+                    "left" | "begin" => depth += 1,
+                    "right" | "end" => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                stats.commands += 1;
+                stats.command_names.insert(format!("\\{}", name));
+            }
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        stats.depth = stats.depth.max(depth);
+    }
+    stats
+}
+
+// The width of a rendered formula in em, from the size of its SVG at the font size
+// usvg resolves `ex` units with.
+pub fn width_em(svg: &str) -> Option<f32> {
+    let options = usvg::Options::default();
+    let tree = usvg::Tree::from_str(svg, &options).ok()?;
+    Some(tree.size().width() / options.font_size)
+}
@@ -0,0 +1,234 @@
+use crate::i18n;
+use crate::starmath;
+use eframe::egui;
+
+pub struct Symbol {
+    pub typst: &'static str,
+    // Without the backslash.
+    pub latex: &'static str,
+    pub glyph: &'static str,
+}
+
+const fn symbol(typst: &'static str, latex: &'static str, glyph: &'static str) -> Symbol {
+    Symbol {
+        typst,
+        latex,
+        glyph,
+    }
+}
+
+// Typst symbol names and the LaTeX commands pandoc turns them into. Behind the
+// symbol lookup and the pairing of tokens between the panes.
+pub const SYMBOLS: &[Symbol] = &[
+    symbol("alpha", "alpha", "α"),
+    symbol("beta", "beta", "β"),
+    symbol("gamma", "gamma", "γ"),
+    symbol("delta", "delta", "δ"),
+    // Typst's plain epsilon and phi are the shapes LaTeX calls `var`.
+    symbol("epsilon", "varepsilon", "ε"),
+    symbol("epsilon.alt", "epsilon", "ϵ"),
+    symbol("zeta", "zeta", "ζ"),
+    symbol("eta", "eta", "η"),
+    symbol("theta", "theta", "θ"),
+    symbol("theta.alt", "vartheta", "ϑ"),
+    symbol("iota", "iota", "ι"),
+    symbol("kappa", "kappa", "κ"),
+    symbol("lambda", "lambda", "λ"),
+    symbol("mu", "mu", "μ"),
+    symbol("nu", "nu", "ν"),
+    symbol("xi", "xi", "ξ"),
+    symbol("pi", "pi", "π"),
+    symbol("pi.alt", "varpi", "ϖ"),
+    symbol("rho", "rho", "ρ"),
+    symbol("rho.alt", "varrho", "ϱ"),
+    symbol("sigma", "sigma", "σ"),
+    symbol("sigma.alt", "varsigma", "ς"),
+    symbol("tau", "tau", "τ"),
+    symbol("upsilon", "upsilon", "υ"),
+    symbol("phi", "varphi", "φ"),
+    symbol("phi.alt", "phi", "ϕ"),
+    symbol("chi", "chi", "χ"),
+    symbol("psi", "psi", "ψ"),
+    symbol("omega", "omega", "ω"),
+    symbol("Gamma", "Gamma", "Γ"),
+    symbol("Delta", "Delta", "Δ"),
+    symbol("Theta", "Theta", "Θ"),
+    symbol("Lambda", "Lambda", "Λ"),
+    symbol("Xi", "Xi", "Ξ"),
+    symbol("Pi", "Pi", "Π"),
+    symbol("Sigma", "Sigma", "Σ"),
+    symbol("Upsilon", "Upsilon", "Υ"),
+    symbol("Phi", "Phi", "Φ"),
+    symbol("Psi", "Psi", "Ψ"),
+    symbol("Omega", "Omega", "Ω"),
+    symbol("plus.minus", "pm", "±"),
+    symbol("minus.plus", "mp", "∓"),
+    symbol("times", "times", "×"),
+    symbol("div", "div", "÷"),
+    symbol("dot.op", "cdot", "⋅"),
+    symbol("ast", "ast", "∗"),
+    symbol("star", "star", "⋆"),
+    symbol("compose", "circ", "∘"),
+    symbol("plus.circle", "oplus", "⊕"),
+    symbol("times.circle", "otimes", "⊗"),
+    symbol("eq.not", "neq", "≠"),
+    symbol("lt.eq", "leq", "≤"),
+    symbol("gt.eq", "geq", "≥"),
+    symbol("lt.double", "ll", "≪"),
+    symbol("gt.double", "gg", "≫"),
+    symbol("approx", "approx", "≈"),
+    symbol("tilde.op", "sim", "∼"),
+    symbol("tilde.eq", "simeq", "≃"),
+    symbol("equiv", "equiv", "≡"),
+    symbol("prop", "propto", "∝"),
+    symbol("prec", "prec", "≺"),
+    symbol("succ", "succ", "≻"),
+    symbol("in", "in", "∈"),
+    symbol("in.not", "notin", "∉"),
+    symbol("in.rev", "ni", "∋"),
+    symbol("subset", "subset", "⊂"),
+    symbol("subset.eq", "subseteq", "⊆"),
+    symbol("supset", "supset", "⊃"),
+    symbol("supset.eq", "supseteq", "⊇"),
+    symbol("union", "cup", "∪"),
+    symbol("sect", "cap", "∩"),
+    symbol("union.big", "bigcup", "⋃"),
+    symbol("sect.big", "bigcap", "⋂"),
+    symbol("without", "setminus", "∖"),
+    symbol("emptyset", "emptyset", "∅"),
+    symbol("forall", "forall", "∀"),
+    symbol("exists", "exists", "∃"),
+    symbol("exists.not", "nexists", "∄"),
+    symbol("not", "neg", "¬"),
+    symbol("and", "land", "∧"),
+    symbol("or", "lor", "∨"),
+    symbol("therefore", "therefore", "∴"),
+    symbol("because", "because", "∵"),
+    symbol("top", "top", "⊤"),
+    symbol("bot", "bot", "⊥"),
+    symbol("arrow.r", "rightarrow", "→"),
+    symbol("arrow.l", "leftarrow", "←"),
+    symbol("arrow.l.r", "leftrightarrow", "↔"),
+    symbol("arrow.t", "uparrow", "↑"),
+    symbol("arrow.b", "downarrow", "↓"),
+    symbol("arrow.r.double", "Rightarrow", "⇒"),
+    symbol("arrow.l.double", "Leftarrow", "⇐"),
+    symbol("arrow.l.r.double", "Leftrightarrow", "⇔"),
+    symbol("arrow.r.long", "longrightarrow", "⟶"),
+    symbol("arrow.r.long.double", "Longrightarrow", "⟹"),
+    symbol("arrow.r.bar", "mapsto", "↦"),
+    symbol("arrow.r.hook", "hookrightarrow", "↪"),
+    symbol("sum", "sum", "∑"),
+    symbol("product", "prod", "∏"),
+    symbol("product.co", "coprod", "∐"),
+    symbol("integral", "int", "∫"),
+    symbol("integral.double", "iint", "∬"),
+    symbol("integral.triple", "iiint", "∭"),
+    symbol("integral.cont", "oint", "∮"),
+    symbol("infinity", "infty", "∞"),
+    symbol("partial", "partial", "∂"),
+    symbol("nabla", "nabla", "∇"),
+    symbol("planck.reduce", "hbar", "ℏ"),
+    symbol("ell", "ell", "ℓ"),
+    symbol("aleph", "aleph", "ℵ"),
+    symbol("Re", "Re", "ℜ"),
+    symbol("Im", "Im", "ℑ"),
+    symbol("dots.h", "ldots", "…"),
+    symbol("dots.c", "cdots", "⋯"),
+    symbol("dots.v", "vdots", "⋮"),
+    symbol("dots.down", "ddots", "⋱"),
+    symbol("angle.l", "langle", "⟨"),
+    symbol("angle.r", "rangle", "⟩"),
+    symbol("perp", "perp", "⟂"),
+    symbol("parallel", "parallel", "∥"),
+    symbol("divides", "mid", "∣"),
+    symbol("prime", "prime", "′"),
+    symbol("angle", "angle", "∠"),
+    symbol("triangle", "triangle", "△"),
+];
+
+// Symbols matching a LaTeX command, Typst name or glyph, best matches first.
+pub fn search(query: &str) -> Vec<&'static Symbol> {
+    let query = query.trim().trim_start_matches('\\');
+    let rank = |symbol: &Symbol| {
+        let names = [symbol.typst, symbol.latex, symbol.glyph];
+        if query.is_empty() || names.contains(&query) {
+            Some(0)
+        } else if names.iter().any(|name| name.starts_with(query)) {
+            Some(1)
+        } else if names
+            .iter()
+            .any(|name| name.to_lowercase().contains(&query.to_lowercase()))
+        {
+            Some(2)
+        } else {
+            None
+        }
+    };
+    let mut matches: Vec<(usize, &Symbol)> = SYMBOLS
+        .iter()
+        .filter_map(|symbol| Some((rank(symbol)?, symbol)))
+        .collect();
+    matches.sort_by_key(|(rank, _)| *rank);
+    matches.into_iter().map(|(_, symbol)| symbol).collect()
+}
+
+// A searchable table of symbols in both syntaxes.
+#[derive(Default)]
+pub struct Lookup {
+    pub open: bool,
+    query: String,
+}
+
+impl Lookup {
+    // Draw the table and return the symbol whose insert button was clicked.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<&'static Symbol> {
+        let mut inserted = None;
+        let mut open = self.open;
+        egui::Window::new(i18n::tr("symbols"))
+            .open(&mut open)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text(i18n::tr("symbols-search"))
+                        .desired_width(f32::INFINITY),
+                );
+                ui.separator();
+                let matches = search(&self.query);
+                if matches.is_empty() {
+                    ui.weak(i18n::tr("symbols-none"));
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(360.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("symbols_grid")
+                            .num_columns(5)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("");
+                                ui.strong("Typst");
+                                ui.strong("LaTeX");
+                                ui.strong("StarMath");
+                                ui.end_row();
+                                for symbol in matches {
+                                    ui.label(egui::RichText::new(symbol.glyph).size(24.0));
+                                    ui.monospace(symbol.typst);
+                                    ui.monospace(format!("\\{}", symbol.latex));
+                                    if starmath::supports(symbol.latex) {
+                                        ui.label("✔");
+                                    } else {
+                                        ui.weak("–").on_hover_text(i18n::tr("symbols-unsupported"));
+                                    }
+                                    if ui.button(i18n::tr("symbols-insert")).clicked() {
+                                        inserted = Some(symbol);
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+        self.open = open;
+        inserted
+    }
+}
@@ -0,0 +1,65 @@
+use crate::i18n;
+
+// A common structure written in Typst, inserted from the Insert menu and converted
+// right away, so that beginners can see both syntaxes side by side.
+pub struct Template {
+    name: &'static str,
+    pub typst: &'static str,
+}
+
+impl Template {
+    pub fn label(&self) -> &'static str {
+        i18n::tr(self.name)
+    }
+}
+
+pub const ALL: [Template; 12] = [
+    Template {
+        name: "template-limit",
+        typst: "lim_(x -> 0) (sin x) / x = 1",
+    },
+    Template {
+        name: "template-derivative",
+        typst: "(dif f)/(dif x) = lim_(h -> 0) (f(x + h) - f(x)) / h",
+    },
+    Template {
+        name: "template-integral",
+        typst: "integral_a^b f(x) dif x = F(b) - F(a)",
+    },
+    Template {
+        name: "template-sum",
+        typst: "sum_(0 <= k <= n \\ k \"even\") binom(n, k) = 2^(n - 1)",
+    },
+    Template {
+        name: "template-fraction",
+        typst: "(a + b) / (c + d)",
+    },
+    Template {
+        name: "template-root",
+        typst: "sqrt(x^2 + y^2) + root(3, x)",
+    },
+    Template {
+        name: "template-piecewise",
+        typst: "|x| = cases(x & \"if\" x >= 0, -x & \"otherwise\")",
+    },
+    Template {
+        name: "template-matrix",
+        typst: "A = mat(a_(1 1), a_(1 2); a_(2 1), a_(2 2))",
+    },
+    Template {
+        name: "template-determinant",
+        typst: "mat(delim: \"|\", a, b; c, d) = a d - b c",
+    },
+    Template {
+        name: "template-vector",
+        typst: "vec(x_1, x_2, x_3) dot.op vec(y_1, y_2, y_3) = sum_(i = 1)^3 x_i y_i",
+    },
+    Template {
+        name: "template-aligned",
+        typst: "(x + 1)^2 &= (x + 1)(x + 1) \\\n&= x^2 + 2x + 1",
+    },
+    Template {
+        name: "template-arrows",
+        typst: "A limits(-->)^f B limits(-->)^g C, quad A limits(-->)^(g compose f) C",
+    },
+];
@@ -0,0 +1,146 @@
+use crate::convert;
+use crate::diagnostics;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+// OpenType math fonts in tectonic's bundle, by name and font file.
+pub const MATH_FONTS: [(&str, &str); 5] = [
+    ("Latin Modern Math", "latinmodern-math.otf"),
+    ("STIX Two Math", "STIXTwoMath-Regular.otf"),
+    ("Fira Math", "FiraMath-Regular.otf"),
+    ("TeX Gyre Pagella Math", "texgyrepagella-math.otf"),
+    ("TeX Gyre Termes Math", "texgyretermes-math.otf"),
+];
+
+// Wrap LaTeX math in a minimal `standalone` document, typeset in `math_font` (a
+// font file) with unicode-math, or in Computer Modern if it's empty.
+pub fn standalone_document(latex: &str, math_font: &str) -> String {
+    let packages = if math_font.is_empty() {
+        "\\usepackage{amsmath,amssymb}".to_string()
+    } else {
+        format!(
+            "\\usepackage{{amsmath}}\n\\usepackage{{unicode-math}}\n\\setmathfont{{{}}}",
+            math_font
+        )
+    };
+    format!(
+        "\\documentclass[border=2pt]{{standalone}}\n\
+         {}\n\
+         \\begin{{document}}\n\
+         $\\displaystyle\n\
+         \\begin{{gathered}}\n\
+         {}\n\
+         \\end{{gathered}}\n\
+         $\n\
+         \\end{{document}}\n",
+        packages,
+        latex.trim()
+    )
+}
+
+// A fresh temporary directory per compile, so that concurrent compiles (preview
+// and validation) never clobber each other's files.
+fn work_dir() -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "typst2latex-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+// Run a TeX tool, which is killed when the render or check it's for is cancelled
+// with `convert::cancel_on`.
+fn run(command: &mut Command, name: &str) -> Result<String, String> {
+    diagnostics::log(format!("{:?}", command));
+    let start = Instant::now();
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|_| format!("Failed to execute {}. Do you have it installed?", name))?;
+    let output = convert::wait(child, name)?;
+    let log = String::from_utf8_lossy(&output.stdout).to_string()
+        + &String::from_utf8_lossy(&output.stderr);
+    diagnostics::log(format!(
+        "{} exited with {} after {} ms",
+        name,
+        output.status,
+        start.elapsed().as_millis()
+    ));
+    if !output.status.success() {
+        diagnostics::log(format!("{} output: {}", name, log.trim()));
+    }
+    if output.status.success() {
+        Ok(log)
+    } else {
+        Err(log)
+    }
+}
+
+// The first TeX error in a compile log, e.g. "Undefined control sequence", or the
+// last line of the log if there is none.
+fn tex_error(log: &str) -> String {
+    log.lines()
+        .find_map(|line| line.trim().strip_prefix("! "))
+        .or_else(|| log.lines().rev().find(|line| !line.trim().is_empty()))
+        .unwrap_or("LaTeX compilation failed")
+        .trim()
+        .to_string()
+}
+
+// Compile the math with tectonic into `formula.xdv` inside `dir`.
+fn compile_xdv(latex: &str, math_font: &str, dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|_| "Failed to create temporary directory")?;
+    fs::write(
+        dir.join("formula.tex"),
+        standalone_document(latex, math_font),
+    )
+    .map_err(|_| "Failed to write temporary file")?;
+    run(
+        Command::new("tectonic").current_dir(dir).args([
+            "--outfmt",
+            "xdv",
+            "--chatter",
+            "minimal",
+            "formula.tex",
+        ]),
+        "tectonic",
+    )
+    .map(|_| ())
+    .map_err(|log| tex_error(&log))
+}
+
+// Compile the math and convert the result to SVG with dvisvgm, which gives a
+// ground-truth render from a real TeX engine.
+pub fn compile_to_svg(latex: &str, math_font: &str) -> Result<String, String> {
+    let dir = work_dir();
+    let result = compile_xdv(latex, math_font, &dir).and_then(|_| {
+        run(
+            Command::new("dvisvgm").current_dir(&dir).args([
+                "--no-fonts",
+                "--exact-bbox",
+                "--output=formula.svg",
+                "formula.xdv",
+            ]),
+            "dvisvgm",
+        )?;
+        fs::read_to_string(dir.join("formula.svg"))
+            .map_err(|_| "Failed to read dvisvgm output".to_string())
+    });
+    fs::remove_dir_all(&dir).ok();
+    result
+}
+
+// Check that the math compiles with a real TeX engine, which is far stricter than
+// MathJax.
+pub fn validate(latex: &str) -> Result<(), String> {
+    let dir = work_dir();
+    let result = compile_xdv(latex, "", &dir);
+    fs::remove_dir_all(&dir).ok();
+    result
+}
@@ -0,0 +1,88 @@
+use crate::i18n;
+use crate::settings::Settings;
+use eframe::egui;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ThemeMode {
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemeMode {
+    pub const ALL: [ThemeMode; 3] = [ThemeMode::System, ThemeMode::Light, ThemeMode::Dark];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThemeMode::System => i18n::tr("theme-system"),
+            ThemeMode::Light => i18n::tr("theme-light"),
+            ThemeMode::Dark => i18n::tr("theme-dark"),
+        }
+    }
+
+    // Name stored in the settings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemeMode::System => "system",
+            ThemeMode::Light => "light",
+            ThemeMode::Dark => "dark",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ThemeMode> {
+        ThemeMode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+// Parse a `#rrggbb` color as stored in the settings file.
+pub fn parse_color(hex: &str) -> Option<egui::Color32> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(egui::Color32::from_rgb(
+        channel(0)?,
+        channel(2)?,
+        channel(4)?,
+    ))
+}
+
+pub fn color_hex(color: egui::Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+// Apply the theme mode and custom colors to both the light and the dark style, so
+// that following the system theme keeps the customization.
+pub fn apply(ctx: &egui::Context, settings: &Settings) {
+    ctx.set_theme(
+        match ThemeMode::from_name(&settings.theme).unwrap_or(ThemeMode::System) {
+            ThemeMode::System => egui::ThemePreference::System,
+            ThemeMode::Light => egui::ThemePreference::Light,
+            ThemeMode::Dark => egui::ThemePreference::Dark,
+        },
+    );
+    for (theme, mut visuals) in [
+        (egui::Theme::Light, egui::Visuals::light()),
+        (egui::Theme::Dark, egui::Visuals::dark()),
+    ] {
+        if let Some(accent) = parse_color(&settings.accent_color) {
+            visuals.selection.bg_fill = accent;
+            visuals.hyperlink_color = accent;
+        }
+        if let Some(background) = parse_color(&settings.background_color) {
+            visuals.panel_fill = background;
+            visuals.window_fill = background;
+        }
+        if let Some(text) = parse_color(&settings.text_color) {
+            visuals.override_text_color = Some(text);
+        }
+        ctx.set_visuals_of(theme, visuals);
+    }
+}
+
+// The color formulas are drawn in: the custom preview color, or the strong text
+// color of the current theme.
+pub fn preview_color(ctx: &egui::Context, settings: &Settings) -> egui::Color32 {
+    parse_color(&settings.preview_color).unwrap_or_else(|| ctx.style().visuals.strong_text_color())
+}
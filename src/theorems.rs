@@ -0,0 +1,351 @@
+// ctheorems' usual environments, which amsthm documents name alike.
+const ENVIRONMENTS: [&str; 10] = [
+    "theorem",
+    "lemma",
+    "proposition",
+    "corollary",
+    "definition",
+    "example",
+    "remark",
+    "conjecture",
+    "claim",
+    "proof",
+];
+
+// ctheorems' functions that define an environment, as in
+// `#let lemma = thmbox("theorem", "Lemma")`.
+const DEFINERS: [&str; 4] = ["thmbox", "thmplain", "thmproof", "thmenv"];
+
+// Byte index of the bracket or parenthesis closing the one at `open`, past math,
+// raw text and escapes in content and strings in code.
+fn closing(text: &str, open: usize) -> Option<usize> {
+    let content = text[open..].starts_with('[');
+    let (opening, closing) = if content { ('[', ']') } else { ('(', ')') };
+    let mut depth = 0;
+    let mut chars = text[open..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '$' | '`' if content => {
+                while let Some((_, next)) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == c {
+                        break;
+                    }
+                }
+            }
+            '"' if !content => {
+                while let Some((_, next)) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == '"' {
+                        break;
+                    }
+                }
+            }
+            c if c == opening => depth += 1,
+            c if c == closing => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// The arguments in parentheses `arguments`, as `(name, value)` with "" for the
+// name of positional ones.
+fn arguments(arguments: &str) -> Vec<(&str, &str)> {
+    let mut split = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = 0;
+    let mut previous = ' ';
+    for (i, c) in arguments.char_indices() {
+        match c {
+            '"' if previous != '\\' => quoted = !quoted,
+            _ if quoted => {}
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                split.push(&arguments[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    split.push(&arguments[start..]);
+    split
+        .into_iter()
+        .map(str::trim)
+        .filter(|argument| !argument.is_empty())
+        .map(|argument| match argument.split_once(':') {
+            Some((name, value))
+                if name
+                    .trim()
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_') =>
+            {
+                (name.trim(), value.trim())
+            }
+            _ => ("", argument),
+        })
+        .collect()
+}
+
+// A string or content argument as text, like `"Name"` or `[Name]`.
+fn text(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .or_else(|| {
+            value
+                .strip_prefix('[')
+                .and_then(|value| value.strip_suffix(']'))
+        })
+        .unwrap_or(value)
+}
+
+// The amsthm environment for each Typst theorem function: the usual ones, those
+// the document defines with ctheorems and `names`, lines like `theorem = thm`
+// that rename them.
+fn environments(typst: &str, names: &str) -> Vec<(String, String)> {
+    let mut environments: Vec<(String, String)> = ENVIRONMENTS
+        .iter()
+        .map(|name| (name.to_string(), name.to_string()))
+        .collect();
+    for line in typst.lines() {
+        if let Some(Definition { name, .. }) = definition(line) {
+            environments.push((name.to_string(), name.to_string()));
+        }
+    }
+    for line in names.lines() {
+        if let Some((name, environment)) = line.split_once('=') {
+            let (name, environment) = (name.trim(), environment.trim());
+            environments.retain(|(found, _)| found != name);
+            environments.push((name.to_string(), environment.to_string()));
+        }
+    }
+    environments
+}
+
+// A line like `#let lemma = thmbox("theorem", "Lemma")`.
+struct Definition<'a> {
+    name: &'a str,
+    definer: &'a str,
+    arguments: Vec<(&'a str, &'a str)>,
+}
+
+fn definition(line: &str) -> Option<Definition<'_>> {
+    let (name, value) = line.trim().strip_prefix("#let ")?.split_once('=')?;
+    let value = value.trim();
+    let open = value.find('(')?;
+    let definer = &value[..open];
+    if !DEFINERS.contains(&definer) {
+        return None;
+    }
+    let close = closing(value, open)?;
+    Some(Definition {
+        name: name.trim(),
+        definer,
+        arguments: arguments(&value[open + 1..close]),
+    })
+}
+
+// The amsthm counterpart of a line of ctheorems setup, `None` to keep the line and
+// `Some("")` to drop it. `counters` holds the environment first numbered with each
+// ctheorems counter.
+fn setup_line(
+    line: &str,
+    environments: &[(String, String)],
+    counters: &mut Vec<(String, String)>,
+) -> Option<String> {
+    let line = line.trim();
+    if line.starts_with("#show: thmrules") || line.starts_with("#import \"@preview/ctheorems") {
+        return Some(String::new());
+    }
+    let Definition {
+        name,
+        definer,
+        arguments,
+    } = definition(line)?;
+    let environment = environments
+        .iter()
+        .find(|(found, _)| found == name)
+        .map_or(name, |(_, environment)| environment.as_str());
+    let mut positional = arguments.iter().filter(|(name, _)| name.is_empty());
+    let counter = positional.next().map_or(name, |(_, counter)| text(counter));
+    let supplement = positional
+        .next()
+        .map_or(name, |(_, supplement)| text(supplement));
+    if definer == "thmproof" {
+        return Some(match environment {
+            // amsthm has its own.
+            "proof" => String::new(),
+            _ => format!("\\newtheorem*{{{}}}{{{}}}", environment, supplement),
+        });
+    }
+    match counters.iter().find(|(found, _)| found == counter) {
+        Some((_, shared)) => Some(format!(
+            "\\newtheorem{{{}}}[{}]{{{}}}",
+            environment, shared, supplement
+        )),
+        None => {
+            counters.push((counter.to_string(), environment.to_string()));
+            Some(format!("\\newtheorem{{{}}}{{{}}}", environment, supplement))
+        }
+    }
+}
+
+fn blocks(typst: &str, environments: &[(String, String)]) -> String {
+    let mut out = String::with_capacity(typst.len());
+    let mut rest = typst;
+    while let Some(at) = rest.find('#') {
+        out.push_str(&rest[..at]);
+        let call = &rest[at + 1..];
+        let name_length = call
+            .find(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+            .unwrap_or(call.len());
+        let environment = environments
+            .iter()
+            .find(|(name, _)| *name == call[..name_length])
+            .map(|(_, environment)| environment);
+        let block = environment.and_then(|environment| {
+            let mut next = name_length;
+            let mut title = None;
+            if call[next..].starts_with('(') {
+                let close = closing(call, next)?;
+                title = arguments(&call[next + 1..close])
+                    .into_iter()
+                    .find(|(name, _)| matches!(*name, "" | "name" | "title"))
+                    .map(|(_, title)| text(title).to_string());
+                next = close + 1;
+            }
+            if !call[next..].starts_with('[') {
+                return None;
+            }
+            let close = closing(call, next)?;
+            let body = blocks(call[next + 1..close].trim(), environments);
+            next = close + 1;
+            let mut label = String::new();
+            let after = call[next..].trim_start_matches(' ');
+            if let Some(end) = after.strip_prefix('<').and_then(|after| after.find('>')) {
+                label = format!("\\label{{{}}}", &after[1..end + 1]);
+                next = call.len() - after.len() + end + 2;
+            }
+            let title = title.map_or(String::new(), |title| format!("[{}]", title));
+            let block = format!(
+                "\\begin{{{}}}{}{}\n{}\n\\end{{{}}}",
+                environment, title, label, body, environment
+            );
+            Some((block, next))
+        });
+        match block {
+            Some((block, next)) => {
+                out.push_str(&block);
+                rest = &call[next..];
+            }
+            None => {
+                out.push('#');
+                rest = call;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// Turn the theorem blocks of a Typst document, like `#theorem("Name")[...]` and
+// `#proof[...]`, into amsthm environments, and ctheorems' definitions into
+// `\newtheorem`. `names` renames environments, with lines like `theorem = thm`.
+pub fn to_amsthm(typst: &str, names: &str) -> String {
+    let environments = environments(typst, names);
+    let mut counters = Vec::new();
+    let mut setup = String::with_capacity(typst.len());
+    for line in typst.split_inclusive('\n') {
+        match setup_line(line, &environments, &mut counters) {
+            Some(replaced) if replaced.is_empty() => {}
+            Some(replaced) => {
+                setup.push_str(&replaced);
+                if line.ends_with('\n') {
+                    setup.push('\n');
+                }
+            }
+            None => setup.push_str(line),
+        }
+    }
+    blocks(&setup, &environments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titled() {
+        assert_eq!(
+            to_amsthm("#theorem(\"Fermat\")[No solutions.]", ""),
+            "\\begin{theorem}[Fermat]\nNo solutions.\n\\end{theorem}"
+        );
+        assert_eq!(
+            to_amsthm("#lemma(name: [Zorn])[ Every chain... ] <zorn>.", ""),
+            "\\begin{lemma}[Zorn]\\label{zorn}\nEvery chain...\n\\end{lemma}."
+        );
+        assert_eq!(
+            to_amsthm("#proof[Trivial.]", ""),
+            "\\begin{proof}\nTrivial.\n\\end{proof}"
+        );
+    }
+
+    #[test]
+    fn nested() {
+        assert_eq!(
+            to_amsthm("#theorem[A #proof[B $x]$] C]", ""),
+            "\\begin{theorem}\nA \\begin{proof}\nB $x]$\n\\end{proof} C\n\\end{theorem}"
+        );
+    }
+
+    #[test]
+    fn unknown_kinds() {
+        assert_eq!(
+            to_amsthm("#figure[x] #axiom(\"T\")[y]", ""),
+            "#figure[x] #axiom(\"T\")[y]"
+        );
+        // Without a body, it isn't a block.
+        assert_eq!(to_amsthm("#theorem(\"T\")", ""), "#theorem(\"T\")");
+        assert_eq!(to_amsthm("#theorem[open", ""), "#theorem[open");
+    }
+
+    #[test]
+    fn ctheorems_definitions() {
+        let typst = "#import \"@preview/ctheorems:1.1.3\": *\n\
+            #show: thmrules\n\
+            #let axiom = thmbox(\"axiom\", \"Axiom\")\n\
+            #let lemma = thmbox(\"axiom\", \"Lemma\")\n\
+            #let proof = thmproof(\"proof\", \"Proof\")\n\
+            #let note = thmproof(\"note\", \"Note\")\n\
+            #axiom[x]";
+        assert_eq!(
+            to_amsthm(typst, ""),
+            "\\newtheorem{axiom}{Axiom}\n\
+             \\newtheorem{lemma}[axiom]{Lemma}\n\
+             \\newtheorem*{note}{Note}\n\
+             \\begin{axiom}\nx\n\\end{axiom}"
+        );
+    }
+
+    #[test]
+    fn renamed() {
+        assert_eq!(
+            to_amsthm("#theorem[x] #thm[y]", "theorem = thm\nthm = thm"),
+            "\\begin{thm}\nx\n\\end{thm} \\begin{thm}\ny\n\\end{thm}"
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use crate::diagnostics;
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Info,
+    Error,
+}
+
+struct Toast {
+    level: Level,
+    message: String,
+    details: Option<String>,
+    created: Instant,
+    expanded: bool,
+}
+
+impl Toast {
+    fn remaining(&self) -> Duration {
+        let lifetime = match self.level {
+            Level::Info => Duration::from_secs(3),
+            Level::Error => Duration::from_secs(8),
+        };
+        lifetime.saturating_sub(self.created.elapsed())
+    }
+}
+
+// In-app notifications shown in the bottom-right corner, so that failures reach
+// GUI users instead of only being printed to stderr.
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(Level::Info, message.into(), None);
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(Level::Error, message.into(), None);
+    }
+
+    // An error with details that are shown when the toast is clicked.
+    pub fn error_with_details(&mut self, message: impl Into<String>, details: impl Into<String>) {
+        self.push(Level::Error, message.into(), Some(details.into()));
+    }
+
+    fn push(&mut self, level: Level, message: String, details: Option<String>) {
+        diagnostics::log(match &details {
+            Some(details) => format!("{}: {}", message, details),
+            None => message.clone(),
+        });
+        // Don't stack up the same failure on every keystroke.
+        self.toasts.retain(|toast| toast.message != message);
+        self.toasts.push(Toast {
+            level,
+            message,
+            details,
+            created: Instant::now(),
+            expanded: false,
+        });
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        // Expanded toasts stay until dismissed; others fade after a while.
+        self.toasts
+            .retain(|toast| toast.expanded || toast.remaining() > Duration::ZERO);
+        if self.toasts.is_empty() {
+            return;
+        }
+        // Wake up only when the next toast is due to go, not on a timer.
+        if let Some(next) = self
+            .toasts
+            .iter()
+            .filter(|toast| !toast.expanded)
+            .map(Toast::remaining)
+            .min()
+        {
+            ctx.request_repaint_after(next);
+        }
+
+        let mut dismissed = None;
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.set_max_width(300.0);
+                for (i, toast) in self.toasts.iter_mut().enumerate() {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            let color = match toast.level {
+                                Level::Info => ui.visuals().text_color(),
+                                Level::Error => ui.visuals().error_fg_color,
+                            };
+                            let response = ui.add(
+                                egui::Label::new(egui::RichText::new(&toast.message).color(color))
+                                    .sense(egui::Sense::click()),
+                            );
+                            if response.clicked() && toast.details.is_some() {
+                                toast.expanded = !toast.expanded;
+                            }
+                            if ui.small_button("×").clicked() {
+                                dismissed = Some(i);
+                            }
+                        });
+                        if let Some(details) = toast.details.as_ref().filter(|_| toast.expanded) {
+                            ui.separator();
+                            ui.label(egui::RichText::new(details).monospace().small());
+                        }
+                    });
+                }
+            });
+        if let Some(i) = dismissed {
+            self.toasts.remove(i);
+        }
+    }
+}
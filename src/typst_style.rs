@@ -0,0 +1,321 @@
+use crate::symbols::SYMBOLS;
+
+// Typst's shorthands in math, longest first so that `<=>` isn't read as `<=` and
+// `>`, with the names and glyphs of the symbols they stand for.
+const SHORTHANDS: [(&str, &str, &str); 19] = [
+    ("<==>", "arrow.l.r.double.long", "⟺"),
+    ("-->", "arrow.r.long", "⟶"),
+    ("==>", "arrow.r.double.long", "⟹"),
+    ("<--", "arrow.l.long", "⟵"),
+    ("<==", "arrow.l.double.long", "⟸"),
+    ("<->", "arrow.l.r", "↔"),
+    ("<=>", "arrow.l.r.double", "⇔"),
+    ("|->", "arrow.r.bar", "↦"),
+    ("|=>", "arrow.r.double.bar", "⤇"),
+    ("...", "dots.h", "…"),
+    ("->", "arrow.r", "→"),
+    ("<-", "arrow.l", "←"),
+    ("=>", "arrow.r.double", "⇒"),
+    ("<=", "lt.eq", "≤"),
+    (">=", "gt.eq", "≥"),
+    ("!=", "eq.not", "≠"),
+    (":=", "colon.eq", "≔"),
+    ("<<", "lt.double", "≪"),
+    (">>", "gt.double", "≫"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Space,
+    // A symbol name such as `alpha` or `arrow.r`, or a variable.
+    Name,
+    Shorthand,
+    Glyph,
+    Operator,
+    Other,
+}
+
+struct Token<'a> {
+    kind: Kind,
+    text: &'a str,
+    // Rewritten from another form, so it may need a space to stand apart.
+    changed: bool,
+}
+
+// Length of the string literal `text` starts with, up to its closing quote.
+fn string_length(text: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            '"' if !escaped => return i + 1,
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    text.len()
+}
+
+// Length of the name `text` starts with, fields such as `.r.double` included.
+// Other letters, such as `α`, are symbols of their own.
+fn name_length(text: &str) -> usize {
+    let mut end = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c.is_ascii_alphabetic() {
+            end = i + 1;
+        } else if c == '.' && end == i && chars.peek().is_some_and(|(_, c)| c.is_ascii_alphabetic())
+        {
+            continue;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+fn tokenize(typst: &str) -> Vec<Token<'_>> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut i = 0;
+    while let Some(c) = typst[i..].chars().next() {
+        let rest = &typst[i..];
+        let (mut kind, length) = if c == '"' {
+            // Text is left as it is.
+            (Kind::Other, string_length(rest))
+        } else if c == '\\' {
+            let next = rest[1..].chars().next().map_or(0, char::len_utf8);
+            (Kind::Other, 1 + next)
+        } else if c.is_whitespace() {
+            let length = rest.find(|c: char| !c.is_whitespace());
+            (Kind::Space, length.unwrap_or(rest.len()))
+        } else if c.is_ascii_alphabetic() {
+            (Kind::Name, name_length(rest))
+        } else if let Some((shorthand, _, _)) = SHORTHANDS
+            .iter()
+            .find(|(shorthand, _, _)| rest.starts_with(shorthand))
+        {
+            (Kind::Shorthand, shorthand.len())
+        } else if "+-=<>".contains(c) {
+            (Kind::Operator, 1)
+        } else if name_of_glyph(c).is_some() {
+            (Kind::Glyph, c.len_utf8())
+        } else {
+            (Kind::Other, c.len_utf8())
+        };
+        // Functions such as `frac(..)` and `cancel(..)`, and code after `#`, are
+        // no symbols.
+        let called = typst[i + length..].starts_with('(');
+        let code = tokens.last().is_some_and(|token| token.text.ends_with('#'));
+        if kind == Kind::Name && (called || code) {
+            kind = Kind::Other;
+        }
+        tokens.push(Token {
+            kind,
+            text: &typst[i..i + length],
+            changed: false,
+        });
+        i += length;
+    }
+    tokens
+}
+
+fn name_of_glyph(glyph: char) -> Option<&'static str> {
+    let glyph = glyph.to_string();
+    SHORTHANDS
+        .iter()
+        .find(|(_, _, found)| *found == glyph)
+        .map(|(_, name, _)| *name)
+        .or_else(|| {
+            SYMBOLS
+                .iter()
+                .find(|symbol| symbol.glyph == glyph)
+                .map(|symbol| symbol.typst)
+        })
+}
+
+fn glyph_of_name(name: &str) -> Option<&'static str> {
+    SHORTHANDS
+        .iter()
+        .find(|(_, found, _)| *found == name)
+        .map(|(_, _, glyph)| *glyph)
+        .or_else(|| {
+            SYMBOLS
+                .iter()
+                .find(|symbol| symbol.typst == name)
+                .map(|symbol| symbol.glyph)
+        })
+}
+
+// Write a symbol the way `shorthands` ("shorthand" or "explicit") and `symbols`
+// ("names" or "unicode") ask for, or as it is for "".
+fn restyle_symbol(token: &mut Token, shorthands: &str, symbols: &str) {
+    let name = match token.kind {
+        Kind::Name if glyph_of_name(token.text).is_some() => token.text,
+        Kind::Shorthand => SHORTHANDS
+            .iter()
+            .find(|(shorthand, _, _)| *shorthand == token.text)
+            .map_or(token.text, |(_, name, _)| *name),
+        Kind::Glyph => match token.text.chars().next().and_then(name_of_glyph) {
+            Some(name) => name,
+            None => return,
+        },
+        _ => return,
+    };
+    let shorthand = SHORTHANDS.iter().find(|(_, found, _)| *found == name);
+    let (kind, text) = match (shorthand, shorthands, symbols) {
+        (Some((shorthand, _, _)), "shorthand", _) => (Kind::Shorthand, *shorthand),
+        _ if token.kind == Kind::Shorthand && shorthands != "explicit" => return,
+        (_, _, "unicode") => match glyph_of_name(name) {
+            Some(glyph) => (Kind::Glyph, glyph),
+            None => (Kind::Name, name),
+        },
+        (_, _, "names") => (Kind::Name, name),
+        // Shorthands spelled out, the rest as they are.
+        _ if token.kind == Kind::Shorthand => (Kind::Name, name),
+        _ => return,
+    };
+    token.changed = token.text != text;
+    token.kind = kind;
+    token.text = text;
+}
+
+fn is_operator(token: &Token) -> bool {
+    matches!(token.kind, Kind::Operator | Kind::Shorthand)
+}
+
+// An operator is binary unless it starts the formula or follows another operator,
+// an opening bracket or a separator, like the minus in `(-x, -y)`.
+fn is_binary(previous: Option<&Token>) -> bool {
+    previous.is_some_and(|previous| {
+        !is_operator(previous)
+            && !previous
+                .text
+                .ends_with(['(', '[', '{', ',', ';', '^', '_', '/'])
+    })
+}
+
+// Rewrite Typst the way a codebase writes it: arrows and relations as
+// `shorthands` ("shorthand" like `->` or "explicit" like `arrow.r`), symbols as
+// `symbols` ("names" like `alpha` or "unicode" like `α`) and `spacing` around
+// operators ("spaced" like `a + b` or "compact" like `a+b`). "" leaves each as
+// pandoc writes it.
+pub fn restyle(typst: &str, shorthands: &str, symbols: &str, spacing: &str) -> String {
+    let mut tokens = tokenize(typst);
+    for token in &mut tokens {
+        restyle_symbol(token, shorthands, symbols);
+    }
+    let mut out = String::new();
+    // The last token that isn't space, whether it's a binary operator, and the
+    // space after it.
+    let mut previous: Option<&Token> = None;
+    let mut binary = false;
+    let mut space: Option<&str> = None;
+    for token in &tokens {
+        if token.kind == Kind::Space {
+            space = Some(token.text);
+            continue;
+        }
+        let operator = is_operator(token);
+        let separate = match (previous, spacing) {
+            (None, _) => space.is_some(),
+            // Keep operators apart that would run into a shorthand, like `< -`.
+            (Some(previous), "compact") if is_operator(previous) && operator => {
+                let joined = format!("{}{}", previous.text, token.text);
+                SHORTHANDS
+                    .iter()
+                    .any(|(shorthand, _, _)| shorthand.contains(&joined))
+            }
+            (Some(previous), "compact") if is_operator(previous) || operator => false,
+            (Some(previous), "spaced") if is_operator(previous) && !binary => false,
+            (Some(previous), "spaced")
+                if is_operator(previous) || operator && is_binary(Some(previous)) =>
+            {
+                true
+            }
+            (Some(previous), _) => {
+                space.is_some()
+                    || previous.changed
+                        && previous.kind == Kind::Name
+                        && token
+                            .text
+                            .starts_with(|c: char| c.is_alphanumeric() || c == '(')
+                    || token.changed
+                        && token.kind == Kind::Name
+                        && previous.text.ends_with(char::is_alphanumeric)
+            }
+        };
+        if separate {
+            out.push_str(space.unwrap_or(" "));
+        }
+        if operator {
+            binary = is_binary(previous);
+        }
+        out.push_str(token.text);
+        previous = Some(token);
+        space = None;
+    }
+    if let Some(space) = space {
+        out.push_str(space);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shorthands() {
+        assert_eq!(restyle("a -> b", "explicit", "", ""), "a arrow.r b");
+        assert_eq!(
+            restyle("a <=> b", "explicit", "", ""),
+            "a arrow.l.r.double b"
+        );
+        assert_eq!(restyle("a arrow.r b", "shorthand", "", ""), "a -> b");
+        assert_eq!(restyle("x arrow.r.double y", "shorthand", "", ""), "x => y");
+        // Strings are left alone.
+        assert_eq!(
+            restyle(r#""a->b" -> c"#, "explicit", "", ""),
+            r#""a->b" arrow.r c"#
+        );
+    }
+
+    #[test]
+    fn symbols() {
+        assert_eq!(restyle("alpha + beta", "", "unicode", ""), "α + β");
+        assert_eq!(restyle("α + β", "", "names", ""), "alpha + beta");
+    }
+
+    #[test]
+    fn spacing() {
+        assert_eq!(restyle("a+b", "", "", "spaced"), "a + b");
+        assert_eq!(restyle("a + b", "", "", "compact"), "a+b");
+        // Signs aren't binary operators.
+        assert_eq!(restyle("(-x, -y)", "", "", "spaced"), "(-x, -y)");
+        assert_eq!(restyle("a - -b", "", "", "compact"), "a- -b");
+    }
+
+    #[test]
+    fn empty_styles_keep_the_output() {
+        assert_eq!(restyle("a->b+alpha", "", "", ""), "a->b+alpha");
+    }
+
+    #[test]
+    fn whole_identifiers_only() {
+        // `alphabet` isn't `alpha` followed by `bet`, and a variant keeps its name.
+        assert_eq!(restyle("alphabet", "", "unicode", ""), "alphabet");
+        assert_eq!(restyle("alpha.alt", "", "unicode", ""), "alpha.alt");
+        assert_eq!(restyle(r#""α" + α"#, "", "names", ""), r#""α" + alpha"#);
+    }
+
+    #[test]
+    fn shorthands_without_spaces() {
+        assert_eq!(restyle("a<=b", "explicit", "", ""), "a lt.eq b");
+        assert_eq!(restyle("x != y", "explicit", "", ""), "x eq.not y");
+        assert_eq!(
+            restyle("a -> b -> c", "explicit", "", ""),
+            "a arrow.r b arrow.r c"
+        );
+        assert_eq!(restyle("a<=b", "", "", "spaced"), "a <= b");
+        assert_eq!(restyle("a -> b -> c", "", "", "compact"), "a->b->c");
+    }
+}
@@ -0,0 +1,50 @@
+use crate::convert::Direction;
+
+const MAX_SNAPSHOTS: usize = 100;
+
+// The editor contents before a programmatic change such as Clear or loading a file.
+#[derive(Clone, PartialEq)]
+pub struct Snapshot {
+    pub input: String,
+    pub output: String,
+    pub direction: Direction,
+}
+
+// Application-level undo for changes that the text editors' own undo can't see.
+#[derive(Default)]
+pub struct UndoStack {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl UndoStack {
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.undo.last() != Some(&snapshot) {
+            self.undo.push(snapshot);
+            if self.undo.len() > MAX_SNAPSHOTS {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.undo.pop()?;
+        self.redo.push(current);
+        Some(snapshot)
+    }
+
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        self.undo.push(current);
+        Some(snapshot)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
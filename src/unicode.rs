@@ -0,0 +1,227 @@
+use crate::latex::{self, Token};
+use crate::symbols::SYMBOLS;
+
+// Characters with LaTeX for them besides the symbols, ahead of the symbols so
+// that a prime is `'` rather than `\prime`.
+const CHARACTERS: [(char, &str); 24] = [
+    ('ℝ', "\\mathbb{R}"),
+    ('ℕ', "\\mathbb{N}"),
+    ('ℤ', "\\mathbb{Z}"),
+    ('ℚ', "\\mathbb{Q}"),
+    ('ℂ', "\\mathbb{C}"),
+    ('−', "-"),
+    ('·', "\\cdot"),
+    ('°', "^{\\circ}"),
+    ('µ', "\\mu"),
+    ('′', "'"),
+    ('″', "''"),
+    ('¹', "^{1}"),
+    ('²', "^{2}"),
+    ('³', "^{3}"),
+    ('\u{a0}', "~"),
+    ('\u{2009}', "\\,"),
+    ('–', "--"),
+    ('—', "---"),
+    ('‘', "`"),
+    ('’', "'"),
+    ('“', "``"),
+    ('”', "''"),
+    ('ß', "\\ss{}"),
+    ('ø', "\\o{}"),
+];
+
+// Accented letters, the letters under them and the accent command.
+const ACCENTS: [(&str, &str, char); 6] = [
+    ("áéíóúýÁÉÍÓÚÝ", "aeiouyAEIOUY", '\''),
+    ("àèìòùÀÈÌÒÙ", "aeiouAEIOU", '`'),
+    ("äëïöüÿÄËÏÖÜ", "aeiouyAEIOU", '"'),
+    ("âêîôûÂÊÎÔÛ", "aeiouAEIOU", '^'),
+    ("ãñõÃÑÕ", "anoANO", '~'),
+    ("çÇ", "cC", 'c'),
+];
+
+// Commands whose arguments aren't math, left as they are.
+const SKIPPED: [&str; 6] = ["text", "mathrm", "operatorname", "label", "begin", "end"];
+
+fn command_for(c: char) -> Option<String> {
+    if let Some((_, latex)) = CHARACTERS.iter().find(|(found, _)| *found == c) {
+        return Some(latex.to_string());
+    }
+    let glyph = c.to_string();
+    if let Some(symbol) = SYMBOLS.iter().find(|symbol| symbol.glyph == glyph) {
+        return Some(format!("\\{}", symbol.latex));
+    }
+    ACCENTS.iter().find_map(|(accented, letters, accent)| {
+        let at = accented.chars().position(|found| found == c)?;
+        let letter = letters.chars().nth(at)?;
+        Some(format!("\\{}{{{}}}", accent, letter))
+    })
+}
+
+// Write the non-ASCII characters of LaTeX output as commands, like `\alpha` for
+// `α`, for submission systems that reject anything else. Characters without one
+// are written as their UTF-8 bytes, like `^^ce^^b1`, which pdfTeX reads back.
+pub fn to_ascii(latex: &str) -> String {
+    if latex.is_ascii() {
+        return latex.to_string();
+    }
+    let mut out = String::with_capacity(latex.len());
+    let mut chars = latex.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii() {
+            out.push(c);
+            continue;
+        }
+        match command_for(c) {
+            Some(command) => {
+                let letters = command.ends_with(|c: char| c.is_ascii_alphabetic());
+                out.push_str(&command);
+                // Keep the command from running into a following letter.
+                if letters && chars.peek().is_some_and(char::is_ascii_alphabetic) {
+                    out.push(' ');
+                }
+            }
+            None => {
+                let mut bytes = [0; 4];
+                for byte in c.encode_utf8(&mut bytes).bytes() {
+                    out.push_str(&format!("^^{:02x}", byte));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn glyphs(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let glyph = match &tokens[i] {
+            // Blackboard letters, as in `\mathbb{R}`.
+            Token::Command(name) if name == "mathbb" => {
+                let (letter, next) = latex::argument(tokens, i + 1);
+                let latex = format!("\\mathbb{{{}}}", latex::to_string(letter));
+                CHARACTERS
+                    .iter()
+                    .find(|(_, found)| *found == latex)
+                    .map(|(glyph, _)| (glyph.to_string(), next))
+            }
+            Token::Command(name) => SYMBOLS
+                .iter()
+                .find(|symbol| symbol.latex == name)
+                .map(|symbol| (symbol.glyph.to_string(), i + 1)),
+            _ => None,
+        };
+        if let Some((glyph, next)) = glyph {
+            out.push_str(&glyph);
+            i = next;
+            continue;
+        }
+        match &tokens[i] {
+            Token::Command(name) if SKIPPED.contains(&name.as_str()) => {
+                let mut end = i + 1;
+                while matches!(tokens.get(end), Some(Token::Group(_) | Token::Char('*'))) {
+                    end += 1;
+                }
+                out.push_str(&latex::to_string(&tokens[i..end]));
+                i = end;
+                continue;
+            }
+            Token::Group(group) => {
+                out.push('{');
+                out.push_str(&glyphs(group));
+                out.push('}');
+            }
+            token => out.push_str(&latex::to_string(std::slice::from_ref(token))),
+        }
+        // Keep a command name from running into a following letter.
+        if let (Token::Command(name), Some(Token::Char(c))) = (&tokens[i], tokens.get(i + 1)) {
+            if name.chars().all(|c| c.is_ascii_alphabetic()) && c.is_ascii_alphabetic() {
+                out.push(' ');
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+// Write symbol commands of LaTeX output as the characters themselves, like `α`
+// for `\alpha`, for documents set with unicode-math.
+pub fn to_unicode(latex: &str) -> String {
+    let tokens = latex::parse(latex);
+    let out = glyphs(&tokens);
+    if out == latex::to_string(&tokens) {
+        latex.to_string()
+    } else {
+        out
+    }
+}
+
+// The LaTeX output with its characters as `style` asks: "ascii", "unicode" or ""
+// for as it is.
+pub fn restyle(latex: &str, style: &str) -> String {
+    match style {
+        "ascii" => to_ascii(latex),
+        "unicode" => to_unicode(latex),
+        _ => latex.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn characters_become_commands() {
+        assert_eq!(to_ascii("α + β"), r"\alpha + \beta");
+        assert_eq!(to_ascii("x ≤ y"), r"x \leq y");
+        assert_eq!(to_ascii("ℵ"), r"\aleph");
+        // A prime is the apostrophe rather than `\prime`.
+        assert_eq!(to_ascii("x′"), "x'");
+    }
+
+    #[test]
+    fn accented_letters() {
+        assert_eq!(to_ascii("é"), r"\'{e}");
+        assert_eq!(to_ascii("ñ"), r"\~{n}");
+        assert_eq!(to_ascii(r"\text{é}"), r"\text{\'{e}}");
+    }
+
+    #[test]
+    fn characters_without_a_command_become_bytes() {
+        assert_eq!(to_ascii("😀"), "^^f0^^9f^^98^^80");
+    }
+
+    #[test]
+    fn commands_become_characters() {
+        assert_eq!(to_unicode(r"\alpha + \beta"), "α + β");
+        assert_eq!(to_unicode(r"\alpha_1 \mathbb{R}"), "α_1 ℝ");
+        assert_eq!(to_unicode(r"x \leq y"), "x ≤ y");
+        assert_eq!(to_unicode(r"\text{\alpha}"), r"\text{\alpha}");
+        assert_eq!(to_unicode(r"\alphax"), r"\alphax");
+    }
+
+    #[test]
+    fn empty_style_keeps_the_output() {
+        assert_eq!(restyle("α", ""), "α");
+        assert_eq!(restyle("α", "ascii"), r"\alpha");
+        assert_eq!(restyle(r"\alpha", "unicode"), "α");
+    }
+
+    #[test]
+    fn scripts_and_letterlike_symbols() {
+        assert_eq!(to_ascii("x²"), "x^{2}");
+        assert_eq!(to_ascii("ℝ"), r"\mathbb{R}");
+        assert_eq!(to_ascii("αβ"), r"\alpha\beta");
+        // `½` has no math command.
+        assert_eq!(to_ascii("½"), "^^c2^^bd");
+    }
+
+    #[test]
+    fn only_known_commands_become_characters() {
+        assert_eq!(to_unicode(r"\mathbb{Z} \mathbb {R}"), "ℤ ℝ");
+        assert_eq!(to_unicode(r"\alpha\beta"), "αβ");
+        assert_eq!(to_unicode(r"\mathbb{x}"), r"\mathbb{x}");
+        assert_eq!(to_unicode(r"\mathcal{F}"), r"\mathcal{F}");
+    }
+}
@@ -0,0 +1,501 @@
+#[cfg(feature = "gui")]
+use crate::latex::{self, Token};
+#[cfg(feature = "gui")]
+use crate::packages::{PREFIXES, UNITS};
+
+// A unit in a quantity, e.g. `km` in `km/s`: the siunitx names of its prefix and
+// unit and its power, negative after a slash or `per`.
+#[cfg(feature = "gui")]
+struct Factor {
+    prefix: Option<&'static str>,
+    unit: &'static str,
+    power: i32,
+    // Written after a slash, rather than with a negative power.
+    slash: bool,
+}
+
+#[cfg(feature = "gui")]
+impl Factor {
+    // The symbol, like `km` for `\kilo\meter`.
+    fn symbol(&self) -> String {
+        let symbol = |table: &[(&str, &'static str)], name| {
+            table
+                .iter()
+                .find(|(found, _)| *found == name)
+                .map_or("", |(_, symbol)| *symbol)
+        };
+        let prefix = self.prefix.map_or("", |prefix| symbol(&PREFIXES, prefix));
+        format!("{}{}", prefix, symbol(&UNITS, self.unit))
+    }
+}
+
+// The siunitx prefix and unit for a symbol such as `km` or a name such as
+// `meter`.
+#[cfg(feature = "gui")]
+fn resolve(word: &str) -> Option<(Option<&'static str>, &'static str)> {
+    let unit = |word: &str| {
+        UNITS
+            .iter()
+            .find(|(name, symbol)| *symbol == word || *name == word)
+            .map(|(name, _)| *name)
+    };
+    if let Some(unit) = unit(word) {
+        return Some((None, unit));
+    }
+    PREFIXES.iter().find_map(|(name, symbol)| {
+        let rest = word.strip_prefix(symbol)?;
+        Some((Some(*name), unit(rest)?))
+    })
+}
+
+// Superscript digits, as in `m²`, as a power.
+#[cfg(feature = "gui")]
+fn superscript_power(text: &str) -> Option<i32> {
+    let digits: String = text
+        .chars()
+        .map(|c| match c {
+            '⁻' => Some('-'),
+            '¹' => Some('1'),
+            '²' => Some('2'),
+            '³' => Some('3'),
+            '⁴'..='⁹' => char::from_digit(c as u32 - '⁰' as u32, 10),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    digits.parse().ok()
+}
+
+// The units written out in `text`, like `km/s^2`, `kg m²` or unify's
+// `kilo meter per second`, or `None` unless it's all units.
+#[cfg(feature = "gui")]
+fn parse_units(text: &str) -> Option<Vec<Factor>> {
+    let mut factors: Vec<Factor> = Vec::new();
+    let mut slash = false;
+    let mut per = false;
+    let spaced = text.replace('/', " / ");
+    let mut prefix = None;
+    for word in spaced
+        .split([' ', '*', '·'])
+        .filter(|word| !word.is_empty())
+    {
+        match word {
+            "/" => slash = true,
+            "per" => per = true,
+            "squared" | "cubed" => {
+                factors.last_mut()?.power *= if word == "squared" { 2 } else { 3 };
+            }
+            word => {
+                if let Some((name, _)) = PREFIXES.iter().find(|(name, _)| *name == word) {
+                    prefix = Some(*name);
+                    continue;
+                }
+                let (symbol, power) = match word.split_once('^') {
+                    Some((symbol, power)) => (
+                        symbol,
+                        power.trim_matches(['(', ')', '{', '}']).parse().ok()?,
+                    ),
+                    None => match word.find(|c: char| "⁻¹²³⁴⁵⁶⁷⁸⁹".contains(c)) {
+                        Some(at) => (&word[..at], superscript_power(&word[at..])?),
+                        None => (word, 1),
+                    },
+                };
+                let (found_prefix, unit) = resolve(symbol)?;
+                let negative = slash || per;
+                factors.push(Factor {
+                    prefix: prefix.take().or(found_prefix),
+                    unit,
+                    power: if negative { -power } else { power },
+                    slash,
+                });
+                slash = false;
+                per = false;
+            }
+        }
+    }
+    (!factors.is_empty()).then_some(factors)
+}
+
+// The units in the tokens at `i`, such as `\text{m}/\text{s}^{2}`, and the index
+// after them.
+#[cfg(feature = "gui")]
+fn unit_run(tokens: &[Token], i: usize) -> Option<(Vec<Factor>, usize)> {
+    let mut factors: Vec<Factor> = Vec::new();
+    let mut slash = false;
+    let mut j = i;
+    let mut end = i;
+    loop {
+        match tokens.get(j) {
+            Some(Token::Command(command))
+                if matches!(command.as_str(), "text" | "textrm" | "mathrm" | "mathup") =>
+            {
+                let (content, next) = latex::argument(tokens, j + 1);
+                let Some(mut units) = parse_units(latex::to_string(content).trim()) else {
+                    break;
+                };
+                if slash {
+                    for factor in &mut units {
+                        factor.power = -factor.power;
+                        factor.slash = true;
+                    }
+                }
+                j = next;
+                if tokens.get(j) == Some(&Token::Char('^')) {
+                    let (power, next) = latex::argument(tokens, j + 1);
+                    let Ok(power) = latex::to_string(power).trim().parse::<i32>() else {
+                        break;
+                    };
+                    if let Some(last) = units.last_mut() {
+                        last.power *= power;
+                    }
+                    j = next;
+                }
+                factors.extend(units);
+                slash = false;
+                end = j;
+            }
+            Some(Token::Char('/')) if !factors.is_empty() && !slash => {
+                slash = true;
+                j += 1;
+            }
+            Some(Token::Space | Token::Char('~')) if !factors.is_empty() => j += 1,
+            Some(Token::Command(command))
+                if !factors.is_empty() && matches!(command.as_str(), "," | ";" | " " | "cdot") =>
+            {
+                j += 1
+            }
+            _ => break,
+        }
+    }
+    (!factors.is_empty()).then_some((factors, end))
+}
+
+// The number at `i`, like `1.5` or `3 \times 10^{8}`, in siunitx's form and as
+// written, and the index after it.
+#[cfg(feature = "gui")]
+fn number(tokens: &[Token], i: usize) -> Option<(String, String, usize)> {
+    let mut j = i;
+    let mut digits = String::new();
+    while let Some(Token::Char(c)) = tokens.get(j) {
+        let decimal = (*c == '.' || *c == ',')
+            && !digits.is_empty()
+            && matches!(tokens.get(j + 1), Some(Token::Char(d)) if d.is_ascii_digit());
+        if !c.is_ascii_digit() && !decimal {
+            break;
+        }
+        digits.push(*c);
+        j += 1;
+    }
+    if digits.is_empty() {
+        return None;
+    }
+    let mut siunitx = digits.clone();
+    // A power of ten, as in `3 \times 10^{8}`.
+    let times = latex::skip_spaces(tokens, j);
+    if tokens.get(times) == Some(&Token::Command("times".to_string())) {
+        let ten = latex::skip_spaces(tokens, times + 1);
+        let base = [Token::Char('1'), Token::Char('0'), Token::Char('^')];
+        if tokens.get(ten..ten + 3) == Some(&base[..]) {
+            let (exponent, next) = latex::argument(tokens, ten + 3);
+            siunitx = format!("{}e{}", digits, latex::to_string(exponent).trim());
+            j = next;
+        }
+    }
+    Some((siunitx, latex::to_string(&tokens[i..j]), j))
+}
+
+#[cfg(feature = "gui")]
+fn siunitx_units(factors: &[Factor]) -> String {
+    let mut out = String::new();
+    for factor in factors {
+        if factor.power < 0 {
+            out.push_str("\\per");
+        }
+        if let Some(prefix) = factor.prefix {
+            out.push_str(&format!("\\{}", prefix));
+        }
+        out.push_str(&format!("\\{}", factor.unit));
+        match factor.power.abs() {
+            1 => {}
+            2 => out.push_str("\\squared"),
+            3 => out.push_str("\\cubed"),
+            power => out.push_str(&format!("\\tothe{{{}}}", power)),
+        }
+    }
+    out
+}
+
+#[cfg(feature = "gui")]
+fn upright_units(factors: &[Factor]) -> String {
+    let mut out = String::new();
+    for (i, factor) in factors.iter().enumerate() {
+        let power = if factor.slash {
+            out.push('/');
+            factor.power.abs()
+        } else {
+            if i > 0 {
+                out.push_str("\\,");
+            }
+            factor.power
+        };
+        out.push_str(&format!("\\mathrm{{{}}}", factor.symbol()));
+        if power != 1 {
+            out.push_str(&format!("^{{{}}}", power));
+        }
+    }
+    out
+}
+
+// A quantity or units alone at `i` in `style`, and the index after it.
+#[cfg(feature = "gui")]
+fn quantity(tokens: &[Token], i: usize, style: &str) -> Option<(String, usize)> {
+    // Not the digits of a name, as in `x_2`, or of an exponent.
+    let after_name = i > 0
+        && matches!(&tokens[i - 1], Token::Char(c) if c.is_alphanumeric() || *c == '^' || *c == '_');
+    let (number, j) = match number(tokens, i) {
+        Some(_) if after_name => return None,
+        Some((siunitx, written, j)) => (Some((siunitx, written)), j),
+        None => (None, i),
+    };
+    let mut k = j;
+    while matches!(tokens.get(k), Some(Token::Space | Token::Char('~')))
+        || matches!(tokens.get(k), Some(Token::Command(c)) if c == "," || c == " ")
+    {
+        k += 1;
+    }
+    let (factors, next) = unit_run(tokens, if number.is_some() { k } else { i })?;
+    // A single word alone, like `\text{s}`, is more likely text than a unit.
+    if number.is_none() && factors.len() == 1 && factors[0].power == 1 {
+        return None;
+    }
+    let text = match (style, number) {
+        ("siunitx", Some((number, _))) => {
+            format!("\\SI{{{}}}{{{}}}", number, siunitx_units(&factors))
+        }
+        ("siunitx", None) => format!("\\si{{{}}}", siunitx_units(&factors)),
+        (_, Some((_, written))) => {
+            let units = upright_units(&factors);
+            // No space before a degree or percent sign.
+            if matches!(factors[0].unit, "degree" | "percent") {
+                format!("{}{}", written.trim(), units)
+            } else {
+                format!("{}\\,{}", written.trim(), units)
+            }
+        }
+        (_, None) => upright_units(&factors),
+    };
+    Some((text, next))
+}
+
+#[cfg(feature = "gui")]
+fn restyled(tokens: &[Token], style: &str) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some((text, next)) = quantity(tokens, i, style) {
+            out.push_str(&text);
+            i = next;
+            continue;
+        }
+        match &tokens[i] {
+            Token::Group(group) => {
+                out.push('{');
+                out.push_str(&restyled(group, style));
+                out.push('}');
+            }
+            token => out.push_str(&latex::to_string(std::slice::from_ref(token))),
+        }
+        // Keep a command name from running into a following letter.
+        if let (Token::Command(name), Some(Token::Char(c))) = (&tokens[i], tokens.get(i + 1)) {
+            if name.chars().all(|c| c.is_ascii_alphabetic()) && c.is_ascii_alphabetic() {
+                out.push(' ');
+            }
+        }
+        i += 1;
+    }
+    out
+}
+
+// Write the quantities and units of LaTeX output in `style`: "siunitx" for
+// `\SI{3}{\meter\per\second}` and `\si{...}`, "upright" for
+// `3\,\mathrm{m}/\mathrm{s}`, or "" to leave them as pandoc writes them.
+#[cfg(feature = "gui")]
+pub fn restyle(latex: &str, style: &str) -> String {
+    if style.is_empty() {
+        return latex.to_string();
+    }
+    let tokens = latex::parse(latex);
+    let out = restyled(&tokens, style);
+    if out == latex::to_string(&tokens) {
+        latex.to_string()
+    } else {
+        out
+    }
+}
+
+// The arguments of the call whose parenthesis opens at the start of `text`,
+// unquoted, and the length of the call.
+fn call_arguments(text: &str) -> Option<(Vec<String>, usize)> {
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut arguments = vec![String::new()];
+    for (i, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            }
+            ')' if !quoted => {
+                depth -= 1;
+                if depth == 0 {
+                    let arguments = arguments.iter().map(|a| a.trim().to_string()).collect();
+                    return Some((arguments, i + 1));
+                }
+            }
+            ',' if !quoted && depth == 1 => {
+                arguments.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        if let Some(argument) = arguments.last_mut() {
+            argument.push(c);
+        }
+    }
+    None
+}
+
+// A number as the Typst unit packages take it, like `"1.5e3"`, in Typst math.
+fn typst_number(number: &str) -> String {
+    let number = number.trim_matches('"');
+    match number.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => format!("{} times 10^({})", mantissa, exponent),
+        None => number.to_string(),
+    }
+}
+
+// Calls of the Typst unit packages unify and metro, `#qty(3, "m/s")`,
+// `#unit("m/s")` and `#num("3e8")`, which pandoc can't evaluate, as the Typst math
+// they stand for.
+pub fn expand_unit_calls(typst: &str) -> String {
+    let mut out = String::new();
+    let mut rest = typst;
+    while let Some(at) = rest.find('#') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let name_length = rest[1..]
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .map_or(rest.len(), |length| length + 1);
+        let name = &rest[1..name_length];
+        let call = call_arguments(&rest[name_length..]).filter(|_| {
+            rest[name_length..].starts_with('(') && matches!(name, "qty" | "unit" | "num")
+        });
+        let Some((arguments, length)) = call else {
+            out.push('#');
+            rest = &rest[1..];
+            continue;
+        };
+        let text = match (name, &arguments[..]) {
+            ("qty", [value, unit, ..]) => format!("{} {}", typst_number(value), unit),
+            ("unit", [unit, ..]) => unit.clone(),
+            ("num", [value, ..]) => typst_number(value),
+            _ => {
+                out.push('#');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        out.push_str(&text);
+        rest = &rest[name_length + length..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_calls_become_math() {
+        assert_eq!(expand_unit_calls(r#"#qty(3, "m/s")"#), r#"3 "m/s""#);
+        assert_eq!(expand_unit_calls(r#"#unit("km^2")"#), r#""km^2""#);
+        assert_eq!(expand_unit_calls(r#"#num("3e8")"#), "3 times 10^(8)");
+        assert_eq!(
+            expand_unit_calls(r#"#qty("1.5e3", "N")"#),
+            r#"1.5 times 10^(3) "N""#
+        );
+        // Other calls, and ones left open, stay for pandoc to report.
+        assert_eq!(expand_unit_calls("#x + 1"), "#x + 1");
+        assert_eq!(expand_unit_calls("#qty(3"), "#qty(3");
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn quantities_in_siunitx() {
+        assert_eq!(
+            restyle(r"3\text{m/s}", "siunitx"),
+            r"\SI{3}{\meter\per\second}"
+        );
+        assert_eq!(
+            restyle(r"3 \text{ kg m/s}^{2}", "siunitx"),
+            r"\SI{3}{\kilogram\meter\per\second\squared}"
+        );
+        assert_eq!(
+            restyle(r"1.5 \times 10^{3}\text{N}", "siunitx"),
+            r"\SI{1.5e3}{\newton}"
+        );
+        assert_eq!(restyle(r"2\text{m²}", "siunitx"), r"\SI{2}{\meter\squared}");
+        assert_eq!(restyle(r"\text{m/s}", "siunitx"), r"\si{\meter\per\second}");
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn upright_quantities() {
+        assert_eq!(
+            restyle(r"3\text{m/s}", "upright"),
+            r"3\,\mathrm{m}/\mathrm{s}"
+        );
+        assert_eq!(
+            restyle(r"3\,\text{km}^{2}", "upright"),
+            r"3\,\mathrm{km}^{2}"
+        );
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn text_and_other_styles_are_kept() {
+        assert_eq!(restyle(r"x\text{ if }y", "siunitx"), r"x\text{ if }y");
+        assert_eq!(restyle(r"t = 1\text{s}", ""), r"t = 1\text{s}");
+        // A single word alone is more likely text than a unit.
+        assert_eq!(restyle(r"\text{s}", "siunitx"), r"\text{s}");
+    }
+
+    #[test]
+    fn unit_calls_among_other_math() {
+        assert_eq!(expand_unit_calls(r#"#qty(-3, "K")"#), r#"-3 "K""#);
+        assert_eq!(expand_unit_calls(r#"#qty(1,"s")"#), r#"1 "s""#);
+        assert_eq!(expand_unit_calls(r#"a #qty(1, "s") b"#), r#"a 1 "s" b"#);
+        assert_eq!(expand_unit_calls(r#"#num("1e-3")"#), "1 times 10^(-3)");
+        assert_eq!(expand_unit_calls("#num(1000)"), "1000");
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn prefixes_and_signs() {
+        assert_eq!(
+            restyle(r"3\text{km/h}", "siunitx"),
+            r"\SI{3}{\kilo\meter\per\hour}"
+        );
+        assert_eq!(restyle(r"5\text{mA}", "siunitx"), r"\SI{5}{\milli\ampere}");
+        // The sign stays outside, as for any other number.
+        assert_eq!(restyle(r"-5\text{K}", "siunitx"), r"-\SI{5}{\kelvin}");
+        assert_eq!(
+            restyle(r"2\text{kg}\cdot 3\text{m}", "siunitx"),
+            r"\SI{2}{\kilogram}\cdot \SI{3}{\meter}"
+        );
+        assert_eq!(restyle(r"3\text{ foo}", "siunitx"), r"3\text{ foo}");
+    }
+}
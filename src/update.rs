@@ -0,0 +1,64 @@
+use crate::diagnostics;
+use crate::json::Value;
+use std::process::Command;
+
+const RELEASES_API: &str = "https://api.github.com/repos/peoxin/math-typst2latex/releases/latest";
+
+pub struct Release {
+    pub version: String,
+    // Markdown, shown as is.
+    pub notes: String,
+    pub url: String,
+}
+
+fn version_numbers(version: &str) -> Vec<u32> {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+// Fetch the latest release through curl, which ships with Windows, macOS and most
+// Linux desktops and spares us a TLS stack.
+fn latest_release() -> Result<Release, String> {
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--max-time", "10"])
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .args(["--user-agent", "typst2latex"])
+        .arg(RELEASES_API)
+        .output()
+        .map_err(|_| "Failed to execute curl. Do you have it installed?")?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let release = Value::parse(&String::from_utf8_lossy(&output.stdout))?;
+    let field = |name: &str| {
+        release
+            .get(name)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let version = field("tag_name");
+    if version.is_empty() {
+        return Err("GitHub's reply has no release version".into());
+    }
+    Ok(Release {
+        version: version.trim_start_matches('v').to_string(),
+        notes: field("body"),
+        url: field("html_url"),
+    })
+}
+
+// The latest release if it's newer than this build.
+pub fn check() -> Result<Option<Release>, String> {
+    let release = latest_release()?;
+    let current = env!("CARGO_PKG_VERSION");
+    diagnostics::log(format!(
+        "Latest release is {}, this is {}",
+        release.version, current
+    ));
+    Ok((version_numbers(&release.version) > version_numbers(current)).then_some(release))
+}
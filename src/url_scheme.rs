@@ -0,0 +1,155 @@
+#[cfg(feature = "gui")]
+use crate::convert::Direction;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+// Links like `typst2latex://convert?src=x%5E2&direction=typst-to-latex` open the app
+// with the formula filled in, so web pages and editor plugins can hand one over.
+pub const SCHEME: &str = "typst2latex";
+
+#[cfg(feature = "gui")]
+pub struct Link {
+    pub src: Option<String>,
+    pub direction: Option<Direction>,
+}
+
+#[cfg(feature = "gui")]
+pub fn is_link(arg: &str) -> bool {
+    arg.get(..SCHEME.len() + 1)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&format!("{}:", SCHEME)))
+}
+
+// Decode `%XX` escapes. `+` is left alone rather than read as a space, since it's
+// far more likely to be a plus sign in a formula.
+#[cfg(feature = "gui")]
+fn percent_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| "Link text is not valid UTF-8".to_string())
+}
+
+#[cfg(feature = "gui")]
+pub fn parse(link: &str) -> Result<Link, String> {
+    let rest = &link[SCHEME.len() + 1..];
+    let rest = rest.strip_prefix("//").unwrap_or(rest);
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+    if action.trim_end_matches('/') != "convert" {
+        return Err(format!("Unknown link action '{}'", action));
+    }
+    let mut parsed = Link {
+        src: None,
+        direction: None,
+    };
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value)?;
+        match key {
+            "src" => parsed.src = Some(value),
+            "direction" => {
+                parsed.direction = Some(
+                    Direction::from_name(&value)
+                        .ok_or_else(|| format!("Unknown direction '{}'", value))?,
+                )
+            }
+            // Leave room for parameters added later.
+            _ => {}
+        }
+    }
+    Ok(parsed)
+}
+
+fn executable() -> Result<String, String> {
+    std::env::current_exe()
+        .map(|path| path.display().to_string())
+        .map_err(|err| format!("Failed to locate the executable: {}", err))
+}
+
+fn run(program: &str, args: &[&str]) -> Result<(), String> {
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|err| format!("Failed to run {}: {}", program, err))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} failed with {}", program, status))
+    }
+}
+
+// Make this executable the handler for `typst2latex://` links, returning where it
+// was registered.
+pub fn register() -> Result<String, String> {
+    let exe = executable()?;
+    if cfg!(windows) {
+        let key = format!("HKCU\\Software\\Classes\\{}", SCHEME);
+        let command = format!("\"{}\" \"%1\"", exe);
+        run(
+            "reg",
+            &["add", &key, "/ve", "/d", &format!("URL:{}", SCHEME), "/f"],
+        )?;
+        run("reg", &["add", &key, "/v", "URL Protocol", "/d", "", "/f"])?;
+        run(
+            "reg",
+            &[
+                "add",
+                &format!("{}\\shell\\open\\command", key),
+                "/ve",
+                "/d",
+                &command,
+                "/f",
+            ],
+        )?;
+        Ok(key)
+    } else if cfg!(target_os = "macos") {
+        // macOS delivers links as Apple events to a bundle that declares the scheme
+        // in its Info.plist, not as arguments.
+        Err(format!(
+            "On macOS the scheme is declared by the app bundle: add {} to CFBundleURLSchemes in its Info.plist",
+            SCHEME
+        ))
+    } else {
+        let data = match std::env::var_os("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME").ok_or("HOME is not set")?)
+                .join(".local/share"),
+        };
+        let dir = data.join("applications");
+        fs::create_dir_all(&dir).map_err(|_| format!("Failed to create {}", dir.display()))?;
+        let name = format!("{}-url.desktop", SCHEME);
+        let path = dir.join(&name);
+        // Desktop entries quote arguments with double quotes and backslash escapes.
+        let quoted = exe
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('`', "\\`")
+            .replace('$', "\\$");
+        let entry = format!(
+            "[Desktop Entry]\nType=Application\nName=Typst2LaTeX\nExec=\"{}\" %u\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+            quoted, SCHEME
+        );
+        fs::write(&path, entry).map_err(|_| format!("Failed to write {}", path.display()))?;
+        run(
+            "xdg-mime",
+            &["default", &name, &format!("x-scheme-handler/{}", SCHEME)],
+        )?;
+        Ok(path.display().to_string())
+    }
+}
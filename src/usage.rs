@@ -0,0 +1,157 @@
+use crate::i18n;
+use crate::json::Value;
+use crate::settings::config_dir;
+use crate::stats;
+use eframe::egui;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const TOP_SYMBOLS: usize = 15;
+
+// Counts of how the app is used, kept in the config directory and never sent
+// anywhere. Meant for spotting slow settings, e.g. a preview engine that makes
+// every keystroke lag.
+#[derive(Default)]
+pub struct Usage {
+    pub open: bool,
+    conversions: u64,
+    failures: u64,
+    convert_ms: u64,
+    renders: u64,
+    render_ms: u64,
+    // How many conversions used each LaTeX command.
+    symbols: BTreeMap<String, u64>,
+}
+
+impl Usage {
+    fn path() -> Option<PathBuf> {
+        Some(config_dir()?.join("usage.json"))
+    }
+
+    pub fn load() -> Self {
+        let Some(value) = Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| Value::parse(&text).ok())
+        else {
+            return Self::default();
+        };
+        let count = |key: &str| value.get(key).and_then(Value::as_f64).unwrap_or(0.0) as u64;
+        let symbols = match value.get("symbols") {
+            Some(Value::Object(entries)) => entries
+                .iter()
+                .filter_map(|(name, count)| Some((name.clone(), count.as_f64()? as u64)))
+                .collect(),
+            _ => BTreeMap::new(),
+        };
+        Self {
+            open: false,
+            conversions: count("conversions"),
+            failures: count("failures"),
+            convert_ms: count("convert_ms"),
+            renders: count("renders"),
+            render_ms: count("render_ms"),
+            symbols,
+        }
+    }
+
+    // Written when the window closes rather than after every conversion, which
+    // happen on each keystroke.
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("Failed to locate config directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|_| "Failed to create config directory")?;
+        }
+        let value = Value::object([
+            ("conversions", (self.conversions as f64).into()),
+            ("failures", (self.failures as f64).into()),
+            ("convert_ms", (self.convert_ms as f64).into()),
+            ("renders", (self.renders as f64).into()),
+            ("render_ms", (self.render_ms as f64).into()),
+            (
+                "symbols",
+                Value::object(
+                    self.symbols
+                        .iter()
+                        .map(|(name, &count)| (name.as_str(), (count as f64).into())),
+                ),
+            ),
+        ]);
+        fs::write(&path, value.pretty(2)).map_err(|_| "Failed to write usage statistics".into())
+    }
+
+    // `latex` is the LaTeX side of a successful conversion.
+    pub fn record_conversion(&mut self, ms: u128, latex: Option<&str>) {
+        self.conversions += 1;
+        self.convert_ms += ms as u64;
+        match latex {
+            Some(latex) => {
+                for name in stats::latex(latex).command_names {
+                    *self.symbols.entry(name).or_default() += 1;
+                }
+            }
+            None => self.failures += 1,
+        }
+    }
+
+    pub fn record_render(&mut self, ms: u128) {
+        self.renders += 1;
+        self.render_ms += ms as u64;
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let mut open = self.open;
+        let mut reset = false;
+        egui::Window::new(i18n::tr("usage"))
+            .open(&mut open)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                ui.weak(i18n::tr("usage-local"));
+                ui.separator();
+                let average = |total: u64, count: u64| match count {
+                    0 => "–".to_string(),
+                    _ => i18n::tr_args("usage-ms", &[("ms", &(total / count).to_string())]),
+                };
+                egui::Grid::new("usage_grid").num_columns(2).show(ui, |ui| {
+                    ui.label(i18n::tr("usage-conversions"));
+                    ui.label(self.conversions.to_string());
+                    ui.end_row();
+                    ui.label(i18n::tr("usage-failures"));
+                    ui.label(self.failures.to_string());
+                    ui.end_row();
+                    ui.label(i18n::tr("usage-convert-time"));
+                    ui.label(average(self.convert_ms, self.conversions));
+                    ui.end_row();
+                    ui.label(i18n::tr("usage-render-time"));
+                    ui.label(average(self.render_ms, self.renders));
+                    ui.end_row();
+                });
+                ui.separator();
+                ui.strong(i18n::tr("usage-symbols"));
+                let mut symbols: Vec<(&String, &u64)> = self.symbols.iter().collect();
+                symbols.sort_by(|a, b| b.1.cmp(a.1));
+                if symbols.is_empty() {
+                    ui.weak(i18n::tr("usage-no-symbols"));
+                }
+                egui::Grid::new("usage_symbols")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        for (name, count) in symbols.into_iter().take(TOP_SYMBOLS) {
+                            ui.monospace(name.as_str());
+                            ui.label(count.to_string());
+                            ui.end_row();
+                        }
+                    });
+                ui.separator();
+                reset = ui.button(i18n::tr("usage-reset")).clicked();
+            });
+        if reset {
+            *self = Self {
+                open,
+                ..Self::default()
+            };
+        } else {
+            self.open = open;
+        }
+    }
+}
@@ -0,0 +1,553 @@
+use crate::cursors::{index_of, line_column};
+use eframe::egui;
+
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum Mode {
+    #[default]
+    Normal,
+    Insert,
+}
+
+pub enum Action {
+    Moved(usize),
+    Edited(usize),
+    Undo,
+    Redo,
+}
+
+#[derive(PartialEq)]
+enum Class {
+    Space,
+    Word,
+    Punctuation,
+}
+
+fn class(c: char) -> Class {
+    if c.is_whitespace() {
+        Class::Space
+    } else if c.is_alphanumeric() || c == '_' {
+        Class::Word
+    } else {
+        Class::Punctuation
+    }
+}
+
+fn line_start(chars: &[char], at: usize) -> usize {
+    chars[..at]
+        .iter()
+        .rposition(|&c| c == '\n')
+        .map_or(0, |i| i + 1)
+}
+
+// The index of the line's newline, or the end of the text.
+fn line_end(chars: &[char], at: usize) -> usize {
+    at + chars[at..].iter().take_while(|&&c| c != '\n').count()
+}
+
+// The last character of the line, where normal mode's caret stops.
+fn last_on_line(chars: &[char], at: usize) -> usize {
+    line_end(chars, at)
+        .saturating_sub(1)
+        .max(line_start(chars, at))
+}
+
+fn first_non_blank(chars: &[char], at: usize) -> usize {
+    let start = line_start(chars, at);
+    start
+        + chars[start..]
+            .iter()
+            .take_while(|&&c| c == ' ' || c == '\t')
+            .count()
+}
+
+fn next_word(chars: &[char], at: usize) -> usize {
+    let mut i = at;
+    if let Some(&c) = chars.get(i) {
+        let first = class(c);
+        while first != Class::Space && i < chars.len() && class(chars[i]) == first {
+            i += 1;
+        }
+    }
+    while i < chars.len() && class(chars[i]) == Class::Space {
+        i += 1;
+    }
+    i
+}
+
+fn previous_word(chars: &[char], at: usize) -> usize {
+    let mut i = at;
+    while i > 0 && class(chars[i - 1]) == Class::Space {
+        i -= 1;
+    }
+    if i > 0 {
+        let last = class(chars[i - 1]);
+        while i > 0 && class(chars[i - 1]) == last {
+            i -= 1;
+        }
+    }
+    i
+}
+
+fn word_end(chars: &[char], at: usize) -> usize {
+    let mut i = at + 1;
+    while i < chars.len() && class(chars[i]) == Class::Space {
+        i += 1;
+    }
+    if let Some(&c) = chars.get(i) {
+        let word = class(c);
+        while i + 1 < chars.len() && class(chars[i + 1]) == word {
+            i += 1;
+        }
+    }
+    i.min(chars.len().saturating_sub(1))
+}
+
+// Modal editing for the input editor: normal mode takes keys as commands, insert
+// mode leaves them to the editor. Covers the motions and edits used most on short
+// formulas, not counts, visual mode or ex commands.
+#[derive(Default)]
+pub struct Vim {
+    pub mode: Mode,
+    // The first key of a two-key command such as `dd` or `gg`.
+    pending: Option<char>,
+    // What `x`, `d`, `c` and `y` took, for `p` to put back. Whole lines end with a
+    // newline.
+    register: String,
+}
+
+impl Vim {
+    pub fn label(&self) -> &'static str {
+        match self.mode {
+            Mode::Normal => "-- NORMAL --",
+            Mode::Insert => "-- INSERT --",
+        }
+    }
+
+    // Take this frame's keys out of the input while in normal mode and run them as
+    // commands on `text`. Keys after one that enters insert mode are left to the
+    // editor.
+    pub fn handle(
+        &mut self,
+        ctx: &egui::Context,
+        text: &mut String,
+        caret: usize,
+    ) -> Option<Action> {
+        let mut chars: Vec<char> = text.chars().collect();
+        let mut caret = caret.min(chars.len());
+        let mut keys = Vec::new();
+        let mut control = None;
+        ctx.input_mut(|i| {
+            let mut mode = self.mode;
+            i.events.retain(|event| match event {
+                egui::Event::Key {
+                    key: egui::Key::Escape,
+                    pressed: true,
+                    ..
+                } => {
+                    mode = Mode::Normal;
+                    keys.push('\u{1b}');
+                    false
+                }
+                _ if mode == Mode::Insert => true,
+                egui::Event::Text(text) => {
+                    // Whatever follows a key that enters insert mode is typed.
+                    for c in text.chars() {
+                        keys.push(c);
+                        if "iaIAoOsCS".contains(c) {
+                            mode = Mode::Insert;
+                        }
+                    }
+                    false
+                }
+                egui::Event::Key {
+                    key: egui::Key::R,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if modifiers.command_only() => {
+                    control = Some(Action::Redo);
+                    false
+                }
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                    ..
+                } if modifiers.is_none() => match key {
+                    egui::Key::Backspace => {
+                        keys.push('h');
+                        false
+                    }
+                    egui::Key::Enter => {
+                        keys.push('j');
+                        false
+                    }
+                    egui::Key::Delete => {
+                        keys.push('x');
+                        false
+                    }
+                    egui::Key::Tab | egui::Key::Space => false,
+                    _ => true,
+                },
+                _ => true,
+            });
+        });
+        if control.is_some() {
+            return control;
+        }
+        if keys.is_empty() {
+            return None;
+        }
+        let mut edited = false;
+        for key in keys {
+            if key == '\u{1b}' {
+                if self.mode == Mode::Insert && caret > line_start(&chars, caret) {
+                    caret -= 1;
+                }
+                self.mode = Mode::Normal;
+                self.pending = None;
+                continue;
+            }
+            if self.mode == Mode::Insert {
+                chars.insert(caret, key);
+                caret += 1;
+                edited = true;
+                continue;
+            }
+            match self.key(key, &mut chars, &mut caret) {
+                Some(Action::Edited(_)) => edited = true,
+                Some(action @ (Action::Undo | Action::Redo)) => return Some(action),
+                _ => {}
+            }
+        }
+        if edited {
+            *text = chars.into_iter().collect();
+            Some(Action::Edited(caret))
+        } else {
+            Some(Action::Moved(caret))
+        }
+    }
+
+    // Remove `range` into the register.
+    fn cut(&mut self, chars: &mut Vec<char>, range: std::ops::Range<usize>) {
+        self.register = chars.drain(range).collect();
+    }
+
+    fn key(&mut self, key: char, chars: &mut Vec<char>, caret: &mut usize) -> Option<Action> {
+        let at = *caret;
+        let start = line_start(chars, at);
+        let end = line_end(chars, at);
+        if let Some(first) = self.pending.take() {
+            match (first, key) {
+                ('g', 'g') => *caret = 0,
+                ('r', c) if at < end => {
+                    chars[at] = c;
+                    return Some(Action::Edited(at));
+                }
+                ('d' | 'c', 'd' | 'c') if first == key => {
+                    if first == 'c' {
+                        let indent = first_non_blank(chars, at);
+                        self.cut(chars, indent..end);
+                        *caret = indent;
+                        self.mode = Mode::Insert;
+                    } else {
+                        // Take the line's newline with it, or the one before on the last line.
+                        let (from, to) = if end < chars.len() {
+                            (start, end + 1)
+                        } else {
+                            (start.saturating_sub(1), end)
+                        };
+                        let line: String = chars[start..end].iter().collect();
+                        chars.drain(from..to);
+                        self.register = format!("{}\n", line);
+                        *caret = first_non_blank(chars, from.min(chars.len()));
+                    }
+                    return Some(Action::Edited(*caret));
+                }
+                ('y', 'y') => {
+                    let line: String = chars[start..end].iter().collect();
+                    self.register = format!("{}\n", line);
+                }
+                ('d' | 'c' | 'y', motion @ ('w' | 'e' | '$' | '0' | 'b')) => {
+                    let target = match motion {
+                        // `cw` stops at the end of the word, like `ce`.
+                        'w' if first == 'c' => word_end(chars, at) + 1,
+                        'w' => next_word(chars, at).min(end.max(at + 1)),
+                        'e' => word_end(chars, at) + 1,
+                        '$' => end,
+                        '0' => start,
+                        _ => previous_word(chars, at),
+                    }
+                    .min(chars.len());
+                    let range = at.min(target)..at.max(target);
+                    if first == 'y' {
+                        self.register = chars[range].iter().collect();
+                        return None;
+                    }
+                    *caret = range.start;
+                    self.cut(chars, range);
+                    if first == 'c' {
+                        self.mode = Mode::Insert;
+                    }
+                    return Some(Action::Edited(*caret));
+                }
+                _ => {}
+            }
+            return Some(Action::Moved(*caret));
+        }
+        match key {
+            'g' | 'd' | 'c' | 'y' | 'r' => self.pending = Some(key),
+            'h' => *caret = at.saturating_sub(1).max(start),
+            'l' => *caret = (at + 1).min(last_on_line(chars, at)),
+            'j' | 'k' => {
+                let (line, column) = line_column(chars, at);
+                let target = if key == 'j' {
+                    Some(line + 1)
+                } else {
+                    line.checked_sub(1)
+                };
+                if let Some(index) = target.and_then(|line| index_of(chars, line, column)) {
+                    *caret = index.min(last_on_line(chars, index));
+                }
+            }
+            'w' => *caret = next_word(chars, at),
+            'b' => *caret = previous_word(chars, at),
+            'e' => *caret = word_end(chars, at),
+            '0' => *caret = start,
+            '^' => *caret = first_non_blank(chars, at),
+            '$' => *caret = last_on_line(chars, at),
+            'G' => *caret = line_start(chars, chars.len()),
+            'i' => self.mode = Mode::Insert,
+            'a' => {
+                *caret = (at + 1).min(end);
+                self.mode = Mode::Insert;
+            }
+            'I' => {
+                *caret = first_non_blank(chars, at);
+                self.mode = Mode::Insert;
+            }
+            'A' => {
+                *caret = end;
+                self.mode = Mode::Insert;
+            }
+            'o' | 'O' => {
+                let index = if key == 'o' { end } else { start };
+                chars.insert(index, '\n');
+                *caret = if key == 'o' { end + 1 } else { start };
+                self.mode = Mode::Insert;
+                return Some(Action::Edited(*caret));
+            }
+            'x' | 's' if at < end => {
+                self.cut(chars, at..at + 1);
+                if key == 's' {
+                    self.mode = Mode::Insert;
+                } else {
+                    *caret = at.min(last_on_line(chars, at));
+                }
+                return Some(Action::Edited(*caret));
+            }
+            'X' if at > start => {
+                self.cut(chars, at - 1..at);
+                *caret = at - 1;
+                return Some(Action::Edited(*caret));
+            }
+            'D' | 'C' => {
+                self.cut(chars, at..end);
+                if key == 'C' {
+                    self.mode = Mode::Insert;
+                } else {
+                    *caret = last_on_line(chars, at);
+                }
+                return Some(Action::Edited(*caret));
+            }
+            'S' => {
+                let indent = first_non_blank(chars, at);
+                self.cut(chars, indent..end);
+                *caret = indent;
+                self.mode = Mode::Insert;
+                return Some(Action::Edited(*caret));
+            }
+            'p' | 'P' if !self.register.is_empty() => {
+                let put: Vec<char> = self.register.chars().collect();
+                if let Some(line) = self.register.strip_suffix('\n') {
+                    // Whole lines go below or above the current one.
+                    let (index, inserted) = match key {
+                        'p' if end == chars.len() => (end, format!("\n{}", line)),
+                        'p' => (end + 1, self.register.clone()),
+                        _ => (start, self.register.clone()),
+                    };
+                    chars.splice(index..index, inserted.chars());
+                    *caret =
+                        first_non_blank(chars, index + usize::from(inserted.starts_with('\n')));
+                } else {
+                    let index = if key == 'p' { (at + 1).min(end) } else { at };
+                    chars.splice(index..index, put.iter().copied());
+                    *caret = index + put.len() - 1;
+                }
+                return Some(Action::Edited(*caret));
+            }
+            'u' => return Some(Action::Undo),
+            _ => {}
+        }
+        Some(Action::Moved(*caret))
+    }
+
+    // Normal mode's block caret over the character it's on.
+    pub fn paint(&self, ui: &egui::Ui, output: &egui::text_edit::TextEditOutput, caret: usize) {
+        if self.mode != Mode::Normal {
+            return;
+        }
+        let from = output
+            .galley
+            .pos_from_ccursor(egui::text::CCursor::new(caret));
+        let to = output
+            .galley
+            .pos_from_ccursor(egui::text::CCursor::new(caret + 1));
+        let width = if to.min.y == from.min.y && to.min.x > from.min.x {
+            to.min.x - from.min.x
+        } else {
+            from.height() / 2.0
+        };
+        let rect = egui::Rect::from_min_size(from.min, egui::vec2(width, from.height()))
+            .translate(output.galley_pos.to_vec2());
+        let color = ui.visuals().text_cursor.stroke.color.gamma_multiply(0.4);
+        ui.painter().rect_filled(rect, 0.0, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Send `keys` in one frame, `\u{1b}` standing for Escape, and return the caret.
+    fn run(vim: &mut Vim, text: &mut String, caret: usize, keys: &str) -> usize {
+        let mut events = Vec::new();
+        for part in keys.split_inclusive('\u{1b}') {
+            let typed = part.trim_end_matches('\u{1b}');
+            if !typed.is_empty() {
+                events.push(egui::Event::Text(typed.to_string()));
+            }
+            if part.ends_with('\u{1b}') {
+                events.push(egui::Event::Key {
+                    key: egui::Key::Escape,
+                    physical_key: None,
+                    pressed: true,
+                    repeat: false,
+                    modifiers: egui::Modifiers::NONE,
+                });
+            }
+        }
+        let ctx = egui::Context::default();
+        ctx.begin_pass(egui::RawInput {
+            events,
+            ..Default::default()
+        });
+        match vim.handle(&ctx, text, caret) {
+            Some(Action::Moved(caret) | Action::Edited(caret)) => caret,
+            _ => caret,
+        }
+    }
+
+    fn moved(text: &str, caret: usize, keys: &str) -> usize {
+        run(&mut Vim::default(), &mut text.to_string(), caret, keys)
+    }
+
+    fn edited(text: &str, caret: usize, keys: &str) -> (String, usize) {
+        let mut text = text.to_string();
+        let caret = run(&mut Vim::default(), &mut text, caret, keys);
+        (text, caret)
+    }
+
+    #[test]
+    fn word_motions() {
+        let text = "foo bar.baz qux";
+        assert_eq!(moved(text, 0, "w"), 4);
+        // Punctuation is a word of its own.
+        assert_eq!(moved(text, 4, "w"), 7);
+        assert_eq!(moved(text, 7, "w"), 8);
+        assert_eq!(moved(text, 8, "b"), 7);
+        assert_eq!(moved(text, 12, "bb"), 7);
+        assert_eq!(moved(text, 0, "e"), 2);
+        assert_eq!(moved(text, 2, "e"), 6);
+    }
+
+    #[test]
+    fn line_motions() {
+        let text = "abc def\n  ghi\nj";
+        assert_eq!(moved(text, 2, "$"), 6);
+        assert_eq!(moved(text, 12, "0"), 8);
+        assert_eq!(moved(text, 12, "^"), 10);
+        assert_eq!(moved(text, 2, "j"), 10);
+        // The caret stops on the last character of a shorter line.
+        assert_eq!(moved(text, 6, "jj"), 14);
+        assert_eq!(moved(text, 10, "k"), 2);
+        assert_eq!(moved(text, 0, "G"), 14);
+        assert_eq!(moved(text, 14, "gg"), 0);
+        assert_eq!(moved(text, 0, "h"), 0);
+        assert_eq!(moved(text, 6, "l"), 6);
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(edited("foo bar", 0, "dw"), ("bar".to_string(), 0));
+        assert_eq!(edited("foo bar", 4, "db"), ("bar".to_string(), 0));
+        assert_eq!(edited("foo bar", 1, "d$"), ("f".to_string(), 1));
+        assert_eq!(edited("foo bar", 0, "x"), ("oo bar".to_string(), 0));
+        assert_eq!(edited("foo bar", 4, "D"), ("foo ".to_string(), 3));
+        assert_eq!(edited("foo", 1, "rx"), ("fxo".to_string(), 1));
+        assert_eq!(edited("a\nb\nc", 2, "dd"), ("a\nc".to_string(), 2));
+        // On the last line the newline before it goes.
+        assert_eq!(edited("a\nb", 2, "dd"), ("a".to_string(), 0));
+    }
+
+    #[test]
+    fn registers() {
+        assert_eq!(edited("a\nb", 0, "yyp"), ("a\na\nb".to_string(), 2));
+        assert_eq!(edited("a\nb", 2, "ddP"), ("b\na".to_string(), 0));
+        assert_eq!(edited("abc", 0, "xp"), ("bac".to_string(), 1));
+        assert_eq!(
+            edited("foo bar", 0, "yw$p"),
+            ("foo barfoo ".to_string(), 10)
+        );
+    }
+
+    #[test]
+    fn insert_mode() {
+        let mut vim = Vim::default();
+        let mut text = "foo bar".to_string();
+        // Keys after `cw` are typed, and Escape steps back onto the last one.
+        let caret = run(&mut vim, &mut text, 0, "cwbaz\u{1b}");
+        assert_eq!((text.as_str(), caret), ("baz bar", 2));
+        assert!(vim.mode == Mode::Normal);
+
+        let caret = run(&mut vim, &mut text, caret, "A!");
+        assert_eq!((text.as_str(), caret), ("baz bar!", 8));
+        assert!(vim.mode == Mode::Insert);
+        assert_eq!(vim.label(), "-- INSERT --");
+        // Later frames' keys in insert mode are left to the editor.
+        let caret = run(&mut vim, &mut text, caret, "x");
+        assert_eq!((text.as_str(), caret), ("baz bar!", 8));
+        let caret = run(&mut vim, &mut text, caret, "\u{1b}");
+        assert_eq!(caret, 7);
+        assert_eq!(vim.label(), "-- NORMAL --");
+
+        assert_eq!(edited("ab", 0, "ax\u{1b}"), ("axb".to_string(), 1));
+        assert_eq!(edited("a\nb", 0, "oc\u{1b}"), ("a\nc\nb".to_string(), 2));
+        assert_eq!(edited("a\nb", 2, "Oc\u{1b}"), ("a\nc\nb".to_string(), 2));
+        assert_eq!(edited("  ab", 3, "Ix\u{1b}"), ("  xab".to_string(), 2));
+    }
+
+    #[test]
+    fn undo_is_left_to_the_editor() {
+        let mut text = "abc".to_string();
+        let ctx = egui::Context::default();
+        ctx.begin_pass(egui::RawInput {
+            events: vec![egui::Event::Text("u".to_string())],
+            ..Default::default()
+        });
+        assert!(matches!(
+            Vim::default().handle(&ctx, &mut text, 0),
+            Some(Action::Undo)
+        ));
+    }
+}